@@ -0,0 +1,481 @@
+use crate::oxide::*;
+use crate::oxide::platform::Platform;
+use std::ffi::{c_char, c_void, CString};
+use std::mem::transmute;
+use std::ptr::null_mut;
+use std::time::Instant;
+
+// Only the parts of AppKit, Core Animation and Core Graphics the platform uses, called through the
+// Objective-C runtime so building needs nothing but the system frameworks
+type Id = *mut c_void;
+type Sel = *mut c_void;
+type CGContextRef = *mut c_void;
+type CGImageRef = *mut c_void;
+type CGColorSpaceRef = *mut c_void;
+type CGColorRef = *mut c_void;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct CGPoint {
+    x: f64,
+    y: f64
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct CGSize {
+    width: f64,
+    height: f64
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct CGRect {
+    origin: CGPoint,
+    size: CGSize
+}
+
+#[link(name = "objc")]
+extern "C" {
+    fn objc_getClass(name: *const c_char) -> Id;
+    fn sel_registerName(name: *const c_char) -> Sel;
+    fn objc_msgSend();
+    #[cfg(target_arch = "x86_64")]
+    fn objc_msgSend_stret();
+    fn objc_autoreleasePoolPush() -> *mut c_void;
+    fn objc_autoreleasePoolPop(pool: *mut c_void);
+}
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {}
+
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {
+    static NSDefaultRunLoopMode: Id;
+}
+
+#[link(name = "QuartzCore", kind = "framework")]
+extern "C" {
+    static kCAFilterNearest: Id;
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGColorSpaceCreateDeviceRGB() -> CGColorSpaceRef;
+    fn CGColorCreateGenericRGB(red: f64, green: f64, blue: f64, alpha: f64) -> CGColorRef;
+    fn CGBitmapContextCreate(
+        data: *mut c_void, width: usize, height: usize, bits_per_component: usize,
+        bytes_per_row: usize, space: CGColorSpaceRef, bitmap_info: u32) -> CGContextRef;
+    fn CGBitmapContextCreateImage(context: CGContextRef) -> CGImageRef;
+    fn CGContextRelease(context: CGContextRef);
+    fn CGImageRelease(image: CGImageRef);
+    fn CGColorRelease(color: CGColorRef);
+}
+
+// Calls a method, with the argument and return types spelled out since objc_msgSend takes them
+// as whatever the method expects
+macro_rules! msg {
+    ($ret:ty, $receiver:expr, $selector:literal $(, $arg:expr => $kind:ty)*) => {{
+        let function: unsafe extern "C" fn(Id, Sel $(, $kind)*) -> $ret = transmute(objc_msgSend as unsafe extern "C" fn());
+        function($receiver, sel(concat!($selector, "\0")) $(, $arg)*)
+    }};
+}
+
+// Names end in a nul, the runtime takes C strings
+unsafe fn sel(name: &str) -> Sel {
+    sel_registerName(name.as_ptr() as *const c_char)
+}
+
+unsafe fn class(name: &str) -> Id {
+    objc_getClass(name.as_ptr() as *const c_char)
+}
+
+// Methods returning a rect go through objc_msgSend_stret on Intel, the rect doesn't fit in
+// registers there
+unsafe fn msg_rect(receiver: Id, selector: &str) -> CGRect {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let function: unsafe extern "C" fn(*mut CGRect, Id, Sel) = transmute(objc_msgSend_stret as unsafe extern "C" fn());
+        let mut rect = CGRect::default();
+        function(&mut rect, receiver, sel(selector));
+        rect
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let function: unsafe extern "C" fn(Id, Sel) -> CGRect = transmute(objc_msgSend as unsafe extern "C" fn());
+        function(receiver, sel(selector))
+    }
+}
+
+unsafe fn ns_string(text: &str) -> Id {
+    let text = CString::new(text).unwrap_or_default();
+    msg!(Id, class("NSString\0"), "stringWithUTF8String:", text.as_ptr() => *const c_char)
+}
+
+const NS_LEFT_MOUSE_DOWN: u64 = 1;
+const NS_LEFT_MOUSE_UP: u64 = 2;
+const NS_RIGHT_MOUSE_DOWN: u64 = 3;
+const NS_RIGHT_MOUSE_UP: u64 = 4;
+const NS_MOUSE_MOVED: u64 = 5;
+const NS_LEFT_MOUSE_DRAGGED: u64 = 6;
+const NS_RIGHT_MOUSE_DRAGGED: u64 = 7;
+const NS_KEY_DOWN: u64 = 10;
+const NS_KEY_UP: u64 = 11;
+const NS_FLAGS_CHANGED: u64 = 12;
+const NS_SCROLL_WHEEL: u64 = 22;
+const NS_OTHER_MOUSE_DOWN: u64 = 25;
+const NS_OTHER_MOUSE_UP: u64 = 26;
+const NS_OTHER_MOUSE_DRAGGED: u64 = 27;
+
+const NS_SHIFT_KEY_MASK: u64 = 1 << 17;
+const NS_CONTROL_KEY_MASK: u64 = 1 << 18;
+const NS_COMMAND_KEY_MASK: u64 = 1 << 20;
+
+// Titled, closable, miniaturizable and resizable
+const NS_WINDOW_STYLE: u64 = 1 | 2 | 4 | 8;
+const NS_BACKING_STORE_BUFFERED: u64 = 2;
+const NS_APPLICATION_ACTIVATION_POLICY_REGULAR: i64 = 0;
+
+// Premultiplied BGRA as one little endian 32 bit word, the same as the buffer
+const BITMAP_INFO: u32 = (2 << 12) | 2;
+
+// Virtual key codes, they name the key's place on an ANSI keyboard rather than its letter
+const KVK_A: u16 = 0x00;
+const KVK_S: u16 = 0x01;
+const KVK_D: u16 = 0x02;
+const KVK_F: u16 = 0x03;
+const KVK_Z: u16 = 0x06;
+const KVK_X: u16 = 0x07;
+const KVK_C: u16 = 0x08;
+const KVK_Q: u16 = 0x0C;
+const KVK_W: u16 = 0x0D;
+const KVK_E: u16 = 0x0E;
+const KVK_R: u16 = 0x0F;
+const KVK_Y: u16 = 0x10;
+const KVK_P: u16 = 0x23;
+const KVK_M: u16 = 0x2E;
+const KVK_SPACE: u16 = 0x31;
+const KVK_ESCAPE: u16 = 0x35;
+const KVK_F5: u16 = 0x60;
+const KVK_LEFT: u16 = 0x7B;
+const KVK_RIGHT: u16 = 0x7C;
+const KVK_DOWN: u16 = 0x7D;
+const KVK_UP: u16 = 0x7E;
+
+// One notch of the wheel, the same as WHEEL_DELTA on Windows
+static WHEEL_NOTCH: f64 = 120.0;
+// AppKit doesn't pick a size for new windows, in points
+static DEFAULT_WINDOW_SIZE: CGSize = CGSize { width: 1280.0, height: 720.0 };
+
+pub struct MacPlatform {
+    app: Id,
+    window: Id,
+    // Shows the buffer where the viewport puts it, the content view's own layer behind it is
+    // black for the bars
+    image_layer: Id,
+    color_space: CGColorSpaceRef,
+    // Draws into the back buffer's memory, snapshots of it are what the layer shows
+    context: CGContextRef,
+    window_size: WindowDimensions,
+    dpi_scale: f32,
+    fit: CameraFit,
+    // The back buffer's memory, the buffer points into it
+    pixels: Vec<u32>,
+    buffer: OffscreenBuffer,
+    start: Instant,
+    is_running: bool
+}
+
+impl Platform for MacPlatform {
+    fn create_window(title: &str) -> Result<Self, String> {
+        unsafe {
+            let pool = objc_autoreleasePoolPush();
+
+            let app: Id = msg!(Id, class("NSApplication\0"), "sharedApplication");
+            msg!((), app, "setActivationPolicy:", NS_APPLICATION_ACTIVATION_POLICY_REGULAR => i64);
+
+            let frame = CGRect { origin: CGPoint::default(), size: DEFAULT_WINDOW_SIZE };
+            let window: Id = msg!(Id, class("NSWindow\0"), "alloc");
+            let window: Id = msg!(Id, window, "initWithContentRect:styleMask:backing:defer:",
+                frame => CGRect,
+                NS_WINDOW_STYLE => u64,
+                NS_BACKING_STORE_BUFFERED => u64,
+                0 => i8);
+            if window.is_null() {
+                objc_autoreleasePoolPop(pool);
+                return Err("Unable to create the window".to_string());
+            }
+
+            // Closing the window only hides it, poll_events notices and stops the game
+            msg!((), window, "setReleasedWhenClosed:", 0 => i8);
+            msg!((), window, "setTitle:", ns_string(title) => Id);
+            msg!((), window, "setAcceptsMouseMovedEvents:", 1 => i8);
+            msg!((), window, "center");
+
+            let view: Id = msg!(Id, window, "contentView");
+            msg!((), view, "setWantsLayer:", 1 => i8);
+            let layer: Id = msg!(Id, view, "layer");
+            let black = CGColorCreateGenericRGB(0.0, 0.0, 0.0, 1.0);
+            msg!((), layer, "setBackgroundColor:", black => CGColorRef);
+            CGColorRelease(black);
+
+            let image_layer: Id = msg!(Id, class("CALayer\0"), "layer");
+            // Blocky like StretchDIBits when scaled up, not blurred
+            msg!((), image_layer, "setMagnificationFilter:", kCAFilterNearest => Id);
+            msg!((), layer, "addSublayer:", image_layer => Id);
+
+            msg!((), window, "makeKeyAndOrderFront:", null_mut() => Id);
+            msg!((), app, "activateIgnoringOtherApps:", 1 => i8);
+            msg!((), app, "finishLaunching");
+
+            objc_autoreleasePoolPop(pool);
+
+            let mut platform = MacPlatform {
+                app,
+                window,
+                image_layer,
+                color_space: CGColorSpaceCreateDeviceRGB(),
+                context: null_mut(),
+                window_size: WindowDimensions::default(),
+                dpi_scale: 1.0,
+                fit: CameraFit::default(),
+                pixels: Vec::new(),
+                buffer: OffscreenBuffer::empty(),
+                start: Instant::now(),
+                is_running: true
+            };
+            platform.update_window_size();
+            Ok(platform)
+        }
+    }
+
+    fn window_dimensions(&self) -> WindowDimensions {
+        self.window_size
+    }
+
+    fn dpi_scale(&self) -> f32 {
+        self.dpi_scale
+    }
+
+    fn buffer(&mut self) -> &mut OffscreenBuffer {
+        &mut self.buffer
+    }
+
+    fn fit_buffer(&mut self, fit: CameraFit, internal_resolution: Option<WindowDimensions>, camera: Camera) {
+        let size = match internal_resolution {
+            Some(value) => value,
+            None => fit.buffer_size(camera, self.window_size)
+        };
+        self.fit = fit;
+
+        if self.buffer.width == size.width && self.buffer.height == size.height && !self.buffer.memory.is_null() {
+            return;
+        }
+
+        self.pixels = vec![0; (size.width * size.height) as usize];
+        self.buffer.memory = self.pixels.as_mut_ptr() as *mut c_void;
+        self.buffer.width = size.width;
+        self.buffer.height = size.height;
+        self.buffer.bytes_per_pixel = 4;
+        self.buffer.pitch = size.width * 4;
+
+        unsafe {
+            if !self.context.is_null() {
+                CGContextRelease(self.context);
+            }
+            self.context = CGBitmapContextCreate(
+                self.buffer.memory, size.width as usize, size.height as usize, 8,
+                self.buffer.pitch as usize, self.color_space, BITMAP_INFO);
+        }
+    }
+
+    fn poll_events(&mut self, input: &mut InputController) -> bool {
+        unsafe {
+            let pool = objc_autoreleasePoolPush();
+
+            let distant_past: Id = msg!(Id, class("NSDate\0"), "distantPast");
+            loop {
+                let event: Id = msg!(Id, self.app, "nextEventMatchingMask:untilDate:inMode:dequeue:",
+                    u64::MAX => u64,
+                    distant_past => Id,
+                    NSDefaultRunLoopMode => Id,
+                    1 => i8);
+                if event.is_null() {
+                    break;
+                }
+
+                let kind: u64 = msg!(u64, event, "type");
+                match kind {
+                    // Handled here instead of by the window so keys don't beep
+                    NS_KEY_DOWN | NS_KEY_UP => {
+                        self.process_key(event, kind == NS_KEY_DOWN, input);
+                        continue;
+                    }
+                    NS_FLAGS_CHANGED => {
+                        let flags: u64 = msg!(u64, event, "modifierFlags");
+                        input.shift.is_down = flags & NS_SHIFT_KEY_MASK != 0;
+                        input.ctrl.is_down = flags & NS_CONTROL_KEY_MASK != 0;
+                    }
+                    NS_MOUSE_MOVED | NS_LEFT_MOUSE_DRAGGED | NS_RIGHT_MOUSE_DRAGGED | NS_OTHER_MOUSE_DRAGGED => {
+                        input.mouse_state.pos = self.mouse_position(event);
+                    }
+                    NS_LEFT_MOUSE_DOWN | NS_LEFT_MOUSE_UP => {
+                        input.mouse_state.pos = self.mouse_position(event);
+                        input.mouse_state.left.is_down = kind == NS_LEFT_MOUSE_DOWN;
+                    }
+                    NS_RIGHT_MOUSE_DOWN | NS_RIGHT_MOUSE_UP => {
+                        input.mouse_state.pos = self.mouse_position(event);
+                        input.mouse_state.right.is_down = kind == NS_RIGHT_MOUSE_DOWN;
+                    }
+                    NS_OTHER_MOUSE_DOWN | NS_OTHER_MOUSE_UP => {
+                        input.mouse_state.pos = self.mouse_position(event);
+                        input.mouse_state.middle.is_down = kind == NS_OTHER_MOUSE_DOWN;
+                    }
+                    NS_SCROLL_WHEEL => {
+                        // Roughly lines, one per notch on a mouse wheel
+                        let delta: f64 = msg!(f64, event, "deltaY");
+                        let wheel_delta = (delta * WHEEL_NOTCH).round() as i16;
+                        input.mouse_state.wheel_delta = input.mouse_state.wheel_delta.saturating_add(wheel_delta);
+                    }
+                    _ => {}
+                }
+
+                msg!((), self.app, "sendEvent:", event => Id);
+            }
+            msg!((), self.app, "updateWindows");
+
+            let is_visible: i8 = msg!(i8, self.window, "isVisible");
+            if is_visible == 0 {
+                self.is_running = false;
+            }
+            self.update_window_size();
+
+            objc_autoreleasePoolPop(pool);
+        }
+
+        // TODO: Read gamepads through the GameController framework, until then Macs never have
+        // one connected
+        self.is_running
+    }
+
+    // The layer always shows whole images, so every present sends the full buffer
+    fn present(&mut self, _regions: &[ClipRect]) {
+        if self.context.is_null() || self.buffer.width == 0 || self.buffer.height == 0 {
+            return;
+        }
+
+        let viewport = self.fit.viewport(self.buffer.width, self.buffer.height, self.window_size);
+        // The layer is in points with y going up from the bottom of the window
+        let scale = self.dpi_scale as f64;
+        let frame = CGRect {
+            origin: CGPoint {
+                x: viewport.x as f64 / scale,
+                y: (self.window_size.height as i32 - viewport.y - viewport.height as i32) as f64 / scale
+            },
+            size: CGSize { width: viewport.width as f64 / scale, height: viewport.height as f64 / scale }
+        };
+
+        unsafe {
+            let pool = objc_autoreleasePoolPush();
+
+            // Copied on the next write to the buffer, so the game can draw the next frame while
+            // this one is still on screen
+            let image = CGBitmapContextCreateImage(self.context);
+
+            // Without this the layer animates every change
+            let transaction = class("CATransaction\0");
+            msg!((), transaction, "begin");
+            msg!((), transaction, "setDisableActions:", 1 => i8);
+            msg!((), self.image_layer, "setFrame:", frame => CGRect);
+            msg!((), self.image_layer, "setContents:", image => Id);
+            msg!((), transaction, "commit");
+
+            CGImageRelease(image);
+
+            objc_autoreleasePoolPop(pool);
+        }
+    }
+
+    fn time(&self) -> f64 {
+        self.start.elapsed().as_micros() as f64 / 1000.0
+    }
+}
+
+impl Drop for MacPlatform {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.context.is_null() {
+                CGContextRelease(self.context);
+            }
+            msg!((), self.window, "close");
+        }
+    }
+}
+
+impl MacPlatform {
+    unsafe fn process_key(&mut self, event: Id, is_down: bool, input: &mut InputController) {
+        let key_code: u16 = msg!(u16, event, "keyCode");
+        let is_repeat: i8 = msg!(i8, event, "isARepeat");
+
+        match key_code {
+            KVK_W => input.w.is_down = is_down,
+            KVK_A => input.a.is_down = is_down,
+            KVK_S => input.s.is_down = is_down,
+            KVK_D => input.d.is_down = is_down,
+            KVK_C => input.c.is_down = is_down,
+            KVK_F => input.f.is_down = is_down,
+            KVK_E => input.e.is_down = is_down,
+            KVK_M => input.m.is_down = is_down,
+            KVK_P => input.p.is_down = is_down,
+            KVK_R => input.r.is_down = is_down,
+            KVK_X => input.x.is_down = is_down,
+            KVK_Y => input.y.is_down = is_down,
+            KVK_Z => input.z.is_down = is_down,
+            KVK_UP => input.up.is_down = is_down,
+            KVK_LEFT => input.left.is_down = is_down,
+            KVK_DOWN => input.down.is_down = is_down,
+            KVK_RIGHT => input.right.is_down = is_down,
+            KVK_ESCAPE => input.esc.is_down = is_down,
+            KVK_SPACE => input.space.is_down = is_down,
+            _ => {}
+        }
+
+        if is_down && is_repeat == 0 {
+            if key_code == KVK_F5 {
+                println!("reload");
+                crate::reload_lib();
+            }
+
+            // command + Q, where Windows has alt + F4
+            let flags: u64 = msg!(u64, event, "modifierFlags");
+            if key_code == KVK_Q && flags & NS_COMMAND_KEY_MASK != 0 {
+                self.is_running = false;
+            }
+        }
+    }
+
+    // Keeps the window size in pixels, the scale follows the screen the window is on
+    unsafe fn update_window_size(&mut self) {
+        let view: Id = msg!(Id, self.window, "contentView");
+        let bounds = msg_rect(view, "bounds\0");
+        let scale: f64 = msg!(f64, self.window, "backingScaleFactor");
+
+        self.dpi_scale = scale as f32;
+        self.window_size = WindowDimensions {
+            width: (bounds.size.width * scale).round().max(0.0) as u32,
+            height: (bounds.size.height * scale).round().max(0.0) as u32
+        };
+    }
+
+    // The game works in buffer pixels, which only match the window without a fixed resolution
+    unsafe fn mouse_position(&self, event: Id) -> Vector2u32 {
+        // Points from the bottom left of the content view
+        let location: CGPoint = msg!(CGPoint, event, "locationInWindow");
+        let scale = self.dpi_scale as f64;
+        let x = (location.x * scale) as i32;
+        let y = self.window_size.height as i32 - (location.y * scale) as i32;
+
+        let viewport = self.fit.viewport(self.buffer.width, self.buffer.height, self.window_size);
+        viewport.window_to_buffer(x, y, self.buffer.width, self.buffer.height)
+    }
+}
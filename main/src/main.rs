@@ -1,17 +1,39 @@
 extern crate oxide;
 
+use crate::oxide::*;
+use crate::oxide::actions::ActionMap;
+use crate::oxide::bezier_path::BezierPath;
+use crate::oxide::follower::{FollowMode, PathFollower};
+use crate::oxide::platform::Platform;
+use crate::oxide::scene::{Scene, SCENE_PATH};
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
 use std::mem;
 use std::fs;
 
+#[cfg(windows)]
 pub mod win32;
+#[cfg(target_os = "linux")]
+pub mod x11;
+#[cfg(target_os = "macos")]
+pub mod macos;
+
+// The platform layer is picked when the host is compiled
+#[cfg(windows)]
+type HostPlatform = win32::Win32Platform;
+#[cfg(target_os = "linux")]
+type HostPlatform = x11::X11Platform;
+#[cfg(target_os = "macos")]
+type HostPlatform = macos::MacPlatform;
 
 pub static mut LIBRARY: Option<libloading::Library> = None;
 
+static mut GAME_UPDATE_AND_RENDER: Option<libloading::Symbol<unsafe extern fn(game_state: &mut GameState, input_controller: &mut InputController, buffer: &mut OffscreenBuffer) -> ()>> = None;
+
 static LIB_PATH: &str = "../oxide/target/debug/";
 
 pub fn main() {
     load_lib();
-    win32::start_program();
+    unsafe { run::<HostPlatform>(); }
 }
 
 pub fn reload_lib() {
@@ -19,13 +41,111 @@ pub fn reload_lib() {
     load_lib();
 }
 
+// Loads a copy of the library so the original can be rebuilt while the game runs
 fn load_lib() {
-    fs::copy(format!("{}/oxide.dll", LIB_PATH), format!("{}/oxide_temp.dll", LIB_PATH)).expect("Unable to copy dll to temp");
+    let lib = format!("{}/{}oxide{}", LIB_PATH, DLL_PREFIX, DLL_SUFFIX);
+    let temp = format!("{}/{}oxide_temp{}", LIB_PATH, DLL_PREFIX, DLL_SUFFIX);
+    fs::copy(lib, &temp).expect("Unable to copy dll to temp");
 
     unsafe {
-        LIBRARY = match libloading::Library::new(format!("{}/oxide_temp.dll", LIB_PATH)) {
+        LIBRARY = match libloading::Library::new(temp) {
             Ok(value) => Some(value),
             Err(error) => panic!("Unable to load oxide lib: {}", error)
         };
     }
 }
+
+unsafe fn run<P: Platform>() {
+    let mut platform = match P::create_window("Oxide") {
+        Ok(value) => value,
+        Err(error) => panic!("Unable to create window: {}", error)
+    };
+
+    let mut game_state = GameState::default();
+
+    game_state.camera = Camera::new(0.0, 0.0, 16.0, 9.0);
+    platform.fit_buffer(game_state.camera_fit, game_state.internal_resolution, game_state.camera);
+
+    match Scene::load(SCENE_PATH) {
+        Ok(scene) => scene.apply(&mut game_state),
+        Err(error) => {
+            println!("Using the demo scene, unable to load {}: {}", SCENE_PATH, error);
+            game_state.curves[0] = Some(BezierCurve::new(
+                Vector2 { x: 0.0, y: 0.5 },
+                Vector2 { x: 1.0, y: 0.0 },
+                Vector2 { x: 1.0, y: 1.6 },
+                Vector2 { x: 0.0, y: 2.0 }
+            ));
+        }
+    }
+    game_state.circle_follower = game_state.curves[0].map(|curve| PathFollower::new(BezierPath::new(curve), 0.001, FollowMode::PingPong));
+
+    game_state.actions = match ActionMap::load(CONFIG_PATH) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Using default bindings, unable to load {}: {}", CONFIG_PATH, error);
+            ActionMap::default_bindings()
+        }
+    };
+
+    let mut time_last_frame: f64 = 0.0;
+
+    let mut input = InputController::default();
+
+    loop {
+        let mut new_input = input;
+        // The wheel only counts the notches turned since the last frame
+        new_input.mouse_state.wheel_delta = 0;
+        if !platform.poll_events(&mut new_input) {
+            break;
+        }
+
+        input.update(new_input);
+
+        platform.fit_buffer(game_state.camera_fit, game_state.internal_resolution, game_state.camera);
+
+        let buffer = platform.buffer();
+        let buffer_size = WindowDimensions { width: buffer.width, height: buffer.height };
+        game_state.camera_fit.apply(&mut game_state.camera, buffer_size);
+        game_state.dpi_scale = platform.dpi_scale();
+
+        game_update_and_render(&mut game_state, &mut input, platform.buffer());
+
+        platform.present(game_state.dirty_regions.regions());
+
+        let current_time = platform.time();
+        game_state.delta_time = (current_time - time_last_frame) as f32;
+        time_last_frame = current_time;
+    }
+
+    if let Err(error) = Scene::from_game_state(&game_state).save(SCENE_PATH) {
+        eprintln!("Unable to save the scene to {}: {}", SCENE_PATH, error);
+    }
+}
+
+unsafe fn game_update_and_render(game_state: &mut GameState, input_controller: &mut InputController, buffer: &mut OffscreenBuffer) {
+    match &GAME_UPDATE_AND_RENDER {
+        Some(func) => {
+            func(game_state, input_controller, buffer);
+        },
+        None => {
+            let lib = match &LIBRARY {
+                Some(value) => value,
+                None => {
+                    eprintln!("Library not initialized");
+                    return
+                }
+            };
+
+            let func: libloading::Symbol<unsafe extern fn(game_state: &mut GameState, input_controller: &mut InputController, buffer: &mut OffscreenBuffer) -> ()> =
+                match lib.get(b"game_update_and_render") {
+                    Ok(value) => value,
+                    Err(error) => panic!("Unable to get game_update_and_render from oxide: {}", error)
+                };
+
+            GAME_UPDATE_AND_RENDER = Some(func.clone());
+
+            func(game_state, input_controller, buffer);
+        }
+    };
+}
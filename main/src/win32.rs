@@ -1,10 +1,10 @@
 use crate::oxide::*;
-use crate::LIBRARY;
+use crate::oxide::platform::Platform;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
-use std::ffi::c_void;
+use std::ffi::{c_void, CString};
 use std::mem::size_of;
-use std::ptr::null_mut;
+use std::ptr::{addr_of, addr_of_mut, null_mut};
 use windows::core::*;
 use windows::Win32::Foundation::*;
 use windows::Win32::Graphics::Gdi::*;
@@ -12,94 +12,109 @@ use windows::Win32::System::LibraryLoader::GetModuleHandleA;
 use windows::Win32::System::Memory::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
 use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use windows::Win32::UI::Input::XboxController::*;
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
 
-static mut GAME_UPDATE_AND_RENDER: Option<libloading::Symbol<unsafe extern fn(game_state: &mut GameState, input_controller: &mut InputController, buffer: &mut OffscreenBuffer) -> ()>> = None;
 static mut IS_RUNNING: bool = true;
-// TODO: Figure out how to do this without typing everything out
-// default does not work on statics!
-static mut BACK_BUFFER: OffscreenBuffer = OffscreenBuffer {
-    info: BITMAPINFO {
-        bmiHeader: BITMAPINFOHEADER {
-            biSize: 0,
-            biWidth: 0,
-            biHeight: 0,
-            biPlanes: 0,
-            biBitCount: 0,
-            biCompression: 0,
-            biSizeImage: 0,
-            biXPelsPerMeter: 0,
-            biYPelsPerMeter: 0,
-            biClrUsed: 0,
-            biClrImportant: 0
-        },
-        bmiColors: [RGBQUAD {
-            rgbBlue: 0,
-            rgbGreen: 0,
-            rgbRed: 0,
-            rgbReserved: 0
-        }]
+// Set by fit_buffer every frame so the window procedure can size the buffer too
+static mut INTERNAL_RESOLUTION: Option<WindowDimensions> = None;
+static mut CAMERA_FIT: CameraFit = CameraFit::FitHeight;
+// The game's camera as of the last frame, the fit needs its shape when the window is resized
+static mut CAMERA: Option<Camera> = None;
+static mut BACK_BUFFER: OffscreenBuffer = OffscreenBuffer::empty();
+// Describes the back buffer's pixels to StretchDIBits, kept in step with it by resize_dib_section
+static mut BITMAP_INFO: BITMAPINFO = BITMAPINFO {
+    bmiHeader: BITMAPINFOHEADER {
+        biSize: 0,
+        biWidth: 0,
+        biHeight: 0,
+        biPlanes: 0,
+        biBitCount: 0,
+        biCompression: 0,
+        biSizeImage: 0,
+        biXPelsPerMeter: 0,
+        biYPelsPerMeter: 0,
+        biClrUsed: 0,
+        biClrImportant: 0
     },
-    memory: null_mut(),
-    width: 0,
-    height: 0,
-    bytes_per_pixel: 0,
-    pitch: 0,
+    bmiColors: [RGBQUAD {
+        rgbBlue: 0,
+        rgbGreen: 0,
+        rgbRed: 0,
+        rgbReserved: 0
+    }]
 };
 
-pub fn start_program() {
-    unsafe {
-        let window: HWND = create_window().unwrap();
-
-        let window_size = get_window_dimensions(window);
-
-        resize_dib_section(&mut BACK_BUFFER, window_size.width, window_size.height)
-            .expect("Unable to resize DIB section");
-
-        let device_context: HDC = GetDC(window);
+pub struct Win32Platform {
+    window: HWND,
+    device_context: HDC
+}
 
-        let mut game_state = GameState::default();
+impl Platform for Win32Platform {
+    fn create_window(title: &str) -> std::result::Result<Self, String> {
+        unsafe {
+            let window = create_window(title).map_err(|error| error.to_string())?;
 
-        game_state.camera = Camera::new(0.0, 0.0, 16.0, 9.0);
-        game_state.curves[0] = Some(BezierCurve::new(
-            Vector2 { x: 0.0, y: 0.5 },
-            Vector2 { x: 1.0, y: 0.0 },
-            Vector2 { x: 1.0, y: 1.6 },
-            Vector2 { x: 0.0, y: 2.0 }
-        ));
+            let window_size = get_window_dimensions(window);
+            fit_back_buffer(window_size);
 
-        let mut time_last_frame: f64 = 0.0;
+            let device_context: HDC = GetDC(window);
 
-        let mut input = InputController::default();
+            Ok(Win32Platform { window, device_context })
+        }
+    }
 
-        while IS_RUNNING {
-            let mut new_input = input;
-            process_pending_messages(&mut new_input);
+    fn window_dimensions(&self) -> WindowDimensions {
+        unsafe { get_window_dimensions(self.window) }
+    }
 
-            input.update(new_input);
+    fn dpi_scale(&self) -> f32 {
+        unsafe { GetDpiForWindow(self.window) as f32 / 96.0 }
+    }
 
-            let dimensions = get_window_dimensions(window);
+    fn buffer(&mut self) -> &mut OffscreenBuffer {
+        unsafe { &mut *addr_of_mut!(BACK_BUFFER) }
+    }
 
-            game_state.camera.y_scale = dimensions.height as f32 / game_state.camera.height;
-            game_state.camera.width = dimensions.width as f32 / game_state.camera.y_scale;
+    fn fit_buffer(&mut self, fit: CameraFit, internal_resolution: Option<WindowDimensions>, camera: Camera) {
+        unsafe {
+            INTERNAL_RESOLUTION = internal_resolution;
+            CAMERA_FIT = fit;
+            CAMERA = Some(camera);
+            if fit_back_buffer(self.window_dimensions()) {
+                // Repaint the whole window so the letterbox bars are drawn at their new size
+                InvalidateRect(self.window, None, FALSE);
+            }
+        }
+    }
 
-            game_update_and_render(&mut game_state, &mut input, &mut BACK_BUFFER);
+    fn poll_events(&mut self, input: &mut InputController) -> bool {
+        unsafe {
+            process_pending_messages(input);
+            process_gamepad_input(input);
+            IS_RUNNING
+        }
+    }
 
-            copy_buffer_to_window(
+    fn present(&mut self, regions: &[ClipRect]) {
+        unsafe {
+            let dimensions = self.window_dimensions();
+            copy_dirty_regions_to_window(
                 &mut BACK_BUFFER,
-                device_context,
+                self.device_context,
                 dimensions.width,
-                dimensions.height)
-                .expect("Unable to update window");
-
-            let start = SystemTime::now();
-            let current_time = start.duration_since(UNIX_EPOCH).expect("Time went backwards").as_micros() as f64 / 1000.0;
-            game_state.delta_time = (current_time - time_last_frame) as f32;
-            time_last_frame = current_time;
+                dimensions.height,
+                regions);
         }
     }
+
+    fn time(&self) -> f64 {
+        let start = SystemTime::now();
+        start.duration_since(UNIX_EPOCH).expect("Time went backwards").as_micros() as f64 / 1000.0
+    }
 }
 
-unsafe fn create_window() -> Result<HWND> {
+unsafe fn create_window(title: &str) -> Result<HWND> {
     let instance: HMODULE = GetModuleHandleA(None)?;
     debug_assert!(instance.0 != 0);
 
@@ -117,10 +132,12 @@ unsafe fn create_window() -> Result<HWND> {
     let atom: u16 = RegisterClassA(&wc);
     debug_assert!(atom != 0);
 
+    let window_title = CString::new(title).unwrap_or_default();
+
     Ok(CreateWindowExA(
         WINDOW_EX_STYLE::default(),
         class_name,
-        s!("Oxide"),
+        PCSTR(window_title.as_ptr() as *const u8),
         WS_OVERLAPPEDWINDOW | WS_VISIBLE,
         CW_USEDEFAULT,
         CW_USEDEFAULT,
@@ -164,8 +181,7 @@ extern "system" fn wnd_proc(window: HWND, message: u32, w_param: WPARAM, l_param
                 println!("WM_SIZE");
 
                 let dimensions = get_window_dimensions(window);
-                resize_dib_section(&mut BACK_BUFFER, dimensions.width, dimensions.height)
-                    .expect("Unable to resize dib section");
+                fit_back_buffer(dimensions);
 
                 LRESULT(0)
             }
@@ -192,12 +208,24 @@ unsafe fn process_pending_messages(input: &mut InputController) {
                     'A' => input.a.is_down = is_down,
                     'S' => input.s.is_down = is_down,
                     'D' => input.d.is_down = is_down,
+                    'C' => input.c.is_down = is_down,
+                    'F' => input.f.is_down = is_down,
+                    'E' => input.e.is_down = is_down,
+                    'M' => input.m.is_down = is_down,
+                    'P' => input.p.is_down = is_down,
+                    'R' => input.r.is_down = is_down,
+                    'X' => input.x.is_down = is_down,
+                    'Y' => input.y.is_down = is_down,
+                    'Z' => input.z.is_down = is_down,
                     _ => match VIRTUAL_KEY(vk_code as u16) {
                         VK_UP => input.up.is_down = is_down,
                         VK_LEFT => input.left.is_down = is_down,
                         VK_DOWN => input.down.is_down = is_down,
                         VK_RIGHT => input.right.is_down = is_down,
                         VK_ESCAPE => input.esc.is_down = is_down,
+                        VK_SPACE => input.space.is_down = is_down,
+                        VK_SHIFT => input.shift.is_down = is_down,
+                        VK_CONTROL => input.ctrl.is_down = is_down,
                         _ => {}
                     }
                 }
@@ -219,10 +247,10 @@ unsafe fn process_pending_messages(input: &mut InputController) {
                 let mut mouse_point = POINT::default();
                 GetCursorPos(&mut mouse_point).expect("Unable to get cursor position");
                 ScreenToClient(message.hwnd, &mut mouse_point);
-                input.mouse_state.pos = Vector2u32 {
-                    x: mouse_point.x as u32,
-                    y: mouse_point.y as u32
-                };
+
+                // The game works in buffer pixels, which only match the window without a fixed resolution
+                let viewport = CAMERA_FIT.viewport(BACK_BUFFER.width, BACK_BUFFER.height, get_window_dimensions(message.hwnd));
+                input.mouse_state.pos = viewport.window_to_buffer(mouse_point.x, mouse_point.y, BACK_BUFFER.width, BACK_BUFFER.height);
             }
             WM_LBUTTONDOWN => input.mouse_state.left.is_down = true,
             WM_LBUTTONUP => input.mouse_state.left.is_down = false,
@@ -232,7 +260,7 @@ unsafe fn process_pending_messages(input: &mut InputController) {
             WM_MBUTTONUP => input.mouse_state.middle.is_down = false,
             WM_MOUSEWHEEL => {
                 let wheel_delta = (message.wParam.0 >> 16) as i16;
-                input.mouse_state.wheel_delta = wheel_delta;
+                input.mouse_state.wheel_delta = input.mouse_state.wheel_delta.saturating_add(wheel_delta);
             }
             _ => {
                 TranslateMessage(&message);
@@ -242,6 +270,51 @@ unsafe fn process_pending_messages(input: &mut InputController) {
     }
 }
 
+// Only the first controller is read
+unsafe fn process_gamepad_input(input: &mut InputController) {
+    let mut state = XINPUT_STATE::default();
+    if XInputGetState(0, &mut state) != ERROR_SUCCESS.0 {
+        input.gamepad = GamepadState::default();
+        return;
+    }
+
+    let pad = state.Gamepad;
+    let is_down = |flag: XINPUT_GAMEPAD_BUTTON_FLAGS| pad.wButtons.0 & flag.0 != 0;
+
+    input.gamepad.is_connected = true;
+    input.gamepad.a.is_down = is_down(XINPUT_GAMEPAD_A);
+    input.gamepad.b.is_down = is_down(XINPUT_GAMEPAD_B);
+    input.gamepad.x.is_down = is_down(XINPUT_GAMEPAD_X);
+    input.gamepad.y.is_down = is_down(XINPUT_GAMEPAD_Y);
+    input.gamepad.start.is_down = is_down(XINPUT_GAMEPAD_START);
+    input.gamepad.back.is_down = is_down(XINPUT_GAMEPAD_BACK);
+    input.gamepad.left_shoulder.is_down = is_down(XINPUT_GAMEPAD_LEFT_SHOULDER);
+    input.gamepad.right_shoulder.is_down = is_down(XINPUT_GAMEPAD_RIGHT_SHOULDER);
+    input.gamepad.dpad_up.is_down = is_down(XINPUT_GAMEPAD_DPAD_UP);
+    input.gamepad.dpad_left.is_down = is_down(XINPUT_GAMEPAD_DPAD_LEFT);
+    input.gamepad.dpad_down.is_down = is_down(XINPUT_GAMEPAD_DPAD_DOWN);
+    input.gamepad.dpad_right.is_down = is_down(XINPUT_GAMEPAD_DPAD_RIGHT);
+    input.gamepad.left_stick = Vector2 {
+        x: normalize_stick(pad.sThumbLX, XINPUT_GAMEPAD_LEFT_THUMB_DEADZONE.0),
+        y: -normalize_stick(pad.sThumbLY, XINPUT_GAMEPAD_LEFT_THUMB_DEADZONE.0)
+    };
+    input.gamepad.right_stick = Vector2 {
+        x: normalize_stick(pad.sThumbRX, XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE.0),
+        y: -normalize_stick(pad.sThumbRY, XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE.0)
+    };
+}
+
+// Y is flipped by the caller since screen space is Y-down
+fn normalize_stick(value: i16, deadzone: u16) -> f32 {
+    let magnitude = (value as f32).abs();
+    if magnitude < deadzone as f32 {
+        return 0.0;
+    }
+
+    let normalized = (magnitude - deadzone as f32) / (i16::MAX as f32 - deadzone as f32);
+    normalized.min(1.0) * (value as f32).signum()
+}
+
 unsafe fn resize_dib_section(buffer: &mut OffscreenBuffer, width: u32, height: u32) -> Result<()> {
     if (&buffer).memory != null_mut() {
         VirtualFree((&buffer).memory, 0, MEM_RELEASE)
@@ -252,14 +325,14 @@ unsafe fn resize_dib_section(buffer: &mut OffscreenBuffer, width: u32, height: u
     (*buffer).height = height;
     (*buffer).bytes_per_pixel = 4;
 
-    (*buffer).info = BITMAPINFO::default();
+    BITMAP_INFO = BITMAPINFO::default();
 
-    (*buffer).info.bmiHeader.biSize = size_of::<BITMAPINFOHEADER>() as u32;
-    (*buffer).info.bmiHeader.biWidth = (&buffer).width as i32;
-    (*buffer).info.bmiHeader.biHeight = -((&buffer).height as i32);
-    (*buffer).info.bmiHeader.biPlanes = 1;
-    (*buffer).info.bmiHeader.biBitCount = 32;
-    (*buffer).info.bmiHeader.biCompression = 0;
+    BITMAP_INFO.bmiHeader.biSize = size_of::<BITMAPINFOHEADER>() as u32;
+    BITMAP_INFO.bmiHeader.biWidth = (&buffer).width as i32;
+    BITMAP_INFO.bmiHeader.biHeight = -((&buffer).height as i32);
+    BITMAP_INFO.bmiHeader.biPlanes = 1;
+    BITMAP_INFO.bmiHeader.biBitCount = 32;
+    BITMAP_INFO.bmiHeader.biCompression = 0;
 
     let bitmap_memory_size = (width * height) * (*buffer).bytes_per_pixel;
     (*buffer).memory = VirtualAlloc(
@@ -273,19 +346,69 @@ unsafe fn resize_dib_section(buffer: &mut OffscreenBuffer, width: u32, height: u
     Ok(())
 }
 
+// Resizes the back buffer to the fixed internal resolution, or to the window without one.
+// Returns whether the size changed.
+unsafe fn fit_back_buffer(window: WindowDimensions) -> bool {
+    let size = match (INTERNAL_RESOLUTION, CAMERA) {
+        (Some(value), _) => value,
+        (None, Some(camera)) => CAMERA_FIT.buffer_size(camera, window),
+        (None, None) => window
+    };
+
+    if BACK_BUFFER.width == size.width && BACK_BUFFER.height == size.height && !BACK_BUFFER.memory.is_null() {
+        return false;
+    }
+
+    resize_dib_section(&mut BACK_BUFFER, size.width, size.height)
+        .expect("Unable to resize DIB section");
+    true
+}
+
+// Scales the buffer into the window, see CameraFit::viewport, and fills the rest of the window
+// with black
 unsafe fn copy_buffer_to_window(buffer: &mut OffscreenBuffer, device_context: HDC, width: u32, height: u32)
     -> Result<()> {
+    let viewport = CAMERA_FIT.viewport(buffer.width, buffer.height, WindowDimensions { width, height });
+    let right = viewport.x + viewport.width as i32;
+    let bottom = viewport.y + viewport.height as i32;
+
+    PatBlt(device_context, 0, 0, width as i32, viewport.y, BLACKNESS);
+    PatBlt(device_context, 0, bottom, width as i32, height as i32 - bottom, BLACKNESS);
+    PatBlt(device_context, 0, viewport.y, viewport.x, viewport.height as i32, BLACKNESS);
+    PatBlt(device_context, right, viewport.y, width as i32 - right, viewport.height as i32, BLACKNESS);
+
     StretchDIBits(
         device_context,
-        0, 0, width as i32, height as i32,
+        viewport.x, viewport.y, viewport.width as i32, viewport.height as i32,
         0, 0, (*buffer).width as i32, (*buffer).height as i32,
         Some((*buffer).memory as *const c_void),
-        &(*buffer).info,
+        &*addr_of!(BITMAP_INFO),
         DIB_RGB_COLORS, SRCCOPY);
 
     Ok(())
 }
 
+// Presents only the parts of the buffer that were redrawn, by clipping the full copy to each region
+unsafe fn copy_dirty_regions_to_window(buffer: &mut OffscreenBuffer, device_context: HDC, width: u32, height: u32, regions: &[ClipRect]) {
+    if buffer.width == 0 || buffer.height == 0 {
+        return;
+    }
+
+    let viewport = CAMERA_FIT.viewport(buffer.width, buffer.height, WindowDimensions { width, height });
+
+    for region in regions {
+        let rect = viewport.buffer_to_window(*region, buffer.width, buffer.height);
+
+        SaveDC(device_context);
+        IntersectClipRect(device_context, rect.x0, rect.y0, rect.x1, rect.y1);
+
+        copy_buffer_to_window(buffer, device_context, width, height)
+            .expect("Unable to update window");
+
+        RestoreDC(device_context, -1);
+    }
+}
+
 unsafe fn get_window_dimensions(window: HWND) -> WindowDimensions {
     let mut client_rect: RECT = Default::default();
 
@@ -302,30 +425,3 @@ unsafe fn get_window_dimensions(window: HWND) -> WindowDimensions {
         }
     }
 }
-
-unsafe fn game_update_and_render(game_state: &mut GameState, input_controller: &mut InputController, buffer: &mut OffscreenBuffer) {
-    match &GAME_UPDATE_AND_RENDER {
-        Some(func) => {
-            func(game_state, input_controller, buffer);
-        },
-        None => {
-            let lib = match &LIBRARY {
-                Some(value) => value,
-                None => {
-                    eprintln!("Library not initialized");
-                    return
-                }
-            };
-
-            let func: libloading::Symbol<unsafe extern fn(game_state: &mut GameState, input_controller: &mut InputController, buffer: &mut OffscreenBuffer) -> ()> =
-                match lib.get(b"game_update_and_render") {
-                    Ok(value) => value,
-                    Err(error) => panic!("Unable to get game_update_and_render from oxide: {}", error)
-                };
-
-            GAME_UPDATE_AND_RENDER = Some(func.clone());
-
-            func(game_state, input_controller, buffer);
-        }
-    };
-}
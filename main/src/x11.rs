@@ -0,0 +1,521 @@
+use crate::oxide::*;
+use crate::oxide::platform::Platform;
+use std::ffi::{c_char, c_int, c_long, c_uint, c_ulong, c_void, CStr, CString};
+use std::ptr::null_mut;
+use std::time::Instant;
+
+// Only the parts of Xlib the platform uses, declared by hand so building needs nothing but
+// libX11. Wayland sessions run it through XWayland.
+#[repr(C)]
+struct Display {
+    _private: [u8; 0]
+}
+
+#[repr(C)]
+struct Visual {
+    _private: [u8; 0]
+}
+
+#[repr(C)]
+struct XImage {
+    _private: [u8; 0]
+}
+
+type Window = c_ulong;
+type Atom = c_ulong;
+type KeySym = c_ulong;
+type Gc = *mut c_void;
+
+// Key, button and motion events share this layout, detail is the keycode, button or hint
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct XInputEvent {
+    kind: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: *mut Display,
+    window: Window,
+    root: Window,
+    subwindow: Window,
+    time: c_ulong,
+    x: c_int,
+    y: c_int,
+    x_root: c_int,
+    y_root: c_int,
+    state: c_uint,
+    detail: c_uint,
+    same_screen: c_int
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct XConfigureEvent {
+    kind: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: *mut Display,
+    event: Window,
+    window: Window,
+    x: c_int,
+    y: c_int,
+    width: c_int,
+    height: c_int,
+    border_width: c_int,
+    above: Window,
+    override_redirect: c_int
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct XExposeEvent {
+    kind: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: *mut Display,
+    window: Window,
+    x: c_int,
+    y: c_int,
+    width: c_int,
+    height: c_int,
+    count: c_int
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct XClientMessageEvent {
+    kind: c_int,
+    serial: c_ulong,
+    send_event: c_int,
+    display: *mut Display,
+    window: Window,
+    message_type: Atom,
+    format: c_int,
+    data: [c_long; 5]
+}
+
+#[repr(C)]
+union XEvent {
+    kind: c_int,
+    input: XInputEvent,
+    configure: XConfigureEvent,
+    expose: XExposeEvent,
+    client: XClientMessageEvent,
+    pad: [c_long; 24]
+}
+
+#[link(name = "X11")]
+extern "C" {
+    fn XOpenDisplay(name: *const c_char) -> *mut Display;
+    fn XCloseDisplay(display: *mut Display) -> c_int;
+    fn XDefaultScreen(display: *mut Display) -> c_int;
+    fn XRootWindow(display: *mut Display, screen: c_int) -> Window;
+    fn XBlackPixel(display: *mut Display, screen: c_int) -> c_ulong;
+    fn XDefaultVisual(display: *mut Display, screen: c_int) -> *mut Visual;
+    fn XDefaultDepth(display: *mut Display, screen: c_int) -> c_int;
+    fn XDefaultGC(display: *mut Display, screen: c_int) -> Gc;
+    fn XCreateSimpleWindow(
+        display: *mut Display, parent: Window,
+        x: c_int, y: c_int, width: c_uint, height: c_uint,
+        border_width: c_uint, border: c_ulong, background: c_ulong) -> Window;
+    fn XStoreName(display: *mut Display, window: Window, name: *const c_char) -> c_int;
+    fn XSelectInput(display: *mut Display, window: Window, mask: c_long) -> c_int;
+    fn XMapWindow(display: *mut Display, window: Window) -> c_int;
+    fn XInternAtom(display: *mut Display, name: *const c_char, only_if_exists: c_int) -> Atom;
+    fn XSetWMProtocols(display: *mut Display, window: Window, protocols: *mut Atom, count: c_int) -> c_int;
+    fn XkbSetDetectableAutoRepeat(display: *mut Display, detectable: c_int, supported: *mut c_int) -> c_int;
+    fn XResourceManagerString(display: *mut Display) -> *const c_char;
+    fn XPending(display: *mut Display) -> c_int;
+    fn XNextEvent(display: *mut Display, event: *mut XEvent) -> c_int;
+    fn XLookupKeysym(event: *mut XInputEvent, index: c_int) -> KeySym;
+    fn XCreateImage(
+        display: *mut Display, visual: *mut Visual, depth: c_uint, format: c_int, offset: c_int,
+        data: *mut c_char, width: c_uint, height: c_uint, bitmap_pad: c_int, bytes_per_line: c_int) -> *mut XImage;
+    fn XPutImage(
+        display: *mut Display, drawable: Window, gc: Gc, image: *mut XImage,
+        src_x: c_int, src_y: c_int, dest_x: c_int, dest_y: c_int, width: c_uint, height: c_uint) -> c_int;
+    fn XSetForeground(display: *mut Display, gc: Gc, color: c_ulong) -> c_int;
+    fn XFillRectangle(display: *mut Display, drawable: Window, gc: Gc, x: c_int, y: c_int, width: c_uint, height: c_uint) -> c_int;
+    fn XFree(data: *mut c_void) -> c_int;
+    fn XFlush(display: *mut Display) -> c_int;
+}
+
+const KEY_PRESS: c_int = 2;
+const KEY_RELEASE: c_int = 3;
+const BUTTON_PRESS: c_int = 4;
+const BUTTON_RELEASE: c_int = 5;
+const MOTION_NOTIFY: c_int = 6;
+const EXPOSE: c_int = 12;
+const DESTROY_NOTIFY: c_int = 17;
+const CONFIGURE_NOTIFY: c_int = 22;
+const CLIENT_MESSAGE: c_int = 33;
+
+const EVENT_MASK: c_long = (1 << 0) // KeyPress
+    | (1 << 1) // KeyRelease
+    | (1 << 2) // ButtonPress
+    | (1 << 3) // ButtonRelease
+    | (1 << 6) // PointerMotion
+    | (1 << 15) // Exposure
+    | (1 << 17); // StructureNotify
+
+const Z_PIXMAP: c_int = 2;
+const MOD1_MASK: c_uint = 1 << 3;
+
+const XK_SPACE: KeySym = 0x0020;
+const XK_ESCAPE: KeySym = 0xFF1B;
+const XK_LEFT: KeySym = 0xFF51;
+const XK_UP: KeySym = 0xFF52;
+const XK_RIGHT: KeySym = 0xFF53;
+const XK_DOWN: KeySym = 0xFF54;
+const XK_F4: KeySym = 0xFFC1;
+const XK_F5: KeySym = 0xFFC2;
+const XK_SHIFT_L: KeySym = 0xFFE1;
+const XK_SHIFT_R: KeySym = 0xFFE2;
+const XK_CONTROL_L: KeySym = 0xFFE3;
+const XK_CONTROL_R: KeySym = 0xFFE4;
+
+// One notch of the wheel, the same as WHEEL_DELTA on Windows
+static WHEEL_NOTCH: i16 = 120;
+// Xlib doesn't pick a size for new windows
+static DEFAULT_WINDOW_SIZE: WindowDimensions = WindowDimensions { width: 1280, height: 720 };
+
+pub struct X11Platform {
+    display: *mut Display,
+    window: Window,
+    gc: Gc,
+    visual: *mut Visual,
+    depth: c_int,
+    black: c_ulong,
+    delete_window: Atom,
+    window_size: WindowDimensions,
+    dpi_scale: f32,
+    fit: CameraFit,
+    // The back buffer's memory, the buffer points into it
+    pixels: Vec<u32>,
+    buffer: OffscreenBuffer,
+    // Window sized copy of the buffer scaled to the viewport, Xlib can't scale while drawing an
+    // image so it's only filled when the viewport and the buffer differ in size
+    scaled: Vec<u32>,
+    // Set when the whole window has to be drawn again, like after a resize or an expose
+    repaint: bool,
+    // By keycode, to tell a key being pressed from it repeating
+    keys_down: [bool; 256],
+    start: Instant,
+    is_running: bool
+}
+
+impl Platform for X11Platform {
+    fn create_window(title: &str) -> Result<Self, String> {
+        unsafe {
+            let display = XOpenDisplay(null_mut());
+            if display.is_null() {
+                return Err("Unable to open the X display, is DISPLAY set?".to_string());
+            }
+
+            let screen = XDefaultScreen(display);
+            let black = XBlackPixel(display, screen);
+            let depth = XDefaultDepth(display, screen);
+            if depth != 24 && depth != 32 {
+                XCloseDisplay(display);
+                return Err(format!("Only 24 and 32 bit displays are supported, this one is {} bit", depth));
+            }
+
+            let window = XCreateSimpleWindow(
+                display, XRootWindow(display, screen),
+                0, 0, DEFAULT_WINDOW_SIZE.width, DEFAULT_WINDOW_SIZE.height,
+                0, black, black);
+
+            let window_title = CString::new(title).unwrap_or_default();
+            XStoreName(display, window, window_title.as_ptr());
+            XSelectInput(display, window, EVENT_MASK);
+
+            // Closing the window asks first instead of dropping the connection
+            let mut delete_window = XInternAtom(display, c"WM_DELETE_WINDOW".as_ptr(), 0);
+            XSetWMProtocols(display, window, &mut delete_window, 1);
+
+            // Held keys repeat presses without the releases in between
+            XkbSetDetectableAutoRepeat(display, 1, null_mut());
+
+            XMapWindow(display, window);
+            XFlush(display);
+
+            Ok(X11Platform {
+                display,
+                window,
+                gc: XDefaultGC(display, screen),
+                visual: XDefaultVisual(display, screen),
+                depth,
+                black,
+                delete_window,
+                window_size: DEFAULT_WINDOW_SIZE,
+                dpi_scale: xft_dpi(display).map_or(1.0, |dpi| dpi / 96.0),
+                fit: CameraFit::default(),
+                pixels: Vec::new(),
+                buffer: OffscreenBuffer::empty(),
+                scaled: Vec::new(),
+                repaint: true,
+                keys_down: [false; 256],
+                start: Instant::now(),
+                is_running: true
+            })
+        }
+    }
+
+    fn window_dimensions(&self) -> WindowDimensions {
+        self.window_size
+    }
+
+    fn dpi_scale(&self) -> f32 {
+        self.dpi_scale
+    }
+
+    fn buffer(&mut self) -> &mut OffscreenBuffer {
+        &mut self.buffer
+    }
+
+    fn fit_buffer(&mut self, fit: CameraFit, internal_resolution: Option<WindowDimensions>, camera: Camera) {
+        let size = match internal_resolution {
+            Some(value) => value,
+            None => fit.buffer_size(camera, self.window_size)
+        };
+        if fit != self.fit {
+            self.repaint = true;
+        }
+        self.fit = fit;
+
+        if self.buffer.width == size.width && self.buffer.height == size.height && !self.buffer.memory.is_null() {
+            return;
+        }
+
+        self.pixels = vec![0; (size.width * size.height) as usize];
+        self.buffer.memory = self.pixels.as_mut_ptr() as *mut c_void;
+        self.buffer.width = size.width;
+        self.buffer.height = size.height;
+        self.buffer.bytes_per_pixel = 4;
+        self.buffer.pitch = size.width * 4;
+        // Redraw the letterbox bars at their new size
+        self.repaint = true;
+    }
+
+    fn poll_events(&mut self, input: &mut InputController) -> bool {
+        unsafe {
+            while XPending(self.display) > 0 {
+                let mut event = XEvent { pad: [0; 24] };
+                XNextEvent(self.display, &mut event);
+
+                match event.kind {
+                    KEY_PRESS | KEY_RELEASE => self.process_key(&mut event.input, input),
+                    MOTION_NOTIFY => input.mouse_state.pos = self.window_to_buffer(event.input.x, event.input.y),
+                    BUTTON_PRESS | BUTTON_RELEASE => {
+                        let is_down = event.kind == BUTTON_PRESS;
+                        input.mouse_state.pos = self.window_to_buffer(event.input.x, event.input.y);
+                        match event.input.detail {
+                            1 => input.mouse_state.left.is_down = is_down,
+                            2 => input.mouse_state.middle.is_down = is_down,
+                            3 => input.mouse_state.right.is_down = is_down,
+                            // The wheel comes as buttons, one press and release per notch
+                            4 if is_down => input.mouse_state.wheel_delta = input.mouse_state.wheel_delta.saturating_add(WHEEL_NOTCH),
+                            5 if is_down => input.mouse_state.wheel_delta = input.mouse_state.wheel_delta.saturating_sub(WHEEL_NOTCH),
+                            _ => {}
+                        }
+                    }
+                    CONFIGURE_NOTIFY => {
+                        let size = WindowDimensions {
+                            width: event.configure.width.max(0) as u32,
+                            height: event.configure.height.max(0) as u32
+                        };
+                        if size != self.window_size {
+                            self.window_size = size;
+                            self.repaint = true;
+                        }
+                    }
+                    // Only the last of a batch of exposes, the whole window is drawn anyway
+                    EXPOSE if event.expose.count == 0 => self.repaint = true,
+                    CLIENT_MESSAGE if event.client.data[0] as Atom == self.delete_window => self.is_running = false,
+                    DESTROY_NOTIFY => self.is_running = false,
+                    _ => {}
+                }
+            }
+        }
+
+        // TODO: Read gamepads from /dev/input, until then Linux never has one connected
+        self.is_running
+    }
+
+    fn present(&mut self, regions: &[ClipRect]) {
+        if self.buffer.width == 0 || self.buffer.height == 0 || self.window_size.width == 0 || self.window_size.height == 0 {
+            return;
+        }
+
+        let viewport = self.fit.viewport(self.buffer.width, self.buffer.height, self.window_size);
+
+        unsafe {
+            if self.repaint {
+                self.repaint = false;
+                self.fill_bars(viewport);
+                let full = ClipRect::new(0, 0, self.buffer.width as i32, self.buffer.height as i32);
+                self.copy_region_to_window(viewport, full);
+            } else {
+                for region in regions {
+                    self.copy_region_to_window(viewport, *region);
+                }
+            }
+
+            XFlush(self.display);
+        }
+    }
+
+    fn time(&self) -> f64 {
+        self.start.elapsed().as_micros() as f64 / 1000.0
+    }
+}
+
+impl Drop for X11Platform {
+    fn drop(&mut self) {
+        // Takes the window with it
+        unsafe { XCloseDisplay(self.display); }
+    }
+}
+
+impl X11Platform {
+    unsafe fn process_key(&mut self, event: &mut XInputEvent, input: &mut InputController) {
+        let is_down = event.kind == KEY_PRESS;
+        let keycode = event.detail as usize & 0xFF;
+        let was_down = self.keys_down[keycode];
+        self.keys_down[keycode] = is_down;
+
+        let keysym = XLookupKeysym(event, 0);
+        // Keysyms below 0x80 are ASCII, letters come lowercase without shift
+        let letter = if keysym < 0x80 { (keysym as u8 as char).to_ascii_uppercase() } else { '\0' };
+        match letter {
+            'W' => input.w.is_down = is_down,
+            'A' => input.a.is_down = is_down,
+            'S' => input.s.is_down = is_down,
+            'D' => input.d.is_down = is_down,
+            'C' => input.c.is_down = is_down,
+            'F' => input.f.is_down = is_down,
+            'E' => input.e.is_down = is_down,
+            'M' => input.m.is_down = is_down,
+            'P' => input.p.is_down = is_down,
+            'R' => input.r.is_down = is_down,
+            'X' => input.x.is_down = is_down,
+            'Y' => input.y.is_down = is_down,
+            'Z' => input.z.is_down = is_down,
+            _ => match keysym {
+                XK_UP => input.up.is_down = is_down,
+                XK_LEFT => input.left.is_down = is_down,
+                XK_DOWN => input.down.is_down = is_down,
+                XK_RIGHT => input.right.is_down = is_down,
+                XK_ESCAPE => input.esc.is_down = is_down,
+                XK_SPACE => input.space.is_down = is_down,
+                XK_SHIFT_L | XK_SHIFT_R => input.shift.is_down = is_down,
+                XK_CONTROL_L | XK_CONTROL_R => input.ctrl.is_down = is_down,
+                _ => {}
+            }
+        }
+
+        if !was_down && is_down {
+            if keysym == XK_F5 {
+                println!("reload");
+                crate::reload_lib();
+            }
+
+            // alt + F4, for window managers that don't close the window on it themselves
+            if keysym == XK_F4 && event.state & MOD1_MASK != 0 {
+                self.is_running = false;
+            }
+        }
+    }
+
+    // The game works in buffer pixels, which only match the window without a fixed resolution
+    fn window_to_buffer(&self, x: c_int, y: c_int) -> Vector2u32 {
+        let viewport = self.fit.viewport(self.buffer.width, self.buffer.height, self.window_size);
+        viewport.window_to_buffer(x, y, self.buffer.width, self.buffer.height)
+    }
+
+    unsafe fn fill_bars(&self, viewport: Viewport) {
+        let width = self.window_size.width as i32;
+        let height = self.window_size.height as i32;
+        let right = viewport.x + viewport.width as i32;
+        let bottom = viewport.y + viewport.height as i32;
+        let bars = [
+            ClipRect::new(0, 0, width, viewport.y),
+            ClipRect::new(0, bottom, width, height),
+            ClipRect::new(0, viewport.y, viewport.x, bottom),
+            ClipRect::new(right, viewport.y, width, bottom)
+        ];
+
+        XSetForeground(self.display, self.gc, self.black);
+        for bar in bars {
+            if !bar.is_empty() {
+                XFillRectangle(self.display, self.window, self.gc, bar.x0, bar.y0, (bar.x1 - bar.x0) as c_uint, (bar.y1 - bar.y0) as c_uint);
+            }
+        }
+    }
+
+    // Draws a region of the buffer where the viewport puts it in the window
+    unsafe fn copy_region_to_window(&mut self, viewport: Viewport, region: ClipRect) {
+        let window = ClipRect::new(0, 0, self.window_size.width as i32, self.window_size.height as i32);
+        let target = ClipRect::new(viewport.x, viewport.y, viewport.x + viewport.width as i32, viewport.y + viewport.height as i32);
+        let rect = viewport.buffer_to_window(region, self.buffer.width, self.buffer.height)
+            .intersection(target)
+            .intersection(window);
+        if rect.is_empty() {
+            return;
+        }
+
+        let width = (rect.x1 - rect.x0) as c_uint;
+        let height = (rect.y1 - rect.y0) as c_uint;
+
+        if viewport.width == self.buffer.width && viewport.height == self.buffer.height {
+            // The pixels go across as they are
+            let image = self.create_image(self.buffer.memory as *mut c_char, self.buffer.width, self.buffer.height);
+            XPutImage(
+                self.display, self.window, self.gc, image,
+                rect.x0 - viewport.x, rect.y0 - viewport.y, rect.x0, rect.y0, width, height);
+            XFree(image as *mut c_void);
+            return;
+        }
+
+        // Nearest buffer pixel for every window pixel in the rect
+        let window_width = self.window_size.width as usize;
+        self.scaled.resize(window_width * self.window_size.height as usize, 0);
+        let mut y = rect.y0;
+        while y < rect.y1 {
+            let buffer_y = ((y - viewport.y) as i64 * self.buffer.height as i64 / viewport.height as i64) as usize;
+            let source = &self.pixels[buffer_y * self.buffer.width as usize..][..self.buffer.width as usize];
+            let row = &mut self.scaled[y as usize * window_width..][..window_width];
+            let mut x = rect.x0;
+            while x < rect.x1 {
+                let buffer_x = ((x - viewport.x) as i64 * self.buffer.width as i64 / viewport.width as i64) as usize;
+                row[x as usize] = source[buffer_x];
+                x += 1;
+            }
+            y += 1;
+        }
+
+        let data = self.scaled.as_mut_ptr() as *mut c_char;
+        let image = self.create_image(data, self.window_size.width, self.window_size.height);
+        XPutImage(self.display, self.window, self.gc, image, rect.x0, rect.y0, rect.x0, rect.y0, width, height);
+        XFree(image as *mut c_void);
+    }
+
+    // Wraps pixels in an image without copying them, XFree releases only the wrapper
+    unsafe fn create_image(&self, data: *mut c_char, width: u32, height: u32) -> *mut XImage {
+        XCreateImage(
+            self.display, self.visual, self.depth as c_uint, Z_PIXMAP, 0,
+            data, width, height, 32, (width * 4) as c_int)
+    }
+}
+
+// The DPI the desktop asks applications to render at, from the Xft.dpi resource
+unsafe fn xft_dpi(display: *mut Display) -> Option<f32> {
+    let resources = XResourceManagerString(display);
+    if resources.is_null() {
+        return None;
+    }
+
+    CStr::from_ptr(resources).to_str().ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("Xft.dpi:"))
+        .and_then(|value| value.trim().parse().ok())
+}
@@ -0,0 +1,435 @@
+use crate::{ButtonState, InputController};
+use std::fs;
+use std::io;
+
+// Physical inputs that an action can be bound to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputSource {
+    Key(Key),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Key {
+    W,
+    A,
+    S,
+    D,
+    C,
+    E,
+    F,
+    M,
+    P,
+    R,
+    X,
+    Y,
+    Z,
+    Up,
+    Left,
+    Down,
+    Right,
+    Esc,
+    Space,
+    Shift,
+    Ctrl
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GamepadButton {
+    A,
+    B,
+    X,
+    Y,
+    Start,
+    Back,
+    LeftShoulder,
+    RightShoulder,
+    DpadUp,
+    DpadLeft,
+    DpadDown,
+    DpadRight
+}
+
+static KEYS: [(Key, &str); 21] = [
+    (Key::W, "W"),
+    (Key::A, "A"),
+    (Key::S, "S"),
+    (Key::D, "D"),
+    (Key::C, "C"),
+    (Key::E, "E"),
+    (Key::F, "F"),
+    (Key::M, "M"),
+    (Key::P, "P"),
+    (Key::R, "R"),
+    (Key::X, "X"),
+    (Key::Y, "Y"),
+    (Key::Z, "Z"),
+    (Key::Up, "Up"),
+    (Key::Left, "Left"),
+    (Key::Down, "Down"),
+    (Key::Right, "Right"),
+    (Key::Esc, "Esc"),
+    (Key::Space, "Space"),
+    (Key::Shift, "Shift"),
+    (Key::Ctrl, "Ctrl")
+];
+
+static MOUSE_BUTTONS: [(MouseButton, &str); 3] = [
+    (MouseButton::Left, "Left"),
+    (MouseButton::Right, "Right"),
+    (MouseButton::Middle, "Middle")
+];
+
+static GAMEPAD_BUTTONS: [(GamepadButton, &str); 12] = [
+    (GamepadButton::A, "A"),
+    (GamepadButton::B, "B"),
+    (GamepadButton::X, "X"),
+    (GamepadButton::Y, "Y"),
+    (GamepadButton::Start, "Start"),
+    (GamepadButton::Back, "Back"),
+    (GamepadButton::LeftShoulder, "LeftShoulder"),
+    (GamepadButton::RightShoulder, "RightShoulder"),
+    (GamepadButton::DpadUp, "DpadUp"),
+    (GamepadButton::DpadLeft, "DpadLeft"),
+    (GamepadButton::DpadDown, "DpadDown"),
+    (GamepadButton::DpadRight, "DpadRight")
+];
+
+impl InputSource {
+    // Every source that can be captured while rebinding
+    fn all() -> Vec<InputSource> {
+        let mut sources = Vec::new();
+        for (key, _) in KEYS {
+            sources.push(InputSource::Key(key));
+        }
+        for (button, _) in MOUSE_BUTTONS {
+            sources.push(InputSource::Mouse(button));
+        }
+        for (button, _) in GAMEPAD_BUTTONS {
+            sources.push(InputSource::Gamepad(button));
+        }
+        sources
+    }
+
+    fn button_state(self, input: &InputController) -> ButtonState {
+        match self {
+            InputSource::Key(key) => match key {
+                Key::W => input.w,
+                Key::A => input.a,
+                Key::S => input.s,
+                Key::D => input.d,
+                Key::C => input.c,
+                Key::E => input.e,
+                Key::F => input.f,
+                Key::M => input.m,
+                Key::P => input.p,
+                Key::R => input.r,
+                Key::X => input.x,
+                Key::Y => input.y,
+                Key::Z => input.z,
+                Key::Up => input.up,
+                Key::Left => input.left,
+                Key::Down => input.down,
+                Key::Right => input.right,
+                Key::Esc => input.esc,
+                Key::Space => input.space,
+                Key::Shift => input.shift,
+                Key::Ctrl => input.ctrl
+            },
+            InputSource::Mouse(button) => match button {
+                MouseButton::Left => input.mouse_state.left,
+                MouseButton::Right => input.mouse_state.right,
+                MouseButton::Middle => input.mouse_state.middle
+            },
+            InputSource::Gamepad(button) => match button {
+                GamepadButton::A => input.gamepad.a,
+                GamepadButton::B => input.gamepad.b,
+                GamepadButton::X => input.gamepad.x,
+                GamepadButton::Y => input.gamepad.y,
+                GamepadButton::Start => input.gamepad.start,
+                GamepadButton::Back => input.gamepad.back,
+                GamepadButton::LeftShoulder => input.gamepad.left_shoulder,
+                GamepadButton::RightShoulder => input.gamepad.right_shoulder,
+                GamepadButton::DpadUp => input.gamepad.dpad_up,
+                GamepadButton::DpadLeft => input.gamepad.dpad_left,
+                GamepadButton::DpadDown => input.gamepad.dpad_down,
+                GamepadButton::DpadRight => input.gamepad.dpad_right
+            }
+        }
+    }
+
+    // Formats as "Key:W", "Mouse:Left" or "Gamepad:A", which is what the config file stores
+    pub fn to_config_string(self) -> String {
+        match self {
+            InputSource::Key(key) => {
+                let name = KEYS.iter().find(|(k, _)| *k == key).unwrap().1;
+                format!("Key:{}", name)
+            },
+            InputSource::Mouse(button) => {
+                let name = MOUSE_BUTTONS.iter().find(|(b, _)| *b == button).unwrap().1;
+                format!("Mouse:{}", name)
+            },
+            InputSource::Gamepad(button) => {
+                let name = GAMEPAD_BUTTONS.iter().find(|(b, _)| *b == button).unwrap().1;
+                format!("Gamepad:{}", name)
+            }
+        }
+    }
+
+    pub fn from_config_string(text: &str) -> Option<InputSource> {
+        let (device, name) = text.trim().split_once(':')?;
+        match device {
+            "Key" => KEYS.iter().find(|(_, n)| *n == name).map(|(key, _)| InputSource::Key(*key)),
+            "Mouse" => MOUSE_BUTTONS.iter().find(|(_, n)| *n == name).map(|(button, _)| InputSource::Mouse(*button)),
+            "Gamepad" => GAMEPAD_BUTTONS.iter().find(|(_, n)| *n == name).map(|(button, _)| InputSource::Gamepad(*button)),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ActionBinding {
+    pub action: String,
+    pub sources: Vec<InputSource>
+}
+
+// Maps named actions to physical inputs so game code asks "is Select pressed?"
+// instead of checking the left mouse button directly
+#[derive(Clone, Default, Debug)]
+pub struct ActionMap {
+    pub bindings: Vec<ActionBinding>,
+    // Waiting for an action's input to be pressed, that action is the one rebound
+    pub choosing_rebind: bool,
+    // Action waiting for the next pressed input to become its binding
+    pub rebinding: Option<String>
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        ActionMap {
+            bindings: Vec::new(),
+            choosing_rebind: false,
+            rebinding: None
+        }
+    }
+
+    pub fn default_bindings() -> Self {
+        let mut actions = ActionMap::new();
+
+        actions.bind("PanUp", InputSource::Key(Key::W));
+        actions.bind("PanUp", InputSource::Key(Key::Up));
+        actions.bind("PanUp", InputSource::Gamepad(GamepadButton::DpadUp));
+        actions.bind("PanDown", InputSource::Key(Key::S));
+        actions.bind("PanDown", InputSource::Key(Key::Down));
+        actions.bind("PanDown", InputSource::Gamepad(GamepadButton::DpadDown));
+        actions.bind("PanLeft", InputSource::Key(Key::A));
+        actions.bind("PanLeft", InputSource::Key(Key::Left));
+        actions.bind("PanLeft", InputSource::Gamepad(GamepadButton::DpadLeft));
+        actions.bind("PanRight", InputSource::Key(Key::D));
+        actions.bind("PanRight", InputSource::Key(Key::Right));
+        actions.bind("PanRight", InputSource::Gamepad(GamepadButton::DpadRight));
+        actions.bind("Pan", InputSource::Mouse(MouseButton::Middle));
+        actions.bind("Select", InputSource::Mouse(MouseButton::Left));
+        actions.bind("Select", InputSource::Gamepad(GamepadButton::A));
+        actions.bind("Sketch", InputSource::Key(Key::F));
+        actions.bind("ResetCamera", InputSource::Mouse(MouseButton::Right));
+        actions.bind("ResetCamera", InputSource::Gamepad(GamepadButton::Back));
+        actions.bind("ToggleBoundingBoxes", InputSource::Key(Key::Space));
+        actions.bind("ToggleBoundingBoxes", InputSource::Gamepad(GamepadButton::Y));
+        actions.bind("ToggleCurvatureCombs", InputSource::Key(Key::C));
+        actions.bind("ToggleCurvatureCombs", InputSource::Gamepad(GamepadButton::X));
+        actions.bind("ToggleCursorReadout", InputSource::Key(Key::M));
+        actions.bind("ToggleControlPolygons", InputSource::Key(Key::P));
+        actions.bind("NoSnap", InputSource::Key(Key::Ctrl));
+        actions.bind("ToggleEditor", InputSource::Key(Key::E));
+        actions.bind("AddToSelection", InputSource::Key(Key::Shift));
+        actions.bind("Undo", InputSource::Key(Key::Z));
+        actions.bind("Redo", InputSource::Key(Key::Y));
+        actions.bind("ToggleMeasure", InputSource::Key(Key::R));
+        actions.bind("Rebind", InputSource::Key(Key::Esc));
+        actions.bind("ExportSvg", InputSource::Key(Key::X));
+
+        actions
+    }
+
+    pub fn bind(&mut self, action: &str, source: InputSource) {
+        match self.bindings.iter_mut().find(|binding| binding.action == action) {
+            Some(binding) => {
+                if !binding.sources.contains(&source) {
+                    binding.sources.push(source);
+                }
+            },
+            None => {
+                self.bindings.push(ActionBinding {
+                    action: action.to_string(),
+                    sources: vec![source]
+                });
+            }
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, source: InputSource) {
+        if let Some(binding) = self.bindings.iter_mut().find(|binding| binding.action == action) {
+            binding.sources.retain(|bound| *bound != source);
+        }
+    }
+
+    // Replaces every binding of an action with a single source
+    pub fn rebind(&mut self, action: &str, source: InputSource) {
+        self.clear(action);
+        self.bind(action, source);
+    }
+
+    pub fn clear(&mut self, action: &str) {
+        if let Some(binding) = self.bindings.iter_mut().find(|binding| binding.action == action) {
+            binding.sources.clear();
+        }
+    }
+
+    pub fn sources(&self, action: &str) -> &[InputSource] {
+        match self.bindings.iter().find(|binding| binding.action == action) {
+            Some(binding) => &binding.sources,
+            None => &[]
+        }
+    }
+
+    pub fn is_down(&self, action: &str, input: &InputController) -> bool {
+        self.sources(action).iter().any(|source| source.button_state(input).is_down)
+    }
+
+    pub fn was_pressed(&self, action: &str, input: &InputController) -> bool {
+        let sources = self.sources(action);
+        let is_down = sources.iter().any(|source| source.button_state(input).is_down);
+        let was_down = sources.iter().any(|source| source.button_state(input).was_down);
+        is_down && !was_down
+    }
+
+    pub fn was_released(&self, action: &str, input: &InputController) -> bool {
+        let sources = self.sources(action);
+        let is_down = sources.iter().any(|source| source.button_state(input).is_down);
+        let was_down = sources.iter().any(|source| source.button_state(input).was_down);
+        !is_down && was_down
+    }
+
+    // First action pressed this frame, in the order they were bound
+    pub fn pressed_action(&self, input: &InputController) -> Option<String> {
+        self.bindings.iter()
+            .find(|binding| self.was_pressed(&binding.action, input))
+            .map(|binding| binding.action.clone())
+    }
+
+    // Rebinding without a menu, the action whose input is pressed next gets rebound
+    pub fn begin_choosing_rebind(&mut self) {
+        self.choosing_rebind = true;
+    }
+
+    // The next input pressed after this call replaces the action's bindings
+    pub fn begin_rebind(&mut self, action: &str) {
+        self.choosing_rebind = false;
+        self.rebinding = Some(action.to_string());
+    }
+
+    pub fn is_rebinding(&self) -> bool {
+        self.choosing_rebind || self.rebinding.is_some()
+    }
+
+    // Returns true on the frame a pending rebind got its new input. The input that picked the
+    // action is still held on the next frame, so it isn't taken as the new binding.
+    pub fn update_rebind(&mut self, input: &InputController) -> bool {
+        if self.choosing_rebind {
+            if let Some(action) = self.pressed_action(input) {
+                self.begin_rebind(&action);
+            }
+            return false;
+        }
+
+        let action = match &self.rebinding {
+            Some(value) => value.clone(),
+            None => return false
+        };
+
+        for source in InputSource::all() {
+            let state = source.button_state(input);
+            if state.is_down && !state.was_down {
+                self.rebind(&action, source);
+                self.rebinding = None;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // One action per line: "Select = Mouse:Left, Gamepad:A"
+    pub fn to_config_string(&self) -> String {
+        let mut text = String::new();
+        for binding in &self.bindings {
+            let sources: Vec<String> = binding.sources.iter().map(|source| source.to_config_string()).collect();
+            text.push_str(&format!("{} = {}\n", binding.action, sources.join(", ")));
+        }
+        text
+    }
+
+    // Actions the text doesn't mention keep their default bindings, so configs saved before an
+    // action existed still get it. An action listed with no sources stays unbound, and when an
+    // action is listed more than once the last line wins.
+    pub fn from_config_string(text: &str) -> io::Result<Self> {
+        let mut actions = ActionMap::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (action, sources) = match line.split_once('=') {
+                Some(value) => value,
+                None => return Err(invalid_line(line_index, line))
+            };
+
+            let action = action.trim();
+            match actions.bindings.iter_mut().find(|binding| binding.action == action) {
+                Some(binding) => binding.sources.clear(),
+                None => actions.bindings.push(ActionBinding {
+                    action: action.to_string(),
+                    sources: Vec::new()
+                })
+            }
+
+            for source in sources.split(',').filter(|source| !source.trim().is_empty()) {
+                match InputSource::from_config_string(source) {
+                    Some(value) => actions.bind(action, value),
+                    None => return Err(invalid_line(line_index, line))
+                }
+            }
+        }
+
+        for binding in ActionMap::default_bindings().bindings {
+            if !actions.bindings.iter().any(|existing| existing.action == binding.action) {
+                actions.bindings.push(binding);
+            }
+        }
+
+        Ok(actions)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_config_string())
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        ActionMap::from_config_string(&fs::read_to_string(path)?)
+    }
+}
+
+fn invalid_line(line_index: usize, line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid binding on line {}: {}", line_index + 1, line))
+}
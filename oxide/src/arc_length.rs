@@ -0,0 +1,102 @@
+use crate::{BezierCurve, Vector2};
+
+// Straight pieces the curve is measured with, the error in length is well under a pixel for
+// curves that fit on screen
+static TABLE_SAMPLES: u32 = 64;
+
+// Length of a curve measured up to evenly spaced t values. The speed along a bezier curve
+// changes, so equal steps in t aren't equal steps along the curve. Building this once and
+// looking distances up in it is much cheaper than measuring again for every point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArcLengthTable {
+    pub curve: BezierCurve,
+    // lengths[i] is how long the curve is from t = 0.0 to t = i / TABLE_SAMPLES
+    lengths: Vec<f32>
+}
+
+impl ArcLengthTable {
+    pub fn new(curve: BezierCurve) -> Self {
+        let mut lengths = Vec::with_capacity(TABLE_SAMPLES as usize + 1);
+        lengths.push(0.0);
+
+        let mut previous = curve.p0;
+        let mut total = 0.0;
+        let mut i = 1;
+        while i <= TABLE_SAMPLES {
+            let point = curve.evaluate(i as f32 / TABLE_SAMPLES as f32);
+            total += previous.distance(point);
+            lengths.push(total);
+            previous = point;
+            i += 1;
+        }
+
+        ArcLengthTable { curve, lengths }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    // The t that is the distance along the curve from its start, clamped to the curve
+    pub fn t_at_distance(&self, distance: f32) -> f32 {
+        let total = self.length();
+        if distance <= 0.0 || total <= 0.0 {
+            return 0.0;
+        }
+        if distance >= total {
+            return 1.0;
+        }
+
+        // First sample at or past the distance, the one before it is short of it
+        let index = self.lengths.partition_point(|length| *length < distance).max(1);
+        let start = self.lengths[index - 1];
+        let end = self.lengths[index];
+        let fraction = if end > start { (distance - start) / (end - start) } else { 0.0 };
+
+        (index as f32 - 1.0 + fraction) / TABLE_SAMPLES as f32
+    }
+
+    pub fn evaluate_at_distance(&self, distance: f32) -> Vector2 {
+        self.curve.evaluate(self.t_at_distance(distance))
+    }
+
+    // Points the same distance apart along the whole curve, both ends included
+    pub fn evenly_spaced_points(&self, count: u32) -> Vec<Vector2> {
+        if count < 2 {
+            return vec![self.curve.p0];
+        }
+
+        let spacing = self.length() / (count - 1) as f32;
+        let mut points = Vec::with_capacity(count as usize);
+        let mut i = 0;
+        while i < count {
+            points.push(self.evaluate_at_distance(spacing * i as f32));
+            i += 1;
+        }
+        points
+    }
+
+    // Where items of the given widths go when laid out one after another along the curve,
+    // starting the distance in. Each is the center of the item and the unit direction the curve
+    // heads there, which is what text along a path needs to place and rotate its glyphs. Items
+    // that don't fit on the curve are left out.
+    pub fn layout_along(&self, advances: &[f32], start_distance: f32) -> Vec<(Vector2, Vector2)> {
+        let total = self.length();
+        let mut placements = Vec::with_capacity(advances.len());
+        let mut distance = start_distance;
+        for advance in advances {
+            let center = distance + advance / 2.0;
+            distance += advance;
+            if center < 0.0 {
+                continue;
+            }
+            if center > total {
+                break;
+            }
+
+            let t = self.t_at_distance(center);
+            placements.push((self.curve.evaluate(t), self.curve.tangent(t)));
+        }
+        placements
+    }
+}
@@ -0,0 +1,179 @@
+use crate::bitmap::{blit_bitmap_region, draw_bitmap_region, Bitmap, BitmapTransform, Filter};
+use crate::{world_space_to_screen_space_f32, Camera, ClipRect, OffscreenBuffer};
+
+// Empty pixels between packed sprites, keeps bilinear filtering from reading a neighbour
+static ATLAS_PADDING: u32 = 1;
+
+// Index of a sprite inside the atlas it was added to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpriteId(pub usize);
+
+// Many small sprites packed into one bitmap. Sprites are placed left to right on shelves,
+// a new shelf is started below once a row is full.
+#[derive(Clone, Debug)]
+pub struct TextureAtlas {
+    pub bitmap: Bitmap,
+    regions: Vec<ClipRect>,
+    shelf_x: u32,
+    shelf_y: u32,
+    shelf_height: u32
+}
+
+impl TextureAtlas {
+    pub fn new(width: u32, height: u32) -> Self {
+        TextureAtlas {
+            bitmap: Bitmap::new(width, height),
+            regions: Vec::new(),
+            shelf_x: 0,
+            shelf_y: 0,
+            shelf_height: 0
+        }
+    }
+
+    // Copies the sprite into the atlas, returns None when there is no room left
+    pub fn add(&mut self, sprite: &Bitmap) -> Option<SpriteId> {
+        if sprite.width > self.bitmap.width || sprite.height > self.bitmap.height {
+            return None;
+        }
+
+        if self.shelf_x + sprite.width > self.bitmap.width {
+            self.shelf_x = 0;
+            self.shelf_y += self.shelf_height + ATLAS_PADDING;
+            self.shelf_height = 0;
+        }
+
+        if self.shelf_y + sprite.height > self.bitmap.height {
+            return None;
+        }
+
+        let x = self.shelf_x;
+        let y = self.shelf_y;
+
+        let mut row = 0;
+        while row < sprite.height {
+            let source = (row * sprite.width) as usize;
+            let destination = ((y + row) * self.bitmap.width + x) as usize;
+            self.bitmap.pixels[destination..destination + sprite.width as usize]
+                .copy_from_slice(&sprite.pixels[source..source + sprite.width as usize]);
+            row += 1;
+        }
+
+        self.shelf_x += sprite.width + ATLAS_PADDING;
+        self.shelf_height = self.shelf_height.max(sprite.height);
+
+        self.regions.push(ClipRect::new(x as i32, y as i32, (x + sprite.width) as i32, (y + sprite.height) as i32));
+        Some(SpriteId(self.regions.len() - 1))
+    }
+
+    pub fn region(&self, sprite: SpriteId) -> Option<ClipRect> {
+        self.regions.get(sprite.0).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BatchedSprite {
+    atlas: usize,
+    sprite: SpriteId,
+    transform: BitmapTransform,
+    filter: Filter
+}
+
+// Collects sprite draws for a frame and draws them grouped by atlas. Sprites from the same
+// atlas keep their submission order, but draws from different atlases can be reordered, so
+// use separate batches when sprites from different atlases have to overlap in a set order.
+#[derive(Clone, Default, Debug)]
+pub struct SpriteBatch {
+    sprites: Vec<BatchedSprite>
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        SpriteBatch {
+            sprites: Vec::new()
+        }
+    }
+
+    // Atlas is the index into the slice passed to flush
+    pub fn draw(&mut self, atlas: usize, sprite: SpriteId, transform: BitmapTransform, filter: Filter) {
+        self.sprites.push(BatchedSprite {
+            atlas,
+            sprite,
+            transform,
+            filter
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Draws and clears every batched sprite. Sprites that end up unrotated at their
+    /// original size are copied straight from the atlas without resampling.
+    ///
+    /// # Safety
+    /// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+    pub unsafe fn flush(&mut self, buffer: &mut OffscreenBuffer, camera: Camera, atlases: &[TextureAtlas]) {
+        let mut sprites = std::mem::take(&mut self.sprites);
+        // sort_by_key is stable, so order within an atlas is kept
+        sprites.sort_by_key(|sprite| sprite.atlas);
+
+        let mut i = 0;
+        while i < sprites.len() {
+            let atlas_index = sprites[i].atlas;
+            let atlas = match atlases.get(atlas_index) {
+                Some(value) => value,
+                None => {
+                    eprintln!("Sprite batch references missing atlas {}", atlas_index);
+                    while i < sprites.len() && sprites[i].atlas == atlas_index {
+                        i += 1;
+                    }
+                    continue;
+                }
+            };
+
+            while i < sprites.len() && sprites[i].atlas == atlas_index {
+                let sprite = sprites[i];
+                i += 1;
+
+                let region = match atlas.region(sprite.sprite) {
+                    Some(value) => value,
+                    None => continue
+                };
+
+                let width = (region.x1 - region.x0) as f32;
+                let height = (region.y1 - region.y0) as f32;
+                let screen_width = sprite.transform.size.x * camera.y_scale;
+                let screen_height = sprite.transform.size.y * camera.y_scale;
+
+                let is_one_to_one = camera.screen_rotation(sprite.transform.rotation) == 0.0 &&
+                    (screen_width - width).abs() < 0.01 &&
+                    (screen_height - height).abs() < 0.01;
+
+                if is_one_to_one {
+                    let center = world_space_to_screen_space_f32(camera, sprite.transform.position);
+                    let x = (center.x - width / 2.0).round() as i32;
+                    let y = (center.y - height / 2.0).round() as i32;
+                    blit_bitmap_region(buffer, &atlas.bitmap, region, x, y, 1.0);
+                } else {
+                    draw_bitmap_region(buffer, camera, &atlas.bitmap, region, sprite.transform, sprite.filter);
+                }
+            }
+        }
+
+        // Hand the allocation back so the next frame doesn't have to grow it again
+        sprites.clear();
+        self.sprites = sprites;
+    }
+}
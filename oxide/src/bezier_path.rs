@@ -0,0 +1,341 @@
+use crate::fit::{fit_cubic, fit_points};
+use crate::math::smoothstep;
+use crate::path::Path;
+use crate::{BezierCurve, Vector2};
+
+// Points taken along each curve being merged when a point is deleted
+static FIT_SAMPLES: u32 = 16;
+
+// How the handles on either side of a joint are kept in line when one of them moves
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Continuity {
+    // The segments only share the end point, each handle moves on its own and makes a corner
+    C0,
+    // Handles stay on a straight line through the joint but keep their own lengths, so the
+    // direction is smooth but the speed can change
+    #[default]
+    G1,
+    // Handles mirror each other exactly, the curve passes through at the same speed
+    C1
+}
+
+// Spline of cubic segments where each one starts where the previous one ends. Points are
+// numbered like a flat list of control points, anchors are every third point starting at 0 and
+// the handles sit on either side of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BezierPath {
+    pub segments: Vec<BezierCurve>,
+    // Joint i is where segment i ends and segment i + 1 starts
+    pub joints: Vec<Continuity>
+}
+
+impl BezierPath {
+    pub fn new(first: BezierCurve) -> Self {
+        BezierPath {
+            segments: vec![first],
+            joints: Vec::new()
+        }
+    }
+
+    // Adds a segment starting at the end of the last one. The new first handle is moved to
+    // meet the continuity with the handle before the joint.
+    pub fn push_curve(&mut self, control_1: Vector2, control_2: Vector2, point: Vector2, continuity: Continuity) {
+        let start = match self.segments.last() {
+            Some(value) => value.p3,
+            None => Vector2::zero()
+        };
+
+        self.segments.push(BezierCurve::new(start, control_1, control_2, point));
+        if self.segments.len() > 1 {
+            self.joints.push(continuity);
+            let joint = self.joints.len() - 1;
+            self.enforce_joint(joint, true);
+        }
+    }
+
+    // Path through freehand points, like a stroke drawn with the mouse, see fit_points. The
+    // segments meet smoothly so every joint is G1.
+    pub fn fit(points: &[Vector2], tolerance: f32) -> Self {
+        let segments = fit_points(points, tolerance);
+        let joints = vec![Continuity::G1; segments.len().saturating_sub(1)];
+        BezierPath { segments, joints }
+    }
+
+    pub fn point_count(&self) -> usize {
+        match self.segments.len() {
+            0 => 0,
+            count => count * 3 + 1
+        }
+    }
+
+    pub fn point(&self, index: usize) -> Option<Vector2> {
+        if index >= self.point_count() {
+            return None;
+        }
+
+        let segment = owning_segment(index, self.segments.len());
+        let curve = &self.segments[segment];
+        match index - segment * 3 {
+            0 => Some(curve.p0),
+            1 => Some(curve.p1),
+            2 => Some(curve.p2),
+            _ => Some(curve.p3)
+        }
+    }
+
+    pub fn is_anchor(index: usize) -> bool {
+        index.is_multiple_of(3)
+    }
+
+    // Moving an anchor carries its handles along, moving a handle updates the one across the
+    // joint to keep the continuity
+    pub fn move_point(&mut self, index: usize, position: Vector2) {
+        let old = match self.point(index) {
+            Some(value) => value,
+            None => return
+        };
+
+        if BezierPath::is_anchor(index) {
+            let delta = position - old;
+            for neighbour in [index.wrapping_sub(1), index, index + 1] {
+                if let Some(point) = self.point(neighbour) {
+                    self.set_point(neighbour, point + delta);
+                }
+            }
+            return;
+        }
+
+        self.set_point(index, position);
+
+        // Handle 1 of a segment comes right after joint anchor - 1, handle 2 right before it
+        let anchor = (index + 1) / 3;
+        if anchor == 0 || anchor >= self.segments.len() {
+            return;
+        }
+        self.enforce_joint(anchor - 1, index % 3 == 2);
+    }
+
+    pub fn set_continuity(&mut self, joint: usize, continuity: Continuity) {
+        if joint >= self.joints.len() {
+            return;
+        }
+        self.joints[joint] = continuity;
+        self.enforce_joint(joint, true);
+    }
+
+    // Segment, t and distance of the closest spot on the path to a position
+    pub fn nearest(&self, position: Vector2) -> Option<(usize, f32, f32)> {
+        let mut nearest: Option<(usize, f32, f32)> = None;
+        for (index, segment) in self.segments.iter().enumerate() {
+            let (t, distance) = segment.nearest(position);
+            nearest = match nearest {
+                Some(value) if value.2 <= distance => Some(value),
+                _ => Some((index, t, distance))
+            };
+        }
+        nearest
+    }
+
+    // Splits the segment closest to the position where it passes nearest to it, the shape
+    // doesn't change. Returns the index of the new anchor.
+    pub fn insert_point(&mut self, position: Vector2) -> Option<usize> {
+        let (segment, t, _) = self.nearest(position)?;
+        // Splitting right at an end would give a segment with no length
+        if t <= 1e-4 || t >= 1.0 - 1e-4 {
+            return None;
+        }
+
+        let (before, after) = self.segments[segment].split(t);
+        self.segments[segment] = before;
+        self.segments.insert(segment + 1, after);
+        // The split handles are in line through the new anchor but have different lengths
+        self.joints.insert(segment, Continuity::G1);
+        Some((segment + 1) * 3)
+    }
+
+    // Removes an anchor, the segments on either side of it are replaced by one curve fitted to
+    // their shape. The ends drop their segment instead. Returns false when nothing was removed.
+    pub fn delete_point(&mut self, index: usize) -> bool {
+        if !BezierPath::is_anchor(index) || index >= self.point_count() || self.segments.len() < 2 {
+            return false;
+        }
+
+        let anchor = index / 3;
+        if anchor == 0 {
+            self.segments.remove(0);
+            self.joints.remove(0);
+        } else if anchor == self.segments.len() {
+            self.segments.pop();
+            self.joints.pop();
+        } else {
+            let merged = fit_curves(self.segments[anchor - 1], self.segments[anchor]);
+            self.segments[anchor - 1] = merged;
+            self.segments.remove(anchor);
+            self.joints.remove(anchor - 1);
+        }
+        true
+    }
+
+    pub fn to_path(&self) -> Path {
+        let mut path = Path::new();
+        if let Some(first) = self.segments.first() {
+            path.move_to(first.p0);
+        }
+        for segment in &self.segments {
+            path.cubic_to(segment.p1, segment.p2, segment.p3);
+        }
+        path
+    }
+
+    // Shape part of the way to another path, 0.0 is this path and 1.0 is the other. Paths with
+    // different segment counts are matched up by splitting the longest segments of the shorter
+    // one, which doesn't change its shape.
+    pub fn lerp(&self, other: &BezierPath, t: f32) -> BezierPath {
+        if self.segments.is_empty() || other.segments.is_empty() {
+            return if t < 0.5 { self.clone() } else { other.clone() };
+        }
+
+        let count = self.segments.len().max(other.segments.len());
+        let from = self.subdivided(count);
+        let to = other.subdivided(count);
+        let segments = from.segments.iter().zip(&to.segments).map(|(a, b)| a.lerp(b, t)).collect();
+        // Corners only stay while the path is closer to the side that has them
+        let joints = if t < 0.5 { from.joints } else { to.joints };
+        BezierPath { segments, joints }
+    }
+
+    // The same shape with at least count segments
+    fn subdivided(&self, count: usize) -> BezierPath {
+        let mut path = self.clone();
+        let mut lengths: Vec<f32> = path.segments.iter().map(|segment| segment.arc_length()).collect();
+        while path.segments.len() < count {
+            let mut longest = 0;
+            let mut i = 1;
+            while i < lengths.len() {
+                if lengths[i] > lengths[longest] {
+                    longest = i;
+                }
+                i += 1;
+            }
+
+            let (before, after) = path.segments[longest].split(0.5);
+            path.segments[longest] = before;
+            path.segments.insert(longest + 1, after);
+            path.joints.insert(longest, Continuity::G1);
+            lengths[longest] = before.arc_length();
+            lengths.insert(longest + 1, after.arc_length());
+        }
+        path
+    }
+
+    // Writes a point into the segment that owns it, anchors between two segments go into both
+    fn set_point(&mut self, index: usize, position: Vector2) {
+        if index >= self.point_count() {
+            return;
+        }
+
+        let segment = owning_segment(index, self.segments.len());
+        match index - segment * 3 {
+            0 => {
+                self.segments[segment].p0 = position;
+                if segment > 0 {
+                    self.segments[segment - 1].p3 = position;
+                }
+            },
+            1 => self.segments[segment].p1 = position,
+            2 => self.segments[segment].p2 = position,
+            _ => self.segments[segment].p3 = position
+        }
+    }
+
+    // Keeps the handle before the joint where it is and moves the one after it, or the other
+    // way around when after_moves is false
+    fn enforce_joint(&mut self, joint: usize, after_moves: bool) {
+        let anchor = self.segments[joint].p3;
+        let (fixed, moving) = if after_moves {
+            (self.segments[joint].p2, self.segments[joint + 1].p1)
+        } else {
+            (self.segments[joint + 1].p1, self.segments[joint].p2)
+        };
+
+        let fixed_offset = fixed - anchor;
+        let moved = match self.joints[joint] {
+            Continuity::C0 => return,
+            Continuity::C1 => anchor - fixed_offset,
+            Continuity::G1 => {
+                let fixed_length = fixed_offset.length();
+                if fixed_length < 1e-6 {
+                    return;
+                }
+                anchor - fixed_offset * ((moving - anchor).length() / fixed_length)
+            }
+        };
+
+        if after_moves {
+            self.segments[joint + 1].p1 = moved;
+        } else {
+            self.segments[joint].p2 = moved;
+        }
+    }
+}
+
+// One cubic through the outer ends of two connected curves that follows them as closely as it
+// can. The end directions are kept so the joints further along stay smooth.
+fn fit_curves(first: BezierCurve, second: BezierCurve) -> BezierCurve {
+    let start = first.p0;
+    let end = second.p3;
+
+    // Points along both curves, parameterized by how far along the chords they are
+    let mut points = vec![start];
+    for curve in [first, second] {
+        let mut i = 1;
+        while i <= FIT_SAMPLES {
+            points.push(curve.evaluate(i as f32 / FIT_SAMPLES as f32));
+            i += 1;
+        }
+    }
+    let mut lengths = vec![0.0; points.len()];
+    let mut i = 1;
+    while i < points.len() {
+        lengths[i] = lengths[i - 1] + (points[i] - points[i - 1]).length();
+        i += 1;
+    }
+    let total_length = lengths[lengths.len() - 1].max(1e-6);
+    let samples: Vec<(f32, Vector2)> = lengths.iter().zip(&points).map(|(distance, point)| (distance / total_length, *point)).collect();
+
+    fit_cubic(start, end, first.end_direction(false) * -1.0, second.end_direction(true) * -1.0, &samples)
+}
+
+// Segment that owns a point, the last anchor belongs to the last segment
+fn owning_segment(index: usize, segment_count: usize) -> usize {
+    (index / 3).min(segment_count - 1)
+}
+
+// One path turning into another over a duration, eased so it starts and stops gently
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathMorph {
+    pub from: BezierPath,
+    pub to: BezierPath,
+    // Milliseconds, like GameState::delta_time
+    pub duration: f32,
+    pub elapsed: f32
+}
+
+impl PathMorph {
+    pub fn new(from: BezierPath, to: BezierPath, duration: f32) -> Self {
+        PathMorph { from, to, duration, elapsed: 0.0 }
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn current(&self) -> BezierPath {
+        let progress = if self.duration > 0.0 { self.elapsed / self.duration } else { 1.0 };
+        self.from.lerp(&self.to, smoothstep(0.0, 1.0, progress))
+    }
+}
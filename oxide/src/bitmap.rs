@@ -0,0 +1,353 @@
+use crate::color::scale_color;
+use crate::{world_space_to_screen_space_f32, Camera, ClipRect, Color, OffscreenBuffer, Rectangle, Vector2};
+
+// Image in the renderer's pixel format, premultiplied 0xAARRGGBB rows from top to bottom
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>
+}
+
+impl Bitmap {
+    // Fully transparent
+    pub fn new(width: u32, height: u32) -> Self {
+        Bitmap {
+            width,
+            height,
+            pixels: vec![0; (width * height) as usize]
+        }
+    }
+
+    pub fn from_colors(width: u32, height: u32, colors: &[Color]) -> Self {
+        assert_eq!(colors.len(), (width * height) as usize, "Bitmap needs one color per pixel");
+        Bitmap {
+            width,
+            height,
+            pixels: colors.iter().map(|color| color.to_pixel()).collect()
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> u32 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn bounds(&self) -> ClipRect {
+        ClipRect::new(0, 0, self.width as i32, self.height as i32)
+    }
+
+    // Coordinates are relative to the region, outside it the nearest edge pixel is used
+    // so neighbouring sprites in an atlas never bleed in
+    fn pixel_clamped(&self, region: ClipRect, x: i32, y: i32) -> u32 {
+        let x = region.x0 + x.clamp(0, region.x1 - region.x0 - 1);
+        let y = region.y0 + y.clamp(0, region.y1 - region.y0 - 1);
+        self.pixel(x as u32, y as u32)
+    }
+
+    // u and v are in bitmap pixels, (0, 0) being the top left corner of the first pixel
+    pub fn sample(&self, u: f32, v: f32, filter: Filter) -> u32 {
+        self.sample_region(self.bounds(), u, v, filter)
+    }
+
+    // Same as sample but u and v are relative to a region of the bitmap, and only pixels
+    // inside the region are read
+    pub fn sample_region(&self, region: ClipRect, u: f32, v: f32, filter: Filter) -> u32 {
+        match filter {
+            Filter::Nearest => self.pixel_clamped(region, u.floor() as i32, v.floor() as i32),
+            Filter::Bilinear => {
+                // Pixel centers are at half coordinates
+                let u = u - 0.5;
+                let v = v - 0.5;
+                let x = u.floor();
+                let y = v.floor();
+                let tx = u - x;
+                let ty = v - y;
+                let x = x as i32;
+                let y = y as i32;
+
+                weighted_sum(
+                    [
+                        self.pixel_clamped(region, x, y),
+                        self.pixel_clamped(region, x + 1, y),
+                        self.pixel_clamped(region, x, y + 1),
+                        self.pixel_clamped(region, x + 1, y + 1)
+                    ],
+                    [(1.0 - tx) * (1.0 - ty), tx * (1.0 - ty), (1.0 - tx) * ty, tx * ty])
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Filter {
+    // Blocky, for pixel art, and cheaper
+    Nearest,
+    #[default]
+    Bilinear
+}
+
+// Where and how big a bitmap is drawn in the world, rotation is in radians around the center
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct BitmapTransform {
+    pub position: Vector2,
+    pub size: Vector2,
+    pub rotation: f32
+}
+
+impl BitmapTransform {
+    pub fn new(position: Vector2, size: Vector2) -> Self {
+        BitmapTransform {
+            position,
+            size,
+            rotation: 0.0
+        }
+    }
+
+    pub fn with_rotation(self, rotation: f32) -> Self {
+        BitmapTransform {
+            rotation,
+            ..self
+        }
+    }
+}
+
+// Premultiplied colors can be averaged channel by channel, weights should add up to 1
+fn weighted_sum(pixels: [u32; 4], weights: [f32; 4]) -> u32 {
+    let channel = |shift: u32| {
+        let mut sum = 0.0;
+        let mut i = 0;
+        while i < 4 {
+            sum += ((pixels[i] >> shift) & 0xFF) as f32 * weights[i];
+            i += 1;
+        }
+        (sum.round() as u32).min(255) << shift
+    };
+
+    channel(24) | channel(16) | channel(8) | channel(0)
+}
+
+/// Composites a bitmap 1:1 onto the buffer with its top left corner at (x, y) in pixels,
+/// using the buffer's blend mode and clip rect. Opacity fades the whole bitmap.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn blit_bitmap(buffer: &mut OffscreenBuffer, bitmap: &Bitmap, x: i32, y: i32, opacity: f32) {
+    blit_bitmap_region(buffer, bitmap, bitmap.bounds(), x, y, opacity);
+}
+
+/// Same as blit_bitmap but only copies a region of the bitmap
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn blit_bitmap_region(buffer: &mut OffscreenBuffer, bitmap: &Bitmap, region: ClipRect, x: i32, y: i32, opacity: f32) {
+    let region = region.intersection(bitmap.bounds());
+    if opacity <= 0.0 || region.is_empty() {
+        return;
+    }
+
+    let target = buffer.clip_rect().intersection(ClipRect::new(
+        x,
+        y,
+        x + region.x1 - region.x0,
+        y + region.y1 - region.y0));
+
+    if target.is_empty() {
+        return;
+    }
+
+    let mut faded: Vec<u32> = Vec::new();
+    let mut dest_y = target.y0;
+    while dest_y < target.y1 {
+        let row_start = ((region.y0 + dest_y - y) as u32 * bitmap.width + region.x0 as u32) as usize;
+        let span = &bitmap.pixels[row_start + (target.x0 - x) as usize..row_start + (target.x1 - x) as usize];
+
+        if opacity < 1.0 {
+            faded.clear();
+            faded.extend(span.iter().map(|pixel| scale_color(*pixel, opacity)));
+            buffer.blend_span(dest_y, target.x0, &faded);
+        } else {
+            buffer.blend_span(dest_y, target.x0, span);
+        }
+        dest_y += 1;
+    }
+}
+
+/// Draws a bitmap scaled and rotated into a world space rectangle. Each buffer pixel maps
+/// back into the bitmap, so there are no gaps at any zoom level.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_bitmap(buffer: &mut OffscreenBuffer, camera: Camera, bitmap: &Bitmap, transform: BitmapTransform, filter: Filter) {
+    draw_bitmap_region(buffer, camera, bitmap, bitmap.bounds(), transform, filter);
+}
+
+/// Same as draw_bitmap but only draws a region of the bitmap, e.g. one sprite of an atlas
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_bitmap_region(buffer: &mut OffscreenBuffer, camera: Camera, bitmap: &Bitmap, region: ClipRect, transform: BitmapTransform, filter: Filter) {
+    let region = region.intersection(bitmap.bounds());
+    if region.is_empty() {
+        return;
+    }
+    let region_width = (region.x1 - region.x0) as f32;
+    let region_height = (region.y1 - region.y0) as f32;
+
+    let center = world_space_to_screen_space_f32(camera, transform.position);
+    let screen_width = transform.size.x * camera.y_scale;
+    let screen_height = transform.size.y * camera.y_scale;
+    if screen_width.abs() < 1e-6 || screen_height.abs() < 1e-6 {
+        return;
+    }
+
+    // The camera's rotation turns the bitmap the other way on screen. Bitmaps stay upright when
+    // world y points up, only their rotation flips.
+    let (sin, cos) = camera.screen_rotation(transform.rotation).sin_cos();
+
+    // Corners of the rotated rectangle decide which rows and columns to visit
+    let half_width = screen_width / 2.0;
+    let half_height = screen_height / 2.0;
+    let mut min_corner = center;
+    let mut max_corner = center;
+    for (corner_x, corner_y) in [(-half_width, -half_height), (half_width, -half_height), (half_width, half_height), (-half_width, half_height)] {
+        let x = center.x + corner_x * cos - corner_y * sin;
+        let y = center.y + corner_x * sin + corner_y * cos;
+        min_corner = Vector2 { x: min_corner.x.min(x), y: min_corner.y.min(y) };
+        max_corner = Vector2 { x: max_corner.x.max(x), y: max_corner.y.max(y) };
+    }
+
+    let target = buffer.clip_rect().intersection(ClipRect::new(
+        min_corner.x.floor() as i32,
+        min_corner.y.floor() as i32,
+        max_corner.x.ceil() as i32 + 1,
+        max_corner.y.ceil() as i32 + 1));
+
+    // Inverse transform, stepping one pixel right in the buffer moves (du, dv) in the bitmap
+    let scale_u = region_width / screen_width;
+    let scale_v = region_height / screen_height;
+    let du_dx = cos * scale_u;
+    let dv_dx = -sin * scale_v;
+    let du_dy = sin * scale_u;
+    let dv_dy = cos * scale_v;
+
+
+    let mut run: Vec<u32> = Vec::new();
+    let mut y = target.y0;
+    while y < target.y1 {
+        // Sample at pixel centers
+        let offset_x = target.x0 as f32 + 0.5 - center.x;
+        let offset_y = y as f32 + 0.5 - center.y;
+        let mut u = offset_x * du_dx + offset_y * du_dy + region_width / 2.0;
+        let mut v = offset_x * dv_dx + offset_y * dv_dy + region_height / 2.0;
+
+        // The covered pixels of a row are always contiguous, so they're written as one span
+        let mut run_start = target.x0;
+        run.clear();
+
+        let mut x = target.x0;
+        while x < target.x1 {
+            if u >= 0.0 && v >= 0.0 && u < region_width && v < region_height {
+                if run.is_empty() {
+                    run_start = x;
+                }
+                run.push(bitmap.sample_region(region, u, v, filter));
+            } else if !run.is_empty() {
+                break;
+            }
+
+            u += du_dx;
+            v += dv_dx;
+            x += 1;
+        }
+
+        buffer.blend_span(y, run_start, &run);
+        y += 1;
+    }
+}
+
+// Border sizes in bitmap pixels for nine-slice drawing
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct NineSlice {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32
+}
+
+impl NineSlice {
+    pub fn uniform(inset: u32) -> Self {
+        NineSlice {
+            left: inset,
+            top: inset,
+            right: inset,
+            bottom: inset
+        }
+    }
+}
+
+/// Draws a bitmap stretched over a rectangle while its corners keep their size. Edges stretch
+/// along one axis and the center along both. Pixel size is how many world units one bitmap
+/// pixel of the borders covers, 1.0 for UI cameras. Borders shrink if the rectangle is too small.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_nine_slice(
+    buffer: &mut OffscreenBuffer,
+    camera: Camera,
+    bitmap: &Bitmap,
+    insets: NineSlice,
+    rectangle: Rectangle,
+    pixel_size: f32,
+    filter: Filter
+) {
+    let insets = NineSlice {
+        left: insets.left.min(bitmap.width),
+        right: insets.right.min(bitmap.width - insets.left.min(bitmap.width)),
+        top: insets.top.min(bitmap.height),
+        bottom: insets.bottom.min(bitmap.height - insets.top.min(bitmap.height))
+    };
+
+    let mut left = insets.left as f32 * pixel_size;
+    let mut right = insets.right as f32 * pixel_size;
+    let mut top = insets.top as f32 * pixel_size;
+    let mut bottom = insets.bottom as f32 * pixel_size;
+
+    if left + right > rectangle.width && left + right > 0.0 {
+        let shrink = rectangle.width / (left + right);
+        left *= shrink;
+        right *= shrink;
+    }
+    if top + bottom > rectangle.height && top + bottom > 0.0 {
+        let shrink = rectangle.height / (top + bottom);
+        top *= shrink;
+        bottom *= shrink;
+    }
+
+    // Edges of the three columns and rows, in the bitmap and in the world
+    let source_x = [0, insets.left as i32, (bitmap.width - insets.right) as i32, bitmap.width as i32];
+    let source_y = [0, insets.top as i32, (bitmap.height - insets.bottom) as i32, bitmap.height as i32];
+    let world_x = [rectangle.x, rectangle.x + left, rectangle.x + rectangle.width - right, rectangle.x + rectangle.width];
+    let world_y = [rectangle.y, rectangle.y + top, rectangle.y + rectangle.height - bottom, rectangle.y + rectangle.height];
+
+    let mut row = 0;
+    while row < 3 {
+        let mut column = 0;
+        while column < 3 {
+            let region = ClipRect::new(source_x[column], source_y[row], source_x[column + 1], source_y[row + 1]);
+            let size = Vector2 {
+                x: world_x[column + 1] - world_x[column],
+                y: world_y[row + 1] - world_y[row]
+            };
+
+            if !region.is_empty() && size.x > 0.0 && size.y > 0.0 {
+                let position = Vector2 {
+                    x: world_x[column] + size.x / 2.0,
+                    y: world_y[row] + size.y / 2.0
+                };
+                draw_bitmap_region(buffer, camera, bitmap, region, BitmapTransform::new(position, size), filter);
+            }
+            column += 1;
+        }
+        row += 1;
+    }
+}
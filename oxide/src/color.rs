@@ -0,0 +1,369 @@
+use crate::srgb;
+use std::cmp::min;
+
+// Straight alpha color, this is what game code works with. The buffer stores pixels as
+// premultiplied 0xAARRGGBB (BGRA in memory), to_pixel does the conversion.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const RED: Color = Color::rgb(255, 0, 0);
+    pub const GREEN: Color = Color::rgb(0, 255, 0);
+    pub const BLUE: Color = Color::rgb(0, 0, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    // Packed straight 0xAARRGGBB, the way colors used to be written as u32s
+    pub const fn from_argb(argb: u32) -> Self {
+        Color {
+            r: (argb >> 16) as u8,
+            g: (argb >> 8) as u8,
+            b: argb as u8,
+            a: (argb >> 24) as u8
+        }
+    }
+
+    pub const fn to_argb(self) -> u32 {
+        ((self.a as u32) << 24) | ((self.r as u32) << 16) | ((self.g as u32) << 8) | self.b as u32
+    }
+
+    // "#RRGGBB" or "#RRGGBBAA", the # is optional
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().trim_start_matches('#');
+        let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+
+        match hex.len() {
+            6 => Some(Color::rgb(channel(0)?, channel(2)?, channel(4)?)),
+            8 => Some(Color::rgba(channel(0)?, channel(2)?, channel(4)?, channel(6)?)),
+            _ => None
+        }
+    }
+
+    pub fn to_hex(self) -> String {
+        if self.a == 255 {
+            format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    pub fn with_alpha(self, a: u8) -> Self {
+        Color { a, ..self }
+    }
+
+    pub fn alpha(self) -> f32 {
+        self.a as f32 / 255.0
+    }
+
+    // Premultiplied pixel in the layout the buffer uses
+    pub fn to_pixel(self) -> u32 {
+        premultiply(self.to_argb())
+    }
+
+    pub fn from_pixel(pixel: u32) -> Self {
+        Color::from_argb(unpremultiply(pixel))
+    }
+
+    // Interpolates through premultiplied space so fading to transparent doesn't darken
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        Color::from_pixel(lerp_color(self.to_pixel(), other.to_pixel(), t))
+    }
+
+    // Hue in degrees, saturation and value from 0.0 to 1.0
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        Color::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max <= 0.0 { 0.0 } else { (max - min) / max };
+        (hue, saturation, max)
+    }
+
+    // Hue in degrees, saturation and lightness from 0.0 to 1.0
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation.clamp(0.0, 1.0), lightness)
+    }
+
+    pub fn hue_shift(self, degrees: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue + degrees, saturation, lightness).with_alpha(self.a)
+    }
+
+    // Adds to the HSL saturation, negative amounts desaturate
+    pub fn saturate(self, amount: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue, saturation + amount, lightness).with_alpha(self.a)
+    }
+
+    // Adds to the HSL lightness, negative amounts darken
+    pub fn lighten(self, amount: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue, saturation, lightness + amount).with_alpha(self.a)
+    }
+
+    pub fn darken(self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    // Shared by the HSV and HSL constructors, offset is added to every channel
+    fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x)
+        };
+
+        let channel = |value: f32| ((value + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color::rgb(channel(r), channel(g), channel(b))
+    }
+
+    // Hue in degrees plus the largest and smallest channel from 0.0 to 1.0
+    fn hue_max_min(self) -> (f32, f32, f32) {
+        let r = self.r as f32 / 255.0;
+        let g = self.g as f32 / 255.0;
+        let b = self.b as f32 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+
+        (hue, max, min)
+    }
+
+    // Composites self on top of a background color
+    pub fn blend_over(self, background: Color, mode: BlendMode) -> Self {
+        Color::from_pixel(blend_pixel(background.to_pixel(), self.to_pixel(), mode))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BlendMode {
+    // Regular transparency, lerps from the buffer to the color by its alpha
+    #[default]
+    Alpha,
+    // Adds the color scaled by its alpha, for glows and lights
+    Additive,
+    // Darkens by multiplying with the color, for shadows
+    Multiply,
+    // Inverse of multiply, lightens without blowing out as quickly as additive
+    Screen,
+    // Writes the color as is, alpha included
+    Replace
+}
+
+// Both colors are premultiplied, the result keeps the buffer premultiplied as well
+pub fn blend_pixel(destination: u32, source: u32, mode: BlendMode) -> u32 {
+    blend_pixel_dithered(destination, source, mode, 0.5)
+}
+
+// Same as blend_pixel but channels are rounded up once their fraction passes the threshold
+// instead of at 0.5, see dither_threshold
+pub fn blend_pixel_dithered(destination: u32, source: u32, mode: BlendMode, threshold: f32) -> u32 {
+    let source_alpha = get_alpha(source);
+
+    match mode {
+        BlendMode::Alpha => {
+            if source_alpha == 1.0 {
+                source
+            } else {
+                map_channels(destination, source, threshold, |dst, src| src + dst * (1.0 - source_alpha))
+            }
+        },
+        BlendMode::Additive => {
+            map_channels(destination, source, threshold, |dst, src| dst + src)
+        },
+        BlendMode::Multiply => {
+            map_channels(destination, source, threshold, |dst, src| src * dst / 255.0 + dst * (1.0 - source_alpha))
+        },
+        BlendMode::Screen => {
+            map_channels(destination, source, threshold, |dst, src| src + dst - src * dst / 255.0)
+        },
+        BlendMode::Replace => source
+    }
+}
+
+// Same as blend_pixel but the math happens in linear light, converting through lookup tables
+pub fn blend_pixel_linear(destination: u32, source: u32, mode: BlendMode) -> u32 {
+    blend_pixel_linear_dithered(destination, source, mode, None)
+}
+
+// Dithering a linear blend needs the exact transfer curve since the lookup table already rounds
+pub fn blend_pixel_linear_dithered(destination: u32, source: u32, mode: BlendMode, threshold: Option<f32>) -> u32 {
+    let source_alpha = get_alpha(source);
+    if mode == BlendMode::Replace || (mode == BlendMode::Alpha && source_alpha == 1.0) {
+        return source;
+    }
+
+    let dst = srgb::color_to_linear(destination);
+    let src = srgb::color_to_linear(source);
+
+    let mut result = [0.0; 4];
+    result[0] = src[0] + dst[0] * (1.0 - source_alpha);
+
+    let mut i = 1;
+    while i < 4 {
+        result[i] = match mode {
+            BlendMode::Alpha => src[i] + dst[i] * (1.0 - source_alpha),
+            BlendMode::Additive => dst[i] + src[i],
+            BlendMode::Multiply => src[i] * dst[i] + dst[i] * (1.0 - source_alpha),
+            BlendMode::Screen => src[i] + dst[i] - src[i] * dst[i],
+            BlendMode::Replace => src[i]
+        };
+        // Keep the color valid as premultiplied
+        result[i] = result[i].clamp(0.0, result[0].min(1.0));
+        i += 1;
+    }
+
+    match threshold {
+        Some(value) => srgb::linear_to_color_dithered(result, value),
+        None => srgb::linear_to_color(result)
+    }
+}
+
+// Combines each channel of two colors, the alpha channel is composited with the usual
+// "over" operator no matter how the colors are combined. Alpha is rounded with the same
+// threshold as the color so the result stays valid as premultiplied.
+fn map_channels<F: Fn(f32, f32) -> f32>(a: u32, b: u32, threshold: f32, combine: F) -> u32 {
+    let channel = |shift: u32| {
+        let value = combine(((a >> shift) & 0xFF) as f32, ((b >> shift) & 0xFF) as f32);
+        (value + threshold).floor().clamp(0.0, 255.0) as u32
+    };
+
+    let a_alpha = (a >> 24) as f32;
+    let b_alpha = (b >> 24) as f32;
+    let alpha = (b_alpha + a_alpha * (1.0 - b_alpha / 255.0) + threshold).floor().clamp(0.0, 255.0) as u32;
+
+    (alpha << 24) | (channel(16) << 16) | (channel(8) << 8) | channel(0)
+}
+
+// 8x8 Bayer matrix, every value appears once and neighbouring cells are as far apart as possible
+static BAYER_MATRIX: [u8; 64] = [
+     0, 32,  8, 40,  2, 34, 10, 42,
+    48, 16, 56, 24, 50, 18, 58, 26,
+    12, 44,  4, 36, 14, 46,  6, 38,
+    60, 28, 52, 20, 62, 30, 54, 22,
+     3, 35, 11, 43,  1, 33,  9, 41,
+    51, 19, 59, 27, 49, 17, 57, 25,
+    15, 47,  7, 39, 13, 45,  5, 37,
+    63, 31, 55, 23, 61, 29, 53, 21
+];
+
+// Ordered dither threshold for a pixel, between 0 and 1. Rounding a channel up once its
+// fraction passes the threshold keeps the average of an area at the exact value, so slow
+// gradients turn into a fine pattern instead of visible bands. 0.5 is plain rounding.
+pub fn dither_threshold(x: i32, y: i32) -> f32 {
+    let index = ((y & 7) * 8 + (x & 7)) as usize;
+    (BAYER_MATRIX[index] as f32 + 0.5) / 64.0
+}
+
+pub fn get_alpha(color: u32) -> f32 {
+    (color >> 24) as f32 / 255.0
+}
+
+// Colors are premultiplied, so every channel including alpha is interpolated the same way
+pub fn lerp_color(a: u32, b: u32, t: f32) -> u32 {
+    let a_alpha = (a >> 24) as u8;
+    let a_red = (a >> 16) as u8;
+    let a_green = (a >> 8) as u8;
+    let a_blue = a as u8;
+
+    let b_alpha = (b >> 24) as u8;
+    let b_red = (b >> 16) as u8;
+    let b_green = (b >> 8) as u8;
+    let b_blue = b as u8;
+
+    let alpha = (a_alpha as f32 + t * (b_alpha as f32 - a_alpha as f32)) as u8;
+    let red = (a_red as f32 + t * (b_red as f32 - a_red as f32)) as u8;
+    let green = (a_green as f32 + t * (b_green as f32 - a_green as f32)) as u8;
+    let blue = (a_blue as f32 + t * (b_blue as f32 - a_blue as f32)) as u8;
+
+    // NOTE: This version uses fewer variables, if a performance concern
+    //let red = (a >> 16) as u8 + (t * ((b >> 16) as u8 - (a >> 16) as u8) as f32) as u8;
+    //let green = (a >> 8) as u8 + (t * ((b >> 8) as u8 - (a >> 8) as u8) as f32) as u8;
+    //let blue = a as u8 + (t * (b as u8 - a as u8) as f32) as u8;
+
+    ((alpha as u32) << 24) | ((red as u32) << 16) | ((green as u32) << 8) | blue as u32
+}
+
+// Converts a straight alpha color (like the ones picked in an image editor) to the premultiplied
+// form the renderer works with. Opaque colors are the same in both.
+pub fn premultiply(color: u32) -> u32 {
+    let alpha = color >> 24;
+    let scale = |channel: u32| (channel * alpha + 127) / 255;
+
+    (alpha << 24) |
+    (scale((color >> 16) & 0xFF) << 16) |
+    (scale((color >> 8) & 0xFF) << 8) |
+    scale(color & 0xFF)
+}
+
+pub fn unpremultiply(color: u32) -> u32 {
+    let alpha = color >> 24;
+    if alpha == 0 {
+        return 0;
+    }
+    let scale = |channel: u32| min((channel * 255 + alpha / 2) / alpha, 255);
+
+    (alpha << 24) |
+    (scale((color >> 16) & 0xFF) << 16) |
+    (scale((color >> 8) & 0xFF) << 8) |
+    scale(color & 0xFF)
+}
+
+// Scales every channel of a premultiplied color, used to apply antialiasing coverage
+pub fn scale_color(color: u32, t: f32) -> u32 {
+    let t = t.clamp(0.0, 1.0);
+    let scale = |channel: u32| (channel as f32 * t + 0.5) as u32;
+
+    (scale(color >> 24) << 24) |
+    (scale((color >> 16) & 0xFF) << 16) |
+    (scale((color >> 8) & 0xFF) << 8) |
+    scale(color & 0xFF)
+}
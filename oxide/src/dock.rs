@@ -0,0 +1,370 @@
+use crate::ui::UiLayout;
+use crate::{draw_rectangle, ButtonState, Color, OffscreenBuffer, Rectangle, Vector2};
+use std::fs;
+use std::io;
+
+// Thickness of the draggable bar between two docked regions, in UI units
+static SPLITTER_SIZE: f32 = 4.0;
+// Regions can't be dragged smaller than this fraction of their split
+static MIN_SPLIT_RATIO: f32 = 0.05;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitDirection {
+    // Children are placed side by side
+    Horizontal,
+    // Children are placed on top of each other
+    Vertical
+}
+
+#[derive(Clone, Debug)]
+pub enum DockNode {
+    Panel {
+        name: String
+    },
+    Split {
+        direction: SplitDirection,
+        // Fraction of the region given to the first child
+        ratio: f32,
+        first: Box<DockNode>,
+        second: Box<DockNode>
+    }
+}
+
+impl DockNode {
+    fn contains_panel(&self, panel: &str) -> bool {
+        match self {
+            DockNode::Panel { name } => name == panel,
+            DockNode::Split { first, second, .. } => first.contains_panel(panel) || second.contains_panel(panel)
+        }
+    }
+
+    fn split_panel(&mut self, panel: &str, direction: SplitDirection, ratio: f32, new_panel: &str) -> bool {
+        match self {
+            DockNode::Panel { name } => {
+                if name != panel {
+                    return false;
+                }
+
+                let old = DockNode::Panel { name: name.clone() };
+                *self = DockNode::Split {
+                    direction,
+                    ratio: ratio.clamp(MIN_SPLIT_RATIO, 1.0 - MIN_SPLIT_RATIO),
+                    first: Box::new(old),
+                    second: Box::new(DockNode::Panel { name: new_panel.to_string() })
+                };
+                true
+            },
+            DockNode::Split { first, second, .. } => {
+                first.split_panel(panel, direction, ratio, new_panel) ||
+                second.split_panel(panel, direction, ratio, new_panel)
+            }
+        }
+    }
+
+    // Removes a panel by replacing its parent split with the sibling
+    fn remove_panel(&mut self, panel: &str) -> bool {
+        let replacement = match self {
+            DockNode::Panel { .. } => return false,
+            DockNode::Split { first, second, .. } => {
+                if matches!(first.as_ref(), DockNode::Panel { name } if name == panel) {
+                    second.as_ref().clone()
+                } else if matches!(second.as_ref(), DockNode::Panel { name } if name == panel) {
+                    first.as_ref().clone()
+                } else {
+                    return first.remove_panel(panel) || second.remove_panel(panel);
+                }
+            }
+        };
+
+        *self = replacement;
+        true
+    }
+
+    fn collect_rects(&self, bounds: Rectangle, rects: &mut Vec<(String, Rectangle)>) {
+        match self {
+            DockNode::Panel { name } => rects.push((name.clone(), bounds)),
+            DockNode::Split { direction, ratio, first, second } => {
+                let (first_bounds, second_bounds) = split_bounds(bounds, *direction, *ratio);
+                first.collect_rects(first_bounds, rects);
+                second.collect_rects(second_bounds, rects);
+            }
+        }
+    }
+
+    fn collect_splitters(&self, bounds: Rectangle, path: &mut Vec<bool>, splitters: &mut Vec<(Vec<bool>, Rectangle)>) {
+        if let DockNode::Split { direction, ratio, first, second } = self {
+            splitters.push((path.clone(), splitter_rect(bounds, *direction, *ratio)));
+
+            let (first_bounds, second_bounds) = split_bounds(bounds, *direction, *ratio);
+            path.push(false);
+            first.collect_splitters(first_bounds, path, splitters);
+            path.pop();
+            path.push(true);
+            second.collect_splitters(second_bounds, path, splitters);
+            path.pop();
+        }
+    }
+
+    fn write_config(&self, text: &mut String) {
+        match self {
+            DockNode::Panel { name } => text.push_str(&format!("panel {}\n", name)),
+            DockNode::Split { direction, ratio, first, second } => {
+                let direction = match direction {
+                    SplitDirection::Horizontal => "horizontal",
+                    SplitDirection::Vertical => "vertical"
+                };
+                text.push_str(&format!("split {} {}\n", direction, ratio));
+                first.write_config(text);
+                second.write_config(text);
+            }
+        }
+    }
+
+    fn read_config<'a>(lines: &mut impl Iterator<Item = &'a str>) -> io::Result<DockNode> {
+        let line = match lines.next() {
+            Some(value) => value,
+            None => return Err(invalid_layout("Layout ended before every split had two children"))
+        };
+
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("panel") => {
+                let name: Vec<&str> = words.collect();
+                Ok(DockNode::Panel { name: name.join(" ") })
+            },
+            Some("split") => {
+                let direction = match words.next() {
+                    Some("horizontal") => SplitDirection::Horizontal,
+                    Some("vertical") => SplitDirection::Vertical,
+                    _ => return Err(invalid_layout(line))
+                };
+                let ratio: f32 = match words.next().and_then(|ratio| ratio.parse().ok()) {
+                    Some(value) => value,
+                    None => return Err(invalid_layout(line))
+                };
+
+                let first = DockNode::read_config(lines)?;
+                let second = DockNode::read_config(lines)?;
+
+                Ok(DockNode::Split {
+                    direction,
+                    ratio: ratio.clamp(MIN_SPLIT_RATIO, 1.0 - MIN_SPLIT_RATIO),
+                    first: Box::new(first),
+                    second: Box::new(second)
+                })
+            },
+            _ => Err(invalid_layout(line))
+        }
+    }
+}
+
+// A tree of resizable regions that tools can place inspectors, timelines and viewports in
+#[derive(Clone, Debug)]
+pub struct DockLayout {
+    pub root: DockNode,
+    // Path to the split whose bar is being dragged, false means first child
+    dragging: Option<Vec<bool>>
+}
+
+impl DockLayout {
+    pub fn new(panel: &str) -> Self {
+        DockLayout {
+            root: DockNode::Panel { name: panel.to_string() },
+            dragging: None
+        }
+    }
+
+    pub fn contains(&self, panel: &str) -> bool {
+        self.root.contains_panel(panel)
+    }
+
+    // Splits an existing panel in two, the new panel gets 1.0 - ratio of the space
+    pub fn split(&mut self, panel: &str, direction: SplitDirection, ratio: f32, new_panel: &str) -> bool {
+        if self.contains(new_panel) {
+            return false;
+        }
+        self.dragging = None;
+        self.root.split_panel(panel, direction, ratio, new_panel)
+    }
+
+    pub fn remove(&mut self, panel: &str) -> bool {
+        self.dragging = None;
+        self.root.remove_panel(panel)
+    }
+
+    pub fn panel_rects(&self, bounds: Rectangle) -> Vec<(String, Rectangle)> {
+        let mut rects = Vec::new();
+        self.root.collect_rects(bounds, &mut rects);
+        rects
+    }
+
+    pub fn panel_rect(&self, bounds: Rectangle, panel: &str) -> Option<Rectangle> {
+        self.panel_rects(bounds)
+            .into_iter()
+            .find(|(name, _)| name == panel)
+            .map(|(_, rect)| rect)
+    }
+
+    // Lets the splitter bars be dragged with a mouse button, cursor is in UI units.
+    // Returns true while a splitter is being dragged so the caller can ignore the input.
+    pub fn update(&mut self, bounds: Rectangle, cursor: Vector2, button: ButtonState) -> bool {
+        let pressed = button.is_down && !button.was_down;
+
+        if !button.is_down {
+            self.dragging = None;
+            return false;
+        }
+
+        if pressed {
+            let mut splitters = Vec::new();
+            self.root.collect_splitters(bounds, &mut Vec::new(), &mut splitters);
+            self.dragging = splitters
+                .into_iter()
+                .find(|(_, rect)| rect_contains(*rect, cursor))
+                .map(|(path, _)| path);
+        }
+
+        let path = match &self.dragging {
+            Some(value) => value.clone(),
+            None => return false
+        };
+
+        // Walk down to the dragged split while keeping track of its region
+        let mut node = &mut self.root;
+        let mut node_bounds = bounds;
+        for second in path {
+            match node {
+                DockNode::Split { direction, ratio, first, second: second_node } => {
+                    let (first_bounds, second_bounds) = split_bounds(node_bounds, *direction, *ratio);
+                    if second {
+                        node = second_node.as_mut();
+                        node_bounds = second_bounds;
+                    } else {
+                        node = first.as_mut();
+                        node_bounds = first_bounds;
+                    }
+                },
+                DockNode::Panel { .. } => {
+                    self.dragging = None;
+                    return false;
+                }
+            }
+        }
+
+        if let DockNode::Split { direction, ratio, .. } = node {
+            let new_ratio = match direction {
+                SplitDirection::Horizontal => (cursor.x - node_bounds.x) / node_bounds.width,
+                SplitDirection::Vertical => (cursor.y - node_bounds.y) / node_bounds.height
+            };
+
+            if new_ratio.is_finite() {
+                *ratio = new_ratio.clamp(MIN_SPLIT_RATIO, 1.0 - MIN_SPLIT_RATIO);
+            }
+        }
+
+        true
+    }
+
+    /// # Safety
+    /// `buffer.memory` must point to a live pixel buffer matching its width, height and pitch
+    pub unsafe fn draw(&self, buffer: &mut OffscreenBuffer, ui: &UiLayout, bounds: Rectangle, panel_color: Color, splitter_color: Color) {
+        let camera = ui.camera();
+
+        for (_, rect) in self.panel_rects(bounds) {
+            draw_rectangle(buffer, camera, rect, panel_color);
+        }
+
+        let mut splitters = Vec::new();
+        self.root.collect_splitters(bounds, &mut Vec::new(), &mut splitters);
+        for (_, rect) in splitters {
+            draw_rectangle(buffer, camera, rect, splitter_color);
+        }
+    }
+
+    // Preorder list of nodes, one per line: "split horizontal 0.25" followed by its two children, or "panel Inspector"
+    pub fn to_config_string(&self) -> String {
+        let mut text = String::new();
+        self.root.write_config(&mut text);
+        text
+    }
+
+    pub fn from_config_string(text: &str) -> io::Result<Self> {
+        let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let root = DockNode::read_config(&mut lines)?;
+
+        if let Some(line) = lines.next() {
+            return Err(invalid_layout(line));
+        }
+
+        Ok(DockLayout {
+            root,
+            dragging: None
+        })
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_config_string())
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        DockLayout::from_config_string(&fs::read_to_string(path)?)
+    }
+}
+
+// Regions on either side of a split, leaving room for the splitter bar
+fn split_bounds(bounds: Rectangle, direction: SplitDirection, ratio: f32) -> (Rectangle, Rectangle) {
+    let half_splitter = SPLITTER_SIZE / 2.0;
+
+    match direction {
+        SplitDirection::Horizontal => {
+            let split_x = bounds.width * ratio;
+            (
+                Rectangle { x: bounds.x, y: bounds.y, width: (split_x - half_splitter).max(0.0), height: bounds.height },
+                Rectangle {
+                    x: bounds.x + split_x + half_splitter,
+                    y: bounds.y,
+                    width: (bounds.width - split_x - half_splitter).max(0.0),
+                    height: bounds.height
+                }
+            )
+        },
+        SplitDirection::Vertical => {
+            let split_y = bounds.height * ratio;
+            (
+                Rectangle { x: bounds.x, y: bounds.y, width: bounds.width, height: (split_y - half_splitter).max(0.0) },
+                Rectangle {
+                    x: bounds.x,
+                    y: bounds.y + split_y + half_splitter,
+                    width: bounds.width,
+                    height: (bounds.height - split_y - half_splitter).max(0.0)
+                }
+            )
+        }
+    }
+}
+
+fn splitter_rect(bounds: Rectangle, direction: SplitDirection, ratio: f32) -> Rectangle {
+    match direction {
+        SplitDirection::Horizontal => Rectangle {
+            x: bounds.x + bounds.width * ratio - SPLITTER_SIZE / 2.0,
+            y: bounds.y,
+            width: SPLITTER_SIZE,
+            height: bounds.height
+        },
+        SplitDirection::Vertical => Rectangle {
+            x: bounds.x,
+            y: bounds.y + bounds.height * ratio - SPLITTER_SIZE / 2.0,
+            width: bounds.width,
+            height: SPLITTER_SIZE
+        }
+    }
+}
+
+fn rect_contains(rect: Rectangle, point: Vector2) -> bool {
+    point.x >= rect.x && point.x <= rect.x + rect.width &&
+    point.y >= rect.y && point.y <= rect.y + rect.height
+}
+
+fn invalid_layout(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid dock layout line: {}", line))
+}
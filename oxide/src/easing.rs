@@ -0,0 +1,155 @@
+use crate::math::angle_difference;
+use crate::{Camera, Color, Vector2};
+use std::f32::consts::TAU;
+
+// Shape of an animation over time. Each takes how far along it is from 0.0 to 1.0 and gives how
+// far along the value should be, which can overshoot past either end for the elastic ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    // Winds up with growing wobbles before shooting to the end
+    ElasticIn,
+    // Shoots past the end and wobbles back into place like a spring
+    ElasticOut,
+    BounceIn,
+    // Hits the end and bounces a few times like a dropped ball
+    BounceOut
+}
+
+impl Easing {
+    // t is clamped, the ends are always exactly 0.0 and 1.0
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - 2.0 * (1.0 - t) * (1.0 - t) },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - 4.0 * (1.0 - t).powi(3) },
+            Easing::ElasticIn => 1.0 - elastic_out(1.0 - t),
+            Easing::ElasticOut => elastic_out(t),
+            Easing::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Easing::BounceOut => bounce_out(t)
+        }
+    }
+}
+
+// Decaying sine wave around the end, three wobbles over the animation
+fn elastic_out(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+    2.0_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * TAU / 3.0).sin() + 1.0
+}
+
+// Parabolas that each reach the end, smaller every time
+fn bounce_out(t: f32) -> f32 {
+    let strength = 7.5625;
+    let width = 2.75;
+    if t < 1.0 / width {
+        strength * t * t
+    } else if t < 2.0 / width {
+        let t = t - 1.5 / width;
+        strength * t * t + 0.75
+    } else if t < 2.5 / width {
+        let t = t - 2.25 / width;
+        strength * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / width;
+        strength * t * t + 0.984375
+    }
+}
+
+// Values a Tween can animate, blended part of the way to another value
+pub trait Tweenable: Copy {
+    fn tween(self, other: Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn tween(self, other: f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for Vector2 {
+    fn tween(self, other: Vector2, t: f32) -> Vector2 {
+        self.lerp(other, t)
+    }
+}
+
+impl Tweenable for Color {
+    fn tween(self, other: Color, t: f32) -> Color {
+        self.lerp(other, t)
+    }
+}
+
+impl Tweenable for Camera {
+    fn tween(self, other: Camera, t: f32) -> Camera {
+        Camera {
+            x: self.x.tween(other.x, t),
+            y: self.y.tween(other.y, t),
+            width: self.width.tween(other.width, t),
+            height: self.height.tween(other.height, t),
+            y_scale: self.y_scale.tween(other.y_scale, t),
+            // The short way around
+            rotation: self.rotation + angle_difference(self.rotation, other.rotation) * t,
+            screen_offset: self.screen_offset.tween(other.screen_offset, t),
+            // Flips halfway, it can't be between
+            y_up: if t < 0.5 { self.y_up } else { other.y_up }
+        }
+    }
+}
+
+// A value moving from one place to another over a duration, advanced with the frame's delta time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tween<T: Tweenable> {
+    pub from: T,
+    pub to: T,
+    // Milliseconds, like GameState::delta_time
+    pub duration: f32,
+    pub elapsed: f32,
+    pub easing: Easing
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Tween { from, to, duration, elapsed: 0.0, easing }
+    }
+
+    // Returns the value after the step, to assign in one line
+    pub fn advance(&mut self, delta_time: f32) -> T {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+        self.value()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    // How far along the duration it is, before easing
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 { self.elapsed / self.duration } else { 1.0 }
+    }
+
+    pub fn value(&self) -> T {
+        self.from.tween(self.to, self.easing.apply(self.progress()))
+    }
+
+    // Heads somewhere new from wherever the value is now, so changing the target halfway
+    // through doesn't jump
+    pub fn retarget(&mut self, to: T, duration: f32) {
+        self.from = self.value();
+        self.to = to;
+        self.duration = duration;
+        self.elapsed = 0.0;
+    }
+}
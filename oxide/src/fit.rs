@@ -0,0 +1,161 @@
+use crate::{BezierCurve, Vector2};
+
+// Newton steps tried on the sample t values before a piece that nearly fits is split instead
+static MAX_REPARAMETERIZE: u32 = 4;
+
+// The cubic from start to end, leaving along start_tangent and arriving against end_tangent,
+// that passes closest to the samples. Each sample is the t it should be at and the point. Only
+// the handle lengths are fitted, with least squares as in Philip Schneider's curve fitting.
+pub fn fit_cubic(start: Vector2, end: Vector2, start_tangent: Vector2, end_tangent: Vector2, samples: &[(f32, Vector2)]) -> BezierCurve {
+    let start_tangent = start_tangent.normalized();
+    let end_tangent = end_tangent.normalized();
+
+    let mut c = [[0.0; 2]; 2];
+    let mut x = [0.0; 2];
+    for (t, sample) in samples {
+        let u = *t;
+        let v = 1.0 - u;
+        let (b0, b1, b2, b3) = (v * v * v, 3.0 * u * v * v, 3.0 * u * u * v, u * u * u);
+        let a1 = start_tangent * b1;
+        let a2 = end_tangent * b2;
+        let rest = *sample - (start * (b0 + b1) + end * (b2 + b3));
+
+        c[0][0] += a1.dot(a1);
+        c[0][1] += a1.dot(a2);
+        c[1][1] += a2.dot(a2);
+        x[0] += a1.dot(rest);
+        x[1] += a2.dot(rest);
+    }
+
+    let determinant = c[0][0] * c[1][1] - c[0][1] * c[0][1];
+    let fallback = (end - start).length() / 3.0;
+    let (mut alpha_1, mut alpha_2) = (fallback, fallback);
+    if determinant.abs() > 1e-9 {
+        alpha_1 = (x[0] * c[1][1] - x[1] * c[0][1]) / determinant;
+        alpha_2 = (c[0][0] * x[1] - c[0][1] * x[0]) / determinant;
+    }
+    // Handles pointing backwards would make a loop, a third of the chord is a safe guess
+    if alpha_1 < 1e-6 || alpha_2 < 1e-6 {
+        alpha_1 = fallback;
+        alpha_2 = fallback;
+    }
+
+    BezierCurve::new(start, start + start_tangent * alpha_1, end + end_tangent * alpha_2, end)
+}
+
+// Fewest cubics, joined smoothly, that pass within tolerance of every point. Made for freehand
+// strokes recorded from the mouse, following Schneider's algorithm: fit one cubic to all the
+// points, nudge the t of each point closer to the curve if it nearly fits, and otherwise split
+// at the worst point and fit both halves.
+pub fn fit_points(points: &[Vector2], tolerance: f32) -> Vec<BezierCurve> {
+    let mut curves = Vec::new();
+
+    // Repeated points have no direction, they would give broken tangents
+    let mut path: Vec<Vector2> = Vec::with_capacity(points.len());
+    for point in points {
+        match path.last() {
+            Some(last) if (*point - *last).length() < 1e-6 => {},
+            _ => path.push(*point)
+        }
+    }
+    if path.len() < 2 {
+        return curves;
+    }
+
+    let last = path.len() - 1;
+    let start_tangent = (path[1] - path[0]).normalized();
+    let end_tangent = (path[last - 1] - path[last]).normalized();
+    fit_range(&path, start_tangent, end_tangent, tolerance, &mut curves);
+    curves
+}
+
+fn fit_range(points: &[Vector2], start_tangent: Vector2, end_tangent: Vector2, tolerance: f32, curves: &mut Vec<BezierCurve>) {
+    let start = points[0];
+    let end = points[points.len() - 1];
+    if points.len() == 2 {
+        let handle = (end - start).length() / 3.0;
+        curves.push(BezierCurve::new(start, start + start_tangent * handle, end + end_tangent * handle, end));
+        return;
+    }
+
+    let mut parameters = chord_parameters(points);
+    let mut curve = fit_cubic(start, end, start_tangent, end_tangent, &samples(&parameters, points));
+    let (mut error, mut split) = max_error(&curve, points, &parameters);
+    if error <= tolerance {
+        curves.push(curve);
+        return;
+    }
+
+    // Close misses are usually the t values being off rather than the shape being wrong
+    if error <= tolerance * 2.0 {
+        let mut i = 0;
+        while i < MAX_REPARAMETERIZE {
+            reparameterize(&curve, points, &mut parameters);
+            curve = fit_cubic(start, end, start_tangent, end_tangent, &samples(&parameters, points));
+            (error, split) = max_error(&curve, points, &parameters);
+            if error <= tolerance {
+                curves.push(curve);
+                return;
+            }
+            i += 1;
+        }
+    }
+
+    // Both halves meet at the worst point heading the same way, so the joint is smooth
+    let mut center_tangent = (points[split - 1] - points[split + 1]).normalized();
+    if center_tangent.x == 0.0 && center_tangent.y == 0.0 {
+        center_tangent = (points[split - 1] - points[split]).normalized();
+    }
+    fit_range(&points[..=split], start_tangent, center_tangent, tolerance, curves);
+    fit_range(&points[split..], center_tangent * -1.0, end_tangent, tolerance, curves);
+}
+
+// t of each point by how far along the polyline it is
+fn chord_parameters(points: &[Vector2]) -> Vec<f32> {
+    let mut parameters = vec![0.0; points.len()];
+    let mut i = 1;
+    while i < points.len() {
+        parameters[i] = parameters[i - 1] + (points[i] - points[i - 1]).length();
+        i += 1;
+    }
+
+    let total = parameters[points.len() - 1].max(1e-6);
+    for parameter in parameters.iter_mut() {
+        *parameter /= total;
+    }
+    parameters
+}
+
+fn samples(parameters: &[f32], points: &[Vector2]) -> Vec<(f32, Vector2)> {
+    parameters.iter().copied().zip(points.iter().copied()).collect()
+}
+
+// Furthest any point is from where the curve puts it, and which point that is. The ends are on
+// the curve already, so the worst point is always one that can be split at.
+fn max_error(curve: &BezierCurve, points: &[Vector2], parameters: &[f32]) -> (f32, usize) {
+    let mut error = 0.0;
+    let mut split = points.len() / 2;
+    let mut i = 1;
+    while i + 1 < points.len() {
+        let distance = (curve.evaluate(parameters[i]) - points[i]).length();
+        if distance > error {
+            error = distance;
+            split = i;
+        }
+        i += 1;
+    }
+    (error, split)
+}
+
+// One Newton step per point towards the t where the curve is closest to it
+fn reparameterize(curve: &BezierCurve, points: &[Vector2], parameters: &mut [f32]) {
+    for (parameter, point) in parameters.iter_mut().zip(points) {
+        let offset = curve.evaluate(*parameter) - *point;
+        let first = curve.derivative(*parameter);
+        let second = curve.second_derivative(*parameter);
+        let denominator = first.dot(first) + offset.dot(second);
+        if denominator.abs() > 1e-9 {
+            *parameter = (*parameter - offset.dot(first) / denominator).clamp(0.0, 1.0);
+        }
+    }
+}
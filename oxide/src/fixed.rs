@@ -0,0 +1,225 @@
+use crate::{BezierCurve, Vector2};
+
+// Q32.32 fixed point number, 32 bits of whole part and 32 of fraction in an i64. Every operation
+// is integer math, so a simulation run with these gives bit for bit the same results on every
+// machine, which floats don't promise. Only convert to f32 for drawing.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(pub i64);
+
+static FRACTION_BITS: u32 = 32;
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << 32);
+
+    pub fn from_int(value: i32) -> Self {
+        Fixed((value as i64) << FRACTION_BITS)
+    }
+
+    // Exact for any fraction with a power of two below, so literals like 0.5 are safe to
+    // build a simulation from
+    pub fn from_f32(value: f32) -> Self {
+        Fixed((value as f64 * (1u64 << FRACTION_BITS) as f64).round() as i64)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        (self.0 as f64 / (1u64 << FRACTION_BITS) as f64) as f32
+    }
+
+    // Rounds towards negative infinity like the integer shifts do
+    pub fn floor(self) -> i32 {
+        (self.0 >> FRACTION_BITS) as i32
+    }
+
+    pub fn abs(self) -> Self {
+        Fixed(self.0.abs())
+    }
+
+    // Zero for negative numbers
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Fixed::ZERO;
+        }
+        // sqrt(raw * 2^32) is the raw value of the root
+        Fixed(integer_sqrt((self.0 as u128) << FRACTION_BITS) as i64)
+    }
+}
+
+// Largest whole number whose square is at most value, with Newton's method
+fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    let mut root = 1u128 << ((128 - value.leading_zeros()).div_ceil(2));
+    loop {
+        let next = (root + value / root) / 2;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+impl std::ops::Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_add(other.0))
+    }
+}
+
+impl std::ops::Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl std::ops::Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed(((self.0 as i128 * other.0 as i128) >> FRACTION_BITS) as i64)
+    }
+}
+
+impl std::ops::Div for Fixed {
+    type Output = Fixed;
+
+    // Dividing by zero saturates instead of panicking, a simulation shouldn't crash on it
+    fn div(self, other: Fixed) -> Fixed {
+        if other.0 == 0 {
+            return if self.0 < 0 { Fixed(i64::MIN) } else { Fixed(i64::MAX) };
+        }
+        Fixed((((self.0 as i128) << FRACTION_BITS) / other.0 as i128) as i64)
+    }
+}
+
+impl std::ops::Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(self.0.wrapping_neg())
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedVector2 {
+    pub x: Fixed,
+    pub y: Fixed
+}
+
+impl FixedVector2 {
+    pub fn new(x: Fixed, y: Fixed) -> Self {
+        FixedVector2 { x, y }
+    }
+
+    pub fn from_vector2(vector: Vector2) -> Self {
+        FixedVector2 { x: Fixed::from_f32(vector.x), y: Fixed::from_f32(vector.y) }
+    }
+
+    pub fn to_vector2(self) -> Vector2 {
+        Vector2 { x: self.x.to_f32(), y: self.y.to_f32() }
+    }
+
+    pub fn lerp(self, other: FixedVector2, t: Fixed) -> Self {
+        self + (other - self) * t
+    }
+
+    pub fn dot(self, other: FixedVector2) -> Fixed {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length(self) -> Fixed {
+        self.dot(self).sqrt()
+    }
+}
+
+impl std::ops::Add for FixedVector2 {
+    type Output = FixedVector2;
+
+    fn add(self, other: FixedVector2) -> FixedVector2 {
+        FixedVector2 {
+            x: self.x + other.x,
+            y: self.y + other.y
+        }
+    }
+}
+
+impl std::ops::Sub for FixedVector2 {
+    type Output = FixedVector2;
+
+    fn sub(self, other: FixedVector2) -> FixedVector2 {
+        FixedVector2 {
+            x: self.x - other.x,
+            y: self.y - other.y
+        }
+    }
+}
+
+impl std::ops::Mul<Fixed> for FixedVector2 {
+    type Output = FixedVector2;
+
+    fn mul(self, scalar: Fixed) -> FixedVector2 {
+        FixedVector2 {
+            x: self.x * scalar,
+            y: self.y * scalar
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedBezierCurve {
+    pub p0: FixedVector2,
+    pub p1: FixedVector2,
+    pub p2: FixedVector2,
+    pub p3: FixedVector2
+}
+
+impl FixedBezierCurve {
+    pub fn new(p0: FixedVector2, p1: FixedVector2, p2: FixedVector2, p3: FixedVector2) -> Self {
+        FixedBezierCurve { p0, p1, p2, p3 }
+    }
+
+    pub fn from_bezier_curve(curve: BezierCurve) -> Self {
+        FixedBezierCurve::new(
+            FixedVector2::from_vector2(curve.p0),
+            FixedVector2::from_vector2(curve.p1),
+            FixedVector2::from_vector2(curve.p2),
+            FixedVector2::from_vector2(curve.p3))
+    }
+
+    // For drawing, the renderer only takes f32 curves
+    pub fn to_bezier_curve(&self) -> BezierCurve {
+        BezierCurve::new(self.p0.to_vector2(), self.p1.to_vector2(), self.p2.to_vector2(), self.p3.to_vector2())
+    }
+
+    // With de Casteljau's lerps, the same steps on every machine
+    pub fn evaluate(&self, t: Fixed) -> FixedVector2 {
+        let p01 = self.p0.lerp(self.p1, t);
+        let p12 = self.p1.lerp(self.p2, t);
+        let p23 = self.p2.lerp(self.p3, t);
+        p01.lerp(p12, t).lerp(p12.lerp(p23, t), t)
+    }
+
+    pub fn derivative(&self, t: Fixed) -> FixedVector2 {
+        let three = Fixed::from_int(3);
+        let d0 = (self.p1 - self.p0) * three;
+        let d1 = (self.p2 - self.p1) * three;
+        let d2 = (self.p3 - self.p2) * three;
+        d0.lerp(d1, t).lerp(d1.lerp(d2, t), t)
+    }
+
+    pub fn split(&self, t: Fixed) -> (FixedBezierCurve, FixedBezierCurve) {
+        let p01 = self.p0.lerp(self.p1, t);
+        let p12 = self.p1.lerp(self.p2, t);
+        let p23 = self.p2.lerp(self.p3, t);
+        let p012 = p01.lerp(p12, t);
+        let p123 = p12.lerp(p23, t);
+        let point = p012.lerp(p123, t);
+
+        (FixedBezierCurve::new(self.p0, p01, p012, point), FixedBezierCurve::new(point, p123, p23, self.p3))
+    }
+}
@@ -0,0 +1,119 @@
+use crate::arc_length::ArcLengthTable;
+use crate::bezier_path::BezierPath;
+use crate::Vector2;
+
+// What a follower does when it reaches the end of its path
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FollowMode {
+    // Stops at the end
+    #[default]
+    Once,
+    // Jumps back to the start, meant for closed paths
+    Loop,
+    // Turns around and heads back, forever
+    PingPong
+}
+
+// Moves along a path at a constant speed. Equal steps in t aren't equal distances along a
+// bezier, so the position is found by distance through the arc length of each segment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathFollower {
+    // World units per millisecond, like GameState::delta_time
+    pub speed: f32,
+    pub mode: FollowMode,
+    path: BezierPath,
+    // One per segment of the path, rebuilt whenever the path changes
+    tables: Vec<ArcLengthTable>,
+    distance: f32,
+    forward: bool
+}
+
+impl PathFollower {
+    pub fn new(path: BezierPath, speed: f32, mode: FollowMode) -> Self {
+        let tables = path.segments.iter().map(|segment| ArcLengthTable::new(*segment)).collect();
+        PathFollower {
+            speed,
+            mode,
+            path,
+            tables,
+            distance: 0.0,
+            forward: true
+        }
+    }
+
+    pub fn path(&self) -> &BezierPath {
+        &self.path
+    }
+
+    // Swaps the path being followed and keeps the distance travelled, clamped to the new length
+    pub fn set_path(&mut self, path: BezierPath) {
+        self.tables = path.segments.iter().map(|segment| ArcLengthTable::new(*segment)).collect();
+        self.path = path;
+        self.distance = self.distance.clamp(0.0, self.length());
+    }
+
+    pub fn length(&self) -> f32 {
+        self.tables.iter().map(|table| table.length()).sum()
+    }
+
+    // Distance travelled from the start of the path
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.mode == FollowMode::Once && self.distance >= self.length()
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        let length = self.length();
+        if length <= 0.0 {
+            self.distance = 0.0;
+            return;
+        }
+
+        let step = self.speed * delta_time;
+        let distance = if self.forward { self.distance + step } else { self.distance - step };
+        self.distance = match self.mode {
+            FollowMode::Once => distance.clamp(0.0, length),
+            FollowMode::Loop => distance.rem_euclid(length),
+            FollowMode::PingPong => {
+                // Every whole length travelled past an end is one more turn around
+                let bounced = distance.rem_euclid(length * 2.0);
+                let turns = (distance / length).floor() as i64;
+                if turns.rem_euclid(2) == 1 {
+                    self.forward = !self.forward;
+                }
+                if bounced > length { length * 2.0 - bounced } else { bounced }
+            }
+        };
+    }
+
+    pub fn position(&self) -> Vector2 {
+        match self.locate() {
+            Some((table, distance)) => table.evaluate_at_distance(distance),
+            None => Vector2::zero()
+        }
+    }
+
+    // Unit direction the follower is moving in, for rotating whatever rides along the path
+    pub fn orientation(&self) -> Vector2 {
+        let tangent = match self.locate() {
+            Some((table, distance)) => table.curve.tangent(table.t_at_distance(distance)),
+            None => return Vector2::zero()
+        };
+        if self.forward { tangent } else { tangent * -1.0 }
+    }
+
+    // Segment the follower is on and how far into it
+    fn locate(&self) -> Option<(&ArcLengthTable, f32)> {
+        let mut remaining = self.distance;
+        for table in &self.tables {
+            if remaining <= table.length() {
+                return Some((table, remaining));
+            }
+            remaining -= table.length();
+        }
+        self.tables.last().map(|table| (table, table.length()))
+    }
+}
@@ -1,9 +1,90 @@
 use std::ffi::c_void;
-use std::ptr;
 use std::cmp::min;
 use std::cmp::max;
-use windows::Win32::Graphics::Gdi::BITMAPINFO;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::ptr::null_mut;
+use std::time::Instant;
+use actions::ActionMap;
+use arc_length::ArcLengthTable;
+use bezier_path::{BezierPath, PathMorph};
+use easing::Tween;
+use fit::fit_points;
+use follower::PathFollower;
+use offset::offset_curve;
+use color::{blend_pixel_dithered, blend_pixel_linear_dithered, dither_threshold, lerp_color, scale_color};
+use simd::{blend_pixels_alpha, fill_pixels};
+use spatial::CurveIndex;
+use mask::Mask;
+use measure::{curve_between, MeasureTool, Measurement};
+use path::{fill_contours, FillRule};
+use palette::IndexedFrame;
+use quadratic::cubic_to_quadratics;
+use rand::Rng;
+use postprocess::PostProcessStack;
+use stroke::{fill_convex_union, fill_convex_union_shaded, stroke_polyline};
+use shake::CameraShake;
+use snap::Snapping;
+use selection::{Marquee, Selectable, Selection};
+use svg::{SvgDocument, SvgStroke};
+use text::{text_size, TEXT_PADDING};
+use transform::Transform2D;
+use undo::{Edit, UndoStack};
+use profiler::{FrameProfiler, HitchWatchdog};
+use ui::{Anchor, UiElement, UiLayout};
+use zoom::CameraZoom;
+use render::{DirtyRegions, Layer, RenderCommand, RenderCommands};
+
+pub mod actions;
+pub mod arc_length;
+pub mod atlas;
+pub mod bezier_path;
+pub mod bitmap;
+pub mod color;
+pub mod dock;
+pub mod easing;
+pub mod fit;
+#[cfg(feature = "fixed")]
+pub mod fixed;
+pub mod follower;
+pub mod mask;
+pub mod math;
+pub mod measure;
+pub mod offset;
+pub mod path;
+pub mod platform;
+pub mod palette;
+pub mod postprocess;
+pub mod precise;
+pub mod prelude;
+pub mod profiler;
+pub mod quadratic;
+pub mod rand;
+pub mod render;
+pub mod scene;
+pub mod selection;
+pub mod shake;
+pub mod simd;
+pub mod simplify;
+pub mod snap;
+pub mod spatial;
+pub mod spline;
+pub mod srgb;
+pub mod stroke;
+pub mod svg;
+pub mod text;
+pub mod target;
+pub mod transform;
+pub mod undo;
+pub mod ui;
+pub mod zoom;
+
+pub use color::{BlendMode, Color};
+pub use quadratic::QuadraticBezier;
+pub use stroke::{LineCap, LineJoin};
+
+pub static CONFIG_PATH: &str = "oxide.cfg";
+// Written by the ExportSvg action, next to the config
+pub static FRAME_SVG_PATH: &str = "oxide_frame.svg";
+pub static SCENE_SVG_PATH: &str = "oxide_scene.svg";
 
 #[derive(Clone, Copy, Default)]
 pub struct ButtonState {
@@ -28,11 +109,77 @@ pub struct InputController {
     pub a: ButtonState,
     pub s: ButtonState,
     pub d: ButtonState,
+    pub c: ButtonState,
+    pub f: ButtonState,
+    pub e: ButtonState,
+    pub m: ButtonState,
+    pub p: ButtonState,
+    pub r: ButtonState,
+    pub x: ButtonState,
+    pub y: ButtonState,
+    pub z: ButtonState,
     pub up: ButtonState,
     pub left: ButtonState,
     pub down: ButtonState,
     pub right: ButtonState,
-    pub esc: ButtonState
+    pub esc: ButtonState,
+    pub space: ButtonState,
+    pub shift: ButtonState,
+    pub ctrl: ButtonState,
+    pub gamepad: GamepadState
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct GamepadState {
+    pub is_connected: bool,
+    pub a: ButtonState,
+    pub b: ButtonState,
+    pub x: ButtonState,
+    pub y: ButtonState,
+    pub start: ButtonState,
+    pub back: ButtonState,
+    pub left_shoulder: ButtonState,
+    pub right_shoulder: ButtonState,
+    pub dpad_up: ButtonState,
+    pub dpad_left: ButtonState,
+    pub dpad_down: ButtonState,
+    pub dpad_right: ButtonState,
+    // Stick positions are normalized to -1.0..1.0 with the deadzone already removed
+    pub left_stick: Vector2,
+    pub right_stick: Vector2
+}
+
+impl GamepadState {
+    fn update(&mut self, new_input: GamepadState) {
+        self.a.was_down = self.a.is_down;
+        self.b.was_down = self.b.is_down;
+        self.x.was_down = self.x.is_down;
+        self.y.was_down = self.y.is_down;
+        self.start.was_down = self.start.is_down;
+        self.back.was_down = self.back.is_down;
+        self.left_shoulder.was_down = self.left_shoulder.is_down;
+        self.right_shoulder.was_down = self.right_shoulder.is_down;
+        self.dpad_up.was_down = self.dpad_up.is_down;
+        self.dpad_left.was_down = self.dpad_left.is_down;
+        self.dpad_down.was_down = self.dpad_down.is_down;
+        self.dpad_right.was_down = self.dpad_right.is_down;
+
+        self.is_connected = new_input.is_connected;
+        self.a.is_down = new_input.a.is_down;
+        self.b.is_down = new_input.b.is_down;
+        self.x.is_down = new_input.x.is_down;
+        self.y.is_down = new_input.y.is_down;
+        self.start.is_down = new_input.start.is_down;
+        self.back.is_down = new_input.back.is_down;
+        self.left_shoulder.is_down = new_input.left_shoulder.is_down;
+        self.right_shoulder.is_down = new_input.right_shoulder.is_down;
+        self.dpad_up.is_down = new_input.dpad_up.is_down;
+        self.dpad_left.is_down = new_input.dpad_left.is_down;
+        self.dpad_down.is_down = new_input.dpad_down.is_down;
+        self.dpad_right.is_down = new_input.dpad_right.is_down;
+        self.left_stick = new_input.left_stick;
+        self.right_stick = new_input.right_stick;
+    }
 }
 
 impl InputController {
@@ -46,59 +193,674 @@ impl InputController {
         self.a.was_down = self.a.is_down;
         self.s.was_down = self.s.is_down;
         self.d.was_down = self.d.is_down;
+        self.c.was_down = self.c.is_down;
+        self.f.was_down = self.f.is_down;
+        self.e.was_down = self.e.is_down;
+        self.m.was_down = self.m.is_down;
+        self.p.was_down = self.p.is_down;
+        self.r.was_down = self.r.is_down;
+        self.x.was_down = self.x.is_down;
+        self.y.was_down = self.y.is_down;
+        self.z.was_down = self.z.is_down;
         self.up.was_down = self.up.is_down;
         self.left.was_down = self.left.is_down;
         self.down.was_down = self.down.is_down;
         self.right.was_down = self.right.is_down;
         self.esc.was_down = self.esc.is_down;
+        self.space.was_down = self.space.is_down;
+        self.shift.was_down = self.shift.is_down;
+        self.ctrl.was_down = self.ctrl.is_down;
 
         self.mouse_state.left.is_down = new_input.mouse_state.left.is_down;
         self.mouse_state.right.is_down = new_input.mouse_state.right.is_down;
         self.mouse_state.middle.is_down = new_input.mouse_state.middle.is_down;
         self.mouse_state.pos = new_input.mouse_state.pos;
+        self.mouse_state.wheel_delta = new_input.mouse_state.wheel_delta;
         self.w.is_down = new_input.w.is_down;
         self.a.is_down = new_input.a.is_down;
         self.s.is_down = new_input.s.is_down;
         self.d.is_down = new_input.d.is_down;
+        self.c.is_down = new_input.c.is_down;
+        self.f.is_down = new_input.f.is_down;
+        self.e.is_down = new_input.e.is_down;
+        self.m.is_down = new_input.m.is_down;
+        self.p.is_down = new_input.p.is_down;
+        self.r.is_down = new_input.r.is_down;
+        self.x.is_down = new_input.x.is_down;
+        self.y.is_down = new_input.y.is_down;
+        self.z.is_down = new_input.z.is_down;
         self.up.is_down = new_input.up.is_down;
         self.left.is_down = new_input.left.is_down;
         self.down.is_down = new_input.down.is_down;
         self.right.is_down = new_input.right.is_down;
         self.esc.is_down = new_input.esc.is_down;
+        self.space.is_down = new_input.space.is_down;
+        self.shift.is_down = new_input.shift.is_down;
+        self.ctrl.is_down = new_input.ctrl.is_down;
+
+        self.gamepad.update(new_input.gamepad);
     }
 }
 
+// Pixels the game draws into, the platform owns the memory and presents it, see platform::Platform
 pub struct OffscreenBuffer {
-    pub info: BITMAPINFO,
     pub memory: *mut c_void,
     pub width: u32,
     pub height: u32,
     pub bytes_per_pixel: u32,
-    pub pitch: u32
+    pub pitch: u32,
+    // How every draw call combines its color with the pixels already in the buffer
+    pub blend_mode: BlendMode,
+    // Blend in linear light instead of directly on the sRGB values, slower but translucent
+    // overlaps don't get muddy. The buffer itself always holds sRGB.
+    pub linear_blending: bool,
+    // Round blended colors with an ordered dither pattern instead of to the nearest value,
+    // hides banding on large soft gradients and antialiased edges
+    pub dither: bool,
+    // Draw calls only touch pixels inside the top rectangle, see push_clip_rect
+    pub clip_stack: Vec<ClipRect>,
+    // When set, draw calls only touch pixels where the mask is set, scaled by its coverage
+    pub mask: Option<Mask>
+}
+
+// Pixel region from (x0, y0) up to but not including (x1, y1)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ClipRect {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32
+}
+
+impl ClipRect {
+    pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        ClipRect { x0, y0, x1, y1 }
+    }
+
+    pub fn intersection(self, other: ClipRect) -> ClipRect {
+        let x0 = max(self.x0, other.x0);
+        let y0 = max(self.y0, other.y0);
+        // Empty intersections collapse to zero size instead of going negative
+        ClipRect {
+            x0,
+            y0,
+            x1: max(min(self.x1, other.x1), x0),
+            y1: max(min(self.y1, other.y1), y0)
+        }
+    }
+
+    // Smallest rect covering both, empty rects are ignored
+    pub fn union(self, other: ClipRect) -> ClipRect {
+        if self.is_empty() {
+            return other;
+        }
+        if other.is_empty() {
+            return self;
+        }
+
+        ClipRect {
+            x0: min(self.x0, other.x0),
+            y0: min(self.y0, other.y0),
+            x1: max(self.x1, other.x1),
+            y1: max(self.y1, other.y1)
+        }
+    }
+
+    pub fn overlaps(self, other: ClipRect) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    pub fn contains(self, x: i32, y: i32) -> bool {
+        x >= self.x0 && x < self.x1 && y >= self.y0 && y < self.y1
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.x1 <= self.x0 || self.y1 <= self.y0
+    }
+}
+
+impl OffscreenBuffer {
+    // Buffer without any memory yet, const so platforms can keep theirs in a static
+    pub const fn empty() -> Self {
+        OffscreenBuffer {
+            memory: null_mut(),
+            width: 0,
+            height: 0,
+            bytes_per_pixel: 0,
+            pitch: 0,
+            blend_mode: BlendMode::Alpha,
+            linear_blending: false,
+            dither: false,
+            clip_stack: Vec::new(),
+            mask: None
+        }
+    }
+
+    // The region draw calls are currently limited to, always inside the buffer
+    pub fn clip_rect(&self) -> ClipRect {
+        let bounds = ClipRect::new(0, 0, self.width as i32, self.height as i32);
+        match self.clip_stack.last() {
+            Some(clip) => clip.intersection(bounds),
+            None => bounds
+        }
+    }
+
+    // Nested clip rects are intersected with the ones below them
+    pub fn push_clip_rect(&mut self, rect: ClipRect) {
+        let clip = self.clip_rect().intersection(rect);
+        self.clip_stack.push(clip);
+    }
+
+    // Clips to a rectangle given in world space. Clip rects are always along the pixels, under a
+    // rotated camera this clips to the box around the rectangle on screen.
+    pub fn push_clip_rectangle(&mut self, camera: Camera, rectangle: Rectangle) {
+        let corners = rectangle.corners().map(|corner| world_space_to_screen_space_f32(camera, corner));
+        let screen = Rectangle::from_points(&corners);
+        let top_left = Vector2 { x: screen.x, y: screen.y };
+        let bottom_right = Vector2 { x: screen.x + screen.width, y: screen.y + screen.height };
+
+        self.push_clip_rect(ClipRect::new(
+            top_left.x.round() as i32,
+            top_left.y.round() as i32,
+            bottom_right.x.round() as i32,
+            bottom_right.y.round() as i32));
+    }
+
+    pub fn pop_clip_rect(&mut self) {
+        if self.clip_stack.pop().is_none() {
+            eprintln!("pop_clip_rect called without a matching push_clip_rect");
+        }
+    }
+
+    /// All pixels of row y, writing to it directly skips the clip rect, mask and blend mode
+    ///
+    /// # Safety
+    /// `memory` must point to at least `pitch * height` writable bytes and y must be inside the buffer
+    pub unsafe fn row(&mut self, y: i32) -> &mut [u32] {
+        std::slice::from_raw_parts_mut(self.row_pointer(y), self.width as usize)
+    }
+
+    unsafe fn row_pointer(&self, y: i32) -> *mut u32 {
+        (self.memory as *mut u8).offset(self.pitch as isize * y as isize) as *mut u32
+    }
+
+    // Combines a color with the buffer pixel at x, y the way this buffer's draw calls do.
+    // Returned as a closure so it can be used while a row is borrowed.
+    pub fn blender(&self) -> impl Fn(u32, u32, i32, i32) -> u32 {
+        let blend_mode = self.blend_mode;
+        let linear_blending = self.linear_blending;
+        let dither = self.dither;
+
+        move |destination, source, x, y| {
+            let threshold = if dither { dither_threshold(x, y) } else { 0.5 };
+            if linear_blending {
+                blend_pixel_linear_dithered(destination, source, blend_mode, dither.then_some(threshold))
+            } else {
+                blend_pixel_dithered(destination, source, blend_mode, threshold)
+            }
+        }
+    }
+
+    /// Draws one color over pixels x0 up to but not including x1 of row y, clipped. Uses
+    /// the wide row writes when the blend mode allows it.
+    ///
+    /// # Safety
+    /// `memory` must point to at least `pitch * height` writable bytes
+    pub unsafe fn fill_span(&mut self, y: i32, x0: i32, x1: i32, color: u32) {
+        let clip = self.clip_rect();
+        if y < clip.y0 || y >= clip.y1 {
+            return;
+        }
+
+        let x0 = max(x0, clip.x0);
+        let x1 = min(x1, clip.x1);
+        if x0 >= x1 {
+            return;
+        }
+
+        let blend_mode = self.blend_mode;
+        let wide_alpha = !self.linear_blending && !self.dither;
+        let blend = self.blender();
+        let span = std::slice::from_raw_parts_mut(self.row_pointer(y).offset(x0 as isize), (x1 - x0) as usize);
+
+        if let Some(mask) = &self.mask {
+            for (x, pixel) in (x0..).zip(span.iter_mut()) {
+                let coverage = mask.get(x, y);
+                if coverage != 0 {
+                    *pixel = blend(*pixel, mask_color(color, coverage), x, y);
+                }
+            }
+            return;
+        }
+
+        match blend_mode {
+            BlendMode::Replace => fill_pixels(span, color),
+            BlendMode::Alpha if wide_alpha => blend_pixels_alpha(span, color),
+            _ => {
+                for (x, pixel) in (x0..).zip(span.iter_mut()) {
+                    *pixel = blend(*pixel, color, x, y);
+                }
+            }
+        }
+    }
+
+    /// Draws one color down pixels y0 up to but not including y1 of column x, clipped
+    ///
+    /// # Safety
+    /// `memory` must point to at least `pitch * height` writable bytes
+    pub unsafe fn fill_column(&mut self, x: i32, y0: i32, y1: i32, color: u32) {
+        let clip = self.clip_rect();
+        if x < clip.x0 || x >= clip.x1 {
+            return;
+        }
+
+        let blend = self.blender();
+        let mut y = max(y0, clip.y0);
+        let end = min(y1, clip.y1);
+        while y < end {
+            let pixel = &mut *self.row_pointer(y).offset(x as isize);
+            match &self.mask {
+                Some(mask) => {
+                    let coverage = mask.get(x, y);
+                    if coverage != 0 {
+                        *pixel = blend(*pixel, mask_color(color, coverage), x, y);
+                    }
+                },
+                None if self.blend_mode == BlendMode::Replace => *pixel = color,
+                None => *pixel = blend(*pixel, color, x, y)
+            }
+            y += 1;
+        }
+    }
+
+    /// Draws colors[i] onto pixel x0 + i of row y, clipped
+    ///
+    /// # Safety
+    /// `memory` must point to at least `pitch * height` writable bytes
+    pub unsafe fn blend_span(&mut self, y: i32, x0: i32, colors: &[u32]) {
+        let clip = self.clip_rect();
+        if y < clip.y0 || y >= clip.y1 {
+            return;
+        }
+
+        let start = max(x0, clip.x0);
+        let end = min(x0 + colors.len() as i32, clip.x1);
+        if start >= end {
+            return;
+        }
+
+        let blend = self.blender();
+        let span = std::slice::from_raw_parts_mut(self.row_pointer(y).offset(start as isize), (end - start) as usize);
+        let colors = &colors[(start - x0) as usize..(end - x0) as usize];
+
+        match &self.mask {
+            Some(mask) => {
+                for (x, (pixel, color)) in (start..).zip(span.iter_mut().zip(colors)) {
+                    let coverage = mask.get(x, y);
+                    if coverage != 0 {
+                        *pixel = blend(*pixel, mask_color(*color, coverage), x, y);
+                    }
+                }
+            },
+            None => {
+                for (x, (pixel, color)) in (start..).zip(span.iter_mut().zip(colors)) {
+                    *pixel = blend(*pixel, *color, x, y);
+                }
+            }
+        }
+    }
 }
 
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
 pub struct WindowDimensions {
     pub width: u32,
     pub height: u32
 }
 
+// How the camera's extents follow the size of the window. The platform sizes the buffer with
+// buffer_size, then fits the camera to the buffer with apply and presents it with viewport.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraFit {
+    // Keeps the camera's height, it sees more or less to the sides as the window gets wider
+    #[default]
+    FitHeight,
+    // Keeps the camera's width, it sees more or less above and below
+    FitWidth,
+    // Sees exactly the camera's width and height, pulled out of shape to fill the window
+    Stretch,
+    // Sees exactly the camera's width and height, as large as fits with black bars around it
+    Letterbox
+}
+
+impl CameraFit {
+    // The last two render at the camera's aspect ratio, at the largest size that fits
+    pub fn buffer_size(self, camera: Camera, window: WindowDimensions) -> WindowDimensions {
+        match self {
+            CameraFit::FitHeight | CameraFit::FitWidth => window,
+            CameraFit::Stretch | CameraFit::Letterbox => {
+                if camera.width <= 0.0 || camera.height <= 0.0 {
+                    return window;
+                }
+
+                let scale = (window.width as f32 / camera.width).min(window.height as f32 / camera.height);
+                WindowDimensions {
+                    width: ((camera.width * scale).round() as u32).clamp(1, window.width.max(1)),
+                    height: ((camera.height * scale).round() as u32).clamp(1, window.height.max(1))
+                }
+            }
+        }
+    }
+
+    // Works out the camera's y_scale, and the extent that isn't kept, from the buffer it draws into
+    pub fn apply(self, camera: &mut Camera, buffer: WindowDimensions) {
+        if buffer.width == 0 || buffer.height == 0 || camera.width <= 0.0 || camera.height <= 0.0 {
+            return;
+        }
+
+        match self {
+            CameraFit::FitHeight => {
+                camera.y_scale = buffer.height as f32 / camera.height;
+                camera.width = buffer.width as f32 / camera.y_scale;
+            },
+            CameraFit::FitWidth => {
+                camera.y_scale = buffer.width as f32 / camera.width;
+                camera.height = buffer.height as f32 / camera.y_scale;
+            },
+            // The buffer already has the camera's shape, both extents are kept so they don't
+            // drift with the rounding of the buffer size
+            CameraFit::Stretch | CameraFit::Letterbox => {
+                camera.y_scale = buffer.height as f32 / camera.height;
+            }
+        }
+    }
+
+    // Where the buffer goes in the window
+    pub fn viewport(self, buffer_width: u32, buffer_height: u32, window: WindowDimensions) -> Viewport {
+        match self {
+            CameraFit::Stretch => Viewport { x: 0, y: 0, width: window.width, height: window.height },
+            _ => Viewport::letterboxed(buffer_width, buffer_height, window)
+        }
+    }
+}
+
+// A camera drawn into part of the buffer, for split screen or an editor's preview panes. The
+// screen rectangle is in buffer pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraViewport {
+    pub camera: Camera,
+    pub screen: Rectangle
+}
+
+impl CameraViewport {
+    pub fn new(camera: Camera, screen: Rectangle) -> Self {
+        CameraViewport { camera, screen }
+    }
+
+    // The camera moved onto the screen rectangle and sized to it. It keeps its height, the width
+    // follows the shape of the rectangle like CameraFit::FitHeight.
+    pub fn fitted_camera(&self) -> Camera {
+        let mut camera = self.camera;
+        let size = WindowDimensions { width: self.screen.width.max(0.0) as u32, height: self.screen.height.max(0.0) as u32 };
+        CameraFit::FitHeight.apply(&mut camera, size);
+        camera.screen_offset = Vector2 { x: self.screen.x, y: self.screen.y };
+        camera
+    }
+
+    pub fn clip_rect(&self) -> ClipRect {
+        ClipRect::new(
+            self.screen.x.round() as i32,
+            self.screen.y.round() as i32,
+            (self.screen.x + self.screen.width).round() as i32,
+            (self.screen.y + self.screen.height).round() as i32)
+    }
+}
+
+// Where the buffer is drawn inside the window when presented, in window pixels
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32
+}
+
+impl Viewport {
+    // Scales the buffer up by the largest whole number that fits and centers it, the rest of
+    // the window is left for black bars. When the window is smaller than the buffer it's
+    // scaled down as far as needed instead, keeping the aspect ratio.
+    pub fn letterboxed(buffer_width: u32, buffer_height: u32, window: WindowDimensions) -> Viewport {
+        if buffer_width == 0 || buffer_height == 0 {
+            return Viewport::default();
+        }
+
+        let scale = min(window.width / buffer_width, window.height / buffer_height);
+        let (width, height) = if scale >= 1 {
+            (buffer_width * scale, buffer_height * scale)
+        } else {
+            let fraction = (window.width as f32 / buffer_width as f32).min(window.height as f32 / buffer_height as f32);
+            ((buffer_width as f32 * fraction) as u32, (buffer_height as f32 * fraction) as u32)
+        };
+
+        Viewport {
+            x: (window.width as i32 - width as i32) / 2,
+            y: (window.height as i32 - height as i32) / 2,
+            width,
+            height
+        }
+    }
+
+    // Maps a window position, like the cursor, to the buffer pixel under it. Positions on the
+    // bars are clamped to the nearest edge of the buffer.
+    pub fn window_to_buffer(self, x: i32, y: i32, buffer_width: u32, buffer_height: u32) -> Vector2u32 {
+        if self.width == 0 || self.height == 0 {
+            return Vector2u32::default();
+        }
+
+        let map = |position: i32, offset: i32, size: u32, buffer_size: u32| {
+            let scaled = (position - offset) as i64 * buffer_size as i64 / size as i64;
+            scaled.clamp(0, buffer_size.saturating_sub(1) as i64) as u32
+        };
+
+        Vector2u32 {
+            x: map(x, self.x, self.width, buffer_width),
+            y: map(y, self.y, self.height, buffer_height)
+        }
+    }
+
+    // Buffer rect to the window rect it covers, rounded outwards
+    pub fn buffer_to_window(self, rect: ClipRect, buffer_width: u32, buffer_height: u32) -> ClipRect {
+        let scale_x = self.width as f32 / buffer_width as f32;
+        let scale_y = self.height as f32 / buffer_height as f32;
+
+        ClipRect::new(
+            self.x + (rect.x0 as f32 * scale_x).floor() as i32,
+            self.y + (rect.y0 as f32 * scale_y).floor() as i32,
+            self.x + (rect.x1 as f32 * scale_x).ceil() as i32,
+            self.y + (rect.y1 as f32 * scale_y).ceil() as i32)
+    }
+}
+
+// Scales a premultiplied color by mask coverage
+fn mask_color(color: u32, coverage: u8) -> u32 {
+    if coverage == 255 {
+        color
+    } else {
+        scale_color(color, coverage as f32 / 255.0)
+    }
+}
+
 #[derive(Default)]
 pub struct GameState {
     pub delta_time: f32,
+    // Window DPI divided by 96, used to scale the UI
+    pub dpi_scale: f32,
     pub camera: Camera,
-    pub last_perf_print: u128,
     pub curves: [Option<BezierCurve>; 10],
+    // How each curve slot is drawn, None draws it as a thin white line
+    pub curve_styles: [Option<StrokeStyle>; 10],
     pub selected_curve_index: Option<u32>,
-    pub selected_control_point: u32
+    pub selected_control_point: u32,
+    pub actions: ActionMap,
+    pub profiler: FrameProfiler,
+    pub hitch_watchdog: HitchWatchdog,
+    // Parts of the buffer redrawn this frame, the platform only needs to present these
+    pub dirty_regions: DirtyRegions,
+    pub post_process: PostProcessStack,
+    // When set the frame is drawn as palette indices instead of through the render commands
+    pub indexed_frame: Option<IndexedFrame>,
+    // Fixed size to render at, which the platform scales to the window by whole multiples.
+    // None renders at the size the camera fit asks for.
+    pub internal_resolution: Option<WindowDimensions>,
+    pub camera_fit: CameraFit,
+    // Cameras drawn into parts of the buffer, each clipped to its own rectangle. While there are
+    // none the main camera fills the buffer, otherwise only these are drawn, so an editor layout
+    // registers its main view as one of them.
+    pub viewports: Vec<CameraViewport>,
+    pub debug_draw: DebugDraw,
+    pub cursor_readout: Option<CursorReadout>,
+    // World points of the freehand stroke being drawn, fitted to curves when it's finished
+    pub sketch: Vec<Vector2>,
+    // Shapes animating from one path into another, drawn at how far along they are
+    pub morphs: Vec<PathMorph>,
+    // Moves the debug circle along the first curve, the circle stays at the origin without one
+    pub circle_follower: Option<PathFollower>,
+    // Finds the curves in view without checking every one, kept up to date every frame
+    pub curve_index: CurveIndex,
+    // Where every random number in the game comes from, reseed it to replay a session
+    pub rng: Rng,
+    // Moves the camera somewhere over time, the zoom from the window size is kept
+    pub camera_tween: Option<Tween<Camera>>,
+    pub camera_zoom: CameraZoom,
+    // Drawn on top of the camera, the camera itself never moves from it
+    pub camera_shake: CameraShake,
+    pub camera_pan: CameraPan,
+    pub snapping: Snapping,
+    pub editor: CurveEditor,
+    pub selection: Selection,
+    // Being dragged out from empty space with the editor on, selects what it covers on release
+    pub marquee: Option<Marquee>,
+    pub history: UndoStack,
+    // The dragged curve as it was when the drag started, becomes one edit when it's released
+    pub drag_start: Option<BezierCurve>,
+    pub measure: MeasureTool
+}
+
+// Dragging the control points of the curves with the mouse. Turned off, the handles are hidden
+// and dragging anywhere moves the camera.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurveEditor {
+    pub enabled: bool,
+    // Curve and control point under the mouse, drawn highlighted so it's clear what a click grabs
+    pub hovered: Option<(u32, u32)>
+}
+
+impl Default for CurveEditor {
+    fn default() -> Self {
+        CurveEditor { enabled: true, hovered: None }
+    }
+}
+
+// What part of a curve is under a point, see GameState::pick_curve
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurvePick {
+    // Numbered like selected_control_point and BezierCurve::point, 0 is p0 up to 3 is p3
+    ControlPoint(u32),
+    // The line of the curve itself, at t
+    Curve(f32)
+}
+
+impl GameState {
+    pub fn debug_circle_center(&self) -> Vector2 {
+        match &self.circle_follower {
+            Some(follower) => follower.position(),
+            None => Vector2::zero()
+        }
+    }
+
+    // Curve under a world point and what part of it was hit. Control points win over curves,
+    // they sit on top and are much smaller targets. Handles win over end points so a handle
+    // that was pulled onto its end point can be pulled back out. Among curves the closest one
+    // wins.
+    pub fn pick_curve(&self, point: Vector2, threshold: f32) -> Option<(u32, CurvePick)> {
+        for control_point in [1, 2, 0, 3] {
+            for (i, curve) in (0..).zip(self.curves.iter()) {
+                if let Some(curve) = curve {
+                    if point.distance(curve.point(control_point)) < threshold {
+                        return Some((i, CurvePick::ControlPoint(control_point)));
+                    }
+                }
+            }
+        }
+
+        let mut closest: Option<(u32, f32, f32)> = None;
+        for (i, curve) in (0..).zip(self.curves.iter()) {
+            if let Some(curve) = curve {
+                let (t, distance) = curve.nearest(point);
+                if distance <= threshold && closest.is_none_or(|value| distance < value.2) {
+                    closest = Some((i, t, distance));
+                }
+            }
+        }
+        closest.map(|(i, t, _)| (i, CurvePick::Curve(t)))
+    }
+}
+
+// How curve bounding boxes are shown
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum BoundingBoxStyle {
+    #[default]
+    Filled,
+    // One pixel outline with brackets on the corners, keeps the curve inside visible
+    Outline
+}
+
+// Debug drawing settings that can be changed while the game is running
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DebugDraw {
+    pub bounding_boxes: BoundingBoxStyle,
+    // Teeth along each curve as long as its curvature, shows where smooth curves bend unevenly
+    pub curvature_combs: bool,
+    // Crosshair at the mouse and the world coordinates under it, for placing control points
+    pub cursor_readout: bool,
+    // Every curve's control points and the lines between them, even with the editor off
+    pub control_polygons: bool
+}
+
+// Where the mouse is on the buffer and in the world, kept while DebugDraw::cursor_readout is on
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct CursorReadout {
+    pub screen: Vector2u32,
+    pub world: Vector2
 }
 
-#[derive(Default, Clone, Copy)]
+impl CursorReadout {
+    pub fn text(&self) -> String {
+        format!("Cursor: ({:.2}, {:.2})", self.world.x, self.world.y)
+    }
+}
+
+impl DebugDraw {
+    pub fn toggle_bounding_box_style(&mut self) {
+        self.bounding_boxes = match self.bounding_boxes {
+            BoundingBoxStyle::Filled => BoundingBoxStyle::Outline,
+            BoundingBoxStyle::Outline => BoundingBoxStyle::Filled
+        };
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
 pub struct Camera {
     pub x: f32,
     pub y: f32,
     pub width: f32,
     pub height: f32,
-    pub y_scale: f32
+    pub y_scale: f32,
+    // Radians the camera is turned around its middle, the world turns the other way on screen
+    pub rotation: f32,
+    // Buffer pixel the camera's top left corner is drawn at, see CameraViewport
+    pub screen_offset: Vector2,
+    // World y points up on screen like in a math plot instead of down like the buffer rows
+    pub y_up: bool
 }
 
 impl Camera {
@@ -108,30 +870,127 @@ impl Camera {
             y,
             width,
             height,
-            y_scale: 1.0
+            y_scale: 1.0,
+            rotation: 0.0,
+            screen_offset: Vector2 { x: 0.0, y: 0.0 },
+            y_up: false
         }
     }
 
-    fn get_bounding_box(self) -> Rectangle {
-        Rectangle {
+    // Whether the world lines up with the buffer's rows and columns, the fast axis aligned
+    // rasterizers only work when it does
+    pub fn is_axis_aligned(self) -> bool {
+        self.rotation == 0.0 && !self.y_up
+    }
+
+    // Angle on screen of something turned by rotation radians in the world
+    pub fn screen_rotation(self, rotation: f32) -> f32 {
+        if self.y_up { self.rotation - rotation } else { rotation - self.rotation }
+    }
+
+    // Same mapping as world_space_to_screen_space_f32, as a transform so whole curves and paths
+    // can be moved onto the screen at once
+    pub fn screen_transform(self) -> Transform2D {
+        let flip = if self.y_up { -1.0 } else { 1.0 };
+        Transform2D::translation(Vector2 { x: -self.x, y: -self.y })
+            .then(Transform2D::rotation(-self.rotation))
+            .then(Transform2D::scale(1.0, flip))
+            .then(Transform2D::translation(Vector2 { x: self.width / 2.0, y: self.height / 2.0 }))
+            .then(Transform2D::scale(self.y_scale, self.y_scale))
+            .then(Transform2D::translation(self.screen_offset))
+    }
+
+    // World space area the camera sees. A rotated camera sees a rotated rectangle, this is the
+    // box around it.
+    pub fn get_bounding_box(self) -> Rectangle {
+        let view = Rectangle {
             x: self.x - self.width / 2.0,
             y: self.y - self.height / 2.0,
             width: self.width,
             height: self.height
+        };
+        if self.rotation == 0.0 {
+            return view;
+        }
+        Rectangle::from_points(&Transform2D::rotation_around(self.rotation, Vector2 { x: self.x, y: self.y }).apply_rectangle(view))
+    }
+
+    // Eases the camera towards a target every frame instead of snapping to it. Stiffness is how
+    // quickly it catches up, per millisecond, the camera covers 1 - e^-stiffness of the way each
+    // millisecond whatever the frame rate. The deadzone is an area around the middle of the
+    // camera, relative to it and turned with it, where the target can move without the camera
+    // following. Past its edges the camera only follows far enough to bring the target back to
+    // the edge.
+    pub fn follow(&mut self, target: Vector2, stiffness: f32, deadzone: Option<Rectangle>, delta_time: f32) {
+        let mut goal = target;
+        if let Some(zone) = deadzone {
+            let center = Vector2::new(self.x, self.y);
+            let offset = Transform2D::rotation(-self.rotation).apply_vector(target - center);
+            let outside = Vector2::new(
+                offset.x - offset.x.clamp(zone.x, zone.x + zone.width.max(0.0)),
+                offset.y - offset.y.clamp(zone.y, zone.y + zone.height.max(0.0)));
+            goal = center + Transform2D::rotation(self.rotation).apply_vector(outside);
         }
+
+        let blend = 1.0 - (-stiffness.max(0.0) * delta_time).exp();
+        self.x += (goal.x - self.x) * blend;
+        self.y += (goal.y - self.y) * blend;
+    }
+}
+
+// How the camera moves when panned with the keys, dragging it always follows the cursor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraPan {
+    // World units per millisecond
+    pub key_speed: f32
+}
+
+impl Default for CameraPan {
+    fn default() -> Self {
+        CameraPan { key_speed: 0.005 }
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Vector2 {
     pub x: f32,
     pub y: f32
 }
 
 impl Vector2 {
-    fn zero() -> Self {
+    pub fn new(x: f32, y: f32) -> Self {
+        Vector2 { x, y }
+    }
+
+    pub fn zero() -> Self {
         Vector2 { x: 0.0, y: 0.0 }
     }
+
+    // Goes from self at t = 0.0 to other at t = 1.0
+    pub fn lerp(self, other: Vector2, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    pub fn dot(self, other: Vector2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn distance(self, other: Vector2) -> f32 {
+        (self - other).length()
+    }
+
+    // Same direction with length 1.0, or zero when the vector is too short to have a direction
+    pub fn normalized(self) -> Self {
+        let length = self.length();
+        if length < 1e-6 {
+            return Vector2::zero();
+        }
+        self * (1.0 / length)
+    }
 }
 
 impl std::ops::Add for Vector2 {
@@ -150,7 +1009,7 @@ impl std::ops::Sub for Vector2 {
 
     fn sub(self, other: Vector2) -> Self {
         Vector2 {
-            x: self.y - other.x,
+            x: self.x - other.x,
             y: self.y - other.y
         }
     }
@@ -167,13 +1026,14 @@ impl std::ops::Mul<f32> for Vector2 {
     }
 }
 
-#[derive(Clone, Copy, Default, PartialEq)]
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
 pub struct Vector2u32 {
     pub x: u32,
     pub y: u32
 }
 
 impl Vector2u32 {
+    // Both components set to the same value
     pub fn new(value: u32) -> Self {
         Vector2u32 {
             x: value,
@@ -204,13 +1064,36 @@ impl std::ops::Sub<Vector2u32> for Vector2u32 {
     }
 }
 
-#[derive(Clone, Copy)]
-struct Vector2i32 {
-    x: i32,
-    y: i32
+// Screen position that can be off the buffer, left of or above it is negative
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Vector2i32 {
+    pub x: i32,
+    pub y: i32
+}
+
+impl Vector2i32 {
+    pub fn new(x: i32, y: i32) -> Self {
+        Vector2i32 { x, y }
+    }
+
+    // The nearest pixel inside the clip rect, an empty rect gives its corner
+    pub fn clamp_to(self, clip: ClipRect) -> Vector2i32 {
+        Vector2i32 {
+            x: self.x.min(clip.x1 - 1).max(clip.x0),
+            y: self.y.min(clip.y1 - 1).max(clip.y0)
+        }
+    }
+
+    // The pixel as a buffer position, None when it's off the buffer instead of wrapping around
+    pub fn to_buffer_pixel(self, width: u32, height: u32) -> Option<Vector2u32> {
+        if self.x < 0 || self.y < 0 || self.x as u32 >= width || self.y as u32 >= height {
+            return None;
+        }
+        Some(Vector2u32 { x: self.x as u32, y: self.y as u32 })
+    }
 }
 
-#[derive(Default, Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
 pub struct Rectangle {
     pub x: f32,
     pub y: f32,
@@ -219,13 +1102,85 @@ pub struct Rectangle {
 }
 
 impl Rectangle {
-    fn intersects(&self, other: Rectangle) -> bool {
-        self.x + self.width >= other.x ||
-        self.y + self.height >= other.y
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Rectangle { x, y, width, height }
+    }
+
+    // Smallest rectangle around all the points, an empty rectangle at the origin without any
+    pub fn from_points(points: &[Vector2]) -> Self {
+        let first = match points.first() {
+            Some(value) => *value,
+            None => return Rectangle::default()
+        };
+
+        let mut min = first;
+        let mut max = first;
+        for point in points {
+            min = Vector2 { x: min.x.min(point.x), y: min.y.min(point.y) };
+            max = Vector2 { x: max.x.max(point.x), y: max.y.max(point.y) };
+        }
+        Rectangle { x: min.x, y: min.y, width: max.x - min.x, height: max.y - min.y }
+    }
+
+    // Clockwise from the top left on screen, which has y pointing down
+    pub fn corners(&self) -> [Vector2; 4] {
+        [
+            Vector2 { x: self.x, y: self.y },
+            Vector2 { x: self.x + self.width, y: self.y },
+            Vector2 { x: self.x + self.width, y: self.y + self.height },
+            Vector2 { x: self.x, y: self.y + self.height }
+        ]
+    }
+
+    // Points on the edges are inside
+    pub fn contains_point(&self, point: Vector2) -> bool {
+        point.x >= self.x && point.x <= self.x + self.width &&
+        point.y >= self.y && point.y <= self.y + self.height
+    }
+
+    // Overlap on both axes, touching edges count as overlapping
+    pub fn intersects(&self, other: Rectangle) -> bool {
+        self.x <= other.x + other.width &&
+        other.x <= self.x + self.width &&
+        self.y <= other.y + other.height &&
+        other.y <= self.y + self.height
+    }
+
+    // Smallest rectangle covering both
+    pub fn union(&self, other: Rectangle) -> Rectangle {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rectangle {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y
+        }
+    }
+
+    // Grown by amount on every side, a negative amount shrinks it down to nothing at the center
+    pub fn expand(&self, amount: f32) -> Rectangle {
+        let width = (self.width + amount * 2.0).max(0.0);
+        let height = (self.height + amount * 2.0).max(0.0);
+        Rectangle {
+            x: self.x + (self.width - width) / 2.0,
+            y: self.y + (self.height - height) / 2.0,
+            width,
+            height
+        }
     }
 }
 
-#[derive(Default, Clone, Copy)]
+// Each level halves the curve, 2^16 pieces is far more than any curve on screen needs
+static MAX_FLATTEN_DEPTH: u32 = 16;
+// Points checked along a curve before narrowing down the closest one
+static NEAREST_SAMPLES: u32 = 32;
+// World distance the cursor can be from a control point or curve and still pick it
+static PICK_DISTANCE: f32 = 0.02;
+// Smallest marquee that selects anything, a click in empty space only clears the selection
+static MARQUEE_MIN_PIXELS: f32 = 3.0;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
 pub struct BezierCurve {
     pub p0: Vector2,
     pub p1: Vector2,
@@ -243,13 +1198,40 @@ impl BezierCurve {
         }
     }
 
+    // Control point by number, 0 is p0 up to 3 is p3. Numbers past 3 are p3.
+    pub fn point(&self, index: u32) -> Vector2 {
+        match index {
+            0 => self.p0,
+            1 => self.p1,
+            2 => self.p2,
+            _ => self.p3
+        }
+    }
+
+    // Moves a control point, numbered like point. The end points carry their handles along so
+    // the curve keeps its shape near them.
+    pub fn modify(&mut self, index: u32, position: Vector2) {
+        match index {
+            0 => {
+                self.p1 = self.p1 + (position - self.p0);
+                self.p0 = position;
+            },
+            1 => self.p1 = position,
+            2 => self.p2 = position,
+            _ => {
+                self.p2 = self.p2 + (position - self.p3);
+                self.p3 = position;
+            }
+        }
+    }
+
     // This function was stolen from here:
     // https://youtu.be/aVwxzDHniEw?si=1txEvDjoTSHT0zqk&t=665
     // NOTE:
     // A faster method might be to evaluate the whole curve and look for min and max values
     // Could be worth benchmarking to see what's faster
     // This is obviously more accurate tho
-    fn get_bounding_box(&self) -> Rectangle {
+    pub fn get_bounding_box(&self) -> Rectangle {
         let tx = {
             let a = -3.0 * self.p0.x + 9.0 * self.p1.x -9.0 * self.p2.x + 3.0 * self.p3.x;
             let b = 6.0 * self.p0.x -12.0 * self.p1.x + 6.0 * self.p2.x;
@@ -317,18 +1299,11 @@ impl BezierCurve {
         let mut max_y = f32::MIN;
 
         for point in points {
-            if point.x < min_x {
-                min_x = point.x;
-            } else if point.x > max_x {
-                max_x = point.x;
-            }
-
-            if point.y < min_y {
-                min_y = point.y;
-            } else if point.y > max_y {
-                max_y = point.y;
-            }
-        }
+            min_x = min_x.min(point.x);
+            min_y = min_y.min(point.y);
+            max_x = max_x.max(point.x);
+            max_y = max_y.max(point.y);
+        }
 
         Rectangle {
             x: min_x,
@@ -338,212 +1313,1473 @@ impl BezierCurve {
         }
     }
 
-    fn evaluate(&self, t: f32) -> Vector2 {
+    // Point on the curve at t, from p0 at 0.0 to p3 at 1.0
+    pub fn evaluate(&self, t: f32) -> Vector2 {
         self.p0 * (-t * -t * -t + 3.0 * t * t - 3.0 * t + 1.0) +
         self.p1 * (3.0 * t * t * t - 6.0 * t * t + 3.0 * t) +
         self.p2 * (-3.0 * t * t * t + 3.0 * t * t) +
         self.p3 * (t * t * t)
     }
+
+    // Points along the curve, both ends included, that no part of the curve strays more than
+    // tolerance away from when joined with straight lines. Flat parts get few points and tight
+    // bends get many.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Vector2> {
+        let mut points = vec![self.p0];
+        self.flatten_into(tolerance.max(1e-6), 0, &mut points);
+        points
+    }
+
+    // Splits in half until every piece is flat enough, adds the end of each piece
+    fn flatten_into(&self, tolerance: f32, depth: u32, points: &mut Vec<Vector2>) {
+        if depth >= MAX_FLATTEN_DEPTH || self.is_flat(tolerance) {
+            points.push(self.p3);
+            return;
+        }
+
+        let (first, second) = self.split(0.5);
+        first.flatten_into(tolerance, depth + 1, points);
+        second.flatten_into(tolerance, depth + 1, points);
+    }
+
+    // Bound on how far the curve gets from the line between its ends, from Roger Willcocks'
+    // flatness test. Compares 16 * squared distances to skip the square roots.
+    fn is_flat(&self, tolerance: f32) -> bool {
+        let u = self.p1 * 3.0 - self.p0 * 2.0 - self.p3;
+        let v = self.p2 * 3.0 - self.p0 - self.p3 * 2.0;
+        let x = (u.x * u.x).max(v.x * v.x);
+        let y = (u.y * u.y).max(v.y * v.y);
+        x + y <= 16.0 * tolerance * tolerance
+    }
+
+    // Where the curve crosses the line segment from a to b, as the t on the curve and the
+    // point, sorted by t. Touching the segment counts, running along it doesn't.
+    pub fn intersect_line(&self, a: Vector2, b: Vector2) -> Vec<(f32, Vector2)> {
+        let mut hits = Vec::new();
+        let direction = b - a;
+        let length_squared = direction.x * direction.x + direction.y * direction.y;
+        if length_squared <= 0.0 {
+            return hits;
+        }
+
+        // Distance from the line along its normal is a cubic in t, the crossings are its roots
+        let normal = Vector2 { x: -direction.y, y: direction.x };
+        let project = |vector: Vector2| vector.x * normal.x + vector.y * normal.y;
+        let roots = solve_cubic(
+            project(self.p0 * -1.0 + self.p1 * 3.0 - self.p2 * 3.0 + self.p3),
+            project(self.p0 * 3.0 - self.p1 * 6.0 + self.p2 * 3.0),
+            project((self.p1 - self.p0) * 3.0),
+            project(self.p0 - a));
+
+        for root in roots {
+            if !(-1e-5..=1.0 + 1e-5).contains(&root) {
+                continue;
+            }
+
+            let t = root.clamp(0.0, 1.0);
+            let point = self.evaluate(t);
+            let along = ((point.x - a.x) * direction.x + (point.y - a.y) * direction.y) / length_squared;
+            let duplicate = hits.iter().any(|(hit_t, _): &(f32, Vector2)| (hit_t - t).abs() < 1e-5);
+            if (-1e-5..=1.0 + 1e-5).contains(&along) && !duplicate {
+                hits.push((t, point));
+            }
+        }
+
+        hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        hits
+    }
+
+    // Where two curves cross, exact along this curve and within tolerance along the other,
+    // which is flattened into line segments
+    pub fn intersect_curve(&self, other: &BezierCurve, tolerance: f32) -> Vec<Vector2> {
+        let mut points: Vec<Vector2> = Vec::new();
+        if !self.get_bounding_box().intersects(other.get_bounding_box()) {
+            return points;
+        }
+
+        let flattened = other.flatten(tolerance);
+        let mut i = 1;
+        while i < flattened.len() {
+            for (_, point) in self.intersect_line(flattened[i - 1], flattened[i]) {
+                // Crossings right at a joint between two segments are found by both of them
+                if !points.iter().any(|found| found.distance(point) <= tolerance) {
+                    points.push(point);
+                }
+            }
+            i += 1;
+        }
+        points
+    }
+
+    pub fn arc_length(&self) -> f32 {
+        ArcLengthTable::new(*self).length()
+    }
+
+    // Measures the curve once so distances along it can be looked up cheaply. Points at a
+    // distance along the curve come from the table's evaluate_at_distance, the curve doesn't
+    // keep one itself so it stays a small Copy value.
+    pub fn arc_length_table(&self) -> ArcLengthTable {
+        ArcLengthTable::new(*self)
+    }
+
+    // Cuts the curve in two at t with de Casteljau's algorithm, the halves together trace
+    // exactly the same shape. t is clamped to the curve.
+    pub fn split(&self, t: f32) -> (BezierCurve, BezierCurve) {
+        let t = t.clamp(0.0, 1.0);
+        let p01 = self.p0.lerp(self.p1, t);
+        let p12 = self.p1.lerp(self.p2, t);
+        let p23 = self.p2.lerp(self.p3, t);
+        let p012 = p01.lerp(p12, t);
+        let p123 = p12.lerp(p23, t);
+        let point = p012.lerp(p123, t);
+
+        (BezierCurve::new(self.p0, p01, p012, point), BezierCurve::new(point, p123, p23, self.p3))
+    }
+
+    // Tangent of the curve at t, points in the direction the curve is heading
+    pub fn derivative(&self, t: f32) -> Vector2 {
+        let u = 1.0 - t;
+        (self.p1 - self.p0) * (3.0 * u * u) +
+        (self.p2 - self.p1) * (6.0 * u * t) +
+        (self.p3 - self.p2) * (3.0 * t * t)
+    }
+
+    pub fn second_derivative(&self, t: f32) -> Vector2 {
+        (self.p2 - self.p1 * 2.0 + self.p0) * (6.0 * (1.0 - t)) +
+        (self.p3 - self.p2 * 2.0 + self.p1) * (6.0 * t)
+    }
+
+    // Signed 1 / radius of the circle that fits the curve at t, zero where the curve has no
+    // direction. Positive where the curve bends towards (-tangent.y, tangent.x).
+    pub fn curvature(&self, t: f32) -> f32 {
+        let first = self.derivative(t);
+        let second = self.second_derivative(t);
+        let speed = (first.x * first.x + first.y * first.y).sqrt();
+        if speed < 1e-6 {
+            return 0.0;
+        }
+        (first.x * second.y - first.y * second.x) / (speed * speed * speed)
+    }
+
+    // t and distance of the point on the curve closest to a position. Samples the curve to find
+    // roughly where it is, then narrows it down.
+    pub fn nearest(&self, position: Vector2) -> (f32, f32) {
+        let distance_at = |t: f32| self.evaluate(t).distance(position);
+
+        let mut best_t = 0.0;
+        let mut best_distance = distance_at(0.0);
+        let mut i = 1;
+        while i <= NEAREST_SAMPLES {
+            let t = i as f32 / NEAREST_SAMPLES as f32;
+            let distance = distance_at(t);
+            if distance < best_distance {
+                best_t = t;
+                best_distance = distance;
+            }
+            i += 1;
+        }
+
+        // The closest point is within one sample of the best one, halve the range around it
+        let mut step = 1.0 / NEAREST_SAMPLES as f32;
+        while step > 1e-5 {
+            step /= 2.0;
+            for t in [(best_t - step).max(0.0), (best_t + step).min(1.0)] {
+                let distance = distance_at(t);
+                if distance < best_distance {
+                    best_t = t;
+                    best_distance = distance;
+                }
+            }
+        }
+
+        (best_t, best_distance)
+    }
+
+    // t of the closest point when the curve passes within threshold of the point
+    pub fn hit_test(&self, point: Vector2, threshold: f32) -> Option<f32> {
+        let (t, distance) = self.nearest(point);
+        if distance <= threshold { Some(t) } else { None }
+    }
+
+    // Unit length direction the curve is heading at t. Where the derivative vanishes, at a cusp
+    // or a control point sitting on an end point, the direction it's about to head in is used.
+    pub fn tangent(&self, t: f32) -> Vector2 {
+        let derivative = self.derivative(t);
+        if derivative.x.abs() > 1e-6 || derivative.y.abs() > 1e-6 {
+            return derivative.normalized();
+        }
+
+        if t <= 0.0 {
+            (self.end_direction(false) * -1.0).normalized()
+        } else if t >= 1.0 {
+            self.end_direction(true).normalized()
+        } else {
+            // Past a cusp the curve leaves along the second derivative
+            self.second_derivative(t).normalized()
+        }
+    }
+
+    // Tangent turned a quarter towards the side the curve bends to when its curvature is positive
+    pub fn normal(&self, t: f32) -> Vector2 {
+        let tangent = self.tangent(t);
+        Vector2 { x: -tangent.y, y: tangent.x }
+    }
+
+    // Blends every point towards the other curve, 0.0 is this curve and 1.0 is the other
+    pub fn lerp(&self, other: &BezierCurve, t: f32) -> BezierCurve {
+        BezierCurve::new(self.p0.lerp(other.p0, t), self.p1.lerp(other.p1, t), self.p2.lerp(other.p2, t), self.p3.lerp(other.p3, t))
+    }
+
+    // Approximate the curve with quadratics, see cubic_to_quadratics
+    pub fn to_quadratics(&self, tolerance: f32) -> Vec<QuadraticBezier> {
+        cubic_to_quadratics(self, tolerance)
+    }
+
+    // Approximate parallel curve the distance away, see offset_curve
+    pub fn offset(&self, distance: f32, tolerance: f32) -> Vec<BezierCurve> {
+        offset_curve(self, distance, tolerance)
+    }
+
+    // Direction at t = 0.0 or 1.0 that still works when the control point sits on the end point,
+    // where the derivative is zero
+    fn end_direction(&self, at_end: bool) -> Vector2 {
+        let candidates = if at_end {
+            [self.derivative(1.0), self.p3 - self.p1, self.p3 - self.p0]
+        } else {
+            [self.derivative(0.0), self.p2 - self.p0, self.p3 - self.p0]
+        };
+
+        for candidate in candidates {
+            if candidate.x.abs() > 1e-6 || candidate.y.abs() > 1e-6 {
+                return if at_end { candidate } else { candidate * -1.0 };
+            }
+        }
+        Vector2::zero()
+    }
+}
+
+// Real roots of a*t^3 + b*t^2 + c*t + d, falls back to lower degrees when the leading
+// coefficients vanish. Returns nothing when every coefficient is zero.
+fn solve_cubic(a: f32, b: f32, c: f32, d: f32) -> Vec<f32> {
+    let scale = a.abs().max(b.abs()).max(c.abs()).max(d.abs());
+    if scale == 0.0 {
+        return Vec::new();
+    }
+
+    if a.abs() < scale * 1e-6 {
+        if b.abs() < scale * 1e-6 {
+            if c.abs() < scale * 1e-6 {
+                return Vec::new();
+            }
+            return vec![-d / c];
+        }
+
+        let discriminant = c * c - 4.0 * b * d;
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+        let root = discriminant.sqrt();
+        return vec![(-c + root) / (2.0 * b), (-c - root) / (2.0 * b)];
+    }
+
+    // Cardano's method on the depressed cubic u^3 + p*u + q, where t = u - b / 3
+    let (b, c, d) = (b / a, c / a, d / a);
+    let shift = b / 3.0;
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+
+    if discriminant > 1e-9 {
+        let root = discriminant.sqrt();
+        vec![(-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt() - shift]
+    } else if discriminant > -1e-9 {
+        let u = (-q / 2.0).cbrt();
+        vec![2.0 * u - shift, -u - shift]
+    } else {
+        // Three real roots, spread evenly around a circle
+        let radius = (-p * p * p / 27.0).sqrt();
+        let angle = (-q / (2.0 * radius)).clamp(-1.0, 1.0).acos();
+        let magnitude = 2.0 * radius.cbrt();
+        let turn = std::f32::consts::TAU;
+        vec![
+            magnitude * (angle / 3.0).cos() - shift,
+            magnitude * ((angle + turn) / 3.0).cos() - shift,
+            magnitude * ((angle + 2.0 * turn) / 3.0).cos() - shift
+        ]
+    }
+}
+
+// Stroke widths either scale with the camera zoom or stay the same number of pixels on screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StrokeWidth {
+    World(f32),
+    Screen(f32)
+}
+
+impl StrokeWidth {
+    pub fn to_pixels(self, camera: Camera) -> f32 {
+        match self {
+            StrokeWidth::World(width) => width * camera.y_scale,
+            StrokeWidth::Screen(width) => width
+        }
+    }
+
+    pub fn to_world(self, camera: Camera) -> f32 {
+        match self {
+            StrokeWidth::World(width) => width,
+            StrokeWidth::Screen(width) => width / camera.y_scale
+        }
+    }
+}
+
+impl Default for StrokeWidth {
+    fn default() -> Self {
+        StrokeWidth::Screen(1.0)
+    }
+}
+
+// Alternating drawn and skipped lengths along a stroke, in world units. A short on length
+// close to the stroke width gives a dotted line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DashPattern {
+    pub on: f32,
+    pub off: f32,
+    // How far into the pattern the stroke starts, animating it makes the dashes crawl
+    pub offset: f32
+}
+
+impl DashPattern {
+    pub fn new(on: f32, off: f32) -> Self {
+        DashPattern { on, off, offset: 0.0 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StrokeStyle {
+    pub color: Color,
+    pub width: StrokeWidth,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    // None draws a solid stroke
+    pub dash: Option<DashPattern>
+}
+
+impl StrokeStyle {
+    pub fn new(color: Color, width: StrokeWidth) -> Self {
+        StrokeStyle {
+            color,
+            width,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            dash: None
+        }
+    }
+
+    pub fn with_join(self, join: LineJoin) -> Self {
+        StrokeStyle { join, ..self }
+    }
+
+    pub fn with_cap(self, cap: LineCap) -> Self {
+        StrokeStyle { cap, ..self }
+    }
+
+    pub fn with_dash(self, dash: DashPattern) -> Self {
+        StrokeStyle { dash: Some(dash), ..self }
+    }
+}
+
+// Splits a polyline into the pieces covered by the dashes of the pattern. Lengths are measured
+// along the line itself, so dashes are evenly spaced no matter how the points are spread out.
+pub fn dash_polyline(points: &[Vector2], dash: DashPattern) -> Vec<Vec<Vector2>> {
+    let mut dashes = Vec::new();
+    let period = dash.on + dash.off;
+    if points.len() < 2 || dash.on <= 0.0 || period <= 0.0 {
+        return dashes;
+    }
+
+    // Position inside the current period, on while below dash.on
+    let mut phase = dash.offset.rem_euclid(period);
+    let mut current: Vec<Vector2> = Vec::new();
+    if phase < dash.on {
+        current.push(points[0]);
+    }
+
+    let mut i = 1;
+    while i < points.len() {
+        let start = points[i - 1];
+        let end = points[i];
+        let length = start.distance(end);
+
+        let mut traveled = 0.0;
+        while traveled < length {
+            let is_on = phase < dash.on;
+            let boundary = if is_on { dash.on } else { period };
+            let step = (boundary - phase).min(length - traveled);
+
+            traveled += step;
+            phase += step;
+            let point = start + (end - start) * (traveled / length);
+
+            if is_on {
+                current.push(point);
+            }
+
+            if phase >= boundary {
+                if is_on {
+                    dashes.push(std::mem::take(&mut current));
+                } else {
+                    phase = 0.0;
+                    current.push(point);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if current.len() >= 2 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrowStyle {
+    // Solid triangle, the stroke stops at its base
+    #[default]
+    Filled,
+    // Two strokes meeting at the tip
+    Open
+}
+
+// Arrowhead drawn at an end of a line or curve, size is the length from tip to base
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arrowhead {
+    pub style: ArrowStyle,
+    pub size: StrokeWidth
+}
+
+impl Arrowhead {
+    pub fn new(style: ArrowStyle, size: StrokeWidth) -> Self {
+        Arrowhead { style, size }
+    }
+}
+
+// A color at a position along a stroke, t goes from 0.0 at the start to 1.0 at the end
+#[derive(Clone, Copy, Debug)]
+pub struct ColorStop {
+    pub t: f32,
+    pub color: Color
+}
+
+impl ColorStop {
+    pub fn new(t: f32, color: Color) -> Self {
+        ColorStop { t, color }
+    }
+}
+
+// Stops must be sorted by t, values outside the stops use the first or last color
+pub fn sample_color_stops(stops: &[ColorStop], t: f32) -> Color {
+    Color::from_pixel(sample_color_stops_pixel(stops, t))
+}
+
+fn sample_color_stops_pixel(stops: &[ColorStop], t: f32) -> u32 {
+    let first = match stops.first() {
+        Some(value) => value,
+        None => return 0
+    };
+
+    if t <= first.t {
+        return first.color.to_pixel();
+    }
+
+    let mut i = 1;
+    while i < stops.len() {
+        let start = stops[i - 1];
+        let end = stops[i];
+        if t <= end.t {
+            let span = end.t - start.t;
+            let local_t = if span <= 0.0 { 1.0 } else { (t - start.t) / span };
+            return lerp_color(start.color.to_pixel(), end.color.to_pixel(), local_t);
+        }
+        i += 1;
+    }
+
+    stops[stops.len() - 1].color.to_pixel()
 }
 
-static CAMERA_SPEED: f32 = 0.005;
-static CAMERA_SPEED_DIAG: f32 = 0.0035;
+// Screen pixels the fitted curves can stray from a sketched stroke, and the least the cursor has
+// to move before another point is recorded
+static SKETCH_TOLERANCE_PIXELS: f32 = 2.0;
+static SKETCH_SPACING_PIXELS: f32 = 2.0;
 
 #[no_mangle]
 pub unsafe fn game_update_and_render(game_state: &mut GameState, input_controller: &mut InputController, buffer: &mut OffscreenBuffer) {
-    handle_inputs(*input_controller, game_state);
+    game_state.profiler.begin_frame();
+    let state_summary = hitch_state_summary(game_state);
+    game_state.hitch_watchdog.check(game_state.delta_time, &game_state.profiler, state_summary);
+
+    let zone_start = Instant::now();
+    handle_inputs(*input_controller, game_state, WindowDimensions { width: buffer.width, height: buffer.height });
+    game_state.profiler.record("handle_inputs", zone_start);
+
+    for morph in game_state.morphs.iter_mut() {
+        morph.advance(game_state.delta_time);
+    }
+    game_state.camera_zoom.update(&mut game_state.camera, game_state.delta_time);
+    game_state.camera_shake.advance(game_state.delta_time);
+    if let Some(tween) = &mut game_state.camera_tween {
+        let camera = tween.advance(game_state.delta_time);
+        game_state.camera = Camera { y_scale: game_state.camera.y_scale, ..camera };
+        if tween.is_finished() {
+            game_state.camera_tween = None;
+        }
+    }
+    if let (Some(follower), Some(curve)) = (&mut game_state.circle_follower, game_state.curves[0]) {
+        // The curve's handles can be dragged, keep following the shape it has now
+        if follower.path().segments != [curve] {
+            follower.set_path(BezierPath::new(curve));
+        }
+        follower.advance(game_state.delta_time);
+    }
+    game_state.curve_index.update(&game_state.curves);
+
+    match &mut game_state.indexed_frame {
+        Some(frame) => {
+            // Palette changes recolor every pixel, so the whole frame is resolved each time
+            frame.fit(buffer.width, buffer.height);
+            game_state.dirty_regions.invalidate();
+
+            let zone_start = Instant::now();
+            frame.buffer.resolve(buffer, &frame.palette);
+            game_state.profiler.record("resolve_palette", zone_start);
+        },
+        None => {
+            let zone_start = Instant::now();
+            let render_commands = build_render_commands(game_state, buffer.width, buffer.height);
+            game_state.profiler.record("build_render_commands", zone_start);
+
+            // Effects change pixels that weren't redrawn, so partial redraws would apply them twice
+            if !game_state.post_process.is_empty() {
+                game_state.dirty_regions.invalidate();
+            }
+            game_state.dirty_regions.update(&render_commands, buffer);
+            render_commands.execute_regions(buffer, &mut game_state.profiler, game_state.dirty_regions.regions());
+        }
+    }
+
+    let zone_start = Instant::now();
+    game_state.post_process.run(buffer);
+    game_state.profiler.record("post_process", zone_start);
+}
 
-    clear_buffer(buffer);
-    draw_unit_grid(buffer, game_state.camera);
-    draw_circle(buffer, game_state.camera, Vector2::zero(), 0.05, 0xFFFF0000);
-    draw_bounding_boxes(buffer, game_state);
-    draw_bezier_curves(buffer, game_state);
-    draw_control_points(buffer, game_state.camera, game_state);
+// Game side of the frame, describes what to draw without touching the pixel buffer
+fn build_render_commands(game_state: &GameState, buffer_width: u32, buffer_height: u32) -> RenderCommands {
+    let mut commands = RenderCommands::new();
 
-    let start = SystemTime::now();
-    let time_now = start.duration_since(UNIX_EPOCH).expect("Time went backwards");
-    if time_now.as_millis() - game_state.last_perf_print >= 1000 {
-        println!("Frame time: {}", game_state.delta_time);
-        println!("FPS: {}", 1000.0 / game_state.delta_time);
-        game_state.last_perf_print = time_now.as_millis();
+    let shake = game_state.camera_shake;
+    if game_state.viewports.is_empty() {
+        push_view(&mut commands, game_state, shake.apply(game_state.camera));
+    } else {
+        for viewport in &game_state.viewports {
+            commands.push_clip_rect(viewport.clip_rect());
+            push_view(&mut commands, game_state, shake.apply(viewport.fitted_camera()));
+            commands.pop_clip_rect();
+        }
+    }
+
+    if let Some(readout) = game_state.cursor_readout {
+        push_crosshair(&mut commands, buffer_width, buffer_height, readout.screen);
+    }
+    // Rebinding takes over the corner from the readout, nothing else works until it's done
+    let hud_text = match (&game_state.actions.rebinding, game_state.cursor_readout) {
+        (Some(action), _) => Some(format!("Press the new input for {}", action)),
+        (None, _) if game_state.actions.choosing_rebind => Some("Press the input of the action to rebind".to_string()),
+        (None, Some(readout)) => Some(readout.text()),
+        (None, None) => None
+    };
+    if let Some(text) = hud_text {
+        push_hud_text(&mut commands, buffer_width, buffer_height, &text, text_scale(game_state.dpi_scale));
+    }
+    if game_state.hitch_watchdog.is_displaying() {
+        push_hitch_indicator(&mut commands, buffer_width, buffer_height, game_state.dpi_scale);
+    }
+
+    commands
+}
+
+// Everything in the world seen through one camera
+fn push_view(commands: &mut RenderCommands, game_state: &GameState, camera: Camera) {
+    commands.push(Layer::Background, RenderCommand::ClearBuffer);
+    commands.push(Layer::Grid, RenderCommand::DrawUnitGrid { camera });
+    push_grid_labels(commands, camera, text_scale(game_state.dpi_scale));
+    commands.push(Layer::World, RenderCommand::DrawCircle { camera, center: game_state.debug_circle_center(), radius: 0.05, color: Color::RED });
+
+    // Only the curves whose bounding boxes are in view are drawn
+    let view = camera.get_bounding_box();
+    let visible_curves = indexed_curves(game_state, view);
+    push_bounding_boxes(commands, camera, &visible_curves, game_state.debug_draw.bounding_boxes);
+    push_selection(commands, camera, game_state);
+    push_bezier_curves(commands, camera, game_state, view);
+    if game_state.debug_draw.curvature_combs {
+        // Teeth reach past the curve's bounding box
+        push_curvature_combs(commands, camera, &indexed_curves(game_state, view.expand(COMB_MAX_LENGTH)));
+    }
+    for morph in &game_state.morphs {
+        commands.push(Layer::World, RenderCommand::StrokePath {
+            camera,
+            path: morph.current().to_path(),
+            style: StrokeStyle::new(Color::WHITE, StrokeWidth::Screen(2.0))
+        });
+    }
+    push_control_points(commands, camera, game_state);
+    if game_state.sketch.len() > 1 {
+        commands.push(Layer::Overlay, RenderCommand::DrawPolyline { camera, points: game_state.sketch.clone(), color: Color::rgb(0xAA, 0xAA, 0xAA) });
+    }
+    push_selection_marker(commands, camera, game_state);
+    if let Some(measurement) = game_state.measure.measurement {
+        push_measurement(commands, camera, &game_state.curves, measurement, text_scale(game_state.dpi_scale));
+    }
+}
+
+fn indexed_curves(game_state: &GameState, area: Rectangle) -> Vec<BezierCurve> {
+    game_state.curve_index.query(area).into_iter().flat_map(|slot| game_state.curves[slot]).collect()
+}
+
+fn hitch_state_summary(game_state: &GameState) -> String {
+    let curve_count = game_state.curves.iter().flatten().count();
+    format!(
+        "camera ({:.2}, {:.2}) scale {:.2}, {} curves, selected curve {:?}",
+        game_state.camera.x,
+        game_state.camera.y,
+        game_state.camera.y_scale,
+        curve_count,
+        game_state.selected_curve_index)
+}
+
+// Red square in the top right corner while a recent hitch is being reported
+fn push_hitch_indicator(commands: &mut RenderCommands, buffer_width: u32, buffer_height: u32, dpi_scale: f32) {
+    let ui = UiLayout::new(buffer_width, buffer_height, dpi_scale);
+    let indicator = ui.resolve(UiElement::new(
+        Anchor::TopRight,
+        Vector2 { x: 8.0, y: 8.0 },
+        Vector2 { x: 12.0, y: 12.0 }));
+    commands.push(Layer::Ui, RenderCommand::DrawRect {
+        camera: ui.camera(),
+        rectangle: indicator,
+        color: Color::rgb(0xFF, 0x20, 0x20)
+    });
+}
+
+static CROSSHAIR_GAP_PIXELS: f32 = 4.0;
+// Distance from the point a label is about to its corner, before the text scale
+static LABEL_OFFSET_PIXELS: f32 = 8.0;
+static LABEL_BACKGROUND: Color = Color::rgba(0x00, 0x00, 0x00, 0xAA);
+
+// Whole pixels per font pixel, text grows with the display's DPI
+fn text_scale(dpi_scale: f32) -> u32 {
+    dpi_scale.round().max(1.0) as u32
+}
+
+// One world unit per buffer pixel with the origin in the top left, for drawing on the screen
+// itself instead of in the world
+fn pixel_camera(buffer_width: u32, buffer_height: u32) -> Camera {
+    let width = buffer_width as f32;
+    let height = buffer_height as f32;
+    Camera::new(width / 2.0, height / 2.0, width, height)
+}
+
+// Readout in the top left corner of the screen
+fn push_hud_text(commands: &mut RenderCommands, buffer_width: u32, buffer_height: u32, text: &str, scale: u32) {
+    let corner = LABEL_OFFSET_PIXELS * scale as f32;
+    commands.push(Layer::Ui, RenderCommand::DrawText {
+        camera: pixel_camera(buffer_width, buffer_height),
+        position: Vector2 { x: corner, y: corner },
+        text: text.to_string(),
+        scale,
+        color: Color::WHITE,
+        background: LABEL_BACKGROUND
+    });
+}
+
+// Lines across the whole buffer through the pixel under the mouse, with a gap around it so the
+// point being placed isn't covered
+fn push_crosshair(commands: &mut RenderCommands, buffer_width: u32, buffer_height: u32, cursor: Vector2u32) {
+    let width = buffer_width as f32;
+    let height = buffer_height as f32;
+    let camera = pixel_camera(buffer_width, buffer_height);
+    let x = cursor.x as f32 + 0.5;
+    let y = cursor.y as f32 + 0.5;
+    let gap = CROSSHAIR_GAP_PIXELS;
+    let color = Color::rgba(0xFF, 0xFF, 0xFF, 0x80);
+
+    for (from, to) in [
+        (Vector2 { x, y: 0.5 }, Vector2 { x, y: y - gap }),
+        (Vector2 { x, y: y + gap }, Vector2 { x, y: height - 0.5 }),
+        (Vector2 { x: 0.5, y }, Vector2 { x: x - gap, y }),
+        (Vector2 { x: x + gap, y }, Vector2 { x: width - 0.5, y })
+    ] {
+        if (to - from).x >= 0.0 && (to - from).y >= 0.0 {
+            commands.push(Layer::Ui, RenderCommand::DrawLine { camera, from, to, color });
+        }
+    }
+}
+
+// The frame game_update_and_render would draw into a width x height buffer, as an SVG built from
+// the same render commands instead of pixels. Curves come out as real beziers.
+pub fn export_frame_svg(game_state: &GameState, width: u32, height: u32, path: &str) -> std::io::Result<()> {
+    build_render_commands(game_state, width, height).to_svg(width, height).save(path)
+}
+
+// Both exports at once, what's on screen and the whole scene
+fn export_svgs(game_state: &GameState, buffer_size: WindowDimensions) {
+    match export_frame_svg(game_state, buffer_size.width, buffer_size.height, FRAME_SVG_PATH) {
+        Ok(()) => println!("Exported the frame to {}", FRAME_SVG_PATH),
+        Err(error) => eprintln!("Unable to export the frame to {}: {}", FRAME_SVG_PATH, error)
+    }
+    match export_scene_svg(game_state, SCENE_SVG_PATH) {
+        Ok(()) => println!("Exported the scene to {}", SCENE_SVG_PATH),
+        Err(error) => eprintln!("Unable to export the scene to {}: {}", SCENE_SVG_PATH, error)
+    }
+}
+
+// SVG user units per world unit in exported scenes, sets the size the file opens at
+static SCENE_SVG_SCALE: f32 = 100.0;
+// World units of empty space around the scene
+static SCENE_SVG_MARGIN: f32 = 0.25;
+
+// Writes the scene itself rather than what the camera sees: the curves, their bounding boxes and
+// the debug circle, in world coordinates. The view covers everything, wherever the camera is.
+pub fn export_scene_svg(game_state: &GameState, path: &str) -> std::io::Result<()> {
+    let circle_center = game_state.debug_circle_center();
+    let circle_radius = 0.05;
+
+    let mut min_x = circle_center.x - circle_radius;
+    let mut min_y = circle_center.y - circle_radius;
+    let mut max_x = circle_center.x + circle_radius;
+    let mut max_y = circle_center.y + circle_radius;
+    for curve in game_state.curves.iter().flatten() {
+        let bounding_box = curve.get_bounding_box();
+        min_x = min_x.min(bounding_box.x);
+        min_y = min_y.min(bounding_box.y);
+        max_x = max_x.max(bounding_box.x + bounding_box.width);
+        max_y = max_y.max(bounding_box.y + bounding_box.height);
+    }
+
+    let view_box = Rectangle {
+        x: min_x - SCENE_SVG_MARGIN,
+        y: min_y - SCENE_SVG_MARGIN,
+        width: max_x - min_x + SCENE_SVG_MARGIN * 2.0,
+        height: max_y - min_y + SCENE_SVG_MARGIN * 2.0
+    };
+    let mut svg = SvgDocument::with_view_box(view_box.width * SCENE_SVG_SCALE, view_box.height * SCENE_SVG_SCALE, view_box);
+    // One unit of the exported image in world units, lines stay thin whatever the scene size
+    let line_width = 1.0 / SCENE_SVG_SCALE;
+
+    for curve in game_state.curves.iter().flatten() {
+        let bounding_box = curve.get_bounding_box();
+        svg.rectangle(bounding_box.x, bounding_box.y, bounding_box.width, bounding_box.height, Color::rgba(0x00, 0xDD, 0xAA, 0x33));
+    }
+    for curve in game_state.curves.iter().flatten() {
+        svg.cubic_bezier(curve.p0, curve.p1, curve.p2, curve.p3, SvgStroke::new(line_width * 2.0, Color::BLACK));
     }
+    svg.circle(circle_center, circle_radius, Color::RED);
+
+    svg.save(path)
 }
 
-fn handle_inputs(input: InputController, game_state: &mut GameState) {
+fn handle_inputs(input: InputController, game_state: &mut GameState, buffer_size: WindowDimensions) {
+    if !game_state.actions.is_rebinding() && game_state.actions.was_pressed("Rebind", &input) {
+        game_state.actions.begin_choosing_rebind();
+        return;
+    }
+    if game_state.actions.is_rebinding() {
+        if game_state.actions.update_rebind(&input) {
+            if let Err(error) = game_state.actions.save(CONFIG_PATH) {
+                eprintln!("Unable to save bindings to {}: {}", CONFIG_PATH, error);
+            }
+        }
+        // Inputs pressed while rebinding pick the action or become its binding, they don't
+        // trigger anything themselves
+        return;
+    }
+
+    let actions = &game_state.actions;
+
     // Keyboard camera movement
-    let move_up = input.w.is_down || input.up.is_down;
-    let move_down = input.s.is_down || input.down.is_down;
-    let move_left = input.a.is_down || input.left.is_down;
-    let move_right = input.d.is_down || input.right.is_down;
-
-    if move_up && move_left {
-        (*game_state).camera.x -= CAMERA_SPEED_DIAG * game_state.delta_time;
-        (*game_state).camera.y -= CAMERA_SPEED_DIAG * game_state.delta_time;
-    } else if move_up && move_right {
-        (*game_state).camera.x += CAMERA_SPEED_DIAG * game_state.delta_time;
-        (*game_state).camera.y -= CAMERA_SPEED_DIAG * game_state.delta_time;
-    } else if move_down && move_left {
-        (*game_state).camera.x -= CAMERA_SPEED_DIAG * game_state.delta_time;
-        (*game_state).camera.y += CAMERA_SPEED_DIAG * game_state.delta_time;
-    } else if move_down && move_right {
-        (*game_state).camera.x += CAMERA_SPEED_DIAG * game_state.delta_time;
-        (*game_state).camera.y += CAMERA_SPEED_DIAG * game_state.delta_time;
-    } else if move_up {
-        (*game_state).camera.y -= CAMERA_SPEED * game_state.delta_time;
-    } else if move_down {
-        (*game_state).camera.y += CAMERA_SPEED * game_state.delta_time;
-    } else if move_left {
-        (*game_state).camera.x -= CAMERA_SPEED * game_state.delta_time;
-    } else if move_right {
-        (*game_state).camera.x += CAMERA_SPEED * game_state.delta_time;
+    let mut direction = Vector2::zero();
+    if actions.is_down("PanUp", &input) {
+        direction.y -= 1.0;
+    }
+    if actions.is_down("PanDown", &input) {
+        direction.y += 1.0;
+    }
+    if actions.is_down("PanLeft", &input) {
+        direction.x -= 1.0;
+    }
+    if actions.is_down("PanRight", &input) {
+        direction.x += 1.0;
+    }
+    if direction != Vector2::zero() {
+        // Up is up on screen however the camera is turned or flipped, and diagonals move just
+        // as fast as straight lines
+        if game_state.camera.y_up {
+            direction.y = -direction.y;
+        }
+        let direction = Transform2D::rotation(game_state.camera.rotation).apply_vector(direction.normalized());
+        let step = direction * (game_state.camera_pan.key_speed * game_state.delta_time);
+        game_state.camera.x += step.x;
+        game_state.camera.y += step.y;
+    }
+
+    // Middle mouse drag moves the camera whatever is under the cursor
+    if actions.is_down("Pan", &input) {
+        drag_camera(&mut game_state.camera, input.mouse_state);
     }
 
     // Mouse left click actions
-    let left_down = input.mouse_state.left.is_down;
-    let left_released = !left_down && input.mouse_state.left.was_down;
-    let left_pressed = left_down && !input.mouse_state.left.was_down;
+    let actions = &game_state.actions;
+    let left_down = actions.is_down("Select", &input);
+    let left_released = actions.was_released("Select", &input);
+    let left_pressed = actions.was_pressed("Select", &input);
+    let sketch_down = actions.is_down("Sketch", &input);
+    let sketch_released = actions.was_released("Sketch", &input);
+    let reset_camera = actions.was_pressed("ResetCamera", &input);
+    let snap = !actions.is_down("NoSnap", &input);
+    let additive = actions.is_down("AddToSelection", &input);
+    let undo = actions.was_pressed("Undo", &input);
+    let redo = actions.was_pressed("Redo", &input);
+
+    if actions.was_pressed("ToggleBoundingBoxes", &input) {
+        game_state.debug_draw.toggle_bounding_box_style();
+    }
+    if actions.was_pressed("ToggleCurvatureCombs", &input) {
+        game_state.debug_draw.curvature_combs = !game_state.debug_draw.curvature_combs;
+    }
+    if actions.was_pressed("ToggleCursorReadout", &input) {
+        game_state.debug_draw.cursor_readout = !game_state.debug_draw.cursor_readout;
+    }
+    if actions.was_pressed("ToggleControlPolygons", &input) {
+        game_state.debug_draw.control_polygons = !game_state.debug_draw.control_polygons;
+    }
+    if actions.was_pressed("ToggleMeasure", &input) {
+        game_state.measure.enabled = !game_state.measure.enabled;
+        game_state.measure.dragging = false;
+        game_state.measure.measurement = None;
+    }
+    if actions.was_pressed("ExportSvg", &input) {
+        export_svgs(game_state, buffer_size);
+    }
+    if actions.was_pressed("ToggleEditor", &input) {
+        game_state.editor.enabled = !game_state.editor.enabled;
+        game_state.selected_curve_index = None;
+        game_state.marquee = None;
+    }
+
+    // Nothing is undone halfway through a drag, the drag is one edit once it's released
+    if (undo || redo) && game_state.selected_curve_index.is_none() {
+        let changed = if undo {
+            game_state.history.undo(&mut game_state.curves)
+        } else {
+            game_state.history.redo(&mut game_state.curves)
+        };
+        if changed {
+            game_state.curve_index.update(&game_state.curves);
+        }
+    }
 
     if left_released {
+        if let (Some(index), Some(before)) = (game_state.selected_curve_index, game_state.drag_start.take()) {
+            let after = game_state.curves[index as usize];
+            if after != Some(before) {
+                game_state.history.push(Edit::SetCurve { slot: index as usize, before: Some(before), after });
+            }
+        }
         game_state.selected_curve_index = None;
+        game_state.measure.dragging = false;
+        if let Some(marquee) = game_state.marquee.take() {
+            let area = marquee.rectangle();
+            if area.width.max(area.height) * game_state.camera.y_scale >= MARQUEE_MIN_PIXELS {
+                game_state.selection.select_in_rectangle(&game_state.curves, area, true);
+            }
+        }
     }
 
+    let cursor = input.mouse_state.pos;
+    game_state.camera_zoom.scroll(game_state.camera, input.mouse_state.wheel_delta, Vector2::new(cursor.x as f32, cursor.y as f32));
+
     let cursor_pos_world = screen_space_to_world_space(game_state.camera, input.mouse_state.pos);
-    if left_pressed {
-        let mut i = 0;
-        while i < game_state.curves.len() as u32 {
-            match game_state.curves[i as usize] {
-                Some(curve) => {
-                    if distance_f32(cursor_pos_world, curve.p1) < 0.02 {
-                        game_state.selected_curve_index = Some(i);
-                        game_state.selected_control_point = 0;
-                        break;
-                    } else if distance_f32(cursor_pos_world, curve.p2) < 0.02 {
-                        game_state.selected_curve_index = Some(i);
-                        game_state.selected_control_point = 1;
-                        break;
-                    }
-                },
-                None => {}
-            }
+    game_state.cursor_readout = if game_state.debug_draw.cursor_readout {
+        Some(CursorReadout { screen: input.mouse_state.pos, world: cursor_pos_world })
+    } else {
+        None
+    };
+    let pick = game_state.pick_curve(cursor_pos_world, PICK_DISTANCE);
+    game_state.editor.hovered = match pick {
+        Some((index, CurvePick::ControlPoint(point))) if game_state.editor.enabled => Some((index, point)),
+        _ => None
+    };
+    // Measuring takes over the left button, both ends snap like edits do
+    if left_pressed && game_state.measure.enabled {
+        let start = if snap { snapped_cursor(game_state, cursor_pos_world, None) } else { cursor_pos_world };
+        game_state.measure.measurement = Some(Measurement::new(start));
+        game_state.measure.start_on = measured_curve(game_state, start);
+        game_state.measure.dragging = true;
+    }
+    if left_down && game_state.measure.dragging {
+        let end = if snap { snapped_cursor(game_state, cursor_pos_world, None) } else { cursor_pos_world };
+        let along = match (game_state.measure.start_on, measured_curve(game_state, end)) {
+            (Some((slot, start_t)), Some((end_slot, end_t))) if slot == end_slot => Some((slot, start_t, end_t)),
+            _ => None
+        };
+        if let Some(measurement) = &mut game_state.measure.measurement {
+            measurement.end = end;
+            measurement.along = along;
+        }
+    }
 
-            i += 1;
+    // Clicks select what's under the cursor, shift adds it to the selection
+    if left_pressed && game_state.editor.enabled && !game_state.measure.enabled {
+        match pick {
+            Some((index, CurvePick::ControlPoint(point))) => {
+                game_state.selected_curve_index = Some(index);
+                game_state.selected_control_point = point;
+                game_state.drag_start = game_state.curves[index as usize];
+                game_state.selection.select(Selectable::ControlPoint(index, point), additive);
+            },
+            Some((index, CurvePick::Curve(_))) => game_state.selection.select(Selectable::Curve(index), additive),
+            None => {
+                if !additive {
+                    game_state.selection.clear();
+                }
+                game_state.marquee = Some(Marquee::new(cursor_pos_world));
+            }
         }
     }
 
-    if left_down {
+    if left_down && !game_state.measure.enabled {
         match game_state.selected_curve_index {
             Some(index) => {
                 // Moving control point with mouse
+                let target = if snap { snapped_cursor(game_state, cursor_pos_world, Some(index)) } else { cursor_pos_world };
                 match &mut game_state.curves[index as usize] {
                     Some(ref mut value) => {
-                        if game_state.selected_control_point == 0 {
-                            (*value).p1 = cursor_pos_world;
-                        } else if game_state.selected_control_point == 1 {
-                            (*value).p2 = cursor_pos_world;
-                        } else {
-                            panic!("Curve can't have {} control points", index + 1);
-                        }
+                        value.modify(game_state.selected_control_point, target);
                     },
                     None => {
                         panic!("Curve with index {} is None", index);
                     }
                 }
+                // Picking and culling see the curve where it is now
+                game_state.curve_index.update(&game_state.curves);
             },
-            None => {
-                // Moving camera with mouse
-                let mouse_delta = Vector2i32 {
-                    x: input.mouse_state.prev_pos.x as i32 - input.mouse_state.pos.x as i32,
-                    y: input.mouse_state.prev_pos.y as i32 - input.mouse_state.pos.y as i32
-                };
+            None => match &mut game_state.marquee {
+                Some(marquee) => marquee.end = cursor_pos_world,
+                None => {
+                    // Moving camera with mouse
+                    drag_camera(&mut game_state.camera, input.mouse_state);
+                }
+            }
+        }
+    }
+
+    if sketch_down {
+        let far_enough = match game_state.sketch.last() {
+            Some(last) => last.distance(cursor_pos_world) * game_state.camera.y_scale >= SKETCH_SPACING_PIXELS,
+            None => true
+        };
+        // The start of the stroke snaps so new curves can join up with the ones already there
+        if game_state.sketch.is_empty() && snap {
+            let start = snapped_cursor(game_state, cursor_pos_world, None);
+            game_state.sketch.push(start);
+        } else if far_enough {
+            game_state.sketch.push(cursor_pos_world);
+        }
+    }
+    if sketch_released {
+        if snap {
+            let end = snapped_cursor(game_state, cursor_pos_world, None);
+            if let Some(last) = game_state.sketch.last_mut() {
+                *last = end;
+            }
+        }
+        finish_sketch(game_state);
+    }
+
+    // Reset camera
+    if reset_camera {
+        (*game_state).camera.x = 0.0;
+        (*game_state).camera.y = 0.0;
+    }
+}
+
+// Cursor position moved onto the closest snap target, the curve being edited isn't one
+fn snapped_cursor(game_state: &GameState, position: Vector2, editing: Option<u32>) -> Vector2 {
+    let curves: Vec<BezierCurve> = game_state.curves.iter().enumerate()
+        .filter(|(slot, _)| Some(*slot as u32) != editing)
+        .flat_map(|(_, curve)| *curve)
+        .collect();
+    game_state.snapping.apply(game_state.camera, position, &curves)
+}
+
+// Selected curve the point is on and where along it, only selected curves are measured along
+fn measured_curve(game_state: &GameState, point: Vector2) -> Option<(u32, f32)> {
+    for item in &game_state.selection.items {
+        if let Selectable::Curve(slot) = *item {
+            if let Some(curve) = game_state.curves[slot as usize] {
+                let (t, distance) = curve.nearest(point);
+                if distance <= PICK_DISTANCE {
+                    return Some((slot, t));
+                }
+            }
+        }
+    }
+    None
+}
+
+// Moves the camera so the world point under the cursor follows it
+fn drag_camera(camera: &mut Camera, mouse_state: MouseState) {
+    let previous = screen_space_to_world_space(*camera, mouse_state.prev_pos);
+    let current = screen_space_to_world_space(*camera, mouse_state.pos);
+    camera.x += previous.x - current.x;
+    camera.y += previous.y - current.y;
+}
+
+// Fits the sketched stroke and puts the curves into the empty slots. The stroke is kept whole or
+// not at all, when there aren't enough empty slots for all of its curves nothing is added.
+fn finish_sketch(game_state: &mut GameState) {
+    let tolerance = SKETCH_TOLERANCE_PIXELS / game_state.camera.y_scale;
+    let fitted = fit_points(&game_state.sketch, tolerance);
+    game_state.sketch.clear();
+
+    let free = game_state.curves.iter().filter(|slot| slot.is_none()).count();
+    if fitted.len() > free {
+        eprintln!("Stroke not added, it needs {} curves and only {} slots are free", fitted.len(), free);
+        return;
+    }
+
+    let mut fitted = fitted.into_iter();
+    let mut edits = Vec::new();
+    for (index, slot) in game_state.curves.iter_mut().enumerate().filter(|(_, slot)| slot.is_none()) {
+        match fitted.next() {
+            Some(curve) => {
+                *slot = Some(curve);
+                edits.push(Edit::SetCurve { slot: index, before: None, after: Some(curve) });
+            },
+            None => break
+        }
+    }
+    // The whole stroke is undone at once
+    if !edits.is_empty() {
+        game_state.history.push(Edit::Group(edits));
+    }
+}
+
+// The camera's x and y are the world point in the middle of the screen, and width and height are
+// how much of the world it sees. The platform sizes the camera to the buffer, so the middle of the
+// camera is the middle of the buffer, or of its viewport when it has a screen offset. The world is
+// turned around the middle of the screen by the opposite of the camera's rotation. Screen y points
+// down, so world y does too unless the camera has y pointing up, then the world is flipped after
+// turning it.
+
+// The pixel corner nearest to the world point, for edges like the sides of a rectangle
+pub fn world_space_to_screen_space(camera: Camera, pos: Vector2) -> Vector2i32 {
+    let screen = world_space_to_screen_space_f32(camera, pos);
+    Vector2i32 { x: screen.x.round() as i32, y: screen.y.round() as i32 }
+}
+
+// The pixel the world point is inside, for points like the ends of a line
+pub fn world_space_to_pixel(camera: Camera, pos: Vector2) -> Vector2i32 {
+    let screen = world_space_to_screen_space_f32(camera, pos);
+    Vector2i32 { x: screen.x.floor() as i32, y: screen.y.floor() as i32 }
+}
+
+pub fn world_space_to_screen_space_f32(camera: Camera, pos: Vector2) -> Vector2 {
+    let mut offset = pos - Vector2 { x: camera.x, y: camera.y };
+    if camera.rotation != 0.0 {
+        let (sin, cos) = camera.rotation.sin_cos();
+        offset = Vector2 { x: offset.x * cos + offset.y * sin, y: offset.y * cos - offset.x * sin };
+    }
+    if camera.y_up {
+        offset.y = -offset.y;
+    }
+    let x = (offset.x + camera.width / 2.0) * camera.y_scale + camera.screen_offset.x;
+    let y = (offset.y + camera.height / 2.0) * camera.y_scale + camera.screen_offset.y;
+    Vector2 { x, y }
+}
+
+// World point at the top left corner of a pixel
+pub fn screen_space_to_world_space(camera: Camera, pos: Vector2u32) -> Vector2 {
+    screen_space_to_world_space_f32(camera, Vector2 { x: pos.x as f32, y: pos.y as f32 })
+}
+
+// Exact inverse of world_space_to_screen_space_f32
+pub fn screen_space_to_world_space_f32(camera: Camera, pos: Vector2) -> Vector2 {
+    let mut offset = Vector2 {
+        x: (pos.x - camera.screen_offset.x) / camera.y_scale - camera.width / 2.0,
+        y: (pos.y - camera.screen_offset.y) / camera.y_scale - camera.height / 2.0
+    };
+    if camera.y_up {
+        offset.y = -offset.y;
+    }
+    if camera.rotation != 0.0 {
+        let (sin, cos) = camera.rotation.sin_cos();
+        offset = Vector2 { x: offset.x * cos - offset.y * sin, y: offset.x * sin + offset.y * cos };
+    }
+    Vector2 { x: offset.x + camera.x, y: offset.y + camera.y }
+}
+
+// Clears the current clip rect, which is the whole buffer unless one has been pushed
+unsafe fn clear_buffer(buffer: &mut OffscreenBuffer) {
+    let clip = buffer.clip_rect();
+
+    let mut y = clip.y0;
+    while y < clip.y1 {
+        fill_pixels(&mut buffer.row(y)[clip.x0 as usize..clip.x1 as usize], 0);
+        y += 1;
+    }
+}
+
+// Fills a screen rect with one color, clipped
+unsafe fn fill_clip_rect(buffer: &mut OffscreenBuffer, rect: ClipRect, color: u32) {
+    let clip = buffer.clip_rect().intersection(rect);
+
+    let mut y = clip.y0;
+    while y < clip.y1 {
+        buffer.fill_span(y, clip.x0, clip.x1, color);
+        y += 1;
+    }
+}
+
+// Closest the grid lines get on screen, the spacing steps up through 1, 2 and 5 times powers of
+// ten until the lines are at least this far apart
+static GRID_MIN_PIXELS: f32 = 12.0;
+static GRID_MINOR_COLOR: u32 = 0xFF333333;
+static GRID_MAJOR_COLOR: u32 = 0xFF666666;
+// The world's axes, the X axis is the horizontal line at y = 0 and the Y axis the vertical one
+static GRID_X_AXIS_COLOR: u32 = 0xFFAA4444;
+static GRID_Y_AXIS_COLOR: u32 = 0xFF44AA44;
+
+// World units between grid lines at the camera's zoom, and how many of them there are from one
+// major line to the next. Major lines are at the next power of ten.
+fn grid_spacing(y_scale: f32) -> (f32, i32) {
+    let smallest = GRID_MIN_PIXELS / y_scale.max(1e-6);
+    let power = 10.0_f32.powf(smallest.log10().floor());
+    for (step, per_major) in [(1.0, 10), (2.0, 5), (5.0, 2)] {
+        if power * step >= smallest {
+            return (power * step, per_major);
+        }
+    }
+    (power * 10.0, 10)
+}
+
+fn grid_color(line: i64, per_major: i32) -> u32 {
+    if line.rem_euclid(per_major as i64) == 0 { GRID_MAJOR_COLOR } else { GRID_MINOR_COLOR }
+}
+
+// Pixels between a grid line and its label, before the text scale
+static GRID_LABEL_INSET_PIXELS: f32 = 3.0;
+static GRID_LABEL_COLOR: Color = Color::rgb(0xAA, 0xAA, 0xAA);
+
+// Labels the major grid lines with their world coordinates like a graphing tool, x values along
+// the top of the view and y values down its left side. Lines are skipped when the labels would
+// run into each other. Rotated grids aren't labelled, their lines cross the edges at an angle.
+fn push_grid_labels(commands: &mut RenderCommands, camera: Camera, scale: u32) {
+    if camera.rotation != 0.0 {
+        return;
+    }
+
+    let (spacing, per_major) = grid_spacing(camera.y_scale);
+    let major = spacing * per_major as f32;
+    // Major lines are at powers of ten, so their values need no more decimals than this
+    let decimals = (-major.log10().round()).max(0.0) as usize;
+    let label = |line: i64| format!("{:.*}", decimals, line as f32 * major);
+
+    let view = camera.get_bounding_box();
+    let inset = GRID_LABEL_INSET_PIXELS * scale as f32;
+    let padding = (TEXT_PADDING * scale) as f32;
+    let left = camera.screen_offset.x;
+    let top = camera.screen_offset.y;
+    let right = left + camera.width * camera.y_scale;
+    let bottom = top + camera.height * camera.y_scale;
+    let major_pixels = major * camera.y_scale;
+    let first_x = (view.x / major).ceil() as i64;
+    let last_x = ((view.x + view.width) / major).floor() as i64;
+    let first_y = (view.y / major).ceil() as i64;
+    let last_y = ((view.y + view.height) / major).floor() as i64;
+
+    let mut push_label = |line: i64, corner: Vector2| {
+        commands.push(Layer::Ui, RenderCommand::DrawText {
+            camera,
+            position: screen_space_to_world_space_f32(camera, corner),
+            text: label(line),
+            scale,
+            color: GRID_LABEL_COLOR,
+            background: LABEL_BACKGROUND
+        });
+    };
+
+    // Only every stride-th line gets a label when the widest one doesn't fit between them. Lines
+    // are picked by their index so the labels don't jump around while panning.
+    let widest = max(text_size(&label(first_x), scale).0, text_size(&label(last_x), scale).0) as f32;
+    let stride = ((widest + padding * 2.0 + inset) / major_pixels).ceil().max(1.0) as i64;
+    let mut line = first_x;
+    while line <= last_x {
+        let x = world_space_to_screen_space_f32(camera, Vector2 { x: line as f32 * major, y: camera.y }).x + inset;
+        let width = text_size(&label(line), scale).0 as f32;
+        if line.rem_euclid(stride) == 0 && x + width + padding <= right {
+            push_label(line, Vector2 { x: x + padding, y: top + inset + padding });
+        }
+        line += 1;
+    }
+
+    // The top row belongs to the x labels
+    let height = text_size("0", scale).1 as f32;
+    let row = height + padding * 2.0 + inset;
+    let stride = (row / major_pixels).ceil().max(1.0) as i64;
+    let mut line = first_y;
+    while line <= last_y {
+        let y = world_space_to_screen_space_f32(camera, Vector2 { x: camera.x, y: line as f32 * major }).y + inset;
+        if line.rem_euclid(stride) == 0 && y >= top + row && y + height + padding <= bottom {
+            push_label(line, Vector2 { x: left + inset + padding, y: y + padding });
+        }
+        line += 1;
+    }
+}
+
+// Lines at every multiple of the spacing the camera can see, spaced so they never crowd together
+// when zoomed out or vanish when zoomed in. The axes go on top so the other lines don't break them.
+// Their labels are drawn separately as text, see push_grid_labels.
+unsafe fn draw_unit_grid(buffer: &mut OffscreenBuffer, camera: Camera) {
+    if !camera.is_axis_aligned() {
+        draw_unit_grid_rotated(buffer, camera);
+        return;
+    }
 
-                (*game_state).camera.x += mouse_delta.x as f32 / game_state.camera.y_scale;
-                (*game_state).camera.y += mouse_delta.y as f32 / game_state.camera.y_scale;
+    let (spacing, per_major) = grid_spacing(camera.y_scale);
+    let view = camera.get_bounding_box();
+    // Pixels the camera covers, which is the whole buffer unless it's drawn into a viewport
+    let origin_x = camera.screen_offset.x.round() as i32;
+    let origin_y = camera.screen_offset.y.round() as i32;
+    let pixel_width = max((camera.width * camera.y_scale).round() as i32, 1);
+    let pixel_height = max((camera.height * camera.y_scale).round() as i32, 1);
+
+    // Horizontal lines
+    let mut x_axis: Option<i32> = None;
+    let mut line = (view.y / spacing).ceil() as i64;
+    while line as f32 * spacing < view.y + view.height {
+        let y = world_space_to_pixel(camera, Vector2 { x: camera.x, y: line as f32 * spacing }).y;
+        if y >= origin_y && y < origin_y + pixel_height {
+            if line == 0 {
+                x_axis = Some(y);
+            } else {
+                buffer.fill_span(y, origin_x, origin_x + pixel_width, grid_color(line, per_major));
             }
         }
+        line += 1;
     }
 
-    // Reset camera
-    let right_clicked = input.mouse_state.right.is_down && !input.mouse_state.right.was_down;
-    if right_clicked {
-        (*game_state).camera.x = 0.0;
-        (*game_state).camera.y = 0.0;
+    // Vertical lines, the Y axis is last so it's drawn over the others
+    let mut y_axis: Option<i32> = None;
+    let mut line = (view.x / spacing).ceil() as i64;
+    while line as f32 * spacing < view.x + view.width {
+        let x = world_space_to_pixel(camera, Vector2 { x: line as f32 * spacing, y: camera.y }).x;
+        if x >= origin_x && x < origin_x + pixel_width {
+            if line == 0 {
+                y_axis = Some(x);
+            } else {
+                buffer.fill_column(x, origin_y, origin_y + pixel_height, grid_color(line, per_major));
+            }
+        }
+        line += 1;
+    }
+    if let Some(x) = y_axis {
+        buffer.fill_column(x, origin_y, origin_y + pixel_height, GRID_Y_AXIS_COLOR);
     }
-}
 
-fn world_space_to_screen_space(camera: Camera, pos: Vector2) -> Vector2u32 {
-    let x = ((pos.x - camera.x + camera.width / 2.0) * camera.y_scale) as u32;
-    let y = ((pos.y - camera.y + camera.height / 2.0) * camera.y_scale) as u32;
-    Vector2u32 { x, y }
+    if let Some(y) = x_axis {
+        buffer.fill_span(y, origin_x, origin_x + pixel_width, GRID_X_AXIS_COLOR);
+    }
 }
 
-fn world_space_to_screen_space_i32(camera: Camera, pos: Vector2) -> Vector2i32 {
-    let x = ((pos.x - camera.x + camera.width / 2.0) * camera.y_scale) as i32;
-    let y = ((pos.y - camera.y + camera.height / 2.0) * camera.y_scale) as i32;
-    Vector2i32 { x, y }
+// The lines might not be along the rows and columns of the buffer, each one is drawn across the
+// whole view
+unsafe fn draw_unit_grid_rotated(buffer: &mut OffscreenBuffer, camera: Camera) {
+    for (from, to, color) in unit_grid_lines(camera) {
+        draw_line(buffer, camera, from, to, color);
+    }
 }
 
-fn screen_space_to_world_space(camera: Camera, pos: Vector2u32) -> Vector2 {
-    let x = pos.x as f32 / camera.y_scale + camera.x - camera.width / 2.0;
-    let y = pos.y as f32 / camera.y_scale + camera.y - camera.height / 2.0;
-    Vector2 { x, y }
-}
+// Every line of the grid in world space, from one side of the camera's view to the other. The
+// axes come last so they go on top of the lines they cross.
+fn unit_grid_lines(camera: Camera) -> Vec<(Vector2, Vector2, Color)> {
+    let (spacing, per_major) = grid_spacing(camera.y_scale);
+    let view = camera.get_bounding_box();
+    let top = view.y;
+    let bottom = view.y + view.height;
+    let left = view.x;
+    let right = view.x + view.width;
+    let mut lines = Vec::new();
+
+    let mut line = (left / spacing).ceil() as i64;
+    while line as f32 * spacing <= right {
+        if line != 0 {
+            let x = line as f32 * spacing;
+            lines.push((Vector2 { x, y: top }, Vector2 { x, y: bottom }, Color::from_argb(grid_color(line, per_major))));
+        }
+        line += 1;
+    }
+    let mut line = (top / spacing).ceil() as i64;
+    while line as f32 * spacing <= bottom {
+        if line != 0 {
+            let y = line as f32 * spacing;
+            lines.push((Vector2 { x: left, y }, Vector2 { x: right, y }, Color::from_argb(grid_color(line, per_major))));
+        }
+        line += 1;
+    }
 
-unsafe fn clear_buffer(buffer: &mut OffscreenBuffer) {
-    ptr::write_bytes((*buffer).memory, 0u8, (buffer.height * buffer.width * buffer.bytes_per_pixel) as usize);
+    if left <= 0.0 && right >= 0.0 {
+        lines.push((Vector2 { x: 0.0, y: top }, Vector2 { x: 0.0, y: bottom }, Color::from_argb(GRID_Y_AXIS_COLOR)));
+    }
+    if top <= 0.0 && bottom >= 0.0 {
+        lines.push((Vector2 { x: left, y: 0.0 }, Vector2 { x: right, y: 0.0 }, Color::from_argb(GRID_X_AXIS_COLOR)));
+    }
+    lines
 }
 
-// TODO: ????
-unsafe fn draw_unit_grid(buffer: &mut OffscreenBuffer, camera: Camera) {
-    // Horizontal lines
-    let camera_height_fpart = camera.height / 2.0 - ((camera.height / 2.0) as i32) as f32;
-    let camera_y_fpart = if camera.y >= 0.0 {
-        camera.y - (camera.y as i32) as f32
-    } else {
-        1.0 + (camera.y + (camera.y.abs() as i32) as f32)
+/// Single pixel wide line rasterized with Bresenham's algorithm, clipped to the buffer
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_line(buffer: &mut OffscreenBuffer, camera: Camera, from: Vector2, to: Vector2, color: Color) {
+    let color = color.to_pixel();
+    let from_screen = world_space_to_pixel(camera, from);
+    let to_screen = world_space_to_pixel(camera, to);
+
+    let (start, end) = match clip_line_to_buffer(buffer, from_screen, to_screen) {
+        Some(value) => value,
+        None => return
     };
-    let y_offset = camera_y_fpart - camera_height_fpart;
 
-    let mut line_y: u32 = 0;
-    while line_y < camera.height as u32 {
-        let y = (((line_y as f32 - y_offset) * camera.y_scale) as i32).rem_euclid(buffer.height as i32) as u32;
+    let dx = (end.x - start.x).abs();
+    let dy = -(end.y - start.y).abs();
+    let step_x = if start.x < end.x { 1 } else { -1 };
+    let step_y = if start.y < end.y { 1 } else { -1 };
+    let mut error = dx + dy;
 
-        let mut x: u32 = 0;
-        while x < buffer.width as u32 {
-            draw_pixel_to_buffer(buffer, x, y, 0xFF444444);
-            x += 1;
+    let mut x = start.x;
+    let mut y = start.y;
+    loop {
+        draw_pixel_to_buffer(buffer, x as u32, y as u32, color);
+
+        if x == end.x && y == end.y {
+            break;
+        }
+
+        let error_2 = error * 2;
+        if error_2 >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if error_2 <= dx {
+            error += dx;
+            y += step_y;
         }
-        line_y += 1;
     }
+}
 
-    // Vertical lines
-    let camera_width_fpart = camera.width / 2.0 - ((camera.width / 2.0) as i32) as f32;
-    let camera_x_fpart = if camera.x >= 0.0 {
-        camera.x - (camera.x as i32) as f32
-    } else {
-        1.0 + (camera.x + (camera.x.abs() as i32) as f32)
+// Cohen-Sutherland clipping against the buffer, returns None if the line is entirely outside
+fn clip_line_to_buffer(buffer: &OffscreenBuffer, a: Vector2i32, b: Vector2i32) -> Option<(Vector2i32, Vector2i32)> {
+    static INSIDE: u8 = 0;
+    static LEFT: u8 = 1;
+    static RIGHT: u8 = 2;
+    static TOP: u8 = 4;
+    static BOTTOM: u8 = 8;
+
+    if buffer.width == 0 || buffer.height == 0 {
+        return None;
+    }
+
+    let max_x = (buffer.width - 1) as f32;
+    let max_y = (buffer.height - 1) as f32;
+
+    let outcode = |x: f32, y: f32| {
+        let mut code = INSIDE;
+        if x < 0.0 {
+            code |= LEFT;
+        } else if x > max_x {
+            code |= RIGHT;
+        }
+        if y < 0.0 {
+            code |= TOP;
+        } else if y > max_y {
+            code |= BOTTOM;
+        }
+        code
     };
-    let x_offset = camera_x_fpart - camera_width_fpart;
-    println!("offset: {}", x_offset);
 
-    let mut line_x: u32 = 0;
-    while line_x < camera.width as u32 {
-        let x = (((line_x as f32 - x_offset) * camera.y_scale) as i32).rem_euclid(buffer.width as i32) as u32;
+    let (mut x0, mut y0) = (a.x as f32, a.y as f32);
+    let (mut x1, mut y1) = (b.x as f32, b.y as f32);
+    let mut code0 = outcode(x0, y0);
+    let mut code1 = outcode(x1, y1);
 
-        let mut y: u32 = 0;
-        while y < buffer.height as u32 {
-            draw_pixel_to_buffer(buffer, x, y, 0xFF444444);
-            y += 1;
+    loop {
+        if code0 | code1 == INSIDE {
+            break;
+        }
+        if code0 & code1 != INSIDE {
+            return None;
+        }
+
+        // Move the endpoint that is outside onto the edge it crosses
+        let code_out = if code0 != INSIDE { code0 } else { code1 };
+        let (x, y) = if code_out & BOTTOM != 0 {
+            (x0 + (x1 - x0) * (max_y - y0) / (y1 - y0), max_y)
+        } else if code_out & TOP != 0 {
+            (x0 + (x1 - x0) * (0.0 - y0) / (y1 - y0), 0.0)
+        } else if code_out & RIGHT != 0 {
+            (max_x, y0 + (y1 - y0) * (max_x - x0) / (x1 - x0))
+        } else {
+            (0.0, y0 + (y1 - y0) * (0.0 - x0) / (x1 - x0))
+        };
+
+        if code_out == code0 {
+            x0 = x;
+            y0 = y;
+            code0 = outcode(x0, y0);
+        } else {
+            x1 = x;
+            y1 = y;
+            code1 = outcode(x1, y1);
         }
-        line_x += 1;
     }
+
+    Some((
+        Vector2i32 { x: (x0.round() as i32).clamp(0, max_x as i32), y: (y0.round() as i32).clamp(0, max_y as i32) },
+        Vector2i32 { x: (x1.round() as i32).clamp(0, max_x as i32), y: (y1.round() as i32).clamp(0, max_y as i32) }
+    ))
 }
 
 // TODO: Make sure transparent lines work properly
-// TODO: Fix drawing out of bounds
 // Xiaolin Wu's line algorithm
-unsafe fn draw_line(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b: Vector2, color: u32) {
-    let a_screen = world_space_to_screen_space_i32(camera, a);
-    let b_screen = world_space_to_screen_space_i32(camera, b);
+unsafe fn draw_line_antialiased(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b: Vector2, color: Color) {
+    let color = color.to_pixel();
+    draw_line_with_color(buffer, camera, a, b, |_| color);
+}
+
+/// Interpolates from color_a at a to color_b at b
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_line_gradient(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b: Vector2, color_a: Color, color_b: Color) {
+    let color_a = color_a.to_pixel();
+    let color_b = color_b.to_pixel();
+    draw_line_with_color(buffer, camera, a, b, |t| lerp_color(color_a, color_b, t));
+}
+
+// Shared stroke loop, color_at gets how far along the line (0.0 at a, 1.0 at b) the pixel is
+unsafe fn draw_line_with_color<F: Fn(f32) -> u32>(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b: Vector2, color_at: F) {
+    let a_screen = world_space_to_pixel(camera, a);
+    let b_screen = world_space_to_pixel(camera, b);
 
     let mut x0 = a_screen.x;
     let mut y0 = a_screen.y;
@@ -562,7 +2798,8 @@ unsafe fn draw_line(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b:
         y1 = old_x1;
     }
 
-    if x0 > x1 {
+    let reversed = x0 > x1;
+    if reversed {
         let old_x0 = x0;
         x0 = x1;
         x1 = old_x0;
@@ -580,15 +2817,20 @@ unsafe fn draw_line(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b:
 
     let mut y_intersect = y0 as f32;
 
+    let line_t = |x: i32| {
+        let t = if x1 - x0 == 0 { 0.0 } else { (x - x0) as f32 / (x1 - x0) as f32 };
+        if reversed { 1.0 - t } else { t }
+    };
+
     if steep {
         let mut x = x0;
         while x <= x1 {
             let y_intersect_fpart = y_intersect as f32 - (y_intersect as u32) as f32;
-            let alpha = ((1.0 - y_intersect_fpart) * 255.0) as u32;
-            let color_with_alpha = (color & 0x00FFFFFF) | (alpha << 24);
+            let color = color_at(line_t(x));
+            let color_with_alpha = scale_color(color, 1.0 - y_intersect_fpart);
 
-            draw_pixel_to_buffer(buffer, y_intersect as u32, max(x, 0) as u32, color);
-            draw_pixel_to_buffer(buffer, max(y_intersect as i32 - 1, 0) as u32, max(x, 0) as u32, color_with_alpha);
+            draw_pixel_clipped(buffer, y_intersect as i32, x, color);
+            draw_pixel_clipped(buffer, y_intersect as i32 - 1, x, color_with_alpha);
 
             y_intersect += gradient;
             x += 1;
@@ -597,11 +2839,11 @@ unsafe fn draw_line(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b:
         let mut x = x0;
         while x <= x1 {
             let y_intersect_fpart = y_intersect as f32 - (y_intersect as u32) as f32;
-            let alpha = ((1.0 - y_intersect_fpart) * 255.0) as u32;
-            let color_with_alpha = (color & 0x00FFFFFF) | (alpha << 24);
+            let color = color_at(line_t(x));
+            let color_with_alpha = scale_color(color, 1.0 - y_intersect_fpart);
 
-            draw_pixel_to_buffer(buffer, max(x, 0) as u32, y_intersect as u32, color);
-            draw_pixel_to_buffer(buffer, max(x, 0) as u32, max(y_intersect as i32 - 1, 0) as u32, color_with_alpha);
+            draw_pixel_clipped(buffer, x, y_intersect as i32, color);
+            draw_pixel_clipped(buffer, x, y_intersect as i32 - 1, color_with_alpha);
 
             y_intersect += gradient;
             x += 1;
@@ -609,37 +2851,551 @@ unsafe fn draw_line(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b:
     }
 }
 
-unsafe fn draw_control_points(buffer: &mut OffscreenBuffer, camera: Camera, game_state: &GameState) {
-    for curve in game_state.curves {
-        match curve {
-            Some(value) => {
-                draw_line(buffer, camera, value.p0, value.p1, 0xFF888888);
-                draw_line(buffer, camera, value.p2, value.p3, 0xFF888888);
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_polyline(buffer: &mut OffscreenBuffer, camera: Camera, points: &[Vector2], color: Color) {
+    let mut i = 1;
+    while i < points.len() {
+        draw_line_antialiased(buffer, camera, points[i - 1], points[i], color);
+        i += 1;
+    }
+}
 
-                draw_circle(buffer, camera, value.p1, 0.02, 0xFF00FF00);
-                draw_circle(buffer, camera, value.p2, 0.02, 0xFF00FF00);
-            },
-            None => {
-                continue;
+/// Color stops are spread over the length of the whole polyline, not per segment
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_polyline_gradient(buffer: &mut OffscreenBuffer, camera: Camera, points: &[Vector2], stops: &[ColorStop]) {
+    let mut total_length = 0.0;
+    let mut i = 1;
+    while i < points.len() {
+        total_length += points[i - 1].distance(points[i]);
+        i += 1;
+    }
+
+    if total_length <= 0.0 {
+        return;
+    }
+
+    let mut length_so_far = 0.0;
+    let mut i = 1;
+    while i < points.len() {
+        let segment_length = points[i - 1].distance(points[i]);
+        let start_t = length_so_far / total_length;
+        let end_t = (length_so_far + segment_length) / total_length;
+
+        draw_line_with_color(buffer, camera, points[i - 1], points[i], |t| {
+            sample_color_stops_pixel(stops, start_t + (end_t - start_t) * t)
+        });
+
+        length_so_far += segment_length;
+        i += 1;
+    }
+}
+
+// Below this many pixels a stroke is drawn as a plain antialiased line
+static THIN_STROKE_PIXELS: f32 = 1.5;
+
+/// Draws a line with the width, color and caps of a stroke style
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_stroke_line(buffer: &mut OffscreenBuffer, camera: Camera, a: Vector2, b: Vector2, style: StrokeStyle) {
+    draw_stroke_polyline(buffer, camera, &[a, b], style);
+}
+
+/// Thick strokes are outlined with the join and cap styles and filled as one shape, so
+/// translucent strokes don't darken where segments overlap
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_stroke_polyline(buffer: &mut OffscreenBuffer, camera: Camera, points: &[Vector2], style: StrokeStyle) {
+    if let Some(dash) = style.dash {
+        let solid = StrokeStyle { dash: None, ..style };
+        for piece in dash_polyline(points, dash) {
+            draw_stroke_polyline(buffer, camera, &piece, solid);
+        }
+        return;
+    }
+
+    let width = style.width.to_pixels(camera);
+    if width < THIN_STROKE_PIXELS {
+        let mut i = 1;
+        while i < points.len() {
+            draw_line_antialiased(buffer, camera, points[i - 1], points[i], style.color);
+            i += 1;
+        }
+        return;
+    }
+
+    let screen_points: Vec<Vector2> = points.iter().map(|point| world_space_to_screen_space_f32(camera, *point)).collect();
+    let pieces = stroke_polyline(&screen_points, width, style.join, style.cap);
+    fill_convex_union(buffer, &pieces, style.color.to_pixel());
+}
+
+/// Strokes like draw_stroke_polyline, but the color comes from stops spread over the length
+/// of the whole polyline instead of the style's color. Dashes keep the colors of where along
+/// the line they are.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_stroke_polyline_gradient(buffer: &mut OffscreenBuffer, camera: Camera, points: &[Vector2], style: StrokeStyle, stops: &[ColorStop]) {
+    let screen_points: Vec<Vector2> = points.iter().map(|point| world_space_to_screen_space_f32(camera, *point)).collect();
+
+    // Length of the line up to each point
+    let mut lengths = vec![0.0; screen_points.len()];
+    let mut i = 1;
+    while i < screen_points.len() {
+        lengths[i] = lengths[i - 1] + screen_points[i - 1].distance(screen_points[i]);
+        i += 1;
+    }
+
+    let total_length = match lengths.last() {
+        Some(value) if *value > 0.0 => *value,
+        _ => return
+    };
+
+    // How far along the line the closest point to a screen position is
+    let t_at = |position: Vector2| {
+        let mut closest_distance = f32::MAX;
+        let mut closest_length = 0.0;
+
+        let mut i = 1;
+        while i < screen_points.len() {
+            let a = screen_points[i - 1];
+            let delta = screen_points[i] - a;
+            let segment_length = lengths[i] - lengths[i - 1];
+            let along = if segment_length > 0.0 {
+                (((position.x - a.x) * delta.x + (position.y - a.y) * delta.y) / (segment_length * segment_length)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+
+            let distance = position.distance(a + delta * along);
+            if distance < closest_distance {
+                closest_distance = distance;
+                closest_length = lengths[i - 1] + segment_length * along;
+            }
+            i += 1;
+        }
+
+        closest_length / total_length
+    };
+
+    let width = style.width.to_pixels(camera);
+
+    // Undashed thin strokes already know where each segment starts and ends
+    if style.dash.is_none() && width < THIN_STROKE_PIXELS {
+        let mut i = 1;
+        while i < points.len() {
+            let start_t = lengths[i - 1] / total_length;
+            let end_t = lengths[i] / total_length;
+            draw_line_with_color(buffer, camera, points[i - 1], points[i], |t| {
+                sample_color_stops_pixel(stops, start_t + (end_t - start_t) * t)
+            });
+            i += 1;
+        }
+        return;
+    }
+
+    let pieces = match style.dash {
+        Some(dash) => dash_polyline(points, dash),
+        None => vec![points.to_vec()]
+    };
+
+    for piece in pieces {
+        if width < THIN_STROKE_PIXELS {
+            let mut i = 1;
+            while i < piece.len() {
+                let start = world_space_to_screen_space_f32(camera, piece[i - 1]);
+                let end = world_space_to_screen_space_f32(camera, piece[i]);
+                draw_line_with_color(buffer, camera, piece[i - 1], piece[i], |t| {
+                    sample_color_stops_pixel(stops, t_at(start + (end - start) * t))
+                });
+                i += 1;
             }
+            continue;
         }
+
+        let screen_piece: Vec<Vector2> = piece.iter().map(|point| world_space_to_screen_space_f32(camera, *point)).collect();
+        let outline = stroke_polyline(&screen_piece, width, style.join, style.cap);
+        fill_convex_union_shaded(buffer, &outline, |x, y| {
+            sample_color_stops_pixel(stops, t_at(Vector2 { x: x as f32 + 0.5, y: y as f32 + 0.5 }))
+        });
     }
 }
 
-unsafe fn draw_bounding_boxes(buffer: &mut OffscreenBuffer, game_state: &GameState) {
-    let camera_bounding_box = game_state.camera.get_bounding_box();
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_stroke_bezier_curve(buffer: &mut OffscreenBuffer, camera: Camera, bezier: BezierCurve, style: StrokeStyle) {
+    draw_stroke_polyline(buffer, camera, &sample_bezier_points(camera, bezier), style);
+}
 
-    for curve in game_state.curves {
-        match curve {
-            Some(value) => {
-                let bounding_box = value.get_bounding_box();
+/// Draws a stroked line with optional arrowheads pointing out of its start and end
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_arrow_line(buffer: &mut OffscreenBuffer, camera: Camera, from: Vector2, to: Vector2, style: StrokeStyle, start: Option<Arrowhead>, end: Option<Arrowhead>) {
+    let start = start.map(|arrow| (arrow, from - to));
+    let end = end.map(|arrow| (arrow, to - from));
+    draw_arrow_polyline(buffer, camera, &[from, to], style, start, end);
+}
+
+/// Draws a stroked bezier curve with optional arrowheads, oriented along the curve's tangent at
+/// its end points
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_arrow_bezier_curve(buffer: &mut OffscreenBuffer, camera: Camera, bezier: BezierCurve, style: StrokeStyle, start: Option<Arrowhead>, end: Option<Arrowhead>) {
+    let start = start.map(|arrow| (arrow, bezier.end_direction(false)));
+    let end = end.map(|arrow| (arrow, bezier.end_direction(true)));
+    draw_arrow_polyline(buffer, camera, &sample_bezier_points(camera, bezier), style, start, end);
+}
+
+// Each arrowhead comes with the direction it points in, out of the line at that end
+unsafe fn draw_arrow_polyline(
+    buffer: &mut OffscreenBuffer,
+    camera: Camera,
+    points: &[Vector2],
+    style: StrokeStyle,
+    start: Option<(Arrowhead, Vector2)>,
+    end: Option<(Arrowhead, Vector2)>
+) {
+    if points.len() < 2 {
+        return;
+    }
+
+    // Filled heads cover the end of the line, stopping short keeps thick strokes from poking out
+    let trim = |arrow: Option<(Arrowhead, Vector2)>| match arrow {
+        Some((Arrowhead { style: ArrowStyle::Filled, size }, _)) => size.to_world(camera),
+        _ => 0.0
+    };
+    let trimmed = trim_polyline(points, trim(start), trim(end));
+    draw_stroke_polyline(buffer, camera, &trimmed, style);
+
+    if let Some((arrow, direction)) = start {
+        draw_arrowhead(buffer, camera, points[0], direction, arrow, style);
+    }
+    if let Some((arrow, direction)) = end {
+        draw_arrowhead(buffer, camera, points[points.len() - 1], direction, arrow, style);
+    }
+}
+
+unsafe fn draw_arrowhead(buffer: &mut OffscreenBuffer, camera: Camera, tip: Vector2, direction: Vector2, arrow: Arrowhead, style: StrokeStyle) {
+    let length = direction.length();
+    if length <= 0.0 {
+        return;
+    }
+
+    let size = arrow.size.to_world(camera);
+    let forward = direction * (size / length);
+    let side = Vector2 { x: -forward.y * 0.5, y: forward.x * 0.5 };
+    let base = tip - forward;
+
+    match arrow.style {
+        ArrowStyle::Filled => draw_polygon(buffer, camera, &[tip, base + side, base - side], style.color),
+        ArrowStyle::Open => {
+            let solid = StrokeStyle { dash: None, ..style };
+            draw_stroke_polyline(buffer, camera, &[base + side, tip, base - side], solid);
+        }
+    }
+}
+
+// Removes the given lengths from the start and end of a polyline, measured along the line.
+// Nothing is left when they add up to the whole line, negative lengths don't extend it.
+pub fn trim_polyline(points: &[Vector2], start_length: f32, end_length: f32) -> Vec<Vector2> {
+    let trim_start = |points: &[Vector2], length: f32| -> Vec<Vector2> {
+        let mut remaining = length.max(0.0);
+        let mut i = 1;
+        while i < points.len() {
+            let segment = points[i - 1].distance(points[i]);
+            if segment > remaining {
+                let cut = points[i - 1] + (points[i] - points[i - 1]) * (remaining / segment);
+                let mut trimmed = vec![cut];
+                trimmed.extend_from_slice(&points[i..]);
+                return trimmed;
+            }
+            remaining -= segment;
+            i += 1;
+        }
+        Vec::new()
+    };
+
+    if start_length <= 0.0 && end_length <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut trimmed = trim_start(points, start_length);
+    trimmed.reverse();
+    let mut trimmed = trim_start(&trimmed, end_length);
+    trimmed.reverse();
+    trimmed
+}
+
+/// Scanline fills a closed polygon, concave and self-intersecting polygons use the even-odd rule.
+/// The last point connects back to the first.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_polygon(buffer: &mut OffscreenBuffer, camera: Camera, points: &[Vector2], color: Color) {
+    let screen_points: Vec<Vector2> = points.iter().map(|point| world_space_to_screen_space_f32(camera, *point)).collect();
+    fill_contours(buffer, &[screen_points], color.to_pixel(), FillRule::EvenOdd);
+}
 
-                if !bounding_box.intersects(camera_bounding_box) {
-                    continue;
+/// Fills a triangle with the colors of its corners blended across it using barycentric weights
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_triangle_gradient(buffer: &mut OffscreenBuffer, camera: Camera, points: [Vector2; 3], colors: [Color; 3]) {
+    let a = world_space_to_screen_space_f32(camera, points[0]);
+    let mut b = world_space_to_screen_space_f32(camera, points[1]);
+    let mut c = world_space_to_screen_space_f32(camera, points[2]);
+    let mut colors = [colors[0].to_pixel(), colors[1].to_pixel(), colors[2].to_pixel()];
+
+    let mut area = edge_function(a, b, c);
+    if area.abs() < 1e-6 {
+        return;
+    }
+
+    // Wind every triangle the same way so the fill rule below sees consistent edges
+    if area < 0.0 {
+        std::mem::swap(&mut b, &mut c);
+        colors.swap(1, 2);
+        area = -area;
+    }
+
+    let clip = buffer.clip_rect();
+    let start_x = max(a.x.min(b.x).min(c.x).floor() as i32, clip.x0);
+    let start_y = max(a.y.min(b.y).min(c.y).floor() as i32, clip.y0);
+    let end_x = min(a.x.max(b.x).max(c.x).ceil() as i32, clip.x1);
+    let end_y = min(a.y.max(b.y).max(c.y).ceil() as i32, clip.y1);
+
+    // Top-left rule: pixels exactly on an edge only belong to the triangle if it's a top or
+    // left edge, so triangles sharing an edge never draw it twice
+    let include_a = is_top_left_edge(b, c);
+    let include_b = is_top_left_edge(c, a);
+    let include_c = is_top_left_edge(a, b);
+
+    // The inside of a triangle is one contiguous run per row
+    let mut run: Vec<u32> = Vec::new();
+    let mut y = start_y;
+    while y < end_y {
+        let mut run_start = start_x;
+        run.clear();
+
+        let mut x = start_x;
+        while x < end_x {
+            // Sample at the pixel center
+            let p = Vector2 { x: x as f32 + 0.5, y: y as f32 + 0.5 };
+
+            let edge_a = edge_function(b, c, p);
+            let edge_b = edge_function(c, a, p);
+            let edge_c = edge_function(a, b, p);
+
+            let inside =
+                (edge_a > 0.0 || (edge_a == 0.0 && include_a)) &&
+                (edge_b > 0.0 || (edge_b == 0.0 && include_b)) &&
+                (edge_c > 0.0 || (edge_c == 0.0 && include_c));
+
+            if inside {
+                if run.is_empty() {
+                    run_start = x;
                 }
+                let threshold = if buffer.dither { dither_threshold(x, y) } else { 0.5 };
+                run.push(blend_colors_weighted(colors, [edge_a / area, edge_b / area, edge_c / area], threshold));
+            } else if !run.is_empty() {
+                break;
+            }
+            x += 1;
+        }
+
+        buffer.blend_span(y, run_start, &run);
+        y += 1;
+    }
+}
+
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_triangle(buffer: &mut OffscreenBuffer, camera: Camera, points: [Vector2; 3], color: Color) {
+    draw_triangle_gradient(buffer, camera, points, [color; 3]);
+}
+
+/// Draws an indexed triangle list, every three indices form a triangle and colors are per vertex
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_triangle_mesh(buffer: &mut OffscreenBuffer, camera: Camera, vertices: &[Vector2], colors: &[Color], indices: &[usize]) {
+    let mut i = 0;
+    while i + 2 < indices.len() {
+        let (a, b, c) = (indices[i], indices[i + 1], indices[i + 2]);
+        if a < vertices.len() && b < vertices.len() && c < vertices.len() &&
+           a < colors.len() && b < colors.len() && c < colors.len() {
+            draw_triangle_gradient(
+                buffer,
+                camera,
+                [vertices[a], vertices[b], vertices[c]],
+                [colors[a], colors[b], colors[c]]);
+        }
+        i += 3;
+    }
+}
+
+// For triangles wound so the edge function is positive inside
+fn is_top_left_edge(from: Vector2, to: Vector2) -> bool {
+    let is_top = from.y == to.y && to.x > from.x;
+    let is_left = to.y < from.y;
+    is_top || is_left
+}
+
+/// Fills a quad with colors interpolated from its corners, points go around the quad in order.
+/// The quad is split into four triangles around its center so there is no diagonal crease.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_quad_gradient(buffer: &mut OffscreenBuffer, camera: Camera, points: [Vector2; 4], colors: [Color; 4]) {
+    let center = (points[0] + points[1] + points[2] + points[3]) * 0.25;
+    let center_color = colors[0].lerp(colors[1], 0.5).lerp(colors[2].lerp(colors[3], 0.5), 0.5);
+
+    let mut i = 0;
+    while i < 4 {
+        let next = (i + 1) % 4;
+        draw_triangle_gradient(buffer, camera, [points[i], points[next], center], [colors[i], colors[next], center_color]);
+        i += 1;
+    }
+}
+
+// Twice the signed area of the triangle abc
+fn edge_function(a: Vector2, b: Vector2, c: Vector2) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+// Threshold is where a channel's fraction rounds up, anything but 0.5 dithers the gradient
+fn blend_colors_weighted(colors: [u32; 3], weights: [f32; 3], threshold: f32) -> u32 {
+    let mut channels = [0.0; 4];
+    let mut i = 0;
+    while i < 3 {
+        channels[0] += ((colors[i] >> 24) & 0xFF) as f32 * weights[i];
+        channels[1] += ((colors[i] >> 16) & 0xFF) as f32 * weights[i];
+        channels[2] += ((colors[i] >> 8) & 0xFF) as f32 * weights[i];
+        channels[3] += (colors[i] & 0xFF) as f32 * weights[i];
+        i += 1;
+    }
+
+    let quantize = |value: f32| ((value + threshold).floor() as u32).min(255);
+
+    (quantize(channels[0]) << 24) |
+    (quantize(channels[1]) << 16) |
+    (quantize(channels[2]) << 8) |
+    quantize(channels[3])
+}
 
-                draw_rectangle(buffer, game_state.camera, bounding_box, 0x3300DDAA);
+// Glow under selected curves and the marquee being dragged.
+// The glow goes in before the curves so they're drawn on top of it.
+fn push_selection(commands: &mut RenderCommands, camera: Camera, game_state: &GameState) {
+    let color = Color::rgba(0xFF, 0xCC, 0x00, 0xAA);
+    // Selected control points are colored by push_control_points
+    for item in &game_state.selection.items {
+        if let Selectable::Curve(slot) = *item {
+            if let Some(curve) = game_state.curves[slot as usize] {
+                commands.push(Layer::World, RenderCommand::DrawStrokeCurve { camera, curve, style: StrokeStyle::new(color, StrokeWidth::Screen(5.0)) });
             }
+        }
+    }
+
+    if let Some(marquee) = game_state.marquee {
+        let rectangle = marquee.rectangle();
+        commands.push(Layer::Overlay, RenderCommand::DrawRect { camera, rectangle, color: Color::rgba(0xFF, 0xCC, 0x00, 0x22) });
+        commands.push(Layer::Overlay, RenderCommand::DrawRectOutline { camera, rectangle, thickness: StrokeWidth::Screen(1.0), color });
+    }
+}
+
+// The ruler and its ends, the part of the curve measured along, and the numbers next to the end
+fn push_measurement(commands: &mut RenderCommands, camera: Camera, curves: &[Option<BezierCurve>], measurement: Measurement, scale: u32) {
+    let color = Color::rgb(0x00, 0xCC, 0xFF);
+    if let Some((slot, start_t, end_t)) = measurement.along {
+        if let Some(curve) = curves[slot as usize] {
+            let curve = curve_between(&curve, start_t, end_t);
+            commands.push(Layer::Overlay, RenderCommand::DrawStrokeCurve { camera, curve, style: StrokeStyle::new(color, StrokeWidth::Screen(3.0)) });
+        }
+    }
+
+    commands.push(Layer::Overlay, RenderCommand::DrawLineSmooth { camera, from: measurement.start, to: measurement.end, color });
+    for center in [measurement.start, measurement.end] {
+        commands.push(Layer::Overlay, RenderCommand::DrawCircleOutline { camera, center, radius: 0.03, thickness: StrokeWidth::Screen(1.5), color });
+    }
+
+    // Below and right of the end so the label doesn't cover it, the same distance on screen at
+    // any zoom
+    let offset = LABEL_OFFSET_PIXELS * scale as f32;
+    let end = world_space_to_screen_space_f32(camera, measurement.end);
+    let position = screen_space_to_world_space_f32(camera, Vector2 { x: end.x + offset, y: end.y + offset });
+    commands.push(Layer::Overlay, RenderCommand::DrawText {
+        camera,
+        position,
+        text: measurement.text(curves),
+        scale,
+        color,
+        background: LABEL_BACKGROUND
+    });
+}
+
+// Ring around the control point being dragged
+fn push_selection_marker(commands: &mut RenderCommands, camera: Camera, game_state: &GameState) {
+    let curve = match game_state.selected_curve_index {
+        Some(index) => match game_state.curves[index as usize] {
+            Some(value) => value,
+            None => return
+        },
+        None => return
+    };
+
+    let center = curve.point(game_state.selected_control_point);
+    commands.push(Layer::Overlay, RenderCommand::DrawCircleOutline {
+        camera,
+        center,
+        radius: 0.05,
+        thickness: StrokeWidth::Screen(1.5),
+        color: Color::GREEN
+    });
+}
+
+// Handles of every curve while editing, and the whole control polygon including the side from
+// p1 to p2 when DebugDraw::control_polygons is on. Points are colored by whether they're under the
+// mouse, selected or neither, and go on top of the curves.
+fn push_control_points(commands: &mut RenderCommands, camera: Camera, game_state: &GameState) {
+    let editor = game_state.editor;
+    let polygons = game_state.debug_draw.control_polygons;
+    if !editor.enabled && !polygons {
+        return;
+    }
+    let handle_color = Color::rgb(0x88, 0x88, 0x88);
+    let polygon_color = Color::rgba(0x88, 0x88, 0x88, 0x66);
+    let end_point_color = Color::rgb(0xCC, 0xCC, 0xCC);
+    let selected_color = Color::rgb(0xFF, 0xCC, 0x00);
+
+    for (i, curve) in (0..).zip(game_state.curves.iter()) {
+        match curve {
+            Some(value) => {
+                commands.push(Layer::Overlay, RenderCommand::DrawLineSmooth { camera, from: value.p0, to: value.p1, color: handle_color });
+                commands.push(Layer::Overlay, RenderCommand::DrawLineSmooth { camera, from: value.p2, to: value.p3, color: handle_color });
+                if polygons {
+                    commands.push(Layer::Overlay, RenderCommand::DrawLineSmooth { camera, from: value.p1, to: value.p2, color: polygon_color });
+                }
+
+                for control_point in 0..4 {
+                    let radius = if control_point == 0 || control_point == 3 { 0.015 } else { 0.02 };
+                    let (radius, color) = if editor.hovered == Some((i, control_point)) {
+                        (0.03, Color::WHITE)
+                    } else if game_state.selection.is_selected(Selectable::ControlPoint(i, control_point)) {
+                        (radius * 1.5, selected_color)
+                    } else if control_point == 0 || control_point == 3 {
+                        (radius, end_point_color)
+                    } else {
+                        (radius, Color::GREEN)
+                    };
+                    commands.push(Layer::Overlay, RenderCommand::DrawCircle { camera, center: value.point(control_point), radius, color });
+                }
+            },
             None => {
                 continue;
             }
@@ -647,7 +3403,44 @@ unsafe fn draw_bounding_boxes(buffer: &mut OffscreenBuffer, game_state: &GameSta
     }
 }
 
-unsafe fn draw_rectangle(buffer: &mut OffscreenBuffer, camera: Camera, rectangle: Rectangle, color: u32) {
+fn push_bounding_boxes(commands: &mut RenderCommands, camera: Camera, curves: &[BezierCurve], style: BoundingBoxStyle) {
+    for curve in curves {
+        let bounding_box = curve.get_bounding_box();
+        match style {
+            BoundingBoxStyle::Filled => {
+                commands.push(Layer::World, RenderCommand::DrawRect {
+                    camera,
+                    rectangle: bounding_box,
+                    color: Color::rgba(0x00, 0xDD, 0xAA, 0x33)
+                });
+            },
+            BoundingBoxStyle::Outline => {
+                commands.push(Layer::World, RenderCommand::DrawRectOutline {
+                    camera,
+                    rectangle: bounding_box,
+                    thickness: StrokeWidth::Screen(1.0),
+                    color: Color::rgba(0x00, 0xDD, 0xAA, 0x88)
+                });
+                // Opaque so the corners cover the outline instead of blending over it
+                commands.push(Layer::World, RenderCommand::DrawRectCorners {
+                    camera,
+                    rectangle: bounding_box,
+                    length: StrokeWidth::Screen(6.0),
+                    thickness: StrokeWidth::Screen(2.0),
+                    color: Color::rgb(0x00, 0xDD, 0xAA)
+                });
+            }
+        }
+    }
+}
+
+unsafe fn draw_rectangle(buffer: &mut OffscreenBuffer, camera: Camera, rectangle: Rectangle, color: Color) {
+    if !camera.is_axis_aligned() {
+        draw_rectangle_rotated(buffer, camera, rectangle, 0.0, color);
+        return;
+    }
+
+    let color = color.to_pixel();
     let rect_top_left = Vector2 {
         x: rectangle.x,
         y: rectangle.y
@@ -662,128 +3455,345 @@ unsafe fn draw_rectangle(buffer: &mut OffscreenBuffer, camera: Camera, rectangle
     let rect_top_left_screen = world_space_to_screen_space(camera, rect_top_left);
     let rect_bottom_right_screen = world_space_to_screen_space(camera, rect_bottom_right);
 
-    let start_x = max(rect_top_left_screen.x, 0);
-    let start_y = max(rect_top_left_screen.y, 0);
-    let end_x = min(rect_bottom_right_screen.x, buffer.width);
-    let end_y = min(rect_bottom_right_screen.y, buffer.height);
+    fill_clip_rect(buffer, ClipRect::new(
+        rect_top_left_screen.x,
+        rect_top_left_screen.y,
+        rect_bottom_right_screen.x,
+        rect_bottom_right_screen.y), color);
+}
 
-    let mut x = start_x;
-    while x < end_x {
-        let mut y = start_y;
-        while y < end_y {
-            draw_pixel_to_buffer(buffer, x, y, color);
-            y += 1;
+/// Draws a rectangle turned by rotation radians around its center. Each row maps back into
+/// the rectangle's own unrotated space to find the pixels whose centers are inside it. The
+/// camera's rotation turns it the other way on screen, see Camera::screen_rotation.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_rectangle_rotated(buffer: &mut OffscreenBuffer, camera: Camera, rectangle: Rectangle, rotation: f32, color: Color) {
+    let color = color.to_pixel();
+    let center = world_space_to_screen_space_f32(camera, Vector2 {
+        x: rectangle.x + rectangle.width / 2.0,
+        y: rectangle.y + rectangle.height / 2.0
+    });
+    let half_width = (rectangle.width * camera.y_scale / 2.0).abs();
+    let half_height = (rectangle.height * camera.y_scale / 2.0).abs();
+    let (sin, cos) = camera.screen_rotation(rotation).sin_cos();
+
+    // The rotated rectangle fits in a circle through its corners
+    let reach = (half_width * half_width + half_height * half_height).sqrt();
+    let clip = buffer.clip_rect();
+    let mut y = max((center.y - reach).floor() as i32, clip.y0);
+    let end_y = min((center.y + reach).ceil() as i32 + 1, clip.y1);
+    while y < end_y {
+        let offset_y = y as f32 + 0.5 - center.y;
+
+        // Unrotated x is offset_x * cos + offset_y * sin and y is -offset_x * sin + offset_y * cos,
+        // both have to stay within the half size
+        let span = match (inside_range(cos, offset_y * sin, half_width), inside_range(-sin, offset_y * cos, half_height)) {
+            (Some(a), Some(b)) => (a.0.max(b.0), a.1.min(b.1)),
+            _ => {
+                y += 1;
+                continue;
+            }
+        };
+
+        if span.0 <= span.1 {
+            let x0 = (span.0 + center.x - 0.5).ceil().max(clip.x0 as f32) as i32;
+            let x1 = ((span.1 + center.x - 0.5).floor() + 1.0).min(clip.x1 as f32) as i32;
+            if x0 < x1 {
+                buffer.fill_span(y, x0, x1, color);
+            }
         }
-        x += 1;
+        y += 1;
     }
 }
 
-// TODO: Fix circle staying still when moving between y=0 and y=1 (same for x)
-unsafe fn draw_circle(buffer: &mut OffscreenBuffer, camera: Camera, position: Vector2, radius: f32, color: u32) {
-    let screen_pos = world_space_to_screen_space_i32(camera, position);
-    let screen_radius = (radius * camera.y_scale) as i32;
+// Values of x where |slope * x + offset| <= half, None when there are none
+fn inside_range(slope: f32, offset: f32, half: f32) -> Option<(f32, f32)> {
+    if slope.abs() < 1e-9 {
+        return if offset.abs() <= half { Some((f32::MIN, f32::MAX)) } else { None };
+    }
 
-    let start_x = max(screen_pos.x as i32 - screen_radius, 0) as u32;
-    let start_y = max(screen_pos.y as i32 - screen_radius, 0) as u32;
-    let end_x = min(max(screen_pos.x + screen_radius, 0), buffer.width as i32 - 1) as u32;
-    let end_y = min(max(screen_pos.y + screen_radius, 0), buffer.height as i32 - 1) as u32;
+    let a = (-half - offset) / slope;
+    let b = (half - offset) / slope;
+    Some((a.min(b), a.max(b)))
+}
 
-    let mut x = start_x;
-    while x <= end_x {
-        let mut y = start_y;
-        while y <= end_y {
-            let dist = distance_i32(screen_pos, Vector2i32 { x: x as i32, y: y as i32 });
-            if dist <= screen_radius as f32 {
-                draw_pixel_to_buffer(buffer, x, y, color);
-            } else if dist <= screen_radius as f32 + 1.0 {
-                // TODO: Make it so anti-aliasing works properly with transparent circles
-                let dist_dec = dist - (dist as i32) as f32;
-                let alpha = ((1.0 - dist_dec) * 255.0) as u32;
-                let color_with_alpha = (color & 0x00FFFFFF) | (alpha << 24);
-                draw_pixel_to_buffer(buffer, x, y, color_with_alpha);
-            }
-            y += 1;
+/// Draws only the border of a rectangle, the border grows inwards from the rectangle's edges
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_rectangle_outline(buffer: &mut OffscreenBuffer, camera: Camera, rectangle: Rectangle, thickness: StrokeWidth, color: Color) {
+    if !camera.is_axis_aligned() {
+        let thickness = thickness.to_world(camera).min(rectangle.width / 2.0).min(rectangle.height / 2.0);
+        let inner = rectangle.expand(-thickness);
+        let outer = rectangle.corners();
+        let inner = inner.corners();
+        // One piece per side between the outer and inner corners, they only share edges
+        let pieces: Vec<[Vector2; 4]> = (0..4).map(|i| [outer[i], outer[(i + 1) % 4], inner[(i + 1) % 4], inner[i]]).collect();
+        fill_world_pieces(buffer, camera, &pieces, color);
+        return;
+    }
+
+    let color = color.to_pixel();
+    let top_left = world_space_to_screen_space_f32(camera, Vector2 { x: rectangle.x, y: rectangle.y });
+    let bottom_right = world_space_to_screen_space_f32(camera, Vector2 {
+        x: rectangle.x + rectangle.width,
+        y: rectangle.y + rectangle.height
+    });
+
+    let x0 = top_left.x.round() as i32;
+    let y0 = top_left.y.round() as i32;
+    let x1 = bottom_right.x.round() as i32;
+    let y1 = bottom_right.y.round() as i32;
+
+    // Never thinner than a pixel, and never more than half the rectangle so the sides don't overlap
+    let thickness_pixels = max(thickness.to_pixels(camera).round() as i32, 1);
+    let thickness_x = min(thickness_pixels, max((x1 - x0) / 2, 1));
+    let thickness_y = min(thickness_pixels, max((y1 - y0) / 2, 1));
+
+    // Top and bottom span the full width, the sides fill in between so no pixel is blended twice
+    fill_screen_rectangle(buffer, x0, y0, x1, y0 + thickness_y, color);
+    fill_screen_rectangle(buffer, x0, y1 - thickness_y, x1, y1, color);
+    fill_screen_rectangle(buffer, x0, y0 + thickness_y, x0 + thickness_x, y1 - thickness_y, color);
+    fill_screen_rectangle(buffer, x1 - thickness_x, y0 + thickness_y, x1, y1 - thickness_y, color);
+}
+
+/// Draws L shaped brackets over the corners of a rectangle, length is how far each bracket
+/// reaches along the sides. Grows inwards from the edges like draw_rectangle_outline.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_rectangle_corners(buffer: &mut OffscreenBuffer, camera: Camera, rectangle: Rectangle, length: StrokeWidth, thickness: StrokeWidth, color: Color) {
+    if !camera.is_axis_aligned() {
+        let half_size = Vector2 { x: rectangle.width / 2.0, y: rectangle.height / 2.0 };
+        let thickness = thickness.to_world(camera);
+        let length = length.to_world(camera).max(thickness);
+        let thickness = Vector2 { x: thickness.min(half_size.x), y: thickness.min(half_size.y) };
+        let length = Vector2 { x: length.min(half_size.x), y: length.min(half_size.y) };
+
+        // Both arms of each bracket, measured inwards from its corner
+        let mut pieces = Vec::with_capacity(8);
+        for (corner, inwards) in rectangle.corners().into_iter().zip([(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)]) {
+            let arm = |x: f32, y: f32| corner + Vector2 { x: x * inwards.0, y: y * inwards.1 };
+            pieces.push([arm(0.0, 0.0), arm(length.x, 0.0), arm(length.x, thickness.y), arm(0.0, thickness.y)]);
+            pieces.push([arm(0.0, thickness.y), arm(thickness.x, thickness.y), arm(thickness.x, length.y), arm(0.0, length.y)]);
         }
-        x += 1;
+        fill_world_pieces(buffer, camera, &pieces, color);
+        return;
     }
+
+    let color = color.to_pixel();
+    let top_left = world_space_to_screen_space_f32(camera, Vector2 { x: rectangle.x, y: rectangle.y });
+    let bottom_right = world_space_to_screen_space_f32(camera, Vector2 {
+        x: rectangle.x + rectangle.width,
+        y: rectangle.y + rectangle.height
+    });
+
+    let x0 = top_left.x.round() as i32;
+    let y0 = top_left.y.round() as i32;
+    let x1 = bottom_right.x.round() as i32;
+    let y1 = bottom_right.y.round() as i32;
+
+    // Brackets on the same side never reach past the middle, so they don't overlap
+    let thickness_pixels = max(thickness.to_pixels(camera).round() as i32, 1);
+    let thickness_x = min(thickness_pixels, max((x1 - x0) / 2, 1));
+    let thickness_y = min(thickness_pixels, max((y1 - y0) / 2, 1));
+    let length_pixels = max(length.to_pixels(camera).round() as i32, thickness_pixels);
+    let length_x = min(length_pixels, max((x1 - x0) / 2, 1));
+    let length_y = min(length_pixels, max((y1 - y0) / 2, 1));
+
+    // The horizontal arm of each bracket includes the corner, the vertical arm fills in after it
+    fill_screen_rectangle(buffer, x0, y0, x0 + length_x, y0 + thickness_y, color);
+    fill_screen_rectangle(buffer, x0, y0 + thickness_y, x0 + thickness_x, y0 + length_y, color);
+    fill_screen_rectangle(buffer, x1 - length_x, y0, x1, y0 + thickness_y, color);
+    fill_screen_rectangle(buffer, x1 - thickness_x, y0 + thickness_y, x1, y0 + length_y, color);
+    fill_screen_rectangle(buffer, x0, y1 - thickness_y, x0 + length_x, y1, color);
+    fill_screen_rectangle(buffer, x0, y1 - length_y, x0 + thickness_x, y1 - thickness_y, color);
+    fill_screen_rectangle(buffer, x1 - length_x, y1 - thickness_y, x1, y1, color);
+    fill_screen_rectangle(buffer, x1 - thickness_x, y1 - length_y, x1, y1 - thickness_y, color);
 }
 
-fn distance_f32(a: Vector2, b: Vector2) -> f32 {
-    let dx = (a.x - b.x).abs();
-    let dy = (a.y - b.y).abs();
-    (dx * dx + dy * dy).sqrt()
+// Fills world space quads that only touch along their edges, for rectangle shapes under a rotated
+// or flipped camera. No pixel is drawn twice, the same as the axis aligned spans.
+unsafe fn fill_world_pieces(buffer: &mut OffscreenBuffer, camera: Camera, pieces: &[[Vector2; 4]], color: Color) {
+    let screen_pieces: Vec<Vec<Vector2>> = pieces.iter()
+        .map(|piece| piece.iter().map(|point| world_space_to_screen_space_f32(camera, *point)).collect())
+        .collect();
+    fill_convex_union(buffer, &screen_pieces, color.to_pixel());
 }
 
-fn distance_i32(a: Vector2i32, b: Vector2i32) -> f32 {
-    let dx = (a.x as f32 - b.x as f32).abs();
-    let dy = (a.y as f32 - b.y as f32).abs();
-    (dx * dx + dy * dy).sqrt()
+// Fills pixels from (x0, y0) up to but not including (x1, y1), clipped to the buffer
+unsafe fn fill_screen_rectangle(buffer: &mut OffscreenBuffer, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
+    fill_clip_rect(buffer, ClipRect::new(x0, y0, x1, y1), color);
 }
 
-unsafe fn draw_bezier_curves(buffer: &mut OffscreenBuffer, game_state: &GameState) {
-    for curve in game_state.curves {
-        match curve {
-            Some(value) => {
-                draw_bezier_curve(buffer, game_state.camera, value);
-            },
-            None => {
-                continue;
+unsafe fn draw_circle(buffer: &mut OffscreenBuffer, camera: Camera, position: Vector2, radius: f32, color: Color) {
+    let center = world_space_to_screen_space_f32(camera, position);
+    fill_ring(buffer, center, radius * camera.y_scale, 0.0, color.to_pixel());
+}
+
+/// Draws a ring around center, the thickness grows inwards from the radius. Both edges are
+/// antialiased.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_circle_outline(buffer: &mut OffscreenBuffer, camera: Camera, center: Vector2, radius: f32, thickness: StrokeWidth, color: Color) {
+    let center = world_space_to_screen_space_f32(camera, center);
+    let outer_radius = radius * camera.y_scale;
+    let inner_radius = outer_radius - thickness.to_pixels(camera);
+    fill_ring(buffer, center, outer_radius, inner_radius, color.to_pixel());
+}
+
+// Fills the pixels between two radii around a screen space center, an inner radius of zero or
+// less fills the whole circle. Edge pixels get the fraction of the pixel the shape covers. The
+// color is premultiplied, so scaling it by coverage scales its alpha as well and translucent
+// circles fade out at the edge instead of getting a solid looking rim.
+unsafe fn fill_ring(buffer: &mut OffscreenBuffer, center: Vector2, outer_radius: f32, inner_radius: f32, color: u32) {
+    if outer_radius <= 0.0 || inner_radius >= outer_radius {
+        return;
+    }
+
+    // The antialiased edge reaches half a pixel past the radius. Only the part of the circle
+    // inside the clip rect is visited, so circles at the edge of the screen draw what's visible.
+    let clip = buffer.clip_rect();
+    let start_x = max((center.x - outer_radius - 1.0).floor() as i32, clip.x0);
+    let start_y = max((center.y - outer_radius - 1.0).floor() as i32, clip.y0);
+    let end_x = min((center.x + outer_radius + 1.0).ceil() as i32, clip.x1 - 1);
+    let end_y = min((center.y + outer_radius + 1.0).ceil() as i32, clip.y1 - 1);
+    if start_x > end_x || start_y > end_y {
+        return;
+    }
+
+    // A row of a filled circle is one span, rows through the hole of a ring are two
+    let mut run: Vec<u32> = Vec::new();
+    let mut y = start_y;
+    while y <= end_y {
+        let mut run_start = start_x;
+        run.clear();
+
+        let mut x = start_x;
+        while x <= end_x {
+            // Sample at the pixel center
+            let dist = center.distance(Vector2 { x: x as f32 + 0.5, y: y as f32 + 0.5 });
+            let mut coverage = (outer_radius + 0.5 - dist).clamp(0.0, 1.0);
+            if inner_radius > 0.0 {
+                coverage = coverage.min((dist - inner_radius + 0.5).clamp(0.0, 1.0));
+            }
+
+            if coverage > 0.0 {
+                if run.is_empty() {
+                    run_start = x;
+                }
+                run.push(if coverage >= 1.0 { color } else { scale_color(color, coverage) });
+            } else if !run.is_empty() {
+                buffer.blend_span(y, run_start, &run);
+                run.clear();
             }
+            x += 1;
         }
+
+        buffer.blend_span(y, run_start, &run);
+        y += 1;
     }
 }
 
-unsafe fn draw_bezier_curve(buffer: &mut OffscreenBuffer, camera: Camera, bezier: BezierCurve) {
-    let mut start = bezier.p0;
-    let mut i = 0.1;
-    while i <= 1.0 {
-        let end = bezier.evaluate(i);
-
-        draw_line(buffer, camera, start, end, 0xFFFFFFFF);
-        start = end;
+fn push_bezier_curves(commands: &mut RenderCommands, camera: Camera, game_state: &GameState, view: Rectangle) {
+    for slot in game_state.curve_index.query(view) {
+        let curve = match game_state.curves[slot] {
+            Some(value) => value,
+            None => continue
+        };
 
-        i += 0.1;
+        match game_state.curve_styles[slot] {
+            Some(style) => commands.push(Layer::World, RenderCommand::DrawStrokeCurve { camera, curve, style }),
+            None => commands.push(Layer::World, RenderCommand::DrawCurve { camera, curve, color: Color::WHITE })
+        }
     }
-
-    draw_line(buffer, camera, start, bezier.p3, 0xFFFFFFFF);
 }
 
-unsafe fn draw_pixel_to_buffer(buffer: &mut OffscreenBuffer, x: u32, y: u32, color: u32) {
-    let mut row: *mut u8 = (*buffer).memory as *mut u8;
-    row = row.offset((*buffer).pitch as isize * y as isize);
+// Teeth per curve in the curvature comb
+static COMB_TEETH: u32 = 48;
+// World length of a tooth per unit of curvature, and the longest a tooth gets
+static COMB_SCALE: f32 = 0.1;
+static COMB_MAX_LENGTH: f32 = 0.5;
 
-    let mut pixel: *mut u32 = row as *mut u32;
-    pixel = pixel.offset(x as isize);
+// A tooth sticks out of the outside of each bend, as long as the curvature there. The line along
+// the tips shows how evenly the curvature changes.
+fn push_curvature_combs(commands: &mut RenderCommands, camera: Camera, curves: &[BezierCurve]) {
+    for curve in curves {
+        let mut tips = Vec::with_capacity(COMB_TEETH as usize + 1);
 
-    let alpha = get_alpha(color);
-    if alpha == 1.0 {
-        *pixel = color;
-    } else {
-        *pixel = lerp_color(*pixel, color, alpha);
+        let mut i = 0;
+        while i <= COMB_TEETH {
+            let t = i as f32 / COMB_TEETH as f32;
+            let point = curve.evaluate(t);
+            let tooth_length = (curve.curvature(t) * COMB_SCALE).clamp(-COMB_MAX_LENGTH, COMB_MAX_LENGTH);
+            // The bend's center is on the side of the normal when the curvature is positive
+            let tip = point - curve.normal(t) * tooth_length;
+
+            commands.push(Layer::Overlay, RenderCommand::DrawLineSmooth {
+                camera,
+                from: point,
+                to: tip,
+                color: Color::rgba(0xFF, 0x88, 0x00, 0x88)
+            });
+            tips.push(tip);
+            i += 1;
+        }
+
+        commands.push(Layer::Overlay, RenderCommand::DrawPolyline { camera, points: tips, color: Color::rgb(0xFF, 0x88, 0x00) });
     }
 }
 
-fn get_alpha(color: u32) -> f32 {
-    (color >> 24) as f32 / 255.0
+unsafe fn draw_bezier_curve(buffer: &mut OffscreenBuffer, camera: Camera, bezier: BezierCurve, color: Color) {
+    draw_polyline(buffer, camera, &sample_bezier_points(camera, bezier), color);
+}
+
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_bezier_curve_gradient(buffer: &mut OffscreenBuffer, camera: Camera, bezier: BezierCurve, stops: &[ColorStop]) {
+    draw_polyline_gradient(buffer, camera, &sample_bezier_points(camera, bezier), stops);
+}
+
+/// Spreads the stops from the start of the curve to its end, showing which way it goes
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_stroke_bezier_curve_gradient(buffer: &mut OffscreenBuffer, camera: Camera, bezier: BezierCurve, style: StrokeStyle, stops: &[ColorStop]) {
+    draw_stroke_polyline_gradient(buffer, camera, &sample_bezier_points(camera, bezier), style, stops);
 }
 
-fn lerp_color(a: u32, b: u32, t: f32) -> u32 {
-    let a_red = (a >> 16) as u8;
-    let a_green = (a >> 8) as u8;
-    let a_blue = a as u8;
+// Furthest in screen pixels a flattened curve can be from the real one
+static CURVE_TOLERANCE_PIXELS: f32 = 0.25;
+
+// Flattens the curve finely enough for the camera's zoom, so curves stay smooth when zoomed in
+// without spending points on flat parts
+fn sample_bezier_points(camera: Camera, bezier: BezierCurve) -> Vec<Vector2> {
+    bezier.flatten(CURVE_TOLERANCE_PIXELS / camera.y_scale)
+}
 
-    let b_red = (b >> 16) as u8;
-    let b_green = (b >> 8) as u8;
-    let b_blue = b as u8;
+unsafe fn draw_pixel_to_buffer(buffer: &mut OffscreenBuffer, x: u32, y: u32, color: u32) {
+    if !buffer.clip_rect().contains(x as i32, y as i32) {
+        return;
+    }
 
-    let red = (a_red as f32 + t * (b_red as f32 - a_red as f32)) as u8;
-    let green = (a_green as f32 + t * (b_green as f32 - a_green as f32)) as u8;
-    let blue = (a_blue as f32 + t * (b_blue as f32 - a_blue as f32)) as u8;
+    let color = match &buffer.mask {
+        Some(mask) => match mask.get(x as i32, y as i32) {
+            0 => return,
+            coverage => mask_color(color, coverage)
+        },
+        None => color
+    };
 
-    // NOTE: This version uses fewer variables, if a performance concern
-    //let red = (a >> 16) as u8 + (t * ((b >> 16) as u8 - (a >> 16) as u8) as f32) as u8;
-    //let green = (a >> 8) as u8 + (t * ((b >> 8) as u8 - (a >> 8) as u8) as f32) as u8;
-    //let blue = a as u8 + (t * (b as u8 - a as u8) as f32) as u8;
+    let blend = buffer.blender();
+    let pixel = &mut buffer.row(y as i32)[x as usize];
+    *pixel = blend(*pixel, color, x as i32, y as i32);
+}
 
-    ((red as u32) << 16) | ((green as u32) << 8) | blue as u32
+// Skips pixels outside the buffer instead of writing past it
+unsafe fn draw_pixel_clipped(buffer: &mut OffscreenBuffer, x: i32, y: i32, color: u32) {
+    if x < 0 || y < 0 || x >= buffer.width as i32 || y >= buffer.height as i32 {
+        return;
+    }
+    draw_pixel_to_buffer(buffer, x as u32, y as u32, color);
 }
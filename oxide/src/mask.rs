@@ -0,0 +1,76 @@
+use crate::target::RenderTarget;
+use crate::{
+    draw_circle, draw_polygon, draw_rectangle, draw_stroke_bezier_curve, BezierCurve, Camera, Color, OffscreenBuffer,
+    Rectangle, StrokeStyle, Vector2
+};
+
+// One coverage value per pixel, 0 blocks drawing and 255 lets it through fully.
+// Attach it to a buffer's mask field to clip draw calls to arbitrary shapes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Mask {
+    pub width: u32,
+    pub height: u32,
+    pub values: Vec<u8>
+}
+
+impl Mask {
+    // Starts out empty, so nothing can be drawn through it until shapes are added
+    pub fn new(width: u32, height: u32) -> Self {
+        Mask {
+            width,
+            height,
+            values: vec![0; (width * height) as usize]
+        }
+    }
+
+    // Coverage at a buffer pixel, pixels outside the mask are blocked
+    pub fn get(&self, x: i32, y: i32) -> u8 {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return 0;
+        }
+        self.values[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    pub fn clear(&mut self, value: u8) {
+        self.values.fill(value);
+    }
+
+    // Swaps the inside and outside, for drawing everywhere except the shapes
+    pub fn invert(&mut self) {
+        for value in self.values.iter_mut() {
+            *value = 255 - *value;
+        }
+    }
+
+    // Adds whatever the closure draws to the mask, using the alpha of the drawn pixels
+    // as coverage. Any draw function can be used, so antialiased edges give soft masks.
+    pub fn render<F: FnOnce(&mut OffscreenBuffer)>(&mut self, draw: F) {
+        let mut target = RenderTarget::new(self.width, self.height);
+        draw(target.buffer());
+
+        for (value, pixel) in self.values.iter_mut().zip(target.pixels()) {
+            *value = (*value).max((pixel >> 24) as u8);
+        }
+    }
+
+    pub fn fill_rectangle(&mut self, camera: Camera, rectangle: Rectangle) {
+        self.render(|buffer| unsafe { draw_rectangle(buffer, camera, rectangle, Color::WHITE) });
+    }
+
+    pub fn fill_circle(&mut self, camera: Camera, center: Vector2, radius: f32) {
+        self.render(|buffer| unsafe { draw_circle(buffer, camera, center, radius, Color::WHITE) });
+    }
+
+    pub fn fill_polygon(&mut self, camera: Camera, points: &[Vector2]) {
+        self.render(|buffer| unsafe { draw_polygon(buffer, camera, points, Color::WHITE) });
+    }
+
+    // Only the stroke width of the style is used
+    pub fn stroke_bezier_curve(&mut self, camera: Camera, curve: BezierCurve, style: StrokeStyle) {
+        let style = StrokeStyle {
+            color: Color::WHITE,
+            ..style
+        };
+        self.render(|buffer| unsafe { draw_stroke_bezier_curve(buffer, camera, curve, style) });
+    }
+}
@@ -0,0 +1,64 @@
+use std::f32::consts::{PI, TAU};
+
+// Part of the way from a to b, t of 0.0 is a and 1.0 is b. t isn't clamped.
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// The t that lerp(a, b, t) gives value for, 0.0 when a and b are the same
+pub fn inverse_lerp(a: f32, b: f32, value: f32) -> f32 {
+    if a == b {
+        return 0.0;
+    }
+    (value - a) / (b - a)
+}
+
+// Moves a value from one range to the same place in another, like a slider position to a zoom
+pub fn remap(value: f32, from_min: f32, from_max: f32, to_min: f32, to_max: f32) -> f32 {
+    lerp(to_min, to_max, inverse_lerp(from_min, from_max, value))
+}
+
+// Unlike f32::clamp this doesn't panic when min is past max, it gives min
+pub fn clamp(value: f32, min: f32, max: f32) -> f32 {
+    value.min(max).max(min)
+}
+
+// 0.0 below edge_0, 1.0 past edge_1 and a smooth S curve between, the ends have no slope
+pub fn smoothstep(edge_0: f32, edge_1: f32, value: f32) -> f32 {
+    let t = clamp(inverse_lerp(edge_0, edge_1, value), 0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Part past the whole number below, always from 0.0 up to but not including 1.0. Negative
+// numbers count up from the whole number below, so -0.25 gives 0.75.
+pub fn fract(value: f32) -> f32 {
+    let fraction = value - value.floor();
+    // Rounds up to 1.0 itself for tiny negative numbers
+    if fraction >= 1.0 { 0.0 } else { fraction }
+}
+
+// Same angle between -PI and PI
+pub fn wrap_angle(radians: f32) -> f32 {
+    let wrapped = (radians + PI).rem_euclid(TAU) - PI;
+    // rem_euclid can round up to TAU itself for tiny negative numbers
+    if wrapped >= PI { wrapped - TAU } else { wrapped }
+}
+
+// Same angle between -180 and 180
+pub fn wrap_degrees(degrees: f32) -> f32 {
+    let wrapped = (degrees + 180.0).rem_euclid(360.0) - 180.0;
+    if wrapped >= 180.0 { wrapped - 360.0 } else { wrapped }
+}
+
+// Signed shortest turn from one angle to another, for turning towards a direction
+pub fn angle_difference(from: f32, to: f32) -> f32 {
+    wrap_angle(to - from)
+}
+
+pub fn degrees_to_radians(degrees: f32) -> f32 {
+    degrees * (PI / 180.0)
+}
+
+pub fn radians_to_degrees(radians: f32) -> f32 {
+    radians * (180.0 / PI)
+}
@@ -0,0 +1,77 @@
+use crate::math::radians_to_degrees;
+use crate::{BezierCurve, Vector2};
+
+// Ruler between two world points
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Measurement {
+    pub start: Vector2,
+    pub end: Vector2,
+    // Curve slot and the t at each end when both are on the same curve, the length along it is
+    // measured as well
+    pub along: Option<(u32, f32, f32)>
+}
+
+impl Measurement {
+    pub fn new(start: Vector2) -> Self {
+        Measurement { start, end: start, along: None }
+    }
+
+    pub fn delta(&self) -> Vector2 {
+        self.end - self.start
+    }
+
+    pub fn distance(&self) -> f32 {
+        let delta = self.delta();
+        (delta.x * delta.x + delta.y * delta.y).sqrt()
+    }
+
+    // Degrees from the x axis to the ruler. World y points down, so positive angles turn
+    // clockwise on screen.
+    pub fn angle(&self) -> f32 {
+        let delta = self.delta();
+        radians_to_degrees(delta.y.atan2(delta.x))
+    }
+
+    // Length along the curve between the ends, None when they aren't on the same curve
+    pub fn arc_length(&self, curves: &[Option<BezierCurve>]) -> Option<f32> {
+        let (slot, start_t, end_t) = self.along?;
+        let curve = curves.get(slot as usize).copied().flatten()?;
+        Some(curve_between(&curve, start_t, end_t).arc_length())
+    }
+
+    pub fn text(&self, curves: &[Option<BezierCurve>]) -> String {
+        let delta = self.delta();
+        let mut text = format!(
+            "Distance {:.3}, angle {:.1} degrees, delta ({:.3}, {:.3})",
+            self.distance(),
+            self.angle(),
+            delta.x,
+            delta.y);
+        if let (Some(length), Some((slot, _, _))) = (self.arc_length(curves), self.along) {
+            text.push_str(&format!(", {:.3} along curve {}", length, slot));
+        }
+        text
+    }
+}
+
+// Part of the curve from one t to another, in either order
+pub fn curve_between(curve: &BezierCurve, start_t: f32, end_t: f32) -> BezierCurve {
+    let start = start_t.min(end_t).clamp(0.0, 1.0);
+    let end = start_t.max(end_t).clamp(0.0, 1.0);
+    let (_, rest) = curve.split(start);
+    // The rest of the curve starts at start, so end is that much closer to its start
+    let remaining = 1.0 - start;
+    let local_end = if remaining > 1e-6 { (end - start) / remaining } else { 0.0 };
+    rest.split(local_end).0
+}
+
+// Dragging with the left mouse button measures instead of editing while it's on
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeasureTool {
+    pub enabled: bool,
+    // Last measurement, it stays on screen until the next one starts
+    pub measurement: Option<Measurement>,
+    pub dragging: bool,
+    // Selected curve and t the measurement started on
+    pub start_on: Option<(u32, f32)>
+}
@@ -0,0 +1,194 @@
+use crate::fit::fit_cubic;
+use crate::{BezierCurve, Vector2};
+
+// Steps used to look for cusps, where the offset curve stops and turns back
+static CUSP_SAMPLES: u32 = 64;
+// Points compared against each fitted piece to decide if it needs splitting
+static ERROR_SAMPLES: u32 = 8;
+// Each level halves a piece, 2^8 pieces is far more than any offset needs
+static MAX_OFFSET_DEPTH: u32 = 8;
+// Points per curve when looking for where the two sides of a loop cross
+static TRIM_SAMPLES: u32 = 16;
+
+// Cubics that stay within tolerance of the curve moved the distance along its normal. Positive
+// distances go to the right of the direction the curve heads in, with y pointing down. Where the
+// curve bends tighter than the distance the offset would loop back on itself, the loop is cut off
+// where the two sides cross.
+pub fn offset_curve(curve: &BezierCurve, distance: f32, tolerance: f32) -> Vec<BezierCurve> {
+    if distance == 0.0 {
+        return vec![*curve];
+    }
+    let tolerance = tolerance.max(1e-6);
+
+    // How fast the offset moves compared to the curve, it turns back where this goes negative
+    let speed = |t: f32| 1.0 - distance * curve.curvature(t);
+
+    let mut splits = vec![0.0];
+    let mut i = 1;
+    while i <= CUSP_SAMPLES {
+        let t0 = (i - 1) as f32 / CUSP_SAMPLES as f32;
+        let t1 = i as f32 / CUSP_SAMPLES as f32;
+        if (speed(t0) < 0.0) != (speed(t1) < 0.0) {
+            splits.push(find_sign_change(&speed, t0, t1));
+        }
+        i += 1;
+    }
+    splits.push(1.0);
+
+    // Runs of offset pieces, a new run starts after every loop that was left out
+    let mut runs: Vec<Vec<BezierCurve>> = Vec::new();
+    let mut skipped = true;
+    for window in splits.windows(2) {
+        let (t0, t1) = (window[0], window[1]);
+        if t1 - t0 < 1e-6 {
+            continue;
+        }
+        if speed((t0 + t1) / 2.0) < 0.0 {
+            skipped = true;
+            continue;
+        }
+
+        if skipped || runs.is_empty() {
+            runs.push(Vec::new());
+        }
+        skipped = false;
+        let run = runs.len() - 1;
+        offset_section(curve, t0, t1, distance, tolerance, 0, &mut runs[run]);
+    }
+
+    let mut result = Vec::new();
+    for mut run in runs {
+        trim_crossing(&mut result, &mut run);
+        result.extend(run);
+    }
+    result
+}
+
+fn find_sign_change<F: Fn(f32) -> f32>(function: &F, mut low: f32, mut high: f32) -> f32 {
+    let low_negative = function(low) < 0.0;
+    let mut i = 0;
+    while i < 24 {
+        let middle = (low + high) / 2.0;
+        if (function(middle) < 0.0) == low_negative {
+            low = middle;
+        } else {
+            high = middle;
+        }
+        i += 1;
+    }
+    (low + high) / 2.0
+}
+
+fn offset_point(curve: &BezierCurve, t: f32, distance: f32) -> Vector2 {
+    let tangent = curve.derivative(t);
+    curve.evaluate(t) + Vector2::new(-tangent.y, tangent.x).normalized() * distance
+}
+
+// Fits one cubic to the offset between t0 and t1, halving the range until it's close enough
+fn offset_section(curve: &BezierCurve, t0: f32, t1: f32, distance: f32, tolerance: f32, depth: u32, pieces: &mut Vec<BezierCurve>) {
+    let start = offset_point(curve, t0, distance);
+    let end = offset_point(curve, t1, distance);
+
+    // The offset runs parallel to the curve, so it heads the same way at both ends
+    let section = section(curve, t0, t1);
+    let start_tangent = section.end_direction(false) * -1.0;
+    let end_tangent = section.end_direction(true) * -1.0;
+
+    let mut samples = Vec::with_capacity(ERROR_SAMPLES as usize);
+    let mut i = 1;
+    while i < ERROR_SAMPLES {
+        let u = i as f32 / ERROR_SAMPLES as f32;
+        samples.push((u, offset_point(curve, t0 + (t1 - t0) * u, distance)));
+        i += 1;
+    }
+
+    let fitted = fit_cubic(start, end, start_tangent, end_tangent, &samples);
+    let error = samples.iter().map(|(u, point)| (fitted.evaluate(*u) - *point).length()).fold(0.0, f32::max);
+    if error <= tolerance || depth >= MAX_OFFSET_DEPTH {
+        pieces.push(fitted);
+        return;
+    }
+
+    let middle = (t0 + t1) / 2.0;
+    offset_section(curve, t0, middle, distance, tolerance, depth + 1, pieces);
+    offset_section(curve, middle, t1, distance, tolerance, depth + 1, pieces);
+}
+
+// The part of the curve between t0 and t1, as its own curve
+fn section(curve: &BezierCurve, t0: f32, t1: f32) -> BezierCurve {
+    let after = curve.split(t0).1;
+    if t0 >= 1.0 {
+        return after;
+    }
+    after.split((t1 - t0) / (1.0 - t0)).0
+}
+
+// The pieces on either side of a removed loop overlap, cuts both back to where they cross
+fn trim_crossing(before: &mut Vec<BezierCurve>, after: &mut Vec<BezierCurve>) {
+    if before.is_empty() || after.is_empty() {
+        return;
+    }
+
+    let sample = |curves: &[BezierCurve]| {
+        let mut points = Vec::new();
+        for (index, curve) in curves.iter().enumerate() {
+            let mut i = 0;
+            while i <= TRIM_SAMPLES {
+                let t = i as f32 / TRIM_SAMPLES as f32;
+                points.push((index, t, curve.evaluate(t)));
+                i += 1;
+            }
+        }
+        points
+    };
+    let before_points = sample(before);
+    let after_points = sample(after);
+
+    // The crossing is near the end of the run before and the start of the run after
+    let mut i = before_points.len() - 1;
+    while i > 0 {
+        let (before_index, before_t0, a0) = before_points[i - 1];
+        let (_, before_t1, a1) = before_points[i];
+        let mut j = 1;
+        while j < after_points.len() {
+            let (after_index, after_t0, b0) = after_points[j - 1];
+            let (_, after_t1, b1) = after_points[j];
+            if let Some((s, u)) = segment_intersection(a0, a1, b0, b1) {
+                // Points at t = 1.0 of one curve and t = 0.0 of the next are a pair too
+                if before_t1 < before_t0 || after_t1 < after_t0 {
+                    j += 1;
+                    continue;
+                }
+
+                let before_t = before_t0 + (before_t1 - before_t0) * s;
+                let after_t = after_t0 + (after_t1 - after_t0) * u;
+                before.truncate(before_index + 1);
+                before[before_index] = before[before_index].split(before_t).0;
+                after.drain(..after_index);
+                after[0] = after[0].split(after_t).1;
+                return;
+            }
+            j += 1;
+        }
+        i -= 1;
+    }
+}
+
+// How far along each segment they cross, if they do
+fn segment_intersection(a0: Vector2, a1: Vector2, b0: Vector2, b1: Vector2) -> Option<(f32, f32)> {
+    let a = a1 - a0;
+    let b = b1 - b0;
+    let denominator = a.x * b.y - a.y * b.x;
+    if denominator.abs() < 1e-12 {
+        return None;
+    }
+
+    let offset = b0 - a0;
+    let s = (offset.x * b.y - offset.y * b.x) / denominator;
+    let u = (offset.x * a.y - offset.y * a.x) / denominator;
+    if (0.0..=1.0).contains(&s) && (0.0..=1.0).contains(&u) {
+        Some((s, u))
+    } else {
+        None
+    }
+}
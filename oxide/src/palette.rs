@@ -0,0 +1,189 @@
+use crate::{ClipRect, Color, OffscreenBuffer};
+use std::cmp::min;
+
+// 256 colors an indexed frame is resolved through, stored as premultiplied pixels
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Palette {
+    pub colors: [u32; 256]
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new()
+    }
+}
+
+impl Palette {
+    // Every entry starts out transparent black
+    pub fn new() -> Self {
+        Palette {
+            colors: [0; 256]
+        }
+    }
+
+    // Entries past the end of the slice stay transparent black
+    pub fn from_colors(colors: &[Color]) -> Self {
+        let mut palette = Palette::new();
+        for (entry, color) in palette.colors.iter_mut().zip(colors) {
+            *entry = color.to_pixel();
+        }
+        palette
+    }
+
+    pub fn get(&self, index: u8) -> Color {
+        Color::from_pixel(self.colors[index as usize])
+    }
+
+    pub fn set(&mut self, index: u8, color: Color) {
+        self.colors[index as usize] = color.to_pixel();
+    }
+
+    // Rotates entries first through last (inclusive) by the number of steps, the classic
+    // palette cycling effect for water and fire. Negative steps rotate the other way.
+    pub fn cycle(&mut self, first: u8, last: u8, steps: i32) {
+        if first >= last {
+            return;
+        }
+
+        let range = &mut self.colors[first as usize..=last as usize];
+        let steps = steps.rem_euclid(range.len() as i32) as usize;
+        range.rotate_right(steps);
+    }
+}
+
+// A frame of palette indices instead of colors, resolved to real pixels when it's presented.
+// Changing the palette recolors everything drawn with it without redrawing.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IndexedBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub indices: Vec<u8>
+}
+
+impl IndexedBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        IndexedBuffer {
+            width,
+            height,
+            indices: vec![0; (width * height) as usize]
+        }
+    }
+
+    // Clears to index 0, call this when the window changes size
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.indices.clear();
+        self.indices.resize((width * height) as usize, 0);
+    }
+
+    pub fn bounds(&self) -> ClipRect {
+        ClipRect::new(0, 0, self.width as i32, self.height as i32)
+    }
+
+    // Pixels outside the buffer read as index 0
+    pub fn get(&self, x: i32, y: i32) -> u8 {
+        if !self.bounds().contains(x, y) {
+            return 0;
+        }
+        self.indices[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    // Writes outside the buffer are ignored
+    pub fn set(&mut self, x: i32, y: i32, index: u8) {
+        if !self.bounds().contains(x, y) {
+            return;
+        }
+        self.indices[(y as u32 * self.width + x as u32) as usize] = index;
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [u8] {
+        let start = (y * self.width) as usize;
+        &mut self.indices[start..start + self.width as usize]
+    }
+
+    pub fn clear(&mut self, index: u8) {
+        self.indices.fill(index);
+    }
+
+    pub fn fill_rect(&mut self, rect: ClipRect, index: u8) {
+        let rect = rect.intersection(self.bounds());
+        let mut y = rect.y0;
+        while y < rect.y1 {
+            self.row_mut(y as u32)[rect.x0 as usize..rect.x1 as usize].fill(index);
+            y += 1;
+        }
+    }
+
+    // One pixel wide line between two pixel positions, both ends included
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, index: u8) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+
+        let mut x = x0;
+        let mut y = y0;
+        loop {
+            self.set(x, y, index);
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let doubled = error * 2;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Replaces the pixels of the buffer with the palette colors of the indices. Only the
+    /// area both buffers cover is written, and the clip stack and blend mode are ignored.
+    ///
+    /// # Safety
+    /// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+    pub unsafe fn resolve(&self, buffer: &mut OffscreenBuffer, palette: &Palette) {
+        let width = min(self.width, buffer.width) as usize;
+        let height = min(self.height, buffer.height) as i32;
+
+        let mut y = 0;
+        while y < height {
+            let start = y as usize * self.width as usize;
+            let indices = &self.indices[start..start + width];
+            for (pixel, index) in buffer.row(y)[..width].iter_mut().zip(indices) {
+                *pixel = palette.colors[*index as usize];
+            }
+            y += 1;
+        }
+    }
+}
+
+// Indexed rendering state, when set on the game state it replaces the regular draw calls
+// and is resolved through the palette every frame
+#[derive(Clone, Debug)]
+pub struct IndexedFrame {
+    pub buffer: IndexedBuffer,
+    pub palette: Palette
+}
+
+impl IndexedFrame {
+    pub fn new(width: u32, height: u32, palette: Palette) -> Self {
+        IndexedFrame {
+            buffer: IndexedBuffer::new(width, height),
+            palette
+        }
+    }
+
+    // Keeps the frame the same size as the window, clearing it when the size changes
+    pub fn fit(&mut self, width: u32, height: u32) {
+        if self.buffer.width != width || self.buffer.height != height {
+            self.buffer.resize(width, height);
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use crate::color::scale_color;
+use crate::{
+    draw_stroke_polyline, world_space_to_screen_space_f32, CURVE_TOLERANCE_PIXELS, THIN_STROKE_PIXELS, BezierCurve, Camera, Color,
+    LineCap, OffscreenBuffer, QuadraticBezier, StrokeStyle, Vector2
+};
+
+// Decides which parts of overlapping or self-intersecting outlines are inside
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillRule {
+    // Inside when the outline winds around the point at all, so overlapping parts stay filled
+    // unless an outline goes around the other way. Matches the default of SVG and fonts.
+    #[default]
+    NonZero,
+    // Inside when a ray from the point crosses the outline an odd number of times, so every
+    // overlap flips between filled and empty, like the center of a five pointed star
+    EvenOdd
+}
+
+impl FillRule {
+    // Winding is how many times outlines go around the point, counting direction
+    fn is_inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathSegment {
+    Line { from: Vector2, to: Vector2 },
+    Quadratic(QuadraticBezier),
+    Cubic(BezierCurve)
+}
+
+impl PathSegment {
+    pub fn end(self) -> Vector2 {
+        match self {
+            PathSegment::Line { to, .. } => to,
+            PathSegment::Quadratic(curve) => curve.p2,
+            PathSegment::Cubic(curve) => curve.p3
+        }
+    }
+}
+
+// Connected segments started by a move_to
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubPath {
+    pub start: Vector2,
+    pub segments: Vec<PathSegment>,
+    pub closed: bool
+}
+
+// A flattened sub path, still in world space
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Vector2>,
+    pub closed: bool
+}
+
+// Shape made of lines and cubic bezier curves in world space, built like a pen that moves
+// around. Can be stroked or filled like the other shapes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    pub sub_paths: Vec<SubPath>
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Path {
+            sub_paths: Vec::new()
+        }
+    }
+
+    pub fn from_curve(curve: BezierCurve) -> Self {
+        let mut path = Path::new();
+        path.move_to(curve.p0).cubic_to(curve.p1, curve.p2, curve.p3);
+        path
+    }
+
+    // Starts a new sub path without drawing anything
+    pub fn move_to(&mut self, point: Vector2) -> &mut Self {
+        self.sub_paths.push(SubPath {
+            start: point,
+            segments: Vec::new(),
+            closed: false
+        });
+        self
+    }
+
+    pub fn line_to(&mut self, point: Vector2) -> &mut Self {
+        let from = self.current_point();
+        self.push_segment(PathSegment::Line { from, to: point });
+        self
+    }
+
+    pub fn quadratic_to(&mut self, control: Vector2, point: Vector2) -> &mut Self {
+        let from = self.current_point();
+        self.push_segment(PathSegment::Quadratic(QuadraticBezier::new(from, control, point)));
+        self
+    }
+
+    pub fn cubic_to(&mut self, control_1: Vector2, control_2: Vector2, point: Vector2) -> &mut Self {
+        let from = self.current_point();
+        self.push_segment(PathSegment::Cubic(BezierCurve::new(from, control_1, control_2, point)));
+        self
+    }
+
+    // Connects the pen back to where the sub path started, drawing after this starts a new one
+    pub fn close(&mut self) -> &mut Self {
+        match self.sub_paths.last_mut() {
+            Some(value) if !value.closed => value.closed = true,
+            _ => {}
+        }
+        self
+    }
+
+    // Drawing without a move_to first, or after a close, starts a sub path at the pen
+    fn push_segment(&mut self, segment: PathSegment) {
+        let needs_sub_path = match self.sub_paths.last() {
+            Some(value) => value.closed,
+            None => true
+        };
+        if needs_sub_path {
+            let start = self.current_point();
+            self.move_to(start);
+        }
+
+        if let Some(sub_path) = self.sub_paths.last_mut() {
+            sub_path.segments.push(segment);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sub_paths.iter().all(|sub_path| sub_path.segments.is_empty())
+    }
+
+    // Where the next segment starts, the origin before anything has been added
+    pub fn current_point(&self) -> Vector2 {
+        match self.sub_paths.last() {
+            Some(value) if value.closed => value.start,
+            Some(value) => match value.segments.last() {
+                Some(segment) => segment.end(),
+                None => value.start
+            },
+            None => Vector2 { x: 0.0, y: 0.0 }
+        }
+    }
+
+    // Every point the path can reach, curves stay inside the hull of their control points
+    pub fn control_points(&self) -> Vec<Vector2> {
+        let mut points = Vec::new();
+        for sub_path in &self.sub_paths {
+            points.push(sub_path.start);
+            for segment in &sub_path.segments {
+                match segment {
+                    PathSegment::Line { to, .. } => points.push(*to),
+                    PathSegment::Quadratic(curve) => points.extend_from_slice(&[curve.p1, curve.p2]),
+                    PathSegment::Cubic(curve) => points.extend_from_slice(&[curve.p1, curve.p2, curve.p3])
+                }
+            }
+        }
+        points
+    }
+
+    // Turns curves into enough straight pieces to look smooth at the camera's zoom
+    pub fn flatten(&self, camera: Camera) -> Vec<Contour> {
+        let tolerance = CURVE_TOLERANCE_PIXELS / camera.y_scale;
+        let mut contours = Vec::new();
+
+        for sub_path in &self.sub_paths {
+            if sub_path.segments.is_empty() {
+                continue;
+            }
+
+            let mut points = vec![sub_path.start];
+            for segment in &sub_path.segments {
+                match segment {
+                    PathSegment::Line { to, .. } => points.push(*to),
+                    // Curves skip their first point, it's the end of the previous segment
+                    PathSegment::Quadratic(curve) => points.extend_from_slice(&curve.sample_points(camera)[1..]),
+                    PathSegment::Cubic(curve) => points.extend_from_slice(&curve.flatten(tolerance)[1..])
+                }
+            }
+
+            contours.push(Contour {
+                points,
+                closed: sub_path.closed
+            });
+        }
+
+        contours
+    }
+}
+
+/// Fills the inside of the path, sub paths are closed automatically. The rule decides where
+/// sub paths overlap or cross themselves.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn fill_path(buffer: &mut OffscreenBuffer, camera: Camera, path: &Path, color: Color, rule: FillRule) {
+    let contours: Vec<Vec<Vector2>> = path.flatten(camera).iter()
+        .map(|contour| contour.points.iter().map(|point| world_space_to_screen_space_f32(camera, *point)).collect())
+        .collect();
+    fill_contours(buffer, &contours, color.to_pixel(), rule);
+}
+
+/// Same as fill_path with antialiased edges, for solid shapes like blobs and glyphs
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn fill_path_smooth(buffer: &mut OffscreenBuffer, camera: Camera, path: &Path, color: Color, rule: FillRule) {
+    let contours: Vec<Vec<Vector2>> = path.flatten(camera).iter()
+        .map(|contour| contour.points.iter().map(|point| world_space_to_screen_space_f32(camera, *point)).collect())
+        .collect();
+    fill_contours_smooth(buffer, &contours, color.to_pixel(), rule);
+}
+
+/// Strokes every sub path with the style, closed sub paths get a join where they meet up
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn stroke_path(buffer: &mut OffscreenBuffer, camera: Camera, path: &Path, style: StrokeStyle) {
+    for contour in path.flatten(camera) {
+        let mut points = contour.points;
+        let mut style = style;
+
+        if contour.closed && points.len() > 2 {
+            points.push(points[0]);
+
+            // Going around to the second point again puts a join at the start as well. Thick
+            // solid strokes are filled as one shape, so the overlap isn't drawn twice.
+            let is_thick = style.width.to_pixels(camera) >= THIN_STROKE_PIXELS;
+            if is_thick && style.dash.is_none() {
+                points.push(points[1]);
+            }
+            // There are no ends, caps would only poke out of the corner the stroke stops on
+            style.cap = LineCap::Butt;
+        }
+
+        draw_stroke_polyline(buffer, camera, &points, style);
+    }
+}
+
+/// Scanline fills closed screen space contours together
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn fill_contours(buffer: &mut OffscreenBuffer, contours: &[Vec<Vector2>], color: u32, rule: FillRule) {
+    let mut min_y = f32::MAX;
+    let mut max_y = f32::MIN;
+    for point in contours.iter().flatten() {
+        min_y = min_y.min(point.y);
+        max_y = max_y.max(point.y);
+    }
+
+    let clip = buffer.clip_rect();
+    let start_y = (min_y.floor() as i32).max(clip.y0);
+    let end_y = (max_y.ceil() as i32).min(clip.y1);
+
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    let mut y = start_y;
+    while y < end_y {
+        // Sample at the pixel center
+        let scan_y = y as f32 + 0.5;
+
+        // Fill the pixels whose centers are inside
+        for (x0, x1) in inside_spans(contours, scan_y, rule, &mut crossings) {
+            buffer.fill_span(y, (x0 - 0.5).ceil() as i32, (x1 - 0.5).ceil() as i32, color);
+        }
+
+        y += 1;
+    }
+}
+
+/// Scanline fills closed screen space contours with antialiased edges. Coverage is measured
+/// exactly across each row and on SMOOTH_SUBSAMPLES lines within it.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn fill_contours_smooth(buffer: &mut OffscreenBuffer, contours: &[Vec<Vector2>], color: u32, rule: FillRule) {
+    let mut min_y = f32::MAX;
+    let mut max_y = f32::MIN;
+    for point in contours.iter().flatten() {
+        min_y = min_y.min(point.y);
+        max_y = max_y.max(point.y);
+    }
+
+    let clip = buffer.clip_rect();
+    let start_y = (min_y.floor() as i32).max(clip.y0);
+    let end_y = (max_y.ceil() as i32).min(clip.y1);
+    if clip.x0 >= clip.x1 {
+        return;
+    }
+
+    let sample_weight = 1.0 / SMOOTH_SUBSAMPLES as f32;
+    let mut coverage = vec![0.0; (clip.x1 - clip.x0) as usize];
+    let mut colors = Vec::new();
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+    let mut y = start_y;
+    while y < end_y {
+        coverage.fill(0.0);
+
+        let mut sample = 0;
+        while sample < SMOOTH_SUBSAMPLES {
+            let scan_y = y as f32 + (sample as f32 + 0.5) * sample_weight;
+            for (x0, x1) in inside_spans(contours, scan_y, rule, &mut crossings) {
+                add_span_coverage(&mut coverage, x0 - clip.x0 as f32, x1 - clip.x0 as f32, sample_weight);
+            }
+            sample += 1;
+        }
+
+        // Draw each run of touched pixels with the color scaled by how much of them is covered
+        let mut x = 0;
+        while x < coverage.len() {
+            if coverage[x] <= 0.0 {
+                x += 1;
+                continue;
+            }
+
+            let run_start = x;
+            colors.clear();
+            while x < coverage.len() && coverage[x] > 0.0 {
+                colors.push(scale_color(color, coverage[x].min(1.0)));
+                x += 1;
+            }
+            buffer.blend_span(y, clip.x0 + run_start as i32, &colors);
+        }
+
+        y += 1;
+    }
+}
+
+// Lines sampled inside each pixel row by fill_contours_smooth
+static SMOOTH_SUBSAMPLES: u32 = 4;
+
+// Adds weight times how much of each pixel the span from x0 to x1 covers
+fn add_span_coverage(coverage: &mut [f32], x0: f32, x1: f32, weight: f32) {
+    let x0 = x0.max(0.0);
+    let x1 = x1.min(coverage.len() as f32);
+    if x0 >= x1 {
+        return;
+    }
+
+    let first = x0.floor() as usize;
+    let last = (x1.ceil() as usize - 1).min(coverage.len() - 1);
+    if first == last {
+        coverage[first] += (x1 - x0) * weight;
+        return;
+    }
+
+    coverage[first] += (first as f32 + 1.0 - x0) * weight;
+    for value in &mut coverage[first + 1..last] {
+        *value += weight;
+    }
+    coverage[last] += (x1 - last as f32) * weight;
+}
+
+// Where the horizontal line at scan_y is inside the contours, as (start x, end x) pairs.
+// crossings is scratch space so rows don't each allocate.
+fn inside_spans(contours: &[Vec<Vector2>], scan_y: f32, rule: FillRule, crossings: &mut Vec<(f32, i32)>) -> Vec<(f32, f32)> {
+    // Where each edge crosses the scanline and whether it goes down (1) or up (-1)
+    crossings.clear();
+    for points in contours {
+        if points.len() < 3 {
+            continue;
+        }
+
+        let mut i = 0;
+        while i < points.len() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+
+            // Half open so a vertex shared by two edges only counts once
+            if (a.y <= scan_y && b.y > scan_y) || (b.y <= scan_y && a.y > scan_y) {
+                let x = a.x + (scan_y - a.y) / (b.y - a.y) * (b.x - a.x);
+                crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+            }
+            i += 1;
+        }
+    }
+
+    crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // A span goes from a crossing into the shape to the next crossing out of it
+    let mut spans = Vec::new();
+    let mut winding = 0;
+    let mut span_start = 0.0;
+    for (x, direction) in crossings.iter() {
+        let was_inside = rule.is_inside(winding);
+        winding += direction;
+        let is_inside = rule.is_inside(winding);
+
+        if !was_inside && is_inside {
+            span_start = *x;
+        } else if was_inside && !is_inside {
+            spans.push((span_start, *x));
+        }
+    }
+    spans
+}
@@ -0,0 +1,33 @@
+use crate::{Camera, CameraFit, ClipRect, InputController, OffscreenBuffer, WindowDimensions};
+
+// Everything the host needs from the operating system to run the game, one implementation per
+// OS. The game and renderer only ever see the OffscreenBuffer, so none of this reaches them.
+pub trait Platform: Sized {
+    // Opens a window with the title, shown right away
+    fn create_window(title: &str) -> Result<Self, String>;
+
+    // Size of the window's drawable area in pixels
+    fn window_dimensions(&self) -> WindowDimensions;
+
+    // Window pixels per 96 DPI pixel
+    fn dpi_scale(&self) -> f32;
+
+    // The back buffer the game draws into. The platform owns it so it can repaint the window
+    // from it between frames, like while the window is being resized.
+    fn buffer(&mut self) -> &mut OffscreenBuffer;
+
+    // Sizes the buffer to the fixed internal resolution, or to the window through the fit without
+    // one. Called every frame, the fit is also what present and the mouse position go through.
+    fn fit_buffer(&mut self, fit: CameraFit, internal_resolution: Option<WindowDimensions>, camera: Camera);
+
+    // Reads the window, keyboard, mouse and gamepad events since the last call into the input,
+    // with the mouse position in buffer pixels. Returns false once the window is closed.
+    fn poll_events(&mut self, input: &mut InputController) -> bool;
+
+    // Copies the regions of the buffer to the window, scaled by the fit with black bars around it
+    fn present(&mut self, regions: &[ClipRect]);
+
+    // Milliseconds since some point in the past, only the difference between two calls means
+    // anything
+    fn time(&self) -> f64;
+}
@@ -0,0 +1,100 @@
+use crate::color::scale_color;
+use crate::OffscreenBuffer;
+
+// The finished frame handed to post process hooks, pixels are premultiplied 0xAARRGGBB
+pub struct PostProcessFrame<'a> {
+    pub pixels: &'a mut [u32],
+    pub width: u32,
+    pub height: u32,
+    // Pixels from the start of one row to the next, at least width
+    pub stride: u32
+}
+
+impl PostProcessFrame<'_> {
+    pub fn row_mut(&mut self, y: u32) -> &mut [u32] {
+        let start = (y * self.stride) as usize;
+        &mut self.pixels[start..start + self.width as usize]
+    }
+}
+
+// Function pointers point into the game library, so hooks have to be added again after it's reloaded
+pub type PostProcessHook = fn(&mut PostProcessFrame);
+
+// Full screen effects that run in order after every draw call of the frame, before presenting
+#[derive(Clone, Default)]
+pub struct PostProcessStack {
+    hooks: Vec<(String, PostProcessHook)>
+}
+
+impl PostProcessStack {
+    // Replaces the hook if one with the same name was already added
+    pub fn add(&mut self, name: &str, hook: PostProcessHook) {
+        match self.hooks.iter_mut().find(|(hook_name, _)| hook_name == name) {
+            Some(value) => value.1 = hook,
+            None => self.hooks.push((name.to_string(), hook))
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.hooks.len();
+        self.hooks.retain(|(hook_name, _)| hook_name != name);
+        self.hooks.len() != count
+    }
+
+    pub fn clear(&mut self) {
+        self.hooks.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// # Safety
+    /// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+    pub unsafe fn run(&self, buffer: &mut OffscreenBuffer) {
+        if self.hooks.is_empty() || buffer.width == 0 || buffer.height == 0 {
+            return;
+        }
+
+        let stride = buffer.pitch / 4;
+        let mut frame = PostProcessFrame {
+            pixels: std::slice::from_raw_parts_mut(buffer.memory as *mut u32, (stride * buffer.height) as usize),
+            width: buffer.width,
+            height: buffer.height,
+            stride
+        };
+
+        for (_, hook) in &self.hooks {
+            hook(&mut frame);
+        }
+    }
+}
+
+// Darkens every other row like an old CRT
+pub fn scanlines(frame: &mut PostProcessFrame) {
+    let mut y = 1;
+    while y < frame.height {
+        for pixel in frame.row_mut(y).iter_mut() {
+            *pixel = scale_color(*pixel, 0.7) | (*pixel & 0xFF000000);
+        }
+        y += 2;
+    }
+}
+
+// Gradually darkens the frame towards the corners
+pub fn vignette(frame: &mut PostProcessFrame) {
+    let center_x = frame.width as f32 / 2.0;
+    let center_y = frame.height as f32 / 2.0;
+    let max_distance_squared = center_x * center_x + center_y * center_y;
+
+    let mut y = 0;
+    while y < frame.height {
+        let dy = y as f32 + 0.5 - center_y;
+        for (x, pixel) in frame.row_mut(y).iter_mut().enumerate() {
+            let dx = x as f32 + 0.5 - center_x;
+            let falloff = (dx * dx + dy * dy) / max_distance_squared;
+            *pixel = scale_color(*pixel, 1.0 - falloff * 0.6) | (*pixel & 0xFF000000);
+        }
+        y += 1;
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{BezierCurve, Vector2};
+
+// f64 versions of the geometry types for worlds too big for f32. Far from the origin f32
+// positions are only a few thousandths of a unit apart, which shows up as jitter when zoomed
+// in. Keep the world in these and convert to f32 relative to a point near the camera, like
+// its position, just before building render commands. The rasterizer then only sees small
+// coordinates that f32 handles exactly enough.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Vector2d {
+    pub x: f64,
+    pub y: f64
+}
+
+impl Vector2d {
+    pub fn new(x: f64, y: f64) -> Self {
+        Vector2d { x, y }
+    }
+
+    pub fn lerp(self, other: Vector2d, t: f64) -> Self {
+        self + (other - self) * t
+    }
+
+    pub fn length(self) -> f64 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    // Offset from the origin in f32, the subtraction happens in f64 so nothing is lost before
+    // the result is small
+    pub fn relative_to(self, origin: Vector2d) -> Vector2 {
+        Vector2 {
+            x: (self.x - origin.x) as f32,
+            y: (self.y - origin.y) as f32
+        }
+    }
+
+    pub fn from_f32(vector: Vector2) -> Self {
+        Vector2d { x: vector.x as f64, y: vector.y as f64 }
+    }
+}
+
+impl std::ops::Add for Vector2d {
+    type Output = Vector2d;
+
+    fn add(self, other: Vector2d) -> Vector2d {
+        Vector2d {
+            x: self.x + other.x,
+            y: self.y + other.y
+        }
+    }
+}
+
+impl std::ops::Sub for Vector2d {
+    type Output = Vector2d;
+
+    fn sub(self, other: Vector2d) -> Vector2d {
+        Vector2d {
+            x: self.x - other.x,
+            y: self.y - other.y
+        }
+    }
+}
+
+impl std::ops::Mul<f64> for Vector2d {
+    type Output = Vector2d;
+
+    fn mul(self, scalar: f64) -> Vector2d {
+        Vector2d {
+            x: self.x * scalar,
+            y: self.y * scalar
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct BezierCurved {
+    pub p0: Vector2d,
+    pub p1: Vector2d,
+    pub p2: Vector2d,
+    pub p3: Vector2d
+}
+
+impl BezierCurved {
+    pub fn new(p0: Vector2d, p1: Vector2d, p2: Vector2d, p3: Vector2d) -> Self {
+        BezierCurved { p0, p1, p2, p3 }
+    }
+
+    pub fn evaluate(&self, t: f64) -> Vector2d {
+        let u = 1.0 - t;
+        self.p0 * (u * u * u) + self.p1 * (3.0 * u * u * t) + self.p2 * (3.0 * u * t * t) + self.p3 * (t * t * t)
+    }
+
+    pub fn derivative(&self, t: f64) -> Vector2d {
+        let u = 1.0 - t;
+        (self.p1 - self.p0) * (3.0 * u * u) + (self.p2 - self.p1) * (6.0 * u * t) + (self.p3 - self.p2) * (3.0 * t * t)
+    }
+
+    // Cuts the curve in two at t with de Casteljau's algorithm, t is clamped to the curve
+    pub fn split(&self, t: f64) -> (BezierCurved, BezierCurved) {
+        let t = t.clamp(0.0, 1.0);
+        let p01 = self.p0.lerp(self.p1, t);
+        let p12 = self.p1.lerp(self.p2, t);
+        let p23 = self.p2.lerp(self.p3, t);
+        let p012 = p01.lerp(p12, t);
+        let p123 = p12.lerp(p23, t);
+        let point = p012.lerp(p123, t);
+
+        (BezierCurved::new(self.p0, p01, p012, point), BezierCurved::new(point, p123, p23, self.p3))
+    }
+
+    // The curve for drawing or for the f32 curve tools, see Vector2d::relative_to
+    pub fn relative_to(&self, origin: Vector2d) -> BezierCurve {
+        BezierCurve::new(self.p0.relative_to(origin), self.p1.relative_to(origin), self.p2.relative_to(origin), self.p3.relative_to(origin))
+    }
+
+    pub fn from_f32(curve: BezierCurve) -> Self {
+        BezierCurved::new(Vector2d::from_f32(curve.p0), Vector2d::from_f32(curve.p1), Vector2d::from_f32(curve.p2), Vector2d::from_f32(curve.p3))
+    }
+}
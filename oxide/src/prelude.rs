@@ -0,0 +1,6 @@
+// What game code usually needs, brought in with `use oxide::prelude::*`
+pub use crate::bezier_path::{BezierPath, Continuity};
+pub use crate::path::{FillRule, Path};
+pub use crate::{
+    BezierCurve, Camera, Color, DashPattern, LineCap, LineJoin, QuadraticBezier, Rectangle, StrokeStyle, StrokeWidth, Vector2, Vector2u32
+};
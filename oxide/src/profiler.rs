@@ -0,0 +1,109 @@
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct ProfileZone {
+    pub name: &'static str,
+    pub milliseconds: f32
+}
+
+// Times named sections of a frame, zones from the previous frame are kept
+// around so they can be inspected once the full frame time is known
+#[derive(Clone, Default, Debug)]
+pub struct FrameProfiler {
+    pub frame_index: u64,
+    pub zones: Vec<ProfileZone>,
+    pub last_frame_zones: Vec<ProfileZone>
+}
+
+impl FrameProfiler {
+    pub fn begin_frame(&mut self) {
+        self.last_frame_zones = std::mem::take(&mut self.zones);
+        self.frame_index += 1;
+    }
+
+    // Usage: let start = Instant::now(); do_work(); profiler.record("work", start);
+    pub fn record(&mut self, name: &'static str, start: Instant) {
+        self.zones.push(ProfileZone {
+            name,
+            milliseconds: start.elapsed().as_secs_f32() * 1000.0
+        });
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HitchReport {
+    pub frame_index: u64,
+    pub frame_time: f32,
+    pub zones: Vec<ProfileZone>,
+    // Free form description of the game state when the hitch happened
+    pub state: String
+}
+
+impl HitchReport {
+    pub fn to_log_string(&self) -> String {
+        let mut text = format!("Hitch on frame {}: {:.2} ms\n", self.frame_index, self.frame_time);
+        for zone in &self.zones {
+            text.push_str(&format!("  {}: {:.2} ms\n", zone.name, zone.milliseconds));
+        }
+        text.push_str(&format!("  State: {}", self.state));
+        text
+    }
+}
+
+// Reports frames that take longer than the threshold
+#[derive(Clone, Debug)]
+pub struct HitchWatchdog {
+    pub threshold_ms: f32,
+    // Only the most recent reports are kept
+    pub max_reports: usize,
+    pub reports: Vec<HitchReport>,
+    // Time left, in ms, to keep showing the hitch indicator
+    pub display_time_left: f32
+}
+
+static HITCH_DISPLAY_TIME: f32 = 1000.0;
+
+impl Default for HitchWatchdog {
+    fn default() -> Self {
+        HitchWatchdog {
+            threshold_ms: 100.0,
+            max_reports: 16,
+            reports: Vec::new(),
+            display_time_left: 0.0
+        }
+    }
+}
+
+impl HitchWatchdog {
+    // Call at the start of a frame with the time the previous frame took.
+    // Returns the report if the previous frame was a hitch.
+    pub fn check(&mut self, frame_time: f32, profiler: &FrameProfiler, state: String) -> Option<&HitchReport> {
+        self.display_time_left = (self.display_time_left - frame_time).max(0.0);
+
+        // The first frames measure time from startup and would always look like hitches
+        if profiler.frame_index < 3 || frame_time < self.threshold_ms {
+            return None;
+        }
+
+        let report = HitchReport {
+            frame_index: profiler.frame_index - 1,
+            frame_time,
+            zones: profiler.last_frame_zones.clone(),
+            state
+        };
+
+        println!("{}", report.to_log_string());
+
+        if self.reports.len() >= self.max_reports {
+            self.reports.remove(0);
+        }
+        self.reports.push(report);
+        self.display_time_left = HITCH_DISPLAY_TIME;
+
+        self.reports.last()
+    }
+
+    pub fn is_displaying(&self) -> bool {
+        self.display_time_left > 0.0
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{
+    draw_polyline, draw_stroke_polyline, world_space_to_screen_space_f32, BezierCurve, Camera, Color, OffscreenBuffer, Rectangle,
+    StrokeStyle, Vector2
+};
+
+// Screen pixels per straight piece when drawing, the curve gets at least one and at most 32
+static CURVE_FLATNESS_PIXELS: f32 = 4.0;
+static MAX_CURVE_SEGMENTS: f32 = 32.0;
+// Most quadratics a cubic is cut into when approximating it, however tight the tolerance
+static MAX_QUADRATICS: u32 = 64;
+
+// Bezier curve with a single control point, what TrueType glyphs and some SVG paths are made of.
+// Cheaper to evaluate than a cubic and converts to one exactly with to_cubic.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct QuadraticBezier {
+    pub p0: Vector2,
+    pub p1: Vector2,
+    pub p2: Vector2
+}
+
+impl QuadraticBezier {
+    pub fn new(p0: Vector2, p1: Vector2, p2: Vector2) -> Self {
+        QuadraticBezier { p0, p1, p2 }
+    }
+
+    pub fn evaluate(&self, t: f32) -> Vector2 {
+        let u = 1.0 - t;
+        self.p0 * (u * u) + self.p1 * (2.0 * u * t) + self.p2 * (t * t)
+    }
+
+    // Tangent of the curve at t, points in the direction the curve is heading
+    pub fn derivative(&self, t: f32) -> Vector2 {
+        (self.p1 - self.p0) * (2.0 * (1.0 - t)) + (self.p2 - self.p1) * (2.0 * t)
+    }
+
+    // Each axis has at most one turning point, where its derivative is zero
+    pub fn get_bounding_box(&self) -> Rectangle {
+        let turning_point = |p0: f32, p1: f32, p2: f32| {
+            let denominator = p0 - 2.0 * p1 + p2;
+            // Avoid division by zero, the axis moves at a constant speed so it can't turn
+            if denominator.abs() < 1e-6 {
+                return None;
+            }
+
+            let t = (p0 - p1) / denominator;
+            if t > 0.0 && t < 1.0 { Some(t) } else { None }
+        };
+
+        let mut points = vec![self.p0, self.p2];
+        if let Some(t) = turning_point(self.p0.x, self.p1.x, self.p2.x) {
+            points.push(self.evaluate(t));
+        }
+        if let Some(t) = turning_point(self.p0.y, self.p1.y, self.p2.y) {
+            points.push(self.evaluate(t));
+        }
+
+        let mut min_x = f32::MAX;
+        let mut min_y = f32::MAX;
+        let mut max_x = f32::MIN;
+        let mut max_y = f32::MIN;
+        for point in points {
+            min_x = min_x.min(point.x);
+            min_y = min_y.min(point.y);
+            max_x = max_x.max(point.x);
+            max_y = max_y.max(point.y);
+        }
+
+        Rectangle {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y
+        }
+    }
+
+    // Cuts the curve in two at t with de Casteljau's algorithm, t is clamped to the curve
+    pub fn split(&self, t: f32) -> (QuadraticBezier, QuadraticBezier) {
+        let t = t.clamp(0.0, 1.0);
+        let p01 = self.p0.lerp(self.p1, t);
+        let p12 = self.p1.lerp(self.p2, t);
+        let point = p01.lerp(p12, t);
+
+        (QuadraticBezier::new(self.p0, p01, point), QuadraticBezier::new(point, p12, self.p2))
+    }
+
+    // The same curve as a cubic, for code that only handles cubics. Raising the degree is exact.
+    pub fn to_cubic(&self) -> BezierCurve {
+        BezierCurve::new(
+            self.p0,
+            self.p0 + (self.p1 - self.p0) * (2.0 / 3.0),
+            self.p2 + (self.p1 - self.p2) * (2.0 / 3.0),
+            self.p2)
+    }
+
+    // Points along the curve, close enough together to look smooth at the camera's zoom
+    pub fn sample_points(&self, camera: Camera) -> Vec<Vector2> {
+        let p0 = world_space_to_screen_space_f32(camera, self.p0);
+        let p1 = world_space_to_screen_space_f32(camera, self.p1);
+        let p2 = world_space_to_screen_space_f32(camera, self.p2);
+        let hull_length = (p1 - p0).length() + (p2 - p1).length();
+        let count = (hull_length / CURVE_FLATNESS_PIXELS).ceil().clamp(1.0, MAX_CURVE_SEGMENTS) as u32;
+
+        let mut points = Vec::with_capacity(count as usize + 1);
+        let mut i = 0;
+        while i <= count {
+            points.push(self.evaluate(i as f32 / count as f32));
+            i += 1;
+        }
+        points
+    }
+}
+
+// Quadratics that stay within tolerance of a cubic, the way fonts and SVG renderers convert
+// between them. A cubic that was made from a quadratic comes back as exactly one.
+pub fn cubic_to_quadratics(curve: &BezierCurve, tolerance: f32) -> Vec<QuadraticBezier> {
+    // How far the best single quadratic can be from the cubic, the error of each piece shrinks
+    // with the cube of how many pieces the cubic is cut into
+    let third_difference = curve.p3 - curve.p2 * 3.0 + curve.p1 * 3.0 - curve.p0;
+    let error = third_difference.length() * 3.0_f32.sqrt() / 36.0;
+    let count = if tolerance > 0.0 {
+        (error / tolerance).cbrt().ceil().clamp(1.0, MAX_QUADRATICS as f32) as u32
+    } else {
+        MAX_QUADRATICS
+    };
+
+    let mut quadratics = Vec::with_capacity(count as usize);
+    let mut rest = *curve;
+    let mut i = 0;
+    while i < count {
+        // Cut the next piece off the front of what's left, its share of the remaining t shrinks
+        let piece = if i + 1 == count {
+            rest
+        } else {
+            let (piece, after) = rest.split(1.0 / (count - i) as f32);
+            rest = after;
+            piece
+        };
+
+        // Control point where the tangents of both ends would meet on a quadratic
+        let control = ((piece.p1 + piece.p2) * 3.0 - piece.p0 - piece.p3) * 0.25;
+        quadratics.push(QuadraticBezier::new(piece.p0, control, piece.p3));
+        i += 1;
+    }
+    quadratics
+}
+
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_quadratic_bezier(buffer: &mut OffscreenBuffer, camera: Camera, curve: QuadraticBezier, color: Color) {
+    draw_polyline(buffer, camera, &curve.sample_points(camera), color);
+}
+
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_stroke_quadratic_bezier(buffer: &mut OffscreenBuffer, camera: Camera, curve: QuadraticBezier, style: StrokeStyle) {
+    draw_stroke_polyline(buffer, camera, &curve.sample_points(camera), style);
+}
@@ -0,0 +1,94 @@
+use crate::{Color, Vector2};
+
+// Seed the Rng starts from when none is given, so a fresh GameState always plays out the same
+static DEFAULT_SEED: u64 = 0x853C_49E6_748F_EA9B;
+// Constants from the reference PCG32, the increment has to be odd
+static MULTIPLIER: u64 = 6364136223846793005;
+static INCREMENT: u64 = 1442695040888963407;
+
+// Small PCG32 random number generator. The same seed gives the same numbers on every machine and
+// every run, so procedural content and particles come out the same when a session is replayed.
+// Not for anything that needs to be hard to guess.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(DEFAULT_SEED)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Stepping once after adding the seed keeps nearby seeds from starting out alike
+        let mut rng = Rng { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    // From 0.0 up to but not including 1.0, using the 24 bits an f32 can hold exactly
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u32() >> 31 == 1
+    }
+
+    // From min up to but not including max
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    // From min up to but not including max, every value equally likely. Gives min when the
+    // range is empty.
+    pub fn range_i32(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+
+        let span = (max as i64 - min as i64) as u64;
+        // Multiply and shift instead of modulo, the bias is far too small to matter here. Added
+        // as i64, the offset doesn't fit an i32 when the range is wider than i32::MAX.
+        (min as i64 + ((self.next_u32() as u64 * span) >> 32) as i64) as i32
+    }
+
+    // Item of a slice picked at random, None for an empty one
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.range_i32(0, items.len().min(i32::MAX as usize) as i32) as usize)
+    }
+
+    // Direction of length 1, every angle equally likely
+    pub fn unit_vector(&mut self) -> Vector2 {
+        let angle = self.next_f32() * std::f32::consts::TAU;
+        Vector2 { x: angle.cos(), y: angle.sin() }
+    }
+
+    // Point spread evenly over a disc around the origin
+    pub fn in_circle(&mut self, radius: f32) -> Vector2 {
+        self.unit_vector() * (radius * self.next_f32().sqrt())
+    }
+
+    // Opaque color with any hue, bright and saturated enough to stand out on the dark background
+    pub fn color(&mut self) -> Color {
+        let hue = self.range_f32(0.0, 360.0);
+        let saturation = self.range_f32(0.6, 1.0);
+        let value = self.range_f32(0.7, 1.0);
+        Color::from_hsv(hue, saturation, value)
+    }
+}
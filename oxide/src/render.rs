@@ -0,0 +1,803 @@
+use crate::path::{fill_path, fill_path_smooth, stroke_path, FillRule, Path};
+use crate::profiler::FrameProfiler;
+use crate::quadratic::{draw_quadratic_bezier, draw_stroke_quadratic_bezier};
+use crate::stroke::MITER_LIMIT;
+use crate::svg::{SvgDocument, SvgStroke};
+use crate::text::{draw_text, text_rect, text_size, TEXT_PADDING};
+use crate::transform::Transform2D;
+use crate::{
+    clear_buffer, draw_bezier_curve, draw_circle, draw_circle_outline, draw_line, draw_line_antialiased, draw_polygon, draw_polyline,
+    draw_rectangle, draw_rectangle_corners, draw_rectangle_outline, draw_rectangle_rotated, draw_stroke_bezier_curve, draw_stroke_line, draw_stroke_polyline,
+    draw_triangle, draw_unit_grid, unit_grid_lines, world_space_to_screen_space_f32, BezierCurve, BlendMode, Camera, ClipRect, Color, DashPattern, OffscreenBuffer,
+    QuadraticBezier, Rectangle, LineCap, LineJoin, StrokeStyle, StrokeWidth, Vector2
+};
+use std::cmp::Ordering;
+use std::time::Instant;
+
+// Draw order of the frame, lower layers are drawn first and end up underneath
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Layer {
+    Background,
+    Grid,
+    World,
+    Overlay,
+    Ui
+}
+
+impl Layer {
+    // Used as the profiler zone name when the layer is executed
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Background => "layer_background",
+            Layer::Grid => "layer_grid",
+            Layer::World => "layer_world",
+            Layer::Overlay => "layer_overlay",
+            Layer::Ui => "layer_ui"
+        }
+    }
+}
+
+// Extra pixels around a command's shape, covers antialiasing and rounding
+static BOUNDS_PADDING: f32 = 2.0;
+// Past this many separate dirty rects they are merged into one
+static MAX_DIRTY_RECTS: usize = 16;
+
+// A single draw, positions are in world space of the given camera
+#[derive(Clone, PartialEq)]
+pub enum RenderCommand {
+    ClearBuffer,
+    DrawUnitGrid { camera: Camera },
+    DrawRect { camera: Camera, rectangle: Rectangle, color: Color },
+    // Turned by rotation radians around the rectangle's center
+    DrawRectRotated { camera: Camera, rectangle: Rectangle, rotation: f32, color: Color },
+    DrawRectOutline { camera: Camera, rectangle: Rectangle, thickness: StrokeWidth, color: Color },
+    DrawRectCorners { camera: Camera, rectangle: Rectangle, length: StrokeWidth, thickness: StrokeWidth, color: Color },
+    DrawCircle { camera: Camera, center: Vector2, radius: f32, color: Color },
+    DrawCircleOutline { camera: Camera, center: Vector2, radius: f32, thickness: StrokeWidth, color: Color },
+    // Single pixel line, antialiased lines are drawn with DrawLineSmooth
+    DrawLine { camera: Camera, from: Vector2, to: Vector2, color: Color },
+    DrawLineSmooth { camera: Camera, from: Vector2, to: Vector2, color: Color },
+    DrawStrokeLine { camera: Camera, from: Vector2, to: Vector2, style: StrokeStyle },
+    DrawPolyline { camera: Camera, points: Vec<Vector2>, color: Color },
+    DrawStrokePolyline { camera: Camera, points: Vec<Vector2>, style: StrokeStyle },
+    DrawPolygon { camera: Camera, points: Vec<Vector2>, color: Color },
+    DrawTriangle { camera: Camera, points: [Vector2; 3], color: Color },
+    DrawCurve { camera: Camera, curve: BezierCurve, color: Color },
+    DrawStrokeCurve { camera: Camera, curve: BezierCurve, style: StrokeStyle },
+    DrawQuadraticCurve { camera: Camera, curve: QuadraticBezier, color: Color },
+    DrawStrokeQuadraticCurve { camera: Camera, curve: QuadraticBezier, style: StrokeStyle },
+    FillPath { camera: Camera, path: Path, color: Color, rule: FillRule },
+    // Antialiased edges, slower than FillPath
+    FillPathSmooth { camera: Camera, path: Path, color: Color, rule: FillRule },
+    StrokePath { camera: Camera, path: Path, style: StrokeStyle },
+    // Single line of text from the built in font, its top left corner at the position. The glyphs
+    // are scale screen pixels big, on a background box when the background isn't transparent.
+    DrawText { camera: Camera, position: Vector2, text: String, scale: u32, color: Color, background: Color }
+}
+
+impl RenderCommand {
+    /// # Safety
+    /// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+    pub unsafe fn execute(&self, buffer: &mut OffscreenBuffer) {
+        match self {
+            RenderCommand::ClearBuffer => clear_buffer(buffer),
+            RenderCommand::DrawUnitGrid { camera } => draw_unit_grid(buffer, *camera),
+            RenderCommand::DrawRect { camera, rectangle, color } => draw_rectangle(buffer, *camera, *rectangle, *color),
+            RenderCommand::DrawRectRotated { camera, rectangle, rotation, color } => {
+                draw_rectangle_rotated(buffer, *camera, *rectangle, *rotation, *color)
+            },
+            RenderCommand::DrawRectOutline { camera, rectangle, thickness, color } => {
+                draw_rectangle_outline(buffer, *camera, *rectangle, *thickness, *color)
+            },
+            RenderCommand::DrawRectCorners { camera, rectangle, length, thickness, color } => {
+                draw_rectangle_corners(buffer, *camera, *rectangle, *length, *thickness, *color)
+            },
+            RenderCommand::DrawCircle { camera, center, radius, color } => draw_circle(buffer, *camera, *center, *radius, *color),
+            RenderCommand::DrawCircleOutline { camera, center, radius, thickness, color } => {
+                draw_circle_outline(buffer, *camera, *center, *radius, *thickness, *color)
+            },
+            RenderCommand::DrawLine { camera, from, to, color } => draw_line(buffer, *camera, *from, *to, *color),
+            RenderCommand::DrawLineSmooth { camera, from, to, color } => draw_line_antialiased(buffer, *camera, *from, *to, *color),
+            RenderCommand::DrawStrokeLine { camera, from, to, style } => draw_stroke_line(buffer, *camera, *from, *to, *style),
+            RenderCommand::DrawPolyline { camera, points, color } => draw_polyline(buffer, *camera, points, *color),
+            RenderCommand::DrawStrokePolyline { camera, points, style } => draw_stroke_polyline(buffer, *camera, points, *style),
+            RenderCommand::DrawPolygon { camera, points, color } => draw_polygon(buffer, *camera, points, *color),
+            RenderCommand::DrawTriangle { camera, points, color } => draw_triangle(buffer, *camera, *points, *color),
+            RenderCommand::DrawCurve { camera, curve, color } => draw_bezier_curve(buffer, *camera, *curve, *color),
+            RenderCommand::DrawStrokeCurve { camera, curve, style } => draw_stroke_bezier_curve(buffer, *camera, *curve, *style),
+            RenderCommand::DrawQuadraticCurve { camera, curve, color } => draw_quadratic_bezier(buffer, *camera, *curve, *color),
+            RenderCommand::DrawStrokeQuadraticCurve { camera, curve, style } => {
+                draw_stroke_quadratic_bezier(buffer, *camera, *curve, *style)
+            },
+            RenderCommand::FillPath { camera, path, color, rule } => fill_path(buffer, *camera, path, *color, *rule),
+            RenderCommand::FillPathSmooth { camera, path, color, rule } => fill_path_smooth(buffer, *camera, path, *color, *rule),
+            RenderCommand::StrokePath { camera, path, style } => stroke_path(buffer, *camera, path, *style),
+            RenderCommand::DrawText { camera, position, text, scale, color, background } => {
+                draw_text(buffer, *camera, *position, text, *scale, *color, *background)
+            }
+        }
+    }
+
+    // The same command with its world space geometry moved by the transform. Rectangles that
+    // end up rotated become polygons, circles stay round and grow by the transform's
+    // scale_factor. Screen space widths stay as they are.
+    pub fn transformed(&self, transform: Transform2D) -> RenderCommand {
+        let factor = transform.scale_factor();
+        let width = |width: StrokeWidth| match width {
+            StrokeWidth::World(value) => StrokeWidth::World(value * factor),
+            StrokeWidth::Screen(value) => StrokeWidth::Screen(value)
+        };
+        let style = |style: StrokeStyle| StrokeStyle {
+            width: width(style.width),
+            dash: style.dash.map(|dash| DashPattern { on: dash.on * factor, off: dash.off * factor, offset: dash.offset * factor }),
+            ..style
+        };
+        let points = |points: &[Vector2]| points.iter().map(|point| transform.apply(*point)).collect::<Vec<Vector2>>();
+
+        match self {
+            // The grid is the world itself, it doesn't move with what's drawn on it
+            RenderCommand::ClearBuffer | RenderCommand::DrawUnitGrid { .. } => self.clone(),
+            RenderCommand::DrawRect { camera, rectangle, color } => {
+                if transform.is_axis_aligned() {
+                    RenderCommand::DrawRect { camera: *camera, rectangle: Rectangle::from_points(&transform.apply_rectangle(*rectangle)), color: *color }
+                } else {
+                    RenderCommand::DrawRectRotated { camera: *camera, rectangle: *rectangle, rotation: 0.0, color: *color }.transformed(transform)
+                }
+            },
+            RenderCommand::DrawRectRotated { camera, rectangle, rotation, color } => {
+                match transform.rotation_angle() {
+                    // Still a rectangle, just moved, turned and scaled
+                    Some(angle) => {
+                        let center = transform.apply(Vector2 { x: rectangle.x + rectangle.width / 2.0, y: rectangle.y + rectangle.height / 2.0 });
+                        let width = rectangle.width * factor;
+                        let height = rectangle.height * factor;
+                        RenderCommand::DrawRectRotated {
+                            camera: *camera,
+                            rectangle: Rectangle { x: center.x - width / 2.0, y: center.y - height / 2.0, width, height },
+                            rotation: rotation + angle,
+                            color: *color
+                        }
+                    },
+                    // Skewed into a parallelogram
+                    None => {
+                        let local = Transform2D::rotation_around(*rotation, Vector2 { x: rectangle.x + rectangle.width / 2.0, y: rectangle.y + rectangle.height / 2.0 });
+                        RenderCommand::DrawPolygon { camera: *camera, points: local.then(transform).apply_rectangle(*rectangle).to_vec(), color: *color }
+                    }
+                }
+            },
+            RenderCommand::DrawRectOutline { camera, rectangle, thickness, color } => {
+                let corners = transform.apply_rectangle(*rectangle);
+                if transform.is_axis_aligned() {
+                    RenderCommand::DrawRectOutline { camera: *camera, rectangle: Rectangle::from_points(&corners), thickness: width(*thickness), color: *color }
+                } else {
+                    // Square caps fill in the corner where the outline starts and ends
+                    RenderCommand::DrawStrokePolyline {
+                        camera: *camera,
+                        points: vec![corners[0], corners[1], corners[2], corners[3], corners[0]],
+                        style: StrokeStyle::new(*color, width(*thickness)).with_join(LineJoin::Miter).with_cap(LineCap::Square)
+                    }
+                }
+            },
+            // Brackets can't be rotated, they mark the corners of the rotated rectangle's bounds
+            RenderCommand::DrawRectCorners { camera, rectangle, length, thickness, color } => RenderCommand::DrawRectCorners {
+                camera: *camera,
+                rectangle: Rectangle::from_points(&transform.apply_rectangle(*rectangle)),
+                length: width(*length),
+                thickness: width(*thickness),
+                color: *color
+            },
+            RenderCommand::DrawCircle { camera, center, radius, color } => {
+                RenderCommand::DrawCircle { camera: *camera, center: transform.apply(*center), radius: radius * factor, color: *color }
+            },
+            RenderCommand::DrawCircleOutline { camera, center, radius, thickness, color } => RenderCommand::DrawCircleOutline {
+                camera: *camera,
+                center: transform.apply(*center),
+                radius: radius * factor,
+                thickness: width(*thickness),
+                color: *color
+            },
+            RenderCommand::DrawLine { camera, from, to, color } => {
+                RenderCommand::DrawLine { camera: *camera, from: transform.apply(*from), to: transform.apply(*to), color: *color }
+            },
+            RenderCommand::DrawLineSmooth { camera, from, to, color } => {
+                RenderCommand::DrawLineSmooth { camera: *camera, from: transform.apply(*from), to: transform.apply(*to), color: *color }
+            },
+            RenderCommand::DrawStrokeLine { camera, from, to, style: line_style } => RenderCommand::DrawStrokeLine {
+                camera: *camera,
+                from: transform.apply(*from),
+                to: transform.apply(*to),
+                style: style(*line_style)
+            },
+            RenderCommand::DrawPolyline { camera, points: line_points, color } => {
+                RenderCommand::DrawPolyline { camera: *camera, points: points(line_points), color: *color }
+            },
+            RenderCommand::DrawStrokePolyline { camera, points: line_points, style: line_style } => {
+                RenderCommand::DrawStrokePolyline { camera: *camera, points: points(line_points), style: style(*line_style) }
+            },
+            RenderCommand::DrawPolygon { camera, points: polygon_points, color } => {
+                RenderCommand::DrawPolygon { camera: *camera, points: points(polygon_points), color: *color }
+            },
+            RenderCommand::DrawTriangle { camera, points: triangle_points, color } => RenderCommand::DrawTriangle {
+                camera: *camera,
+                points: triangle_points.map(|point| transform.apply(point)),
+                color: *color
+            },
+            RenderCommand::DrawCurve { camera, curve, color } => {
+                RenderCommand::DrawCurve { camera: *camera, curve: transform.apply_curve(*curve), color: *color }
+            },
+            RenderCommand::DrawStrokeCurve { camera, curve, style: curve_style } => {
+                RenderCommand::DrawStrokeCurve { camera: *camera, curve: transform.apply_curve(*curve), style: style(*curve_style) }
+            },
+            RenderCommand::DrawQuadraticCurve { camera, curve, color } => {
+                RenderCommand::DrawQuadraticCurve { camera: *camera, curve: transform.apply_quadratic(*curve), color: *color }
+            },
+            RenderCommand::DrawStrokeQuadraticCurve { camera, curve, style: curve_style } => RenderCommand::DrawStrokeQuadraticCurve {
+                camera: *camera,
+                curve: transform.apply_quadratic(*curve),
+                style: style(*curve_style)
+            },
+            RenderCommand::FillPath { camera, path, color, rule } => {
+                RenderCommand::FillPath { camera: *camera, path: transform.apply_path(path), color: *color, rule: *rule }
+            },
+            RenderCommand::FillPathSmooth { camera, path, color, rule } => {
+                RenderCommand::FillPathSmooth { camera: *camera, path: transform.apply_path(path), color: *color, rule: *rule }
+            },
+            RenderCommand::StrokePath { camera, path, style: path_style } => {
+                RenderCommand::StrokePath { camera: *camera, path: transform.apply_path(path), style: style(*path_style) }
+            },
+            // Only the position moves, text stays upright and the same size on screen
+            RenderCommand::DrawText { camera, position, text, scale, color, background } => RenderCommand::DrawText {
+                camera: *camera,
+                position: transform.apply(*position),
+                text: text.clone(),
+                scale: *scale,
+                color: *color,
+                background: *background
+            }
+        }
+    }
+
+    pub fn camera(&self) -> Option<Camera> {
+        match self {
+            RenderCommand::ClearBuffer => None,
+            RenderCommand::DrawUnitGrid { camera } |
+            RenderCommand::DrawRect { camera, .. } |
+            RenderCommand::DrawRectRotated { camera, .. } |
+            RenderCommand::DrawRectOutline { camera, .. } |
+            RenderCommand::DrawRectCorners { camera, .. } |
+            RenderCommand::DrawCircle { camera, .. } |
+            RenderCommand::DrawCircleOutline { camera, .. } |
+            RenderCommand::DrawLine { camera, .. } |
+            RenderCommand::DrawLineSmooth { camera, .. } |
+            RenderCommand::DrawStrokeLine { camera, .. } |
+            RenderCommand::DrawPolyline { camera, .. } |
+            RenderCommand::DrawStrokePolyline { camera, .. } |
+            RenderCommand::DrawPolygon { camera, .. } |
+            RenderCommand::DrawTriangle { camera, .. } |
+            RenderCommand::DrawCurve { camera, .. } |
+            RenderCommand::DrawStrokeCurve { camera, .. } |
+            RenderCommand::DrawQuadraticCurve { camera, .. } |
+            RenderCommand::DrawStrokeQuadraticCurve { camera, .. } |
+            RenderCommand::FillPath { camera, .. } |
+            RenderCommand::FillPathSmooth { camera, .. } |
+            RenderCommand::StrokePath { camera, .. } |
+            RenderCommand::DrawText { camera, .. } => Some(*camera)
+        }
+    }
+
+    // Writes the command into the document in screen pixels, the same shapes execute draws.
+    // Curves and paths stay curves, so they're smooth at any size.
+    pub fn write_svg(&self, svg: &mut SvgDocument) {
+        let camera = match self.camera() {
+            Some(value) => value,
+            None => {
+                // Everything under it is cleared to black, see clear_buffer
+                svg.rectangle(0.0, 0.0, svg.width, svg.height, Color::BLACK);
+                return;
+            }
+        };
+        let screen = camera.screen_transform();
+        let hairline = |color: Color| SvgStroke::new(1.0, color);
+        let points = |points: &[Vector2]| points.iter().map(|point| screen.apply(*point)).collect::<Vec<Vector2>>();
+        // A polygon keeps rectangles right when the camera is turned
+        let rectangle = |svg: &mut SvgDocument, corners: [Vector2; 4], color: Color| {
+            if camera.rotation == 0.0 {
+                let bounds = Rectangle::from_points(&corners.map(|corner| screen.apply(corner)));
+                svg.rectangle(bounds.x, bounds.y, bounds.width, bounds.height, color);
+            } else {
+                svg.polygon(&points(&corners), color);
+            }
+        };
+
+        match self {
+            RenderCommand::ClearBuffer => {},
+            RenderCommand::DrawUnitGrid { .. } => {
+                for (from, to, color) in unit_grid_lines(camera) {
+                    svg.line(screen.apply(from), screen.apply(to), hairline(color));
+                }
+            },
+            RenderCommand::DrawRect { rectangle: shape, color, .. } => rectangle(svg, shape.corners(), *color),
+            RenderCommand::DrawRectRotated { rectangle: shape, rotation, color, .. } => {
+                let center = Vector2 { x: shape.x + shape.width / 2.0, y: shape.y + shape.height / 2.0 };
+                svg.polygon(&points(&Transform2D::rotation_around(*rotation, center).apply_rectangle(*shape)), *color);
+            },
+            // The border grows inwards like draw_rectangle_outline, a ring between the rectangle
+            // and a smaller one
+            RenderCommand::DrawRectOutline { rectangle: shape, thickness, color, .. } => {
+                let thickness = thickness.to_world(camera).min(shape.width / 2.0).min(shape.height / 2.0);
+                let mut ring = Path::new();
+                for corners in [shape.corners(), shape.expand(-thickness).corners()] {
+                    ring.move_to(corners[0]).line_to(corners[1]).line_to(corners[2]).line_to(corners[3]).close();
+                }
+                svg.fill_path(&screen.apply_path(&ring), *color, FillRule::EvenOdd);
+            },
+            RenderCommand::DrawRectCorners { rectangle: shape, length, thickness, color, .. } => {
+                let half_size = Vector2 { x: shape.width / 2.0, y: shape.height / 2.0 };
+                let thickness = thickness.to_world(camera);
+                let length = length.to_world(camera).max(thickness);
+                let thickness = Vector2 { x: thickness.min(half_size.x), y: thickness.min(half_size.y) };
+                let length = Vector2 { x: length.min(half_size.x), y: length.min(half_size.y) };
+
+                // Both arms of each bracket, measured inwards from its corner
+                for (corner, inwards) in shape.corners().into_iter().zip([(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)]) {
+                    let arm = |x: f32, y: f32| corner + Vector2 { x: x * inwards.0, y: y * inwards.1 };
+                    rectangle(svg, [arm(0.0, 0.0), arm(length.x, 0.0), arm(length.x, thickness.y), arm(0.0, thickness.y)], *color);
+                    rectangle(svg, [arm(0.0, thickness.y), arm(thickness.x, thickness.y), arm(thickness.x, length.y), arm(0.0, length.y)], *color);
+                }
+            },
+            RenderCommand::DrawCircle { center, radius, color, .. } => svg.circle(screen.apply(*center), radius * camera.y_scale, *color),
+            // Grows inwards from the radius too
+            RenderCommand::DrawCircleOutline { center, radius, thickness, color, .. } => {
+                let radius = radius * camera.y_scale;
+                let thickness = thickness.to_pixels(camera).min(radius);
+                svg.circle_outline(screen.apply(*center), radius - thickness / 2.0, SvgStroke::new(thickness, *color));
+            },
+            RenderCommand::DrawLine { from, to, color, .. } | RenderCommand::DrawLineSmooth { from, to, color, .. } => {
+                svg.line(screen.apply(*from), screen.apply(*to), hairline(*color))
+            },
+            RenderCommand::DrawStrokeLine { from, to, style, .. } => svg.line(screen.apply(*from), screen.apply(*to), svg_stroke(*style, camera)),
+            RenderCommand::DrawPolyline { points: line_points, color, .. } => svg.polyline(&points(line_points), hairline(*color)),
+            RenderCommand::DrawStrokePolyline { points: line_points, style, .. } => svg.polyline(&points(line_points), svg_stroke(*style, camera)),
+            RenderCommand::DrawPolygon { points: polygon_points, color, .. } => svg.polygon(&points(polygon_points), *color),
+            RenderCommand::DrawTriangle { points: triangle_points, color, .. } => svg.polygon(&points(triangle_points), *color),
+            RenderCommand::DrawCurve { curve, color, .. } => {
+                let curve = screen.apply_curve(*curve);
+                svg.cubic_bezier(curve.p0, curve.p1, curve.p2, curve.p3, hairline(*color));
+            },
+            RenderCommand::DrawStrokeCurve { curve, style, .. } => {
+                let curve = screen.apply_curve(*curve);
+                svg.cubic_bezier(curve.p0, curve.p1, curve.p2, curve.p3, svg_stroke(*style, camera));
+            },
+            RenderCommand::DrawQuadraticCurve { curve, color, .. } => {
+                let curve = screen.apply_quadratic(*curve);
+                svg.quadratic_bezier(curve.p0, curve.p1, curve.p2, hairline(*color));
+            },
+            RenderCommand::DrawStrokeQuadraticCurve { curve, style, .. } => {
+                let curve = screen.apply_quadratic(*curve);
+                svg.quadratic_bezier(curve.p0, curve.p1, curve.p2, svg_stroke(*style, camera));
+            },
+            RenderCommand::FillPath { path, color, rule, .. } | RenderCommand::FillPathSmooth { path, color, rule, .. } => {
+                svg.fill_path(&screen.apply_path(path), *color, *rule)
+            },
+            RenderCommand::StrokePath { path, style, .. } => svg.stroke_path(&screen.apply_path(path), svg_stroke(*style, camera)),
+            // Upright and the same size on screen whatever the camera, like draw_text
+            RenderCommand::DrawText { position, text, scale, color, background, .. } => {
+                let corner = screen.apply(*position);
+                let scale = (*scale).max(1);
+                let (width, height) = text_size(text, scale);
+                if background.alpha() > 0.0 {
+                    let padding = (TEXT_PADDING * scale) as f32;
+                    svg.rectangle(corner.x - padding, corner.y - padding, width as f32 + padding * 2.0, height as f32 + padding * 2.0, *background);
+                }
+                svg.text(corner, text, width as f32, height as f32, *color);
+            }
+        }
+    }
+
+    // Pixels the command can touch, None if it covers the whole buffer
+    pub fn screen_bounds(&self) -> Option<ClipRect> {
+        match self {
+            RenderCommand::ClearBuffer | RenderCommand::DrawUnitGrid { .. } => None,
+            RenderCommand::DrawRect { camera, rectangle, .. } |
+            RenderCommand::DrawRectOutline { camera, rectangle, .. } |
+            RenderCommand::DrawRectCorners { camera, rectangle, .. } => {
+                Some(points_bounds(*camera, &rectangle.corners(), 0.0))
+            },
+            RenderCommand::DrawRectRotated { camera, rectangle, rotation, .. } => {
+                let center = Vector2 { x: rectangle.x + rectangle.width / 2.0, y: rectangle.y + rectangle.height / 2.0 };
+                Some(points_bounds(*camera, &Transform2D::rotation_around(*rotation, center).apply_rectangle(*rectangle), 0.0))
+            },
+            RenderCommand::DrawCircle { camera, center, radius, .. } |
+            RenderCommand::DrawCircleOutline { camera, center, radius, .. } => {
+                let square = Rectangle { x: center.x - radius, y: center.y - radius, width: radius * 2.0, height: radius * 2.0 };
+                Some(points_bounds(*camera, &square.corners(), 0.0))
+            },
+            RenderCommand::DrawLine { camera, from, to, .. } | RenderCommand::DrawLineSmooth { camera, from, to, .. } => {
+                Some(points_bounds(*camera, &[*from, *to], 0.0))
+            },
+            RenderCommand::DrawStrokeLine { camera, from, to, style } => {
+                Some(points_bounds(*camera, &[*from, *to], stroke_padding(*camera, *style)))
+            },
+            RenderCommand::DrawPolyline { camera, points, .. } |
+            RenderCommand::DrawPolygon { camera, points, .. } => Some(points_bounds(*camera, points, 0.0)),
+            RenderCommand::DrawStrokePolyline { camera, points, style } => {
+                Some(points_bounds(*camera, points, stroke_padding(*camera, *style)))
+            },
+            RenderCommand::DrawTriangle { camera, points, .. } => Some(points_bounds(*camera, points, 0.0)),
+            // A bezier curve always stays inside the hull of its control points
+            RenderCommand::DrawCurve { camera, curve, .. } => {
+                Some(points_bounds(*camera, &[curve.p0, curve.p1, curve.p2, curve.p3], 0.0))
+            },
+            RenderCommand::DrawStrokeCurve { camera, curve, style } => {
+                Some(points_bounds(*camera, &[curve.p0, curve.p1, curve.p2, curve.p3], stroke_padding(*camera, *style)))
+            },
+            RenderCommand::DrawQuadraticCurve { camera, curve, .. } => Some(points_bounds(*camera, &[curve.p0, curve.p1, curve.p2], 0.0)),
+            RenderCommand::DrawStrokeQuadraticCurve { camera, curve, style } => {
+                Some(points_bounds(*camera, &[curve.p0, curve.p1, curve.p2], stroke_padding(*camera, *style)))
+            },
+            RenderCommand::FillPath { camera, path, .. } | RenderCommand::FillPathSmooth { camera, path, .. } => {
+                Some(points_bounds(*camera, &path.control_points(), 0.0))
+            },
+            RenderCommand::StrokePath { camera, path, style } => {
+                Some(points_bounds(*camera, &path.control_points(), stroke_padding(*camera, *style)))
+            },
+            RenderCommand::DrawText { camera, position, text, scale, .. } => Some(text_rect(*camera, *position, text, (*scale).max(1)))
+        }
+    }
+}
+
+// The style's widths and dashes in screen pixels, which is what the SVG is written in
+fn svg_stroke(style: StrokeStyle, camera: Camera) -> SvgStroke {
+    let scale = camera.y_scale;
+    SvgStroke {
+        width: style.width.to_pixels(camera),
+        color: style.color,
+        join: style.join,
+        cap: style.cap,
+        // Dash lengths are in world units
+        dash: style.dash.map(|dash| DashPattern { on: dash.on * scale, off: dash.off * scale, offset: dash.offset * scale })
+    }
+}
+
+// How far a stroke can reach past its points. Square caps reach out diagonally and miter
+// joins can get up to MITER_LIMIT half widths long.
+fn stroke_padding(camera: Camera, style: StrokeStyle) -> f32 {
+    let width = style.width.to_pixels(camera);
+    match style.join {
+        LineJoin::Miter => width * MITER_LIMIT / 2.0,
+        _ => width * 0.75
+    }
+}
+
+// Screen space bounding box of points, grown by padding pixels on every side
+fn points_bounds(camera: Camera, points: &[Vector2], padding: f32) -> ClipRect {
+    let mut bounds: Option<(Vector2, Vector2)> = None;
+    for point in points {
+        let screen = world_space_to_screen_space_f32(camera, *point);
+        bounds = match bounds {
+            Some((low, high)) => Some((
+                Vector2 { x: low.x.min(screen.x), y: low.y.min(screen.y) },
+                Vector2 { x: high.x.max(screen.x), y: high.y.max(screen.y) })),
+            None => Some((screen, screen))
+        };
+    }
+
+    match bounds {
+        Some((low, high)) => {
+            let padding = padding + BOUNDS_PADDING;
+            ClipRect::new(
+                (low.x - padding).floor() as i32,
+                (low.y - padding).floor() as i32,
+                (high.x + padding).ceil() as i32,
+                (high.y + padding).ceil() as i32)
+        },
+        None => ClipRect::default()
+    }
+}
+
+// A command together with the render state it was submitted with, so commands can be
+// reordered without losing their clip rect or blend mode
+#[derive(Clone, PartialEq)]
+struct SubmittedCommand {
+    layer: Layer,
+    depth: f32,
+    clip: Option<ClipRect>,
+    blend_mode: BlendMode,
+    command: RenderCommand
+}
+
+impl SubmittedCommand {
+    fn screen_bounds(&self, buffer_bounds: ClipRect) -> ClipRect {
+        let bounds = match self.command.screen_bounds() {
+            Some(value) => value.intersection(buffer_bounds),
+            None => buffer_bounds
+        };
+
+        match self.clip {
+            Some(clip) => bounds.intersection(clip),
+            None => bounds
+        }
+    }
+}
+
+// Everything the game wants drawn this frame. Filled by game code without touching the
+// pixel buffer and executed by the engine afterwards.
+#[derive(Clone, Default)]
+pub struct RenderCommands {
+    commands: Vec<SubmittedCommand>,
+    clip_stack: Vec<ClipRect>,
+    // Each entry already includes the ones below it
+    transform_stack: Vec<Transform2D>,
+    blend_mode: BlendMode
+}
+
+impl RenderCommands {
+    pub fn new() -> Self {
+        RenderCommands {
+            commands: Vec::new(),
+            clip_stack: Vec::new(),
+            transform_stack: Vec::new(),
+            blend_mode: BlendMode::Alpha
+        }
+    }
+
+    pub fn push(&mut self, layer: Layer, command: RenderCommand) {
+        self.push_with_depth(layer, 0.0, command);
+    }
+
+    // Within a layer, commands with a greater depth are further away and drawn first so
+    // translucent primitives composite back to front. Equal depths keep submission order.
+    pub fn push_with_depth(&mut self, layer: Layer, depth: f32, command: RenderCommand) {
+        let command = match self.transform_stack.last() {
+            Some(transform) => command.transformed(*transform),
+            None => command
+        };
+        self.commands.push(SubmittedCommand {
+            layer,
+            depth,
+            clip: self.clip_stack.last().copied(),
+            blend_mode: self.blend_mode,
+            command
+        });
+    }
+
+    // Clips commands pushed until the matching pop_clip_rect, nested rects are intersected
+    pub fn push_clip_rect(&mut self, rect: ClipRect) {
+        let clip = match self.clip_stack.last() {
+            Some(value) => value.intersection(rect),
+            None => rect
+        };
+        self.clip_stack.push(clip);
+    }
+
+    pub fn pop_clip_rect(&mut self) {
+        if self.clip_stack.pop().is_none() {
+            eprintln!("pop_clip_rect called without a matching push_clip_rect");
+        }
+    }
+
+    // Moves the world space geometry of commands pushed until the matching pop_transform.
+    // Nested transforms are applied first, then the ones they're inside of, so children can be
+    // placed relative to their parent.
+    pub fn push_transform(&mut self, transform: Transform2D) {
+        let combined = match self.transform_stack.last() {
+            Some(parent) => transform.then(*parent),
+            None => transform
+        };
+        self.transform_stack.push(combined);
+    }
+
+    pub fn pop_transform(&mut self) {
+        if self.transform_stack.pop().is_none() {
+            eprintln!("pop_transform called without a matching push_transform");
+        }
+    }
+
+    // Blend mode used by commands pushed from now on
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.clip_stack.clear();
+        self.transform_stack.clear();
+        self.blend_mode = BlendMode::Alpha;
+    }
+
+    // The frame as an SVG document of width x height pixels, commands are written in the order
+    // they would be executed. Clip rects become clip paths, the blend mode isn't kept and every
+    // command is blended normally.
+    pub fn to_svg(&self, width: u32, height: u32) -> SvgDocument {
+        let mut svg = SvgDocument::new(width as f32, height as f32);
+        let mut clip: Option<ClipRect> = None;
+        for submission in self.sorted_submissions() {
+            if submission.clip != clip {
+                if clip.is_some() {
+                    svg.end_clip();
+                }
+                if let Some(value) = submission.clip {
+                    svg.begin_clip(value);
+                }
+                clip = submission.clip;
+            }
+            submission.command.write_svg(&mut svg);
+        }
+        if clip.is_some() {
+            svg.end_clip();
+        }
+        svg
+    }
+
+    // Commands in the order they will be executed
+    pub fn sorted(&self) -> Vec<&RenderCommand> {
+        self.sorted_submissions().into_iter().map(|submission| &submission.command).collect()
+    }
+
+    fn sorted_submissions(&self) -> Vec<&SubmittedCommand> {
+        let mut submissions: Vec<&SubmittedCommand> = self.commands.iter().collect();
+        // sort_by is stable, so commands that compare equal stay in submission order
+        submissions.sort_by(|a, b| {
+            a.layer.cmp(&b.layer).then_with(|| b.depth.partial_cmp(&a.depth).unwrap_or(Ordering::Equal))
+        });
+        submissions
+    }
+
+    /// Draws every command into the buffer, one profiler zone per layer.
+    /// The buffer's clip stack and blend mode are restored afterwards.
+    ///
+    /// # Safety
+    /// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+    pub unsafe fn execute(&self, buffer: &mut OffscreenBuffer, profiler: &mut FrameProfiler) {
+        let bounds = ClipRect::new(0, 0, buffer.width as i32, buffer.height as i32);
+        self.execute_regions(buffer, profiler, &[bounds]);
+    }
+
+    /// Same as execute but only pixels inside the regions are redrawn, commands that
+    /// don't touch any region are skipped. The regions must not overlap.
+    ///
+    /// # Safety
+    /// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+    pub unsafe fn execute_regions(&self, buffer: &mut OffscreenBuffer, profiler: &mut FrameProfiler, regions: &[ClipRect]) {
+        if regions.is_empty() {
+            return;
+        }
+
+        let blend_mode = buffer.blend_mode;
+        let buffer_bounds = ClipRect::new(0, 0, buffer.width as i32, buffer.height as i32);
+        let submissions = self.sorted_submissions();
+
+        let mut i = 0;
+        while i < submissions.len() {
+            let layer = submissions[i].layer;
+            let zone_start = Instant::now();
+
+            while i < submissions.len() && submissions[i].layer == layer {
+                let submission = submissions[i];
+                let submission_bounds = submission.screen_bounds(buffer_bounds);
+                buffer.blend_mode = submission.blend_mode;
+
+                for region in regions {
+                    if !region.overlaps(submission_bounds) {
+                        continue;
+                    }
+
+                    buffer.push_clip_rect(*region);
+                    match submission.clip {
+                        Some(clip) => {
+                            buffer.push_clip_rect(clip);
+                            submission.command.execute(buffer);
+                            buffer.pop_clip_rect();
+                        },
+                        None => submission.command.execute(buffer)
+                    }
+                    buffer.pop_clip_rect();
+                }
+
+                i += 1;
+            }
+
+            profiler.record(layer.name(), zone_start);
+        }
+
+        buffer.blend_mode = blend_mode;
+    }
+}
+
+// Finds the parts of the buffer that changed since the previous frame by comparing
+// render commands, so static frames don't have to be redrawn or presented
+#[derive(Clone, Default)]
+pub struct DirtyRegions {
+    previous: Vec<SubmittedCommand>,
+    // Memory address and size of the buffer last frame, a new allocation has to be redrawn fully
+    previous_buffer: (usize, u32, u32),
+    regions: Vec<ClipRect>,
+    full_redraw: bool
+}
+
+impl DirtyRegions {
+    // Makes the next update dirty the whole buffer, e.g. after its memory was replaced
+    pub fn invalidate(&mut self) {
+        self.full_redraw = true;
+    }
+
+    // Rects that need to be redrawn and presented this frame, empty if nothing changed
+    pub fn regions(&self) -> &[ClipRect] {
+        &self.regions
+    }
+
+    pub fn update(&mut self, commands: &RenderCommands, buffer: &OffscreenBuffer) {
+        let buffer_bounds = ClipRect::new(0, 0, buffer.width as i32, buffer.height as i32);
+        let buffer_id = (buffer.memory as usize, buffer.width, buffer.height);
+        let current: Vec<SubmittedCommand> = commands.sorted_submissions().into_iter().cloned().collect();
+        self.regions.clear();
+
+        if self.full_redraw || self.previous_buffer != buffer_id {
+            self.previous = current;
+            self.previous_buffer = buffer_id;
+            self.regions.push(buffer_bounds);
+            self.full_redraw = false;
+            return;
+        }
+
+        // Commands are compared in execution order. Any difference dirties where the old
+        // command was and where the new one is.
+        let mut rects: Vec<ClipRect> = Vec::new();
+        let mut i = 0;
+        while i < current.len().max(self.previous.len()) {
+            let old = self.previous.get(i);
+            let new = current.get(i);
+            if old != new {
+                if let Some(value) = old {
+                    rects.push(value.screen_bounds(buffer_bounds));
+                }
+                if let Some(value) = new {
+                    rects.push(value.screen_bounds(buffer_bounds));
+                }
+            }
+            i += 1;
+        }
+
+        self.previous = current;
+        self.regions = merge_rects(rects);
+    }
+}
+
+// Merges rects until none overlap, so no pixel is drawn twice in one frame
+fn merge_rects(rects: Vec<ClipRect>) -> Vec<ClipRect> {
+    let mut merged: Vec<ClipRect> = Vec::new();
+
+    for rect in rects {
+        if rect.is_empty() {
+            continue;
+        }
+
+        let mut rect = rect;
+        let mut i = 0;
+        while i < merged.len() {
+            if merged[i].overlaps(rect) {
+                rect = rect.union(merged.swap_remove(i));
+                // The grown rect may overlap rects that were already checked
+                i = 0;
+            } else {
+                i += 1;
+            }
+        }
+        merged.push(rect);
+    }
+
+    if merged.len() > MAX_DIRTY_RECTS {
+        let union = merged.iter().fold(ClipRect::default(), |union, rect| union.union(*rect));
+        return vec![union];
+    }
+
+    merged
+}
@@ -0,0 +1,123 @@
+use crate::{BezierCurve, Camera, GameState, Vector2};
+use std::fs;
+use std::io;
+
+pub static SCENE_PATH: &str = "oxide.scene";
+
+// The parts of a game state worth keeping between runs, written as lines of text like the
+// bindings config:
+//   camera = x y width height rotation
+//   curve = x y, x y, x y, x y
+// The camera's y_scale follows the window size, so it isn't saved. Scenes saved before cameras
+// could rotate leave the rotation out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scene {
+    pub camera: Camera,
+    pub curves: Vec<BezierCurve>
+}
+
+impl Scene {
+    pub fn from_game_state(game_state: &GameState) -> Self {
+        Scene {
+            camera: game_state.camera,
+            curves: game_state.curves.iter().flatten().copied().collect()
+        }
+    }
+
+    // Replaces the camera position and every curve, curves past the free slots are dropped
+    pub fn apply(&self, game_state: &mut GameState) {
+        game_state.camera.x = self.camera.x;
+        game_state.camera.y = self.camera.y;
+        game_state.camera.width = self.camera.width;
+        game_state.camera.height = self.camera.height;
+        game_state.camera.rotation = self.camera.rotation;
+
+        let mut curves = self.curves.iter();
+        for slot in game_state.curves.iter_mut() {
+            *slot = curves.next().copied();
+        }
+        game_state.selected_curve_index = None;
+        game_state.selection.clear();
+        game_state.history.clear();
+    }
+
+    pub fn to_config_string(&self) -> String {
+        let camera = self.camera;
+        let mut text = format!("camera = {} {} {} {} {}\n", camera.x, camera.y, camera.width, camera.height, camera.rotation);
+        for curve in &self.curves {
+            let points: Vec<String> = [curve.p0, curve.p1, curve.p2, curve.p3].iter().map(|point| vector_to_string(*point)).collect();
+            text.push_str(&format!("curve = {}\n", points.join(", ")));
+        }
+        text
+    }
+
+    pub fn from_config_string(text: &str) -> io::Result<Self> {
+        let mut scene = Scene {
+            camera: Camera::new(0.0, 0.0, 16.0, 9.0),
+            curves: Vec::new()
+        };
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parsed = match line.split_once('=') {
+                Some((name, value)) => match name.trim() {
+                    "camera" => parse_numbers(value).and_then(|numbers| match numbers[..] {
+                        [x, y, width, height] => {
+                            scene.camera = Camera::new(x, y, width, height);
+                            Some(())
+                        },
+                        [x, y, width, height, rotation] => {
+                            scene.camera = Camera { rotation, ..Camera::new(x, y, width, height) };
+                            Some(())
+                        },
+                        _ => None
+                    }),
+                    "curve" => parse_curve(value).map(|curve| scene.curves.push(curve)),
+                    _ => None
+                },
+                None => None
+            };
+
+            if parsed.is_none() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid scene line {}: {}", line_index + 1, line)));
+            }
+        }
+
+        Ok(scene)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_config_string())
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        Scene::from_config_string(&fs::read_to_string(path)?)
+    }
+}
+
+fn vector_to_string(vector: Vector2) -> String {
+    format!("{} {}", vector.x, vector.y)
+}
+
+fn parse_numbers(text: &str) -> Option<Vec<f32>> {
+    text.split_whitespace().map(|number| number.parse::<f32>().ok()).collect()
+}
+
+fn parse_curve(text: &str) -> Option<BezierCurve> {
+    let mut points = Vec::with_capacity(4);
+    for point in text.split(',') {
+        match parse_numbers(point)?[..] {
+            [x, y] => points.push(Vector2 { x, y }),
+            _ => return None
+        }
+    }
+
+    match points[..] {
+        [p0, p1, p2, p3] => Some(BezierCurve::new(p0, p1, p2, p3)),
+        _ => None
+    }
+}
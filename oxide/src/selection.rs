@@ -0,0 +1,81 @@
+use crate::{BezierCurve, Rectangle, Vector2};
+
+// Something in the scene that can be selected, curves by slot and control points by slot and
+// number like BezierCurve::point
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selectable {
+    Curve(u32),
+    ControlPoint(u32, u32)
+}
+
+// What's selected, in the order it was added
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub items: Vec<Selectable>
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn is_selected(&self, item: Selectable) -> bool {
+        self.items.contains(&item)
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    // Replaces the selection with the item, or adds it to what's already selected when additive
+    pub fn select(&mut self, item: Selectable, additive: bool) {
+        if !additive {
+            self.items.clear();
+        }
+        if !self.is_selected(item) {
+            self.items.push(item);
+        }
+    }
+
+    // Everything whose bounding box touches the area, curves by their box and control points by
+    // their position
+    pub fn select_in_rectangle(&mut self, curves: &[Option<BezierCurve>], area: Rectangle, additive: bool) {
+        if !additive {
+            self.items.clear();
+        }
+
+        for (slot, curve) in (0..).zip(curves.iter()) {
+            let curve = match curve {
+                Some(value) => value,
+                None => continue
+            };
+
+            if curve.get_bounding_box().intersects(area) {
+                self.select(Selectable::Curve(slot), true);
+            }
+            for point in 0..4 {
+                if area.contains_point(curve.point(point)) {
+                    self.select(Selectable::ControlPoint(slot, point), true);
+                }
+            }
+        }
+    }
+}
+
+// Rectangle being dragged out to select everything in it, in world coordinates
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Marquee {
+    pub start: Vector2,
+    pub end: Vector2
+}
+
+impl Marquee {
+    pub fn new(start: Vector2) -> Self {
+        Marquee { start, end: start }
+    }
+
+    // Dragging up or left from the start works the same as down and right
+    pub fn rectangle(&self) -> Rectangle {
+        Rectangle::from_points(&[self.start, self.end])
+    }
+}
@@ -0,0 +1,93 @@
+use crate::math::smoothstep;
+use crate::Camera;
+
+// Trauma based camera shake. Hits add trauma, which wears off over time, and the shake grows
+// with the square of it so small hits barely move the view while big ones throw it around.
+// The offset follows smooth noise instead of jumping to a new random spot every frame.
+// The shake is only applied to a copy of the camera for drawing, the camera in GameState stays
+// where it is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraShake {
+    // From 0.0, no shake, to 1.0
+    pub trauma: f32,
+    // Trauma lost every millisecond
+    pub decay: f32,
+    // Furthest the view moves at full trauma, as a fraction of the camera's height so the shake
+    // looks the same at any zoom
+    pub max_offset: f32,
+    // Radians the view turns at full trauma, 0.0 only moves it
+    pub max_rotation: f32,
+    // Times per millisecond the noise picks a new direction
+    pub frequency: f32,
+    // Gives each shake a different pattern, the same seed always shakes the same way
+    pub seed: u32,
+    time: f32
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        CameraShake {
+            trauma: 0.0,
+            decay: 0.001,
+            max_offset: 0.05,
+            max_rotation: 0.05,
+            frequency: 0.02,
+            seed: 0,
+            time: 0.0
+        }
+    }
+}
+
+impl CameraShake {
+    // Trauma from a hit, the total never goes past 1.0
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn is_shaking(&self) -> bool {
+        self.trauma > 0.0
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if !self.is_shaking() {
+            return;
+        }
+        self.trauma = (self.trauma - self.decay * delta_time).max(0.0);
+        self.time += delta_time * self.frequency;
+    }
+
+    // The camera moved and turned by the current shake, the same camera while there's no trauma
+    pub fn apply(&self, camera: Camera) -> Camera {
+        if !self.is_shaking() {
+            return camera;
+        }
+
+        let shake = self.trauma * self.trauma;
+        let offset = self.max_offset * camera.height * shake;
+        Camera {
+            x: camera.x + offset * noise(self.seed, 0, self.time),
+            y: camera.y + offset * noise(self.seed, 1, self.time),
+            rotation: camera.rotation + self.max_rotation * shake * noise(self.seed, 2, self.time),
+            ..camera
+        }
+    }
+}
+
+// Smooth noise from -1.0 to 1.0, with random values at whole numbers eased between. Each channel
+// is its own curve.
+fn noise(seed: u32, channel: u32, time: f32) -> f32 {
+    let index = time.floor();
+    let a = lattice(seed, channel, index as i64);
+    let b = lattice(seed, channel, index as i64 + 1);
+    a + (b - a) * smoothstep(0.0, 1.0, time - index)
+}
+
+// Random value from -1.0 to 1.0 for a whole number, mixed with the steps of SplitMix64
+fn lattice(seed: u32, channel: u32, index: i64) -> f32 {
+    let mut x = (index as u64) ^ ((seed as u64) << 32) ^ ((channel as u64) << 48);
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+    (x >> 40) as f32 / (1u32 << 23) as f32 - 1.0
+}
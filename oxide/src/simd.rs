@@ -0,0 +1,120 @@
+// Row operations that write several pixels per iteration. x86_64 always has SSE2, other
+// targets use the scalar versions.
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+// Sets every pixel to the same value
+pub fn fill_pixels(pixels: &mut [u32], pixel: u32) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        if is_x86_feature_detected!("avx") {
+            fill_pixels_avx(pixels, pixel);
+        } else {
+            fill_pixels_sse2(pixels, pixel);
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fill_pixels_scalar(pixels, pixel);
+}
+
+// Alpha blends one premultiplied color over every pixel, gives the same result as
+// blend_pixel with BlendMode::Alpha
+pub fn blend_pixels_alpha(pixels: &mut [u32], color: u32) {
+    let alpha = color >> 24;
+    if alpha == 0xFF {
+        fill_pixels(pixels, color);
+        return;
+    }
+    if color == 0 {
+        return;
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        blend_pixels_alpha_sse2(pixels, color);
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    blend_pixels_alpha_scalar(pixels, color);
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn fill_pixels_scalar(pixels: &mut [u32], pixel: u32) {
+    for value in pixels.iter_mut() {
+        *value = pixel;
+    }
+}
+
+fn blend_pixels_alpha_scalar(pixels: &mut [u32], color: u32) {
+    let inverse_alpha = 255 - (color >> 24);
+
+    for value in pixels.iter_mut() {
+        let channel = |shift: u32| {
+            let blended = ((color >> shift) & 0xFF) + div_255(((*value >> shift) & 0xFF) * inverse_alpha);
+            blended.min(255) << shift
+        };
+        *value = channel(24) | channel(16) | channel(8) | channel(0);
+    }
+}
+
+// x / 255 rounded to nearest, exact for every x up to 255 * 255
+fn div_255(x: u32) -> u32 {
+    let x = x + 128;
+    (x + (x >> 8)) >> 8
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn fill_pixels_sse2(pixels: &mut [u32], pixel: u32) {
+    let value = _mm_set1_epi32(pixel as i32);
+    let mut chunks = pixels.chunks_exact_mut(4);
+    for chunk in &mut chunks {
+        _mm_storeu_si128(chunk.as_mut_ptr() as *mut __m128i, value);
+    }
+    for remainder in chunks.into_remainder() {
+        *remainder = pixel;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn fill_pixels_avx(pixels: &mut [u32], pixel: u32) {
+    let value = _mm256_set1_epi32(pixel as i32);
+    let mut chunks = pixels.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        _mm256_storeu_si256(chunk.as_mut_ptr() as *mut __m256i, value);
+    }
+    for remainder in chunks.into_remainder() {
+        *remainder = pixel;
+    }
+}
+
+// Four pixels at a time, channels are widened to 16 bits so dst * (255 - alpha) fits
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn blend_pixels_alpha_sse2(pixels: &mut [u32], color: u32) {
+    let zero = _mm_setzero_si128();
+    let source = _mm_set1_epi32(color as i32);
+    let inverse_alpha = _mm_set1_epi16((255 - (color >> 24)) as i16);
+    let rounding = _mm_set1_epi16(128);
+
+    let blend_half = |destination: __m128i| {
+        let scaled = _mm_add_epi16(_mm_mullo_epi16(destination, inverse_alpha), rounding);
+        _mm_srli_epi16(_mm_add_epi16(scaled, _mm_srli_epi16(scaled, 8)), 8)
+    };
+
+    let mut chunks = pixels.chunks_exact_mut(4);
+    for chunk in &mut chunks {
+        let pointer = chunk.as_mut_ptr() as *mut __m128i;
+        let destination = _mm_loadu_si128(pointer);
+
+        let low = blend_half(_mm_unpacklo_epi8(destination, zero));
+        let high = blend_half(_mm_unpackhi_epi8(destination, zero));
+        let blended = _mm_adds_epu8(_mm_packus_epi16(low, high), source);
+
+        _mm_storeu_si128(pointer, blended);
+    }
+    blend_pixels_alpha_scalar(chunks.into_remainder(), color);
+}
@@ -0,0 +1,53 @@
+use crate::Vector2;
+
+// Fewer points that stay within tolerance of the original polyline, with Ramer-Douglas-Peucker.
+// The first and last points are always kept. Keeps recorded strokes and dense samples cheap to
+// fit curves to or draw.
+pub fn simplify_polyline(points: &[Vector2], tolerance: f32) -> Vec<Vector2> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    // Ranges still to be checked, a stack instead of recursion so long strokes can't overflow
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let mut furthest = first;
+        let mut furthest_distance = 0.0;
+        let mut i = first + 1;
+        while i < last {
+            let distance = distance_to_segment(points[i], points[first], points[last]);
+            if distance > furthest_distance {
+                furthest = i;
+                furthest_distance = distance;
+            }
+            i += 1;
+        }
+
+        // Everything between is close enough to a straight line from first to last
+        if furthest_distance <= tolerance {
+            continue;
+        }
+        keep[furthest] = true;
+        ranges.push((first, furthest));
+        ranges.push((furthest, last));
+    }
+
+    points.iter().zip(keep).filter(|(_, keep)| *keep).map(|(point, _)| *point).collect()
+}
+
+fn distance_to_segment(point: Vector2, a: Vector2, b: Vector2) -> f32 {
+    let segment = b - a;
+    let length_squared = segment.x * segment.x + segment.y * segment.y;
+    let t = if length_squared > 0.0 {
+        (((point.x - a.x) * segment.x + (point.y - a.y) * segment.y) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let closest = a + segment * t;
+    ((point.x - closest.x) * (point.x - closest.x) + (point.y - closest.y) * (point.y - closest.y)).sqrt()
+}
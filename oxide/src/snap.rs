@@ -0,0 +1,115 @@
+use crate::{grid_spacing, BezierCurve, Camera, Rectangle, Vector2};
+
+// How closely curves are followed when looking for where they cross, in screen pixels
+static INTERSECTION_TOLERANCE_PIXELS: f32 = 0.25;
+
+// What a snapped position landed on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapTarget {
+    // Crossing of the grid lines drawn at the camera's zoom
+    Grid,
+    // Start or end of a curve
+    Endpoint,
+    // Where two curves cross
+    Intersection
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Snap {
+    pub position: Vector2,
+    pub target: SnapTarget
+}
+
+// Which targets positions are pulled onto while editing, and from how far. Points on curves win
+// over the grid, so joining curves up isn't thrown off by a grid crossing next to the point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Snapping {
+    // Screen pixels, so snapping feels the same at any zoom
+    pub radius: f32,
+    pub grid: bool,
+    pub endpoints: bool,
+    pub intersections: bool
+}
+
+impl Default for Snapping {
+    fn default() -> Self {
+        Snapping {
+            radius: 8.0,
+            grid: true,
+            endpoints: true,
+            intersections: true
+        }
+    }
+}
+
+impl Snapping {
+    // Closest target within the radius of the position, the curves are the ones that can be
+    // snapped to. Leave out the curve being edited, it would snap to itself.
+    pub fn snap(&self, camera: Camera, position: Vector2, curves: &[BezierCurve]) -> Option<Snap> {
+        let radius = self.radius / camera.y_scale;
+
+        // Only curves that reach into the radius can have targets in it
+        let area = Rectangle { x: position.x, y: position.y, width: 0.0, height: 0.0 }.expand(radius);
+        let nearby: Vec<BezierCurve> = curves.iter().filter(|curve| curve.get_bounding_box().intersects(area)).copied().collect();
+
+        let mut candidates = Vec::new();
+        if self.endpoints {
+            for curve in &nearby {
+                candidates.push(Snap { position: curve.p0, target: SnapTarget::Endpoint });
+                candidates.push(Snap { position: curve.p3, target: SnapTarget::Endpoint });
+            }
+        }
+        if self.intersections {
+            let tolerance = INTERSECTION_TOLERANCE_PIXELS / camera.y_scale;
+            let mut i = 0;
+            while i < nearby.len() {
+                let mut j = i + 1;
+                while j < nearby.len() {
+                    for point in nearby[i].intersect_curve(&nearby[j], tolerance) {
+                        candidates.push(Snap { position: point, target: SnapTarget::Intersection });
+                    }
+                    j += 1;
+                }
+                i += 1;
+            }
+        }
+
+        if let Some(snap) = nearest(position, radius, &candidates) {
+            return Some(snap);
+        }
+        if !self.grid {
+            return None;
+        }
+
+        let (spacing, _) = grid_spacing(camera.y_scale);
+        let crossing = Vector2 {
+            x: (position.x / spacing).round() * spacing,
+            y: (position.y / spacing).round() * spacing
+        };
+        nearest(position, radius, &[Snap { position: crossing, target: SnapTarget::Grid }])
+    }
+
+    // The position moved onto the closest target, or where it was when nothing is close enough
+    pub fn apply(&self, camera: Camera, position: Vector2, curves: &[BezierCurve]) -> Vector2 {
+        match self.snap(camera, position, curves) {
+            Some(snap) => snap.position,
+            None => position
+        }
+    }
+}
+
+// Earlier candidates win ties, so an endpoint that's also an intersection snaps as an endpoint
+fn nearest(position: Vector2, radius: f32, candidates: &[Snap]) -> Option<Snap> {
+    let mut nearest: Option<(Snap, f32)> = None;
+    for candidate in candidates {
+        let distance = (candidate.position - position).length();
+        if distance > radius {
+            continue;
+        }
+        nearest = match nearest {
+            Some(value) if value.1 <= distance => Some(value),
+            _ => Some((*candidate, distance))
+        };
+    }
+    nearest.map(|(snap, _)| snap)
+}
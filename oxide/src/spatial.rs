@@ -0,0 +1,123 @@
+use crate::{BezierCurve, Rectangle};
+
+// Bounding volume hierarchy over the bounding boxes of curve slots, so only the curves near the
+// camera are looked at each frame. Every node's box covers the boxes of the nodes under it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CurveIndex {
+    // Bounding box of each curve slot when the tree was built, None for empty slots
+    boxes: Vec<Option<Rectangle>>,
+    // Children come before their parent, the root is the last node
+    nodes: Vec<Node>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Node {
+    bounds: Rectangle,
+    content: NodeContent
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum NodeContent {
+    // Slot of the curve
+    Leaf(usize),
+    // Indices of the two child nodes
+    Branch(usize, usize)
+}
+
+impl CurveIndex {
+    pub fn new(curves: &[Option<BezierCurve>]) -> Self {
+        let mut index = CurveIndex::default();
+        index.update(curves);
+        index
+    }
+
+    // Rebuilds the tree when a curve was added, removed or changed its bounding box since the
+    // last update. Returns whether it was rebuilt.
+    pub fn update(&mut self, curves: &[Option<BezierCurve>]) -> bool {
+        let boxes: Vec<Option<Rectangle>> = curves.iter().map(|curve| curve.map(|value| value.get_bounding_box())).collect();
+        if boxes == self.boxes {
+            return false;
+        }
+
+        self.boxes = boxes;
+        self.nodes.clear();
+        let mut slots: Vec<usize> = (0..self.boxes.len()).filter(|slot| self.boxes[*slot].is_some()).collect();
+        if !slots.is_empty() {
+            self.build(&mut slots);
+        }
+        true
+    }
+
+    // Slots of the curves whose bounding boxes overlap the area, in slot order
+    pub fn query(&self, area: Rectangle) -> Vec<usize> {
+        let mut slots = Vec::new();
+        let mut stack = match self.nodes.len() {
+            0 => return slots,
+            count => vec![count - 1]
+        };
+
+        while let Some(index) = stack.pop() {
+            let node = self.nodes[index];
+            if !node.bounds.intersects(area) {
+                continue;
+            }
+            match node.content {
+                NodeContent::Leaf(slot) => slots.push(slot),
+                NodeContent::Branch(first, second) => {
+                    stack.push(first);
+                    stack.push(second);
+                }
+            }
+        }
+
+        slots.sort_unstable();
+        slots
+    }
+
+    // Splits the slots in half along the axis their centers are most spread out on, returns the
+    // index of the node covering all of them
+    fn build(&mut self, slots: &mut [usize]) -> usize {
+        let boxes: Vec<Rectangle> = slots.iter().flat_map(|slot| self.boxes[*slot]).collect();
+        let bounds = union(&boxes);
+
+        if slots.len() == 1 {
+            self.nodes.push(Node { bounds, content: NodeContent::Leaf(slots[0]) });
+            return self.nodes.len() - 1;
+        }
+
+        let mut min_center = (f32::MAX, f32::MAX);
+        let mut max_center = (f32::MIN, f32::MIN);
+        for rectangle in &boxes {
+            let center = center(*rectangle);
+            min_center = (min_center.0.min(center.0), min_center.1.min(center.1));
+            max_center = (max_center.0.max(center.0), max_center.1.max(center.1));
+        }
+        let split_x = max_center.0 - min_center.0 >= max_center.1 - min_center.1;
+
+        let key = |slot: &usize| match self.boxes[*slot] {
+            Some(rectangle) if split_x => center(rectangle).0,
+            Some(rectangle) => center(rectangle).1,
+            None => 0.0
+        };
+        slots.sort_by(|a, b| key(a).total_cmp(&key(b)));
+
+        let middle = slots.len() / 2;
+        let (first_slots, second_slots) = slots.split_at_mut(middle);
+        let first = self.build(first_slots);
+        let second = self.build(second_slots);
+        self.nodes.push(Node { bounds, content: NodeContent::Branch(first, second) });
+        self.nodes.len() - 1
+    }
+}
+
+fn center(rectangle: Rectangle) -> (f32, f32) {
+    (rectangle.x + rectangle.width / 2.0, rectangle.y + rectangle.height / 2.0)
+}
+
+fn union(rectangles: &[Rectangle]) -> Rectangle {
+    let mut bounds = rectangles[0];
+    for rectangle in &rectangles[1..] {
+        bounds = bounds.union(*rectangle);
+    }
+    bounds
+}
@@ -0,0 +1,139 @@
+use crate::path::Path;
+use crate::{BezierCurve, Vector2};
+
+// Smooth curve that passes through every point, the direction at each point comes from its
+// neighbours. Good for drawing through data points without placing handles by hand.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CatmullRomSpline {
+    pub points: Vec<Vector2>,
+    // Connects the last point back to the first, smoothly
+    pub closed: bool
+}
+
+impl CatmullRomSpline {
+    pub fn new(points: Vec<Vector2>) -> Self {
+        CatmullRomSpline { points, closed: false }
+    }
+
+    pub fn closed(points: Vec<Vector2>) -> Self {
+        CatmullRomSpline { points, closed: true }
+    }
+
+    // One cubic per pair of neighbouring points. Open splines repeat their end points, so the
+    // ends head straight at the next point.
+    pub fn to_bezier_curves(&self) -> Vec<BezierCurve> {
+        let mut curves = Vec::new();
+        let count = self.points.len();
+        if count < 2 {
+            return curves;
+        }
+
+        let point = |i: isize| if self.closed {
+            self.points[i.rem_euclid(count as isize) as usize]
+        } else {
+            self.points[i.clamp(0, count as isize - 1) as usize]
+        };
+
+        let segment_count = if self.closed { count } else { count - 1 };
+        let mut i = 0;
+        while i < segment_count as isize {
+            let previous = point(i - 1);
+            let start = point(i);
+            let end = point(i + 1);
+            let next = point(i + 2);
+
+            // The tangent at a point is half the vector between its neighbours, a third of it
+            // puts the handle where the cubic gets the same tangent
+            curves.push(BezierCurve::new(
+                start,
+                start + (end - previous) * (1.0 / 6.0),
+                end - (next - start) * (1.0 / 6.0),
+                end));
+            i += 1;
+        }
+
+        curves
+    }
+
+    pub fn to_path(&self) -> Path {
+        curves_to_path(&self.to_bezier_curves(), self.closed)
+    }
+}
+
+// Uniform cubic B-spline, pulled towards its control points without going through them. Smoother
+// than a Catmull-Rom spline, moving a point only changes the four segments around it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BSpline {
+    pub control_points: Vec<Vector2>,
+    pub closed: bool
+}
+
+impl BSpline {
+    pub fn new(control_points: Vec<Vector2>) -> Self {
+        BSpline { control_points, closed: false }
+    }
+
+    pub fn closed(control_points: Vec<Vector2>) -> Self {
+        BSpline { control_points, closed: true }
+    }
+
+    // Open splines repeat their end points three times so the curve starts and ends on them
+    pub fn to_bezier_curves(&self) -> Vec<BezierCurve> {
+        let mut curves = Vec::new();
+        let count = self.control_points.len();
+        if count < 2 {
+            return curves;
+        }
+
+        let mut points = Vec::with_capacity(count + 4);
+        if self.closed {
+            points.extend_from_slice(&self.control_points);
+            points.extend_from_slice(&self.control_points[..3.min(count)]);
+            // Fewer than three points have to wrap around more than once
+            while points.len() < count + 3 {
+                points.push(self.control_points[(points.len() - count) % count]);
+            }
+        } else {
+            let first = self.control_points[0];
+            let last = self.control_points[count - 1];
+            points.extend_from_slice(&[first, first]);
+            points.extend_from_slice(&self.control_points);
+            points.extend_from_slice(&[last, last]);
+        }
+
+        let mut i = 3;
+        while i < points.len() {
+            let (q0, q1, q2, q3) = (points[i - 3], points[i - 2], points[i - 1], points[i]);
+            curves.push(BezierCurve::new(
+                (q0 + q1 * 4.0 + q2) * (1.0 / 6.0),
+                (q1 * 2.0 + q2) * (1.0 / 3.0),
+                (q1 + q2 * 2.0) * (1.0 / 3.0),
+                (q1 + q2 * 4.0 + q3) * (1.0 / 6.0)));
+            i += 1;
+        }
+
+        curves
+    }
+
+    pub fn to_path(&self) -> Path {
+        curves_to_path(&self.to_bezier_curves(), self.closed)
+    }
+}
+
+// The curves are expected to be connected, each starting where the previous one ends
+fn curves_to_path(curves: &[BezierCurve], closed: bool) -> Path {
+    let mut path = Path::new();
+    let first = match curves.first() {
+        Some(value) => value,
+        None => return path
+    };
+
+    path.move_to(first.p0);
+    for curve in curves {
+        path.cubic_to(curve.p1, curve.p2, curve.p3);
+    }
+    if closed {
+        path.close();
+    }
+    path
+}
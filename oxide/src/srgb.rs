@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+
+// Resolution of the linear -> sRGB table, high enough that dark values don't band
+static ENCODE_TABLE_SIZE: usize = 4096;
+
+static DECODE_TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+static ENCODE_TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+
+fn decode_table() -> &'static [f32; 256] {
+    DECODE_TABLE.get_or_init(|| {
+        let mut table = [0.0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let value = i as f32 / 255.0;
+            table[i] = if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            };
+            i += 1;
+        }
+        table
+    })
+}
+
+// Linear light to sRGB, both 0.0-1.0
+fn encode(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn encode_table() -> &'static [u8] {
+    ENCODE_TABLE.get_or_init(|| {
+        let mut table = vec![0; ENCODE_TABLE_SIZE];
+        let mut i = 0;
+        while i < ENCODE_TABLE_SIZE {
+            let value = i as f32 / (ENCODE_TABLE_SIZE - 1) as f32;
+            table[i] = (encode(value) * 255.0).round().clamp(0.0, 255.0) as u8;
+            i += 1;
+        }
+        table
+    })
+}
+
+// sRGB channel (0-255) to linear light (0.0-1.0)
+pub fn srgb_to_linear(channel: u8) -> f32 {
+    decode_table()[channel as usize]
+}
+
+// Linear light (0.0-1.0) to an sRGB channel (0-255)
+pub fn linear_to_srgb(value: f32) -> u8 {
+    let index = (value.clamp(0.0, 1.0) * (ENCODE_TABLE_SIZE - 1) as f32).round() as usize;
+    encode_table()[index]
+}
+
+// Unpacks a premultiplied sRGB color to premultiplied linear [alpha, red, green, blue].
+// The color is unpremultiplied first since the transfer curve only applies to the real color.
+pub fn color_to_linear(color: u32) -> [f32; 4] {
+    let alpha = (color >> 24) as f32 / 255.0;
+    if alpha == 0.0 {
+        return [0.0; 4];
+    }
+
+    let channel = |shift: u32| {
+        let premultiplied = ((color >> shift) & 0xFF) as f32;
+        let straight = (premultiplied / alpha).round().min(255.0) as u8;
+        srgb_to_linear(straight) * alpha
+    };
+
+    [alpha, channel(16), channel(8), channel(0)]
+}
+
+pub fn linear_to_color(linear: [f32; 4]) -> u32 {
+    let alpha = linear[0].clamp(0.0, 1.0);
+    if alpha == 0.0 {
+        return 0;
+    }
+
+    let channel = |value: f32| {
+        let straight = linear_to_srgb(value / alpha) as f32;
+        (straight * alpha).round() as u32
+    };
+
+    (((alpha * 255.0).round() as u32) << 24) |
+    (channel(linear[1]) << 16) |
+    (channel(linear[2]) << 8) |
+    channel(linear[3])
+}
+
+// Same as linear_to_color but every channel is rounded up once its fraction passes the
+// threshold. Skips the lookup table since that already rounds to whole values.
+pub fn linear_to_color_dithered(linear: [f32; 4], threshold: f32) -> u32 {
+    let alpha = linear[0].clamp(0.0, 1.0);
+    if alpha == 0.0 {
+        return 0;
+    }
+
+    let quantize = |value: f32| ((value * 255.0 + threshold).floor() as u32).min(255);
+    let channel = |value: f32| quantize(encode((value / alpha).clamp(0.0, 1.0)) * alpha);
+
+    (quantize(alpha) << 24) |
+    (channel(linear[1]) << 16) |
+    (channel(linear[2]) << 8) |
+    channel(linear[3])
+}
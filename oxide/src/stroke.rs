@@ -0,0 +1,254 @@
+use crate::{OffscreenBuffer, Vector2};
+use std::cmp::{max, min};
+
+// How the outer corner between two segments is filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    // Extends the edges until they meet, falls back to bevel past MITER_LIMIT
+    Miter,
+    #[default]
+    Round,
+    // Cuts the corner off straight
+    Bevel
+}
+
+// How the two open ends of a stroke are finished
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineCap {
+    // Ends exactly at the end points
+    #[default]
+    Butt,
+    Round,
+    // Extends half the width past the end points
+    Square
+}
+
+// Furthest a miter tip can reach past the corner, in half widths of the stroke, before the
+// join is beveled instead. Keeps sharp corners from spiking far out of the stroke.
+pub static MITER_LIMIT: f32 = 4.0;
+
+/// Outline of a stroke along screen space points, as convex pieces that together cover it.
+/// Pieces overlap at the joins, fill them with fill_convex_union so no pixel is drawn twice.
+pub fn stroke_polyline(points: &[Vector2], width: f32, join: LineJoin, cap: LineCap) -> Vec<Vec<Vector2>> {
+    let mut pieces = Vec::new();
+    let half_width = width / 2.0;
+
+    // Repeated points have no direction, they would give broken normals
+    let mut path: Vec<Vector2> = Vec::with_capacity(points.len());
+    for point in points {
+        match path.last() {
+            Some(last) if (*point - *last).length() < 1e-4 => {},
+            _ => path.push(*point)
+        }
+    }
+
+    if half_width <= 0.0 {
+        return pieces;
+    }
+    if path.len() < 2 {
+        // A single point only shows up when it has a cap that covers it
+        if let (Some(point), LineCap::Round) = (path.first(), cap) {
+            pieces.push(circle_points(*point, half_width));
+        }
+        return pieces;
+    }
+
+    if cap == LineCap::Square {
+        let last = path.len() - 1;
+        let start_direction = direction(path[0], path[1]);
+        let end_direction = direction(path[last - 1], path[last]);
+        path[0] = path[0] - start_direction * half_width;
+        path[last] = path[last] + end_direction * half_width;
+    }
+
+    let mut i = 1;
+    while i < path.len() {
+        let normal = normal(path[i - 1], path[i]) * half_width;
+        pieces.push(vec![path[i - 1] + normal, path[i] + normal, path[i] - normal, path[i - 1] - normal]);
+        i += 1;
+    }
+
+    let mut i = 1;
+    while i + 1 < path.len() {
+        push_join(&mut pieces, path[i - 1], path[i], path[i + 1], half_width, join);
+        i += 1;
+    }
+
+    if cap == LineCap::Round {
+        pieces.push(circle_points(path[0], half_width));
+        pieces.push(circle_points(path[path.len() - 1], half_width));
+    }
+
+    pieces
+}
+
+fn push_join(pieces: &mut Vec<Vec<Vector2>>, previous: Vector2, point: Vector2, next: Vector2, half_width: f32, join: LineJoin) {
+    let incoming = direction(previous, point);
+    let outgoing = direction(point, next);
+    let turn = incoming.x * outgoing.y - incoming.y * outgoing.x;
+    if turn.abs() < 1e-6 && incoming.x * outgoing.x + incoming.y * outgoing.y > 0.0 {
+        // Straight continuation, the segments already meet flush
+        return;
+    }
+
+    if join == LineJoin::Round {
+        pieces.push(circle_points(point, half_width));
+        return;
+    }
+
+    // The outer corner is on the side the path turns away from
+    let side = if turn > 0.0 { -1.0 } else { 1.0 };
+    let a = point + normal(previous, point) * (half_width * side);
+    let b = point + normal(point, next) * (half_width * side);
+
+    if join == LineJoin::Miter {
+        let bisector = (a - point) + (b - point);
+        let bisector_length = bisector.length();
+        if bisector_length > 1e-6 {
+            // The offsets sum to 2 * cos(angle / 2) half widths, the tip is 1 / cos(angle / 2) out
+            let scale = 2.0 / (bisector_length / half_width);
+            if scale <= MITER_LIMIT {
+                let tip = point + bisector * (half_width * scale / bisector_length);
+                pieces.push(vec![point, a, tip, b]);
+                return;
+            }
+        }
+    }
+
+    pieces.push(vec![point, a, b]);
+}
+
+// Polygon approximation of a circle, with enough points that the edge looks round at its size
+fn circle_points(center: Vector2, radius: f32) -> Vec<Vector2> {
+    let count = (radius * 2.0).ceil().clamp(8.0, 64.0) as usize;
+    let mut points = Vec::with_capacity(count);
+    let mut i = 0;
+    while i < count {
+        let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+        points.push(Vector2 { x: center.x + radius * angle.cos(), y: center.y + radius * angle.sin() });
+        i += 1;
+    }
+    points
+}
+
+fn direction(from: Vector2, to: Vector2) -> Vector2 {
+    let delta = to - from;
+    delta * (1.0 / delta.length())
+}
+
+fn normal(from: Vector2, to: Vector2) -> Vector2 {
+    let direction = direction(from, to);
+    Vector2 { x: -direction.y, y: direction.x }
+}
+
+/// Fills the union of convex screen space polygons, every pixel whose center is inside any of
+/// them is drawn exactly once. Overlapping pieces of a translucent stroke don't darken.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn fill_convex_union(buffer: &mut OffscreenBuffer, pieces: &[Vec<Vector2>], color: u32) {
+    for (y, x0, x1) in union_spans(buffer, pieces) {
+        buffer.fill_span(y, x0, x1, color);
+    }
+}
+
+/// Same as fill_convex_union, with the color of every pixel coming from color_at(x, y)
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn fill_convex_union_shaded<F: Fn(i32, i32) -> u32>(buffer: &mut OffscreenBuffer, pieces: &[Vec<Vector2>], color_at: F) {
+    let clip = buffer.clip_rect();
+    let mut colors = Vec::new();
+    for (y, x0, x1) in union_spans(buffer, pieces) {
+        // Only shade the pixels that can be drawn
+        let x0 = max(x0, clip.x0);
+        let x1 = min(x1, clip.x1);
+        if x0 >= x1 {
+            continue;
+        }
+
+        colors.clear();
+        colors.extend((x0..x1).map(|x| color_at(x, y)));
+        buffer.blend_span(y, x0, &colors);
+    }
+}
+
+// Spans (y, x0, x1) covering the pieces inside the clip rows, merged so none overlap
+fn union_spans(buffer: &OffscreenBuffer, pieces: &[Vec<Vector2>]) -> Vec<(i32, i32, i32)> {
+    let mut spans = Vec::new();
+    let mut min_y = f32::MAX;
+    let mut max_y = f32::MIN;
+    for point in pieces.iter().flatten() {
+        min_y = min_y.min(point.y);
+        max_y = max_y.max(point.y);
+    }
+
+    let clip = buffer.clip_rect();
+    let start_y = max(min_y.floor() as i32, clip.y0);
+    let end_y = min(max_y.ceil() as i32, clip.y1);
+    if start_y >= end_y {
+        return spans;
+    }
+
+    // Spans covered by each piece, collected per row and merged before drawing
+    let mut rows: Vec<Vec<(i32, i32)>> = vec![Vec::new(); (end_y - start_y) as usize];
+    for piece in pieces {
+        let mut piece_min_y = f32::MAX;
+        let mut piece_max_y = f32::MIN;
+        for point in piece {
+            piece_min_y = piece_min_y.min(point.y);
+            piece_max_y = piece_max_y.max(point.y);
+        }
+
+        let mut y = max(piece_min_y.floor() as i32, start_y);
+        let piece_end_y = min(piece_max_y.ceil() as i32, end_y);
+        while y < piece_end_y {
+            // Sample at the pixel center
+            let scan_y = y as f32 + 0.5;
+            let mut low = f32::MAX;
+            let mut high = f32::MIN;
+
+            let mut i = 0;
+            while i < piece.len() {
+                let a = piece[i];
+                let b = piece[(i + 1) % piece.len()];
+                // Half open so a vertex shared by two edges only counts once
+                if (a.y <= scan_y && b.y > scan_y) || (b.y <= scan_y && a.y > scan_y) {
+                    let x = a.x + (scan_y - a.y) / (b.y - a.y) * (b.x - a.x);
+                    low = low.min(x);
+                    high = high.max(x);
+                }
+                i += 1;
+            }
+
+            if low <= high {
+                let span = ((low - 0.5).ceil() as i32, (high - 0.5).ceil() as i32);
+                if span.0 < span.1 {
+                    rows[(y - start_y) as usize].push(span);
+                }
+            }
+            y += 1;
+        }
+    }
+
+    for (y, row) in (start_y..).zip(rows.iter_mut()) {
+        row.sort_unstable();
+
+        let mut current: Option<(i32, i32)> = None;
+        for span in row.iter() {
+            current = match current {
+                Some((x0, x1)) if span.0 <= x1 => Some((x0, max(x1, span.1))),
+                Some((x0, x1)) => {
+                    spans.push((y, x0, x1));
+                    Some(*span)
+                },
+                None => Some(*span)
+            };
+        }
+        if let Some((x0, x1)) = current {
+            spans.push((y, x0, x1));
+        }
+    }
+
+    spans
+}
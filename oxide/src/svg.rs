@@ -0,0 +1,214 @@
+use crate::path::{FillRule, Path, PathSegment};
+use crate::{ClipRect, Color, DashPattern, LineCap, LineJoin, Rectangle, Vector2};
+use std::fs;
+use std::io;
+
+// How lines and outlines are drawn, sizes are in the document's units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgStroke {
+    pub width: f32,
+    pub color: Color,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    // None draws a solid stroke
+    pub dash: Option<DashPattern>
+}
+
+impl SvgStroke {
+    pub fn new(width: f32, color: Color) -> Self {
+        SvgStroke {
+            width,
+            color,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            dash: None
+        }
+    }
+}
+
+// Collects shapes as SVG elements instead of rasterizing them, coordinates are written as given
+pub struct SvgDocument {
+    pub width: f32,
+    pub height: f32,
+    // Part of the coordinate space that is shown, stretched to width x height
+    pub view_box: Rectangle,
+    elements: Vec<String>,
+    // Clip paths written so far, each one gets its own id
+    clip_count: u32
+}
+
+impl SvgDocument {
+    pub fn new(width: f32, height: f32) -> Self {
+        SvgDocument::with_view_box(width, height, Rectangle { x: 0.0, y: 0.0, width, height })
+    }
+
+    pub fn with_view_box(width: f32, height: f32, view_box: Rectangle) -> Self {
+        SvgDocument {
+            width,
+            height,
+            view_box,
+            elements: Vec::new(),
+            clip_count: 0
+        }
+    }
+
+    pub fn rectangle(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.elements.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+            x, y, width, height, fill_attributes(color)));
+    }
+
+    pub fn circle(&mut self, center: Vector2, radius: f32, color: Color) {
+        self.elements.push(format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>",
+            center.x, center.y, radius, fill_attributes(color)));
+    }
+
+    pub fn circle_outline(&mut self, center: Vector2, radius: f32, stroke: SvgStroke) {
+        self.elements.push(format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" {}/>",
+            center.x, center.y, radius, stroke_attributes(stroke)));
+    }
+
+    // Filled with the even-odd rule, like draw_polygon
+    pub fn polygon(&mut self, points: &[Vector2], color: Color) {
+        self.elements.push(format!(
+            "<polygon points=\"{}\" fill-rule=\"evenodd\" {}/>",
+            point_list(points), fill_attributes(color)));
+    }
+
+    pub fn line(&mut self, a: Vector2, b: Vector2, stroke: SvgStroke) {
+        self.elements.push(format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+            a.x, a.y, b.x, b.y, stroke_attributes(stroke)));
+    }
+
+    pub fn polyline(&mut self, points: &[Vector2], stroke: SvgStroke) {
+        self.elements.push(format!(
+            "<polyline points=\"{}\" fill=\"none\" {}/>",
+            point_list(points), stroke_attributes(stroke)));
+    }
+
+    // Kept as a true cubic so the curve stays smooth at any print size
+    pub fn cubic_bezier(&mut self, p0: Vector2, p1: Vector2, p2: Vector2, p3: Vector2, stroke: SvgStroke) {
+        self.elements.push(format!(
+            "<path d=\"M {} {} C {} {}, {} {}, {} {}\" fill=\"none\" {}/>",
+            p0.x, p0.y, p1.x, p1.y, p2.x, p2.y, p3.x, p3.y, stroke_attributes(stroke)));
+    }
+
+    pub fn quadratic_bezier(&mut self, p0: Vector2, p1: Vector2, p2: Vector2, stroke: SvgStroke) {
+        self.elements.push(format!(
+            "<path d=\"M {} {} Q {} {}, {} {}\" fill=\"none\" {}/>",
+            p0.x, p0.y, p1.x, p1.y, p2.x, p2.y, stroke_attributes(stroke)));
+    }
+
+    // Open sub paths are closed by the fill, the same as fill_path
+    pub fn fill_path(&mut self, path: &Path, color: Color, rule: FillRule) {
+        let rule = match rule {
+            FillRule::NonZero => "nonzero",
+            FillRule::EvenOdd => "evenodd"
+        };
+        self.elements.push(format!("<path d=\"{}\" fill-rule=\"{}\" {}/>", path_data(path), rule, fill_attributes(color)));
+    }
+
+    pub fn stroke_path(&mut self, path: &Path, stroke: SvgStroke) {
+        self.elements.push(format!("<path d=\"{}\" fill=\"none\" {}/>", path_data(path), stroke_attributes(stroke)));
+    }
+
+    // Text with its top left corner at the position, squeezed or stretched to width x height so
+    // it covers the same box as the built in font whatever font the viewer picks
+    pub fn text(&mut self, position: Vector2, text: &str, width: f32, height: f32, color: Color) {
+        // Capitals are about 0.7 of the font size in most monospace fonts
+        self.elements.push(format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\" {}>{}</text>",
+            position.x, position.y + height, height / 0.7, width, fill_attributes(color), escape_text(text)));
+    }
+
+    // Elements added until the matching end_clip are only drawn inside the rect
+    pub fn begin_clip(&mut self, clip: ClipRect) {
+        self.clip_count += 1;
+        self.elements.push(format!(
+            "<clipPath id=\"clip{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>",
+            self.clip_count, clip.x0, clip.y0, clip.x1 - clip.x0, clip.y1 - clip.y0));
+        self.elements.push(format!("<g clip-path=\"url(#clip{})\">", self.clip_count));
+    }
+
+    pub fn end_clip(&mut self) {
+        self.elements.push("</g>".to_string());
+    }
+
+    pub fn to_svg_string(&self) -> String {
+        let mut text = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">\n",
+            self.width, self.height, self.view_box.x, self.view_box.y, self.view_box.width, self.view_box.height);
+
+        for element in &self.elements {
+            text.push_str("  ");
+            text.push_str(element);
+            text.push('\n');
+        }
+
+        text.push_str("</svg>\n");
+        text
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_svg_string())
+    }
+}
+
+// Opacity is written separately since not every viewer understands 8 digit hex colors
+fn fill_attributes(color: Color) -> String {
+    format!("fill=\"{}\" fill-opacity=\"{}\"", color.with_alpha(255).to_hex(), color.alpha())
+}
+
+fn stroke_attributes(stroke: SvgStroke) -> String {
+    let color = stroke.color;
+    let join = match stroke.join {
+        LineJoin::Miter => "miter",
+        LineJoin::Round => "round",
+        LineJoin::Bevel => "bevel"
+    };
+    let cap = match stroke.cap {
+        LineCap::Butt => "butt",
+        LineCap::Round => "round",
+        LineCap::Square => "square"
+    };
+    // The miter limit is left at SVG's default of 4, the same as stroke::MITER_LIMIT
+    let mut attributes = format!(
+        "stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"{}\" stroke-linejoin=\"{}\" stroke-linecap=\"{}\"",
+        color.with_alpha(255).to_hex(), color.alpha(), stroke.width, join, cap);
+    if let Some(dash) = stroke.dash {
+        attributes.push_str(&format!(" stroke-dasharray=\"{} {}\" stroke-dashoffset=\"{}\"", dash.on, dash.off, dash.offset));
+    }
+    attributes
+}
+
+fn point_list(points: &[Vector2]) -> String {
+    let points: Vec<String> = points.iter().map(|point| format!("{},{}", point.x, point.y)).collect();
+    points.join(" ")
+}
+
+fn path_data(path: &Path) -> String {
+    let mut data = Vec::new();
+    for sub_path in &path.sub_paths {
+        data.push(format!("M {} {}", sub_path.start.x, sub_path.start.y));
+        for segment in &sub_path.segments {
+            data.push(match *segment {
+                PathSegment::Line { to, .. } => format!("L {} {}", to.x, to.y),
+                PathSegment::Quadratic(curve) => format!("Q {} {}, {} {}", curve.p1.x, curve.p1.y, curve.p2.x, curve.p2.y),
+                PathSegment::Cubic(curve) => {
+                    format!("C {} {}, {} {}, {} {}", curve.p1.x, curve.p1.y, curve.p2.x, curve.p2.y, curve.p3.x, curve.p3.y)
+                }
+            });
+        }
+        if sub_path.closed {
+            data.push("Z".to_string());
+        }
+    }
+    data.join(" ")
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
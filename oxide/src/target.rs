@@ -0,0 +1,70 @@
+use crate::bitmap::{blit_bitmap, Bitmap};
+use crate::{BlendMode, OffscreenBuffer};
+use std::ffi::c_void;
+
+// A software surface with the same pixel format as the back buffer (premultiplied BGRA),
+// for caching expensive renders and compositing UI panels
+pub struct RenderTarget {
+    // Points into the bitmap's pixels, which are never resized so the pointer stays valid
+    buffer: OffscreenBuffer,
+    bitmap: Bitmap
+}
+
+impl RenderTarget {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut bitmap = Bitmap::new(width, height);
+        let buffer = OffscreenBuffer {
+            memory: bitmap.pixels.as_mut_ptr() as *mut c_void,
+            width,
+            height,
+            bytes_per_pixel: 4,
+            pitch: width * 4,
+            blend_mode: BlendMode::Alpha,
+            linear_blending: false,
+            dither: false,
+            clip_stack: Vec::new(),
+            mask: None
+        };
+
+        RenderTarget {
+            buffer,
+            bitmap
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.buffer.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.buffer.height
+    }
+
+    // The surface as a buffer, so every draw function can render into it
+    pub fn buffer(&mut self) -> &mut OffscreenBuffer {
+        &mut self.buffer
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.bitmap.pixels
+    }
+
+    // What has been drawn so far, e.g. to draw it scaled or rotated with draw_bitmap
+    pub fn bitmap(&self) -> &Bitmap {
+        &self.bitmap
+    }
+
+    // Fills the whole surface with a premultiplied pixel, ignoring the clip stack
+    pub fn clear(&mut self, pixel: u32) {
+        self.bitmap.pixels.fill(pixel);
+    }
+
+    /// Composites the surface onto a buffer with its top left corner at (x, y) in pixels,
+    /// using the buffer's blend mode and clip rect. Opacity fades the whole surface.
+    ///
+    /// # Safety
+    /// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+    pub unsafe fn blit(&self, buffer: &mut OffscreenBuffer, x: i32, y: i32, opacity: f32) {
+        blit_bitmap(buffer, &self.bitmap, x, y, opacity);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{world_space_to_screen_space_f32, Camera, ClipRect, Color, OffscreenBuffer, Vector2};
+
+// Built in 5x7 pixel font for labels and readouts. Each row is five bits with the leftmost pixel
+// in the highest bit. Lowercase letters are drawn as capitals.
+pub static GLYPH_WIDTH: u32 = 5;
+pub static GLYPH_HEIGHT: u32 = 7;
+// Pixels from the start of one glyph to the next, before scaling
+pub static GLYPH_ADVANCE: u32 = 6;
+// Pixels between the text and the edge of its background
+pub static TEXT_PADDING: u32 = 2;
+
+static GLYPHS: [(char, [u8; 7]); 59] = [
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('A', [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    (';', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('[', [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E]),
+    (']', [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E]),
+    ('<', [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02]),
+    ('>', [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    ('=', [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00]),
+    ('*', [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03]),
+    ('#', [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A]),
+    ('!', [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+    ('\'', [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00]),
+    ('"', [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F])
+];
+
+// Rows of the character's glyph, characters the font doesn't have are drawn as a question mark
+pub fn glyph(character: char) -> [u8; 7] {
+    let upper = character.to_ascii_uppercase();
+    match GLYPHS.iter().find(|(c, _)| *c == upper) {
+        Some((_, rows)) => *rows,
+        None => glyph('?')
+    }
+}
+
+// Pixels the text covers at the scale, without its background. Text is a single line.
+pub fn text_size(text: &str, scale: u32) -> (u32, u32) {
+    let count = text.chars().count() as u32;
+    if count == 0 {
+        return (0, 0);
+    }
+    ((count * GLYPH_ADVANCE - (GLYPH_ADVANCE - GLYPH_WIDTH)) * scale, GLYPH_HEIGHT * scale)
+}
+
+// Screen rect of the text and its background, the world position is its top left corner
+pub fn text_rect(camera: Camera, position: Vector2, text: &str, scale: u32) -> ClipRect {
+    let screen = world_space_to_screen_space_f32(camera, position);
+    let (width, height) = text_size(text, scale);
+    let padding = (TEXT_PADDING * scale) as i32;
+    let x = screen.x.floor() as i32;
+    let y = screen.y.floor() as i32;
+    ClipRect::new(x - padding, y - padding, x + width as i32 + padding, y + height as i32 + padding)
+}
+
+/// Draws the text with its top left corner at the world position. Glyphs are screen pixels
+/// scaled by a whole number, they keep their size whatever the camera's zoom or rotation.
+///
+/// # Safety
+/// `buffer.memory` must point to at least `buffer.pitch * buffer.height` writable bytes
+pub unsafe fn draw_text(buffer: &mut OffscreenBuffer, camera: Camera, position: Vector2, text: &str, scale: u32, color: Color, background: Color) {
+    let scale = scale.max(1);
+    let rect = text_rect(camera, position, text, scale);
+    if background.a > 0 {
+        let background = background.to_pixel();
+        let mut y = rect.y0;
+        while y < rect.y1 {
+            buffer.fill_span(y, rect.x0, rect.x1, background);
+            y += 1;
+        }
+    }
+
+    let color = color.to_pixel();
+    let padding = (TEXT_PADDING * scale) as i32;
+    let mut x = rect.x0 + padding;
+    let y = rect.y0 + padding;
+    let size = scale as i32;
+    for character in text.chars() {
+        let rows = glyph(character);
+        let mut row = 0;
+        while row < GLYPH_HEIGHT as usize {
+            let mut column = 0;
+            while column < GLYPH_WIDTH as i32 {
+                if rows[row] & (0x10 >> column) != 0 {
+                    let pixel_x = x + column * size;
+                    let pixel_y = y + row as i32 * size;
+                    let mut line = 0;
+                    while line < size {
+                        buffer.fill_span(pixel_y + line, pixel_x, pixel_x + size, color);
+                        line += 1;
+                    }
+                }
+                column += 1;
+            }
+            row += 1;
+        }
+        x += (GLYPH_ADVANCE * scale) as i32;
+    }
+}
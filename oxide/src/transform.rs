@@ -0,0 +1,152 @@
+use crate::path::{Path, PathSegment};
+use crate::{BezierCurve, QuadraticBezier, Rectangle, Vector2};
+
+// Affine transform of world space, a 3x2 matrix that maps (x, y) to
+// (a * x + c * y + tx, b * x + d * y + ty). Bezier curves stay exact under it, transforming the
+// control points transforms the whole curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Transform2D::identity()
+    }
+}
+
+impl Transform2D {
+    pub fn identity() -> Self {
+        Transform2D { a: 1.0, b: 0.0, c: 0.0, d: 1.0, tx: 0.0, ty: 0.0 }
+    }
+
+    pub fn translation(offset: Vector2) -> Self {
+        Transform2D { tx: offset.x, ty: offset.y, ..Transform2D::identity() }
+    }
+
+    // Angle in radians, positive turns x towards y
+    pub fn rotation(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Transform2D { a: cos, b: sin, c: -sin, d: cos, tx: 0.0, ty: 0.0 }
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        Transform2D { a: x, d: y, ..Transform2D::identity() }
+    }
+
+    // Rotation around a point instead of the origin
+    pub fn rotation_around(angle: f32, center: Vector2) -> Self {
+        Transform2D::translation(center * -1.0).then(Transform2D::rotation(angle)).then(Transform2D::translation(center))
+    }
+
+    // This transform followed by the other one
+    pub fn then(self, other: Transform2D) -> Self {
+        Transform2D {
+            a: other.a * self.a + other.c * self.b,
+            b: other.b * self.a + other.d * self.b,
+            c: other.a * self.c + other.c * self.d,
+            d: other.b * self.c + other.d * self.d,
+            tx: other.a * self.tx + other.c * self.ty + other.tx,
+            ty: other.b * self.tx + other.d * self.ty + other.ty
+        }
+    }
+
+    // None when the transform squashes everything onto a line or point
+    pub fn invert(self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let a = self.d * inverse;
+        let b = -self.b * inverse;
+        let c = -self.c * inverse;
+        let d = self.a * inverse;
+        Some(Transform2D {
+            a,
+            b,
+            c,
+            d,
+            tx: -(a * self.tx + c * self.ty),
+            ty: -(b * self.tx + d * self.ty)
+        })
+    }
+
+    pub fn determinant(self) -> f32 {
+        self.a * self.d - self.b * self.c
+    }
+
+    // How much lengths grow on average, exact for transforms that scale the same on both axes
+    pub fn scale_factor(self) -> f32 {
+        self.determinant().abs().sqrt()
+    }
+
+    // Angle the transform turns by when it only moves, turns and scales the same on both
+    // axes. None when it skews, stretches or mirrors.
+    pub fn rotation_angle(self) -> Option<f32> {
+        let tolerance = 1e-5 * self.scale_factor().max(1.0);
+        if (self.a - self.d).abs() > tolerance || (self.b + self.c).abs() > tolerance || self.determinant() <= 0.0 {
+            return None;
+        }
+        Some(self.b.atan2(self.a))
+    }
+
+    // Rectangles stay rectangles with their edges along the axes
+    pub fn is_axis_aligned(self) -> bool {
+        (self.b == 0.0 && self.c == 0.0) || (self.a == 0.0 && self.d == 0.0)
+    }
+
+    pub fn apply(self, point: Vector2) -> Vector2 {
+        Vector2 {
+            x: self.a * point.x + self.c * point.y + self.tx,
+            y: self.b * point.x + self.d * point.y + self.ty
+        }
+    }
+
+    // Directions and offsets aren't moved by the translation
+    pub fn apply_vector(self, vector: Vector2) -> Vector2 {
+        Vector2 {
+            x: self.a * vector.x + self.c * vector.y,
+            y: self.b * vector.x + self.d * vector.y
+        }
+    }
+
+    pub fn apply_curve(self, curve: BezierCurve) -> BezierCurve {
+        BezierCurve::new(self.apply(curve.p0), self.apply(curve.p1), self.apply(curve.p2), self.apply(curve.p3))
+    }
+
+    pub fn apply_quadratic(self, curve: QuadraticBezier) -> QuadraticBezier {
+        QuadraticBezier::new(self.apply(curve.p0), self.apply(curve.p1), self.apply(curve.p2))
+    }
+
+    pub fn apply_path(self, path: &Path) -> Path {
+        let mut transformed = path.clone();
+        for sub_path in transformed.sub_paths.iter_mut() {
+            sub_path.start = self.apply(sub_path.start);
+            for segment in sub_path.segments.iter_mut() {
+                *segment = match *segment {
+                    PathSegment::Line { from, to } => PathSegment::Line { from: self.apply(from), to: self.apply(to) },
+                    PathSegment::Quadratic(curve) => PathSegment::Quadratic(self.apply_quadratic(curve)),
+                    PathSegment::Cubic(curve) => PathSegment::Cubic(self.apply_curve(curve))
+                };
+            }
+        }
+        transformed
+    }
+
+    // The corners of the rectangle after the transform, in order around it
+    pub fn apply_rectangle(self, rectangle: Rectangle) -> [Vector2; 4] {
+        [
+            self.apply(Vector2 { x: rectangle.x, y: rectangle.y }),
+            self.apply(Vector2 { x: rectangle.x + rectangle.width, y: rectangle.y }),
+            self.apply(Vector2 { x: rectangle.x + rectangle.width, y: rectangle.y + rectangle.height }),
+            self.apply(Vector2 { x: rectangle.x, y: rectangle.y + rectangle.height })
+        ]
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{Camera, Rectangle, Vector2};
+
+// Where an element is attached inside its parent rectangle
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    // Fills the parent, offset is used as the margin on every side and size is ignored
+    Stretch
+}
+
+// An element placed relative to its parent in UI units.
+// Offset moves the element away from its anchor, towards the inside of the parent.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UiElement {
+    pub anchor: Anchor,
+    pub offset: Vector2,
+    pub size: Vector2
+}
+
+impl UiElement {
+    pub fn new(anchor: Anchor, offset: Vector2, size: Vector2) -> Self {
+        UiElement {
+            anchor,
+            offset,
+            size
+        }
+    }
+
+    pub fn stretch(margin: f32) -> Self {
+        UiElement {
+            anchor: Anchor::Stretch,
+            offset: Vector2 { x: margin, y: margin },
+            size: Vector2 { x: 0.0, y: 0.0 }
+        }
+    }
+}
+
+// Screen-space coordinate system for HUD elements, independent of the world camera.
+// One UI unit is one pixel at 96 DPI, so elements keep their physical size on high DPI screens.
+#[derive(Clone, Copy, Debug)]
+pub struct UiLayout {
+    pub width: f32,
+    pub height: f32,
+    pub scale: f32
+}
+
+impl UiLayout {
+    pub fn new(buffer_width: u32, buffer_height: u32, dpi_scale: f32) -> Self {
+        let scale = if dpi_scale > 0.0 { dpi_scale } else { 1.0 };
+        UiLayout {
+            width: buffer_width as f32 / scale,
+            height: buffer_height as f32 / scale,
+            scale
+        }
+    }
+
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle {
+            x: 0.0,
+            y: 0.0,
+            width: self.width,
+            height: self.height
+        }
+    }
+
+    // A camera that maps UI units onto the buffer, so the regular draw functions can draw UI
+    pub fn camera(&self) -> Camera {
+        Camera {
+            x: self.width / 2.0,
+            y: self.height / 2.0,
+            width: self.width,
+            height: self.height,
+            y_scale: self.scale,
+            rotation: 0.0,
+            screen_offset: Vector2 { x: 0.0, y: 0.0 },
+            y_up: false
+        }
+    }
+
+    pub fn resolve(&self, element: UiElement) -> Rectangle {
+        self.resolve_in(self.bounds(), element)
+    }
+
+    // Resolves an element inside another resolved rectangle, for nesting elements in panels
+    pub fn resolve_in(&self, parent: Rectangle, element: UiElement) -> Rectangle {
+        if element.anchor == Anchor::Stretch {
+            return Rectangle {
+                x: parent.x + element.offset.x,
+                y: parent.y + element.offset.y,
+                width: (parent.width - element.offset.x * 2.0).max(0.0),
+                height: (parent.height - element.offset.y * 2.0).max(0.0)
+            };
+        }
+
+        let x = match element.anchor {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => parent.x + element.offset.x,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => {
+                parent.x + (parent.width - element.size.x) / 2.0 + element.offset.x
+            },
+            _ => parent.x + parent.width - element.size.x - element.offset.x
+        };
+
+        let y = match element.anchor {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => parent.y + element.offset.y,
+            Anchor::Left | Anchor::Center | Anchor::Right => {
+                parent.y + (parent.height - element.size.y) / 2.0 + element.offset.y
+            },
+            _ => parent.y + parent.height - element.size.y - element.offset.y
+        };
+
+        Rectangle {
+            x,
+            y,
+            width: element.size.x,
+            height: element.size.y
+        }
+    }
+
+    // Converts a UI position to buffer pixels
+    pub fn to_screen(&self, pos: Vector2) -> Vector2 {
+        pos * self.scale
+    }
+
+    // Converts buffer pixels (e.g. the mouse position) to UI units
+    pub fn from_screen(&self, pos: Vector2) -> Vector2 {
+        pos * (1.0 / self.scale)
+    }
+}
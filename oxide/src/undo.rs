@@ -0,0 +1,96 @@
+use crate::BezierCurve;
+
+// Change to the scene that can be undone, with enough of the state from before and after it to
+// go either way
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    // A curve slot changing, None is an empty slot
+    SetCurve { slot: usize, before: Option<BezierCurve>, after: Option<BezierCurve> },
+    // Edits undone and redone together, like the curves of one sketched stroke
+    Group(Vec<Edit>)
+}
+
+impl Edit {
+    pub fn apply(&self, curves: &mut [Option<BezierCurve>]) {
+        match self {
+            Edit::SetCurve { slot, after, .. } => curves[*slot] = *after,
+            Edit::Group(edits) => edits.iter().for_each(|edit| edit.apply(curves))
+        }
+    }
+
+    // Undoes the edits of a group last to first, in case they touch the same slot
+    pub fn revert(&self, curves: &mut [Option<BezierCurve>]) {
+        match self {
+            Edit::SetCurve { slot, before, .. } => curves[*slot] = *before,
+            Edit::Group(edits) => edits.iter().rev().for_each(|edit| edit.revert(curves))
+        }
+    }
+}
+
+// Edits that have been made, newest last, and the ones that were undone since
+#[derive(Clone, Debug, PartialEq)]
+pub struct UndoStack {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    // Most edits kept, the oldest are forgotten past it
+    pub depth: usize
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        UndoStack::new(100)
+    }
+}
+
+impl UndoStack {
+    pub fn new(depth: usize) -> Self {
+        UndoStack { undo: Vec::new(), redo: Vec::new(), depth }
+    }
+
+    // Records an edit that has already been applied. Anything undone before it can't be redone
+    // anymore.
+    pub fn push(&mut self, edit: Edit) {
+        self.redo.clear();
+        self.undo.push(edit);
+        if self.undo.len() > self.depth {
+            let excess = self.undo.len() - self.depth;
+            self.undo.drain(..excess);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // Returns false when there was nothing to undo
+    pub fn undo(&mut self, curves: &mut [Option<BezierCurve>]) -> bool {
+        match self.undo.pop() {
+            Some(edit) => {
+                edit.revert(curves);
+                self.redo.push(edit);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn redo(&mut self, curves: &mut [Option<BezierCurve>]) -> bool {
+        match self.redo.pop() {
+            Some(edit) => {
+                edit.apply(curves);
+                self.undo.push(edit);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{screen_space_to_world_space_f32, Camera, Vector2};
+
+// Windows reports 120 for every notch of the wheel, each notch zooms by ZOOM_STEP
+static WHEEL_NOTCH: f32 = 120.0;
+static ZOOM_STEP: f32 = 1.25;
+// Once the height is this close to the target, as a fraction of it, it snaps there and stops
+static SNAP_FRACTION: f32 = 1e-3;
+
+// Mouse wheel zoom that keeps the world point under the cursor where it is. Zoom is how much of
+// the world the camera sees, the platform works y_scale out from the camera's height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraZoom {
+    // Least and most world units of height the camera can see, the smaller the closer
+    pub min_height: f32,
+    pub max_height: f32,
+    // Milliseconds to get most of the way to the target height, 0.0 zooms at once
+    pub smoothing: f32,
+    target_height: Option<f32>,
+    // Screen point that stays over the same world point while zooming
+    anchor: Vector2
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        CameraZoom {
+            min_height: 0.1,
+            max_height: 500.0,
+            smoothing: 60.0,
+            target_height: None,
+            anchor: Vector2::zero()
+        }
+    }
+}
+
+impl CameraZoom {
+    pub fn is_zooming(&self) -> bool {
+        self.target_height.is_some()
+    }
+
+    // Positive wheel deltas zoom in towards the cursor. Scrolling again before the zoom has
+    // caught up adds to where it was going.
+    pub fn scroll(&mut self, camera: Camera, wheel_delta: i16, cursor: Vector2) {
+        if wheel_delta == 0 {
+            return;
+        }
+
+        let current = self.target_height.unwrap_or(camera.height);
+        let notches = wheel_delta as f32 / WHEEL_NOTCH;
+        self.zoom_to(current * ZOOM_STEP.powf(-notches), cursor);
+    }
+
+    pub fn zoom_to(&mut self, height: f32, anchor: Vector2) {
+        self.target_height = Some(height.min(self.max_height).max(self.min_height));
+        self.anchor = anchor;
+    }
+
+    // Moves the camera's height towards the target, call every frame with its delta time
+    pub fn update(&mut self, camera: &mut Camera, delta_time: f32) {
+        let target = match self.target_height {
+            Some(value) => value,
+            None => return
+        };
+
+        // Exponential so the zoom is just as smooth at any frame rate
+        let blend = if self.smoothing > 0.0 { 1.0 - (-delta_time / self.smoothing).exp() } else { 1.0 };
+        let mut height = camera.height + (target - camera.height) * blend;
+        if (height - target).abs() <= target * SNAP_FRACTION {
+            height = target;
+            self.target_height = None;
+        }
+        zoom_around(camera, height, self.anchor);
+    }
+}
+
+// Changes how much of the world the camera sees, keeping the world point at the anchor on the
+// screen where it was. The camera stays the same size in pixels.
+pub fn zoom_around(camera: &mut Camera, height: f32, anchor: Vector2) {
+    if height <= 0.0 || camera.height <= 0.0 || camera.y_scale <= 0.0 {
+        return;
+    }
+
+    let anchored = screen_space_to_world_space_f32(*camera, anchor);
+    let pixel_width = camera.width * camera.y_scale;
+    let pixel_height = camera.height * camera.y_scale;
+    camera.y_scale = pixel_height / height;
+    camera.width = pixel_width / camera.y_scale;
+    camera.height = height;
+
+    // Where the anchor is from the middle of the camera in world space, rotation included
+    let offset = screen_space_to_world_space_f32(Camera { x: 0.0, y: 0.0, ..*camera }, anchor);
+    camera.x = anchored.x - offset.x;
+    camera.y = anchored.y - offset.y;
+}
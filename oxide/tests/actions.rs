@@ -0,0 +1,102 @@
+use oxide::actions::{ActionMap, GamepadButton, InputSource, Key, MouseButton};
+use oxide::InputController;
+
+#[test]
+fn default_actions_do_not_share_sources() {
+    let actions = ActionMap::default_bindings();
+
+    for (index, binding) in actions.bindings.iter().enumerate() {
+        for other in &actions.bindings[index + 1..] {
+            for source in &binding.sources {
+                assert!(
+                    !other.sources.contains(source),
+                    "{} and {} are both bound to {}", binding.action, other.action, source.to_config_string());
+            }
+        }
+    }
+}
+
+#[test]
+fn config_keeps_defaults_for_missing_actions() {
+    let actions = ActionMap::from_config_string("Select = Mouse:Right\n").unwrap();
+
+    assert_eq!(actions.sources("Select"), &[InputSource::Mouse(MouseButton::Right)]);
+    // Saved before these existed, they come from the defaults
+    let defaults = ActionMap::default_bindings();
+    for action in ["Undo", "Sketch", "ToggleMeasure", "PanUp"] {
+        assert_eq!(actions.sources(action), defaults.sources(action), "{}", action);
+    }
+}
+
+#[test]
+fn config_can_unbind_a_default_action() {
+    let actions = ActionMap::from_config_string("Undo =\n").unwrap();
+    assert!(actions.sources("Undo").is_empty());
+}
+
+#[test]
+fn repeated_config_line_replaces_the_earlier_one() {
+    let actions = ActionMap::from_config_string("Undo = Key:Z\nUndo = Key:Y, Gamepad:B\n").unwrap();
+
+    assert_eq!(actions.sources("Undo"), &[InputSource::Key(Key::Y), InputSource::Gamepad(GamepadButton::B)]);
+    assert_eq!(actions.bindings.iter().filter(|binding| binding.action == "Undo").count(), 1);
+}
+
+#[test]
+fn config_round_trips() {
+    let mut actions = ActionMap::default_bindings();
+    actions.rebind("Sketch", InputSource::Key(Key::E));
+    actions.clear("Redo");
+
+    let loaded = ActionMap::from_config_string(&actions.to_config_string()).unwrap();
+    for binding in &actions.bindings {
+        assert_eq!(loaded.sources(&binding.action), binding.sources.as_slice(), "{}", binding.action);
+    }
+}
+
+#[test]
+fn config_rejects_unknown_sources_and_lines_without_equals() {
+    assert!(ActionMap::from_config_string("Select = Key:Nope\n").is_err());
+    assert!(ActionMap::from_config_string("Select Mouse:Left\n").is_err());
+    assert!(ActionMap::from_config_string("# Comment\n\n  \n").is_ok());
+}
+
+#[test]
+fn rebinding_takes_the_next_press_and_saves_it() {
+    let mut actions = ActionMap::default_bindings();
+    let mut input = InputController::default();
+    actions.begin_choosing_rebind();
+
+    // Pressing Undo's key picks Undo without taking Z as the new binding
+    input.z.is_down = true;
+    assert!(!actions.update_rebind(&input));
+    assert_eq!(actions.rebinding.as_deref(), Some("Undo"));
+    input.z.was_down = true;
+    assert!(!actions.update_rebind(&input));
+
+    input.m.is_down = true;
+    assert!(actions.update_rebind(&input));
+    assert!(!actions.is_rebinding());
+    assert_eq!(actions.sources("Undo"), &[InputSource::Key(Key::M)]);
+
+    let path = std::env::temp_dir().join("oxide_rebind_test.cfg");
+    let path = path.to_str().unwrap();
+    actions.save(path).unwrap();
+    let loaded = ActionMap::load(path);
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.unwrap().sources("Undo"), &[InputSource::Key(Key::M)]);
+}
+
+#[test]
+fn rebind_waits_for_a_press() {
+    let mut actions = ActionMap::default_bindings();
+    let mut input = InputController::default();
+    actions.begin_rebind("Sketch");
+
+    // Still held from before the rebind started
+    input.f.is_down = true;
+    input.f.was_down = true;
+    assert!(!actions.update_rebind(&input));
+    assert_eq!(actions.rebinding.as_deref(), Some("Sketch"));
+    assert_eq!(actions.sources("Sketch"), &[InputSource::Key(Key::F)]);
+}
@@ -0,0 +1,54 @@
+use oxide::{BezierCurve, Vector2};
+
+// Control points evenly along a line, so t moves along it at a constant speed
+fn straight_line(length: f32) -> BezierCurve {
+    BezierCurve::new(
+        Vector2::new(0.0, 0.0),
+        Vector2::new(length / 3.0, 0.0),
+        Vector2::new(length * 2.0 / 3.0, 0.0),
+        Vector2::new(length, 0.0))
+}
+
+fn close(a: Vector2, b: Vector2) -> bool {
+    (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3
+}
+
+#[test]
+fn straight_line_length() {
+    let table = straight_line(3.0).arc_length_table();
+    assert!((table.length() - 3.0).abs() < 1e-4);
+}
+
+#[test]
+fn distances_past_the_ends_are_clamped() {
+    let table = straight_line(2.0).arc_length_table();
+    assert_eq!(table.t_at_distance(-1.0), 0.0);
+    assert_eq!(table.t_at_distance(5.0), 1.0);
+    assert!(close(table.evaluate_at_distance(5.0), Vector2::new(2.0, 0.0)));
+}
+
+#[test]
+fn equal_distances_on_an_uneven_curve() {
+    // Control points bunched at the start, t covers little distance there
+    let curve = BezierCurve::new(
+        Vector2::new(0.0, 0.0),
+        Vector2::new(0.1, 0.0),
+        Vector2::new(0.2, 0.0),
+        Vector2::new(3.0, 0.0));
+    let table = curve.arc_length_table();
+
+    let point = table.evaluate_at_distance(1.5);
+    assert!((point.x - 1.5).abs() < 0.01);
+    assert!(table.t_at_distance(1.5) > 0.5);
+}
+
+#[test]
+fn evenly_spaced_points_include_both_ends() {
+    let table = straight_line(3.0).arc_length_table();
+    let points = table.evenly_spaced_points(4);
+
+    assert_eq!(points.len(), 4);
+    for (index, point) in points.iter().enumerate() {
+        assert!(close(*point, Vector2::new(index as f32, 0.0)));
+    }
+}
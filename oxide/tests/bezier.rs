@@ -0,0 +1,250 @@
+use oxide::{BezierCurve, QuadraticBezier, Vector2};
+
+fn curve() -> BezierCurve {
+    BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 3.0), Vector2::new(4.0, 3.0), Vector2::new(5.0, -1.0))
+}
+
+fn assert_close(a: Vector2, b: Vector2) {
+    assert!((a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn split_halves_trace_the_same_curve() {
+    let curve = curve();
+    let (first, second) = curve.split(0.3);
+
+    assert_close(first.p3, curve.evaluate(0.3));
+    assert_eq!(first.p3, second.p0);
+    for i in 0..=10 {
+        let t = i as f32 / 10.0;
+        assert_close(first.evaluate(t), curve.evaluate(0.3 * t));
+        assert_close(second.evaluate(t), curve.evaluate(0.3 + 0.7 * t));
+    }
+}
+
+#[test]
+fn split_at_the_ends_leaves_an_empty_half() {
+    let curve = curve();
+
+    let (empty, whole) = curve.split(0.0);
+    assert_eq!(whole, curve);
+    assert!([empty.p1, empty.p2, empty.p3].iter().all(|point| *point == curve.p0));
+
+    let (whole, empty) = curve.split(1.0);
+    assert_eq!(whole, curve);
+    assert!([empty.p0, empty.p1, empty.p2].iter().all(|point| *point == curve.p3));
+}
+
+#[test]
+fn split_clamps_t_to_the_curve() {
+    let curve = curve();
+    assert_eq!(curve.split(-0.5), curve.split(0.0));
+    assert_eq!(curve.split(1.5), curve.split(1.0));
+}
+
+#[test]
+fn quadratic_split_halves_trace_the_same_curve() {
+    let curve = QuadraticBezier::new(Vector2::new(0.0, 0.0), Vector2::new(2.0, 4.0), Vector2::new(4.0, 0.0));
+    let (first, second) = curve.split(0.5);
+
+    assert_eq!(first.p2, Vector2::new(2.0, 2.0));
+    assert_eq!(first.p2, second.p0);
+    for i in 0..=10 {
+        let t = i as f32 / 10.0;
+        assert_close(first.evaluate(t), curve.evaluate(0.5 * t));
+        assert_close(second.evaluate(t), curve.evaluate(0.5 + 0.5 * t));
+    }
+
+    let (empty, whole) = curve.split(0.0);
+    assert_eq!(whole, curve);
+    assert_eq!(empty.p2, curve.p0);
+    let (whole, empty) = curve.split(1.0);
+    assert_eq!(whole, curve);
+    assert_eq!(empty.p0, curve.p2);
+}
+
+// Distance from the point to the nearest of the lines joining the points
+fn distance_to_polyline(point: Vector2, points: &[Vector2]) -> f32 {
+    let mut best = f32::MAX;
+    for pair in points.windows(2) {
+        let edge = pair[1] - pair[0];
+        let length_squared = edge.x * edge.x + edge.y * edge.y;
+        let t = if length_squared > 0.0 {
+            (((point.x - pair[0].x) * edge.x + (point.y - pair[0].y) * edge.y) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let closest = pair[0] + edge * t;
+        best = best.min(((point.x - closest.x).powi(2) + (point.y - closest.y).powi(2)).sqrt());
+    }
+    best
+}
+
+#[test]
+fn flatten_stays_within_the_tolerance() {
+    let curve = curve();
+    for tolerance in [0.5, 0.1, 0.01] {
+        let points = curve.flatten(tolerance);
+        assert_eq!(points[0], curve.p0);
+        assert_eq!(*points.last().unwrap(), curve.p3);
+        for i in 0..=200 {
+            let point = curve.evaluate(i as f32 / 200.0);
+            assert!(distance_to_polyline(point, &points) <= tolerance, "{} at {}", tolerance, i);
+        }
+    }
+}
+
+#[test]
+fn flatten_adds_points_where_the_curve_bends() {
+    let curve = curve();
+    assert!(curve.flatten(0.01).len() > curve.flatten(0.1).len());
+
+    let line = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0), Vector2::new(2.0, 2.0), Vector2::new(3.0, 3.0));
+    assert_eq!(line.flatten(0.01), vec![line.p0, line.p3]);
+}
+
+#[test]
+fn flatten_without_tolerance_still_stops() {
+    let points = curve().flatten(0.0);
+    assert!(points.len() > 2 && points.len() <= (1 << 16) + 1);
+    assert_eq!(curve().flatten(-1.0), points);
+}
+
+// Symmetric arch from (0, 0) to (4, 0), y is 12 t (1 - t) and peaks at (2, 3)
+fn arch() -> BezierCurve {
+    BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(0.0, 4.0), Vector2::new(4.0, 4.0), Vector2::new(4.0, 0.0))
+}
+
+#[test]
+fn line_across_an_arch_hits_both_sides_in_order() {
+    let curve = arch();
+    let hits = curve.intersect_line(Vector2::new(-1.0, 1.5), Vector2::new(5.0, 1.5));
+
+    assert_eq!(hits.len(), 2);
+    let half_root = 0.5_f32.sqrt() / 2.0;
+    assert!((hits[0].0 - (0.5 - half_root)).abs() < 1e-4);
+    assert!((hits[1].0 - (0.5 + half_root)).abs() < 1e-4);
+    for (t, point) in hits {
+        assert_close(point, curve.evaluate(t));
+        assert!((point.y - 1.5).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn tangent_line_touches_once() {
+    let hits = arch().intersect_line(Vector2::new(0.0, 3.0), Vector2::new(4.0, 3.0));
+    assert_eq!(hits.len(), 1);
+    assert!((hits[0].0 - 0.5).abs() < 1e-3);
+    assert_close(hits[0].1, Vector2::new(2.0, 3.0));
+
+    // Just above the peak misses
+    assert!(arch().intersect_line(Vector2::new(0.0, 3.01), Vector2::new(4.0, 3.01)).is_empty());
+}
+
+#[test]
+fn segment_has_to_reach_the_curve() {
+    let curve = arch();
+    // On the line through both crossings, but short of them
+    assert!(curve.intersect_line(Vector2::new(1.0, 1.5), Vector2::new(3.0, 1.5)).is_empty());
+    assert_eq!(curve.intersect_line(Vector2::new(-1.0, 1.5), Vector2::new(2.0, 1.5)).len(), 1);
+    // No direction to cross
+    assert!(curve.intersect_line(Vector2::new(1.0, 1.5), Vector2::new(1.0, 1.5)).is_empty());
+}
+
+#[test]
+fn touching_an_end_point_counts() {
+    let hits = arch().intersect_line(Vector2::new(0.0, -1.0), Vector2::new(0.0, 0.0));
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].0, 0.0);
+    assert_close(hits[0].1, Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn running_along_a_straight_curve_does_not_count() {
+    let line = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(2.0, 0.0), Vector2::new(3.0, 0.0));
+    assert!(line.intersect_line(Vector2::new(-1.0, 0.0), Vector2::new(4.0, 0.0)).is_empty());
+
+    let hits = line.intersect_line(Vector2::new(1.5, -1.0), Vector2::new(1.5, 1.0));
+    assert_eq!(hits.len(), 1);
+    assert_close(hits[0].1, Vector2::new(1.5, 0.0));
+}
+
+// Quarter circle of the radius around the origin, from (radius, 0) to (0, radius)
+fn quarter_circle(radius: f32) -> BezierCurve {
+    let handle = 0.5523 * radius;
+    BezierCurve::new(Vector2::new(radius, 0.0), Vector2::new(radius, handle), Vector2::new(handle, radius), Vector2::new(0.0, radius))
+}
+
+#[test]
+fn curvature_of_a_circle_is_one_over_its_radius() {
+    for radius in [1.0, 4.0] {
+        let curve = quarter_circle(radius);
+        // The cubic is only close to a circle, it's about 2% flatter at the ends
+        for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            assert!((curve.curvature(t) - 1.0 / radius).abs() < 0.03 / radius, "{} at {}", radius, t);
+        }
+    }
+}
+
+#[test]
+fn curvature_sign_follows_the_direction() {
+    let curve = quarter_circle(2.0);
+    let reversed = BezierCurve::new(curve.p3, curve.p2, curve.p1, curve.p0);
+    assert!((reversed.curvature(0.5) + curve.curvature(0.5)).abs() < 1e-4);
+    assert!(reversed.curvature(0.5) < 0.0);
+}
+
+#[test]
+fn curvature_is_zero_on_lines_and_where_the_curve_stops() {
+    let line = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0), Vector2::new(2.0, 2.0), Vector2::new(3.0, 3.0));
+    assert_eq!(line.curvature(0.3), 0.0);
+
+    // The first control point sits on the start, so there is no direction there
+    let stopped = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(0.0, 0.0), Vector2::new(2.0, 1.0), Vector2::new(3.0, 0.0));
+    assert_eq!(stopped.curvature(0.0), 0.0);
+}
+
+#[test]
+fn nearest_point_on_the_curve_is_itself() {
+    let curve = curve();
+    for t in [0.0, 0.2, 0.5, 0.9, 1.0] {
+        let (found_t, distance) = curve.nearest(curve.evaluate(t));
+        assert!((found_t - t).abs() < 1e-3, "{} found at {}", t, found_t);
+        assert!(distance < 1e-3);
+    }
+}
+
+#[test]
+fn nearest_is_straight_across_from_a_line() {
+    let line = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(2.0, 0.0), Vector2::new(3.0, 0.0));
+    let (t, distance) = line.nearest(Vector2::new(1.2, 2.0));
+    // The distance barely changes near the closest point, so t is only found roughly
+    assert!((line.evaluate(t).x - 1.2).abs() < 1e-3);
+    assert!((distance - 2.0).abs() < 1e-4);
+}
+
+#[test]
+fn nearest_past_the_ends_is_the_end_point() {
+    let line = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(2.0, 0.0), Vector2::new(3.0, 0.0));
+    assert_eq!(line.nearest(Vector2::new(-3.0, 4.0)), (0.0, 5.0));
+    let (t, distance) = line.nearest(Vector2::new(7.0, 3.0));
+    assert_eq!(t, 1.0);
+    assert!((distance - 5.0).abs() < 1e-4);
+}
+
+#[test]
+fn nearest_finds_the_peak_of_an_arch() {
+    let (t, distance) = arch().nearest(Vector2::new(2.0, 10.0));
+    assert!((t - 0.5).abs() < 1e-3);
+    assert!((distance - 7.0).abs() < 1e-3);
+}
+
+#[test]
+fn hit_test_needs_to_be_within_the_threshold() {
+    let curve = arch();
+    let hit = curve.hit_test(Vector2::new(2.0, 3.2), 0.25);
+    assert!((hit.unwrap() - 0.5).abs() < 1e-3);
+    assert_eq!(curve.hit_test(Vector2::new(2.0, 3.5), 0.25), None);
+    // Inside the arch, far from every part of it
+    assert_eq!(curve.hit_test(Vector2::new(2.0, 1.0), 0.25), None);
+}
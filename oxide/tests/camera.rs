@@ -0,0 +1,127 @@
+use oxide::{
+    screen_space_to_world_space, screen_space_to_world_space_f32, world_space_to_pixel, world_space_to_screen_space,
+    world_space_to_screen_space_f32, Camera, ClipRect, Vector2, Vector2i32, Vector2u32
+};
+
+// Sized the way the platform does it, to fill an 800 by 600 buffer with 8 world units of height
+fn camera(x: f32, y: f32) -> Camera {
+    let y_scale = 600.0 / 8.0;
+    Camera { x, y, width: 800.0 / y_scale, height: 8.0, y_scale, rotation: 0.0, screen_offset: Vector2::new(0.0, 0.0), y_up: false }
+}
+
+fn assert_close(a: Vector2, b: Vector2) {
+    assert!((a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn camera_position_is_the_middle_of_the_screen() {
+    for (x, y) in [(0.0, 0.0), (3.5, -2.25), (-100.0, 40.0)] {
+        let camera = camera(x, y);
+        assert_close(world_space_to_screen_space_f32(camera, Vector2::new(x, y)), Vector2::new(400.0, 300.0));
+        assert_eq!(world_space_to_screen_space(camera, Vector2::new(x, y)), Vector2i32::new(400, 300));
+        assert_close(screen_space_to_world_space(camera, Vector2u32 { x: 400, y: 300 }), Vector2::new(x, y));
+    }
+}
+
+#[test]
+fn screen_corners_are_the_edges_of_the_view() {
+    let camera = camera(1.0, 2.0);
+    let view = camera.get_bounding_box();
+    assert_close(screen_space_to_world_space_f32(camera, Vector2::new(0.0, 0.0)), Vector2::new(view.x, view.y));
+    assert_close(
+        screen_space_to_world_space_f32(camera, Vector2::new(800.0, 600.0)),
+        Vector2::new(view.x + view.width, view.y + view.height));
+}
+
+#[test]
+fn world_to_screen_and_back_round_trips() {
+    let camera = camera(-1.25, 0.75);
+    for (x, y) in [(0.0, 0.0), (-1.25, 0.75), (2.0, -3.5), (-6.0, 4.0), (100.0, -50.0)] {
+        let world = Vector2::new(x, y);
+        let screen = world_space_to_screen_space_f32(camera, world);
+        assert_close(screen_space_to_world_space_f32(camera, screen), world);
+    }
+}
+
+#[test]
+fn pixels_round_trip_through_world_space() {
+    let camera = camera(0.3, -0.7);
+    for (x, y) in [(0, 0), (1, 1), (399, 300), (400, 301), (799, 599)] {
+        let pixel = Vector2u32 { x, y };
+        let world = screen_space_to_world_space(camera, pixel);
+        assert_close(world_space_to_screen_space_f32(camera, world), Vector2::new(x as f32, y as f32));
+    }
+}
+
+#[test]
+fn moving_the_camera_moves_the_screen_the_other_way() {
+    let world = Vector2::new(1.0, 1.0);
+    let before = world_space_to_screen_space_f32(camera(0.0, 0.0), world);
+    let after = world_space_to_screen_space_f32(camera(1.0, -1.0), world);
+    assert_close(after - before, Vector2::new(-75.0, 75.0));
+}
+
+#[test]
+fn rotated_camera_round_trips_around_the_middle() {
+    let camera = Camera { rotation: 0.6, ..camera(2.0, -1.0) };
+    assert_close(world_space_to_screen_space_f32(camera, Vector2::new(2.0, -1.0)), Vector2::new(400.0, 300.0));
+    for (x, y) in [(0.0, 0.0), (5.0, 3.0), (-4.0, 2.5)] {
+        let world = Vector2::new(x, y);
+        let screen = world_space_to_screen_space_f32(camera, world);
+        assert_close(screen_space_to_world_space_f32(camera, screen), world);
+    }
+}
+
+#[test]
+fn quarter_turn_camera_shows_the_world_turned_back() {
+    // Turned a quarter clockwise, what was to the right of the middle is now above it on screen
+    let camera = Camera { rotation: std::f32::consts::FRAC_PI_2, ..camera(0.0, 0.0) };
+    assert_close(world_space_to_screen_space_f32(camera, Vector2::new(1.0, 0.0)), Vector2::new(400.0, 225.0));
+    assert_close(world_space_to_screen_space_f32(camera, Vector2::new(0.0, 1.0)), Vector2::new(475.0, 300.0));
+}
+
+#[test]
+fn y_up_camera_puts_positive_y_above_the_middle() {
+    let camera = Camera { y_up: true, ..camera(1.0, 1.0) };
+    assert_close(world_space_to_screen_space_f32(camera, Vector2::new(1.0, 2.0)), Vector2::new(400.0, 225.0));
+    assert_close(world_space_to_screen_space_f32(camera, Vector2::new(2.0, 1.0)), Vector2::new(475.0, 300.0));
+
+    let rotated = Camera { rotation: 0.4, ..camera };
+    for (x, y) in [(0.0, 0.0), (3.0, -2.0), (-5.0, 1.5)] {
+        let world = Vector2::new(x, y);
+        assert_close(screen_space_to_world_space_f32(rotated, world_space_to_screen_space_f32(rotated, world)), world);
+    }
+}
+
+#[test]
+fn points_off_the_screen_stay_negative() {
+    let camera = camera(0.0, 0.0);
+    // Further left and above the screen than the camera can see
+    let point = Vector2::new(-6.0, -5.0);
+    assert_eq!(world_space_to_screen_space(camera, point), Vector2i32::new(-50, -75));
+    assert_eq!(world_space_to_screen_space(camera, point).to_buffer_pixel(800, 600), None);
+    assert_eq!(world_space_to_screen_space(camera, point).clamp_to(ClipRect::new(0, 0, 800, 600)), Vector2i32::new(0, 0));
+}
+
+#[test]
+fn screen_positions_round_and_pixels_floor() {
+    let camera = camera(0.0, 0.0);
+    // 0.7 of a pixel right and 0.3 of a pixel down from the middle, and the same up and left
+    let right = Vector2::new(0.7 / 75.0, 0.3 / 75.0);
+    assert_eq!(world_space_to_screen_space(camera, right), Vector2i32::new(401, 300));
+    assert_eq!(world_space_to_pixel(camera, right), Vector2i32::new(400, 300));
+    assert_eq!(world_space_to_pixel(camera, right * -1.0), Vector2i32::new(399, 299));
+    assert_eq!(world_space_to_pixel(camera, right).to_buffer_pixel(800, 600), Some(Vector2u32 { x: 400, y: 300 }));
+}
+
+#[test]
+fn screen_transform_matches_the_point_mapping() {
+    let offset = Camera { screen_offset: Vector2::new(10.0, -20.0), ..camera(0.5, -1.5) };
+    for camera in [offset, Camera { rotation: 0.6, ..offset }, Camera { rotation: -1.1, y_up: true, ..offset }] {
+        let transform = camera.screen_transform();
+        for (x, y) in [(0.0, 0.0), (0.5, -1.5), (3.0, 2.0), (-4.5, 1.25)] {
+            let world = Vector2::new(x, y);
+            assert_close(transform.apply(world), world_space_to_screen_space_f32(camera, world));
+        }
+    }
+}
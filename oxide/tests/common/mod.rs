@@ -0,0 +1,32 @@
+// Fixture shared by the drawing tests, not every test uses every helper
+#![allow(dead_code)]
+
+use oxide::target::RenderTarget;
+use oxide::Camera;
+
+// One world unit per pixel with world (0, 0) at the top left corner of the buffer, so test
+// shapes can be given in pixels
+pub fn pixel_camera(width: u32, height: u32) -> Camera {
+    Camera::new(width as f32 / 2.0, height as f32 / 2.0, width as f32, height as f32)
+}
+
+// (x, y) of every white pixel, in the order they're stored
+pub fn lit_pixels(target: &RenderTarget) -> Vec<(u32, u32)> {
+    pixels_where(target, |pixel| pixel == 0xFFFFFFFF)
+}
+
+// (x, y) of every pixel that isn't black, whatever it was drawn with
+pub fn drawn_pixels(target: &RenderTarget) -> Vec<(u32, u32)> {
+    pixels_where(target, |pixel| pixel & 0xFFFFFF != 0)
+}
+
+fn pixels_where(target: &RenderTarget, keep: impl Fn(u32) -> bool) -> Vec<(u32, u32)> {
+    let width = target.width();
+    let mut found = Vec::new();
+    for (index, pixel) in target.pixels().iter().enumerate() {
+        if keep(*pixel) {
+            found.push((index as u32 % width, index as u32 / width));
+        }
+    }
+    found
+}
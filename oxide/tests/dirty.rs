@@ -0,0 +1,115 @@
+mod common;
+
+use common::pixel_camera;
+use oxide::render::{DirtyRegions, Layer, RenderCommand, RenderCommands};
+use oxide::target::RenderTarget;
+use oxide::{ClipRect, Color, Rectangle};
+
+fn frame(rectangles: &[Rectangle]) -> RenderCommands {
+    let mut commands = RenderCommands::new();
+    for rectangle in rectangles {
+        commands.push(Layer::World, RenderCommand::DrawRect { camera: pixel_camera(64, 64), rectangle: *rectangle, color: Color::WHITE });
+    }
+    commands
+}
+
+fn covers(regions: &[ClipRect], x0: i32, y0: i32, x1: i32, y1: i32) -> bool {
+    (y0..y1).all(|y| (x0..x1).all(|x| regions.iter().any(|region| region.contains(x, y))))
+}
+
+fn whole_buffer() -> Vec<ClipRect> {
+    vec![ClipRect::new(0, 0, 64, 64)]
+}
+
+#[test]
+fn first_frame_is_all_dirty() {
+    let mut target = RenderTarget::new(64, 64);
+    let mut dirty = DirtyRegions::default();
+    dirty.update(&frame(&[Rectangle::new(2.0, 2.0, 4.0, 4.0)]), target.buffer());
+    assert_eq!(dirty.regions(), whole_buffer().as_slice());
+}
+
+#[test]
+fn unchanged_frame_is_clean() {
+    let mut target = RenderTarget::new(64, 64);
+    let mut dirty = DirtyRegions::default();
+    let commands = frame(&[Rectangle::new(2.0, 2.0, 4.0, 4.0)]);
+    dirty.update(&commands, target.buffer());
+    dirty.update(&commands, target.buffer());
+    assert!(dirty.regions().is_empty());
+}
+
+#[test]
+fn moved_command_dirties_where_it_was_and_where_it_is() {
+    let mut target = RenderTarget::new(64, 64);
+    let mut dirty = DirtyRegions::default();
+    dirty.update(&frame(&[Rectangle::new(2.0, 2.0, 4.0, 4.0), Rectangle::new(40.0, 40.0, 4.0, 4.0)]), target.buffer());
+    dirty.update(&frame(&[Rectangle::new(10.0, 2.0, 4.0, 4.0), Rectangle::new(40.0, 40.0, 4.0, 4.0)]), target.buffer());
+
+    let regions = dirty.regions();
+    assert!(covers(regions, 2, 2, 6, 6));
+    assert!(covers(regions, 10, 2, 14, 6));
+    // The rect that stayed put isn't redrawn
+    assert!(!regions.iter().any(|region| region.contains(41, 41)));
+}
+
+#[test]
+fn removed_command_dirties_where_it_was() {
+    let mut target = RenderTarget::new(64, 64);
+    let mut dirty = DirtyRegions::default();
+    dirty.update(&frame(&[Rectangle::new(2.0, 2.0, 4.0, 4.0), Rectangle::new(40.0, 40.0, 4.0, 4.0)]), target.buffer());
+    dirty.update(&frame(&[Rectangle::new(2.0, 2.0, 4.0, 4.0)]), target.buffer());
+
+    assert!(covers(dirty.regions(), 40, 40, 44, 44));
+    assert!(!dirty.regions().iter().any(|region| region.contains(3, 3)));
+}
+
+#[test]
+fn dirty_regions_do_not_overlap() {
+    let mut target = RenderTarget::new(64, 64);
+    let mut dirty = DirtyRegions::default();
+    dirty.update(&frame(&[Rectangle::new(2.0, 2.0, 8.0, 8.0), Rectangle::new(30.0, 30.0, 4.0, 4.0)]), target.buffer());
+    // Both move a little, so each old and new rect overlaps
+    dirty.update(&frame(&[Rectangle::new(5.0, 5.0, 8.0, 8.0), Rectangle::new(31.0, 30.0, 4.0, 4.0)]), target.buffer());
+
+    let regions = dirty.regions();
+    assert_eq!(regions.len(), 2);
+    for (index, region) in regions.iter().enumerate() {
+        for other in &regions[index + 1..] {
+            assert!(!region.overlaps(*other));
+        }
+    }
+    assert!(covers(regions, 2, 2, 13, 13));
+}
+
+#[test]
+fn many_changes_become_one_region() {
+    let mut target = RenderTarget::new(64, 64);
+    let mut dirty = DirtyRegions::default();
+    // More separate changes than are worth presenting one by one
+    let before: Vec<Rectangle> = (0..20).map(|i| Rectangle::new((i % 5) as f32 * 12.0, (i / 5) as f32 * 12.0, 2.0, 2.0)).collect();
+    let after: Vec<Rectangle> = before.iter().map(|rectangle| Rectangle::new(rectangle.x + 5.0, rectangle.y + 5.0, 2.0, 2.0)).collect();
+    dirty.update(&frame(&before), target.buffer());
+    dirty.update(&frame(&after), target.buffer());
+
+    assert_eq!(dirty.regions().len(), 1);
+    assert!(covers(dirty.regions(), 0, 0, 55, 43));
+}
+
+#[test]
+fn invalidate_and_new_buffers_redraw_everything() {
+    let mut target = RenderTarget::new(64, 64);
+    let mut dirty = DirtyRegions::default();
+    let commands = frame(&[Rectangle::new(2.0, 2.0, 4.0, 4.0)]);
+    dirty.update(&commands, target.buffer());
+
+    dirty.invalidate();
+    dirty.update(&commands, target.buffer());
+    assert_eq!(dirty.regions(), whole_buffer().as_slice());
+    dirty.update(&commands, target.buffer());
+    assert!(dirty.regions().is_empty());
+
+    let mut resized = RenderTarget::new(32, 32);
+    dirty.update(&commands, resized.buffer());
+    assert_eq!(dirty.regions(), &[ClipRect::new(0, 0, 32, 32)]);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{lit_pixels, pixel_camera};
+use oxide::target::RenderTarget;
+use oxide::{draw_line, Color, Vector2};
+
+static SIZE: u32 = 8;
+
+// Pixels lit by a white line between the pixel centers, the camera maps world units to pixels
+fn line_pixels(from: (f32, f32), to: (f32, f32)) -> Vec<(u32, u32)> {
+    let mut target = RenderTarget::new(SIZE, SIZE);
+    let camera = pixel_camera(SIZE, SIZE);
+    let from = Vector2::new(from.0 + 0.5, from.1 + 0.5);
+    let to = Vector2::new(to.0 + 0.5, to.1 + 0.5);
+    unsafe { draw_line(target.buffer(), camera, from, to, Color::WHITE); }
+    lit_pixels(&target)
+}
+
+#[test]
+fn line_inside_covers_both_endpoints() {
+    assert_eq!(line_pixels((1.0, 2.0), (5.0, 2.0)), vec![(1, 2), (2, 2), (3, 2), (4, 2), (5, 2)]);
+    assert_eq!(line_pixels((3.0, 1.0), (3.0, 4.0)), vec![(3, 1), (3, 2), (3, 3), (3, 4)]);
+    assert_eq!(line_pixels((0.0, 0.0), (3.0, 3.0)), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+}
+
+#[test]
+fn direction_does_not_change_the_pixels() {
+    assert_eq!(line_pixels((1.0, 1.0), (6.0, 3.0)), line_pixels((6.0, 3.0), (1.0, 1.0)));
+}
+
+#[test]
+fn line_fully_outside_draws_nothing() {
+    // Both ends past the same edge
+    assert!(line_pixels((-5.0, -3.0), (-1.0, -8.0)).is_empty());
+    assert!(line_pixels((10.0, 0.0), (12.0, 7.0)).is_empty());
+    // Ends past different edges, the line still misses the corner
+    assert!(line_pixels((-4.0, 1.0), (1.0, -4.0)).is_empty());
+}
+
+#[test]
+fn line_partly_outside_is_clipped_to_the_buffer() {
+    assert_eq!(line_pixels((-4.0, 3.0), (3.0, 3.0)), vec![(0, 3), (1, 3), (2, 3), (3, 3)]);
+    assert_eq!(line_pixels((5.0, 6.0), (5.0, 20.0)), vec![(5, 6), (5, 7)]);
+
+    let diagonal = line_pixels((-2.0, -2.0), (10.0, 10.0));
+    assert_eq!(diagonal, (0..SIZE).map(|i| (i, i)).collect::<Vec<_>>());
+}
+
+#[test]
+fn line_through_the_whole_buffer() {
+    let pixels = line_pixels((-20.0, 4.0), (20.0, 4.0));
+    assert_eq!(pixels, (0..SIZE).map(|x| (x, 4)).collect::<Vec<_>>());
+}
+
+#[test]
+fn zero_length_line_is_a_single_pixel() {
+    assert_eq!(line_pixels((4.0, 5.0), (4.0, 5.0)), vec![(4, 5)]);
+    assert_eq!(line_pixels((0.0, 7.0), (0.0, 7.0)), vec![(0, 7)]);
+    assert!(line_pixels((20.0, 20.0), (20.0, 20.0)).is_empty());
+}
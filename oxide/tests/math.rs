@@ -0,0 +1,93 @@
+use std::f32::consts::PI;
+use oxide::math::{
+    angle_difference, clamp, degrees_to_radians, fract, inverse_lerp, lerp, radians_to_degrees, remap, smoothstep,
+    wrap_angle, wrap_degrees
+};
+
+fn assert_close(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+}
+
+#[test]
+fn lerp_and_inverse_lerp() {
+    assert_eq!(lerp(2.0, 6.0, 0.0), 2.0);
+    assert_eq!(lerp(2.0, 6.0, 1.0), 6.0);
+    assert_eq!(lerp(2.0, 6.0, 0.25), 3.0);
+    // t isn't clamped
+    assert_eq!(lerp(2.0, 6.0, 1.5), 8.0);
+
+    assert_eq!(inverse_lerp(2.0, 6.0, 3.0), 0.25);
+    assert_eq!(inverse_lerp(6.0, 2.0, 3.0), 0.75);
+    assert_eq!(inverse_lerp(4.0, 4.0, 10.0), 0.0);
+}
+
+#[test]
+fn remap_keeps_the_relative_position() {
+    assert_eq!(remap(5.0, 0.0, 10.0, 100.0, 200.0), 150.0);
+    assert_eq!(remap(0.0, -1.0, 1.0, 1.0, 0.0), 0.5);
+    assert_eq!(remap(20.0, 0.0, 10.0, 0.0, 1.0), 2.0);
+}
+
+#[test]
+fn clamp_with_min_past_max_gives_min() {
+    assert_eq!(clamp(5.0, 0.0, 1.0), 1.0);
+    assert_eq!(clamp(-5.0, 0.0, 1.0), 0.0);
+    assert_eq!(clamp(0.5, 0.0, 1.0), 0.5);
+    assert_eq!(clamp(0.5, 1.0, 0.0), 1.0);
+}
+
+#[test]
+fn smoothstep_is_flat_at_the_edges() {
+    assert_eq!(smoothstep(1.0, 3.0, 0.0), 0.0);
+    assert_eq!(smoothstep(1.0, 3.0, 1.0), 0.0);
+    assert_eq!(smoothstep(1.0, 3.0, 2.0), 0.5);
+    assert_eq!(smoothstep(1.0, 3.0, 3.0), 1.0);
+    assert_eq!(smoothstep(1.0, 3.0, 9.0), 1.0);
+    // Slow near the edges, faster than a straight line in the middle
+    assert!(smoothstep(1.0, 3.0, 1.1) < 0.05);
+    assert!(smoothstep(1.0, 3.0, 2.2) > 0.6);
+}
+
+#[test]
+fn fract_is_always_below_one() {
+    assert_close(fract(2.75), 0.75);
+    assert_close(fract(-0.25), 0.75);
+    assert_eq!(fract(3.0), 0.0);
+    assert_eq!(fract(-3.0), 0.0);
+    // Closer to the whole number above than an f32 can tell apart from it
+    let tiny = fract(-1e-9);
+    assert!((0.0..1.0).contains(&tiny), "{}", tiny);
+}
+
+#[test]
+fn angles_wrap_into_a_half_turn_either_way() {
+    assert_close(wrap_angle(0.5), 0.5);
+    assert_close(wrap_angle(0.5 + 4.0 * PI), 0.5);
+    assert_close(wrap_angle(-0.5 - 2.0 * PI), -0.5);
+    assert_close(wrap_angle(1.5 * PI), -0.5 * PI);
+    for value in [-1e-9, 1e-9, PI, -PI, 3.0 * PI, -7.0] {
+        let wrapped = wrap_angle(value);
+        assert!((-PI..PI).contains(&wrapped), "{} gave {}", value, wrapped);
+    }
+
+    assert_close(wrap_degrees(190.0), -170.0);
+    assert_close(wrap_degrees(-540.0), -180.0);
+    assert_close(wrap_degrees(720.5), 0.5);
+    assert!((-180.0..180.0).contains(&wrap_degrees(-1e-6)));
+}
+
+#[test]
+fn angle_difference_takes_the_short_way_round() {
+    assert_close(angle_difference(0.1, 0.3), 0.2);
+    assert_close(angle_difference(0.3, 0.1), -0.2);
+    // Across the wrap from just under a full turn to just over zero
+    assert_close(angle_difference(2.0 * PI - 0.1, 0.1), 0.2);
+    assert_close(angle_difference(0.1, 2.0 * PI - 0.1), -0.2);
+}
+
+#[test]
+fn degrees_and_radians_convert_both_ways() {
+    assert_close(degrees_to_radians(180.0), PI);
+    assert_close(radians_to_degrees(PI / 2.0), 90.0);
+    assert_close(radians_to_degrees(degrees_to_radians(-37.5)), -37.5);
+}
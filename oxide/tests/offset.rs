@@ -0,0 +1,65 @@
+use oxide::{BezierCurve, Vector2};
+
+fn distance(a: Vector2, b: Vector2) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+// Every piece stays the distance away from the curve, give or take the tolerance
+fn assert_offset(curve: &BezierCurve, pieces: &[BezierCurve], distance: f32, tolerance: f32) {
+    for piece in pieces {
+        for i in 0..=20 {
+            let (_, found) = curve.nearest(piece.evaluate(i as f32 / 20.0));
+            assert!((found - distance.abs()).abs() <= tolerance * 2.0, "{} away instead of {}", found, distance.abs());
+        }
+    }
+}
+
+fn assert_connected(pieces: &[BezierCurve]) {
+    for pair in pieces.windows(2) {
+        assert!(distance(pair[0].p3, pair[1].p0) < 1e-3, "{:?} to {:?}", pair[0].p3, pair[1].p0);
+    }
+}
+
+#[test]
+fn zero_distance_is_the_curve() {
+    let curve = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 2.0), Vector2::new(3.0, 2.0), Vector2::new(4.0, 0.0));
+    assert_eq!(curve.offset(0.0, 0.01), vec![curve]);
+}
+
+#[test]
+fn positive_distance_goes_right_of_the_heading() {
+    // Heading along x with y pointing down, so right is +y
+    let line = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(2.0, 0.0), Vector2::new(3.0, 0.0));
+    for (offset, y) in [(0.5, 0.5), (-0.5, -0.5)] {
+        let pieces = line.offset(offset, 0.01);
+        assert!(!pieces.is_empty());
+        assert!(distance(pieces[0].p0, Vector2::new(0.0, y)) < 1e-4);
+        assert!(distance(pieces.last().unwrap().p3, Vector2::new(3.0, y)) < 1e-4);
+        assert_offset(&line, &pieces, offset, 0.01);
+    }
+}
+
+#[test]
+fn offset_of_a_bend_stays_within_tolerance() {
+    let curve = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 3.0), Vector2::new(4.0, 3.0), Vector2::new(5.0, -1.0));
+    for offset in [0.3, -0.3] {
+        let pieces = curve.offset(offset, 0.01);
+        assert_offset(&curve, &pieces, offset, 0.01);
+        assert_connected(&pieces);
+    }
+
+    // A looser tolerance needs fewer pieces
+    assert!(curve.offset(0.3, 0.1).len() <= curve.offset(0.3, 0.001).len());
+}
+
+#[test]
+fn loop_inside_a_tight_bend_is_cut_off() {
+    // Bends sharply around (2, 2), an inner offset wider than the bend would loop back there
+    let curve = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(4.0, 4.0), Vector2::new(4.0, 4.0), Vector2::new(0.0, 4.0));
+    let inside = if curve.curvature(0.5) > 0.0 { -1.0 } else { 1.0 };
+    let pieces = curve.offset(inside, 0.01);
+
+    assert!(!pieces.is_empty());
+    assert_offset(&curve, &pieces, inside, 0.01);
+    assert_connected(&pieces);
+}
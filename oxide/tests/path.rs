@@ -0,0 +1,173 @@
+mod common;
+
+use common::{lit_pixels, pixel_camera};
+use oxide::path::{fill_path, FillRule, Path, PathSegment};
+use oxide::target::RenderTarget;
+use oxide::{BezierCurve, Color, Vector2};
+
+// Pixels lit by filling the path white
+fn fill(size: u32, path: &Path, rule: FillRule) -> Vec<(u32, u32)> {
+    let mut target = RenderTarget::new(size, size);
+    unsafe { fill_path(target.buffer(), pixel_camera(size, size), path, Color::WHITE, rule); }
+    lit_pixels(&target)
+}
+
+fn square(path: &mut Path, x0: f32, y0: f32, x1: f32, y1: f32, clockwise: bool) {
+    path.move_to(Vector2::new(x0, y0));
+    if clockwise {
+        path.line_to(Vector2::new(x1, y0)).line_to(Vector2::new(x1, y1)).line_to(Vector2::new(x0, y1));
+    } else {
+        path.line_to(Vector2::new(x0, y1)).line_to(Vector2::new(x1, y1)).line_to(Vector2::new(x1, y0));
+    }
+    path.close();
+}
+
+#[test]
+fn segments_start_where_the_previous_one_ended() {
+    let mut path = Path::new();
+    path.move_to(Vector2::new(1.0, 1.0)).line_to(Vector2::new(4.0, 1.0)).cubic_to(
+        Vector2::new(5.0, 2.0), Vector2::new(5.0, 3.0), Vector2::new(4.0, 4.0));
+
+    assert_eq!(path.sub_paths.len(), 1);
+    let segments = &path.sub_paths[0].segments;
+    assert_eq!(segments[0], PathSegment::Line { from: Vector2::new(1.0, 1.0), to: Vector2::new(4.0, 1.0) });
+    assert_eq!(segments[1], PathSegment::Cubic(BezierCurve::new(
+        Vector2::new(4.0, 1.0), Vector2::new(5.0, 2.0), Vector2::new(5.0, 3.0), Vector2::new(4.0, 4.0))));
+    assert_eq!(path.current_point(), Vector2::new(4.0, 4.0));
+}
+
+#[test]
+fn drawing_without_move_to_starts_at_the_origin() {
+    let mut path = Path::new();
+    assert_eq!(path.current_point(), Vector2::new(0.0, 0.0));
+    path.line_to(Vector2::new(2.0, 0.0));
+
+    assert_eq!(path.sub_paths.len(), 1);
+    assert_eq!(path.sub_paths[0].start, Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn drawing_after_close_starts_a_new_sub_path_at_the_start() {
+    let mut path = Path::new();
+    path.move_to(Vector2::new(1.0, 1.0)).line_to(Vector2::new(3.0, 1.0)).line_to(Vector2::new(3.0, 3.0)).close();
+    assert_eq!(path.current_point(), Vector2::new(1.0, 1.0));
+
+    path.line_to(Vector2::new(0.0, 5.0));
+    assert_eq!(path.sub_paths.len(), 2);
+    assert!(path.sub_paths[0].closed && !path.sub_paths[1].closed);
+    assert_eq!(path.sub_paths[1].start, Vector2::new(1.0, 1.0));
+    assert_eq!(path.sub_paths[0].segments.len(), 2);
+}
+
+#[test]
+fn move_to_alone_is_empty() {
+    let mut path = Path::new();
+    assert!(path.is_empty());
+    path.move_to(Vector2::new(1.0, 1.0)).move_to(Vector2::new(2.0, 2.0));
+    assert!(path.is_empty());
+    assert!(path.flatten(pixel_camera(8, 8)).is_empty());
+
+    path.line_to(Vector2::new(3.0, 2.0));
+    assert!(!path.is_empty());
+    assert_eq!(path.flatten(pixel_camera(8, 8)).len(), 1);
+}
+
+#[test]
+fn control_points_cover_every_segment() {
+    let mut path = Path::new();
+    path.move_to(Vector2::new(0.0, 0.0)).line_to(Vector2::new(1.0, 0.0)).cubic_to(
+        Vector2::new(2.0, 1.0), Vector2::new(3.0, 1.0), Vector2::new(4.0, 0.0));
+
+    assert_eq!(path.control_points(), vec![
+        Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(2.0, 1.0), Vector2::new(3.0, 1.0), Vector2::new(4.0, 0.0)
+    ]);
+}
+
+#[test]
+fn flattened_curves_keep_their_end_points() {
+    let mut path = Path::new();
+    path.move_to(Vector2::new(0.0, 0.0)).line_to(Vector2::new(2.0, 0.0)).cubic_to(
+        Vector2::new(4.0, 0.0), Vector2::new(6.0, 2.0), Vector2::new(6.0, 6.0)).close();
+    let contours = path.flatten(pixel_camera(8, 8));
+
+    assert_eq!(contours.len(), 1);
+    let points = &contours[0].points;
+    assert!(contours[0].closed);
+    assert_eq!(points[0], Vector2::new(0.0, 0.0));
+    assert_eq!(points[1], Vector2::new(2.0, 0.0));
+    assert_eq!(*points.last().unwrap(), Vector2::new(6.0, 6.0));
+    // The curve is bent enough to need more than its end points
+    assert!(points.len() > 3);
+    // Curves don't repeat the point they start from
+    assert!(points.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn fill_path_closes_open_sub_paths() {
+    let mut path = Path::new();
+    path.move_to(Vector2::new(1.0, 1.0)).line_to(Vector2::new(5.0, 1.0)).line_to(Vector2::new(5.0, 3.0)).line_to(Vector2::new(1.0, 3.0));
+
+    let mut expected = Vec::new();
+    for y in 1..3 {
+        for x in 1..5 {
+            expected.push((x, y));
+        }
+    }
+    assert_eq!(fill(8, &path, FillRule::NonZero), expected);
+}
+
+#[test]
+fn even_odd_leaves_the_middle_of_a_star_empty() {
+    // Five pointed star drawn in one go, its edges cross around the middle
+    let mut path = Path::new();
+    let mut i = 0;
+    while i < 5 {
+        let angle = (-90.0 + 144.0 * i as f32).to_radians();
+        let point = Vector2::new(16.0 + 14.0 * angle.cos(), 16.0 + 14.0 * angle.sin());
+        if i == 0 { path.move_to(point); } else { path.line_to(point); }
+        i += 1;
+    }
+    path.close();
+
+    let non_zero = fill(32, &path, FillRule::NonZero);
+    let even_odd = fill(32, &path, FillRule::EvenOdd);
+    assert!(non_zero.contains(&(16, 16)));
+    assert!(!even_odd.contains(&(16, 16)));
+    // Both fill the points of the star
+    assert!(non_zero.contains(&(15, 6)) && even_odd.contains(&(15, 6)));
+    assert!(even_odd.iter().all(|pixel| non_zero.contains(pixel)));
+}
+
+#[test]
+fn nested_sub_paths_wound_the_same_way() {
+    let mut path = Path::new();
+    square(&mut path, 1.0, 1.0, 15.0, 15.0, true);
+    square(&mut path, 5.0, 5.0, 11.0, 11.0, true);
+
+    assert!(fill(16, &path, FillRule::NonZero).contains(&(8, 8)));
+    let even_odd = fill(16, &path, FillRule::EvenOdd);
+    assert!(!even_odd.contains(&(8, 8)));
+    assert!(even_odd.contains(&(2, 8)));
+    assert_eq!(even_odd.len(), 14 * 14 - 6 * 6);
+}
+
+#[test]
+fn nested_sub_path_wound_the_other_way_is_a_hole() {
+    let mut path = Path::new();
+    square(&mut path, 1.0, 1.0, 15.0, 15.0, true);
+    square(&mut path, 5.0, 5.0, 11.0, 11.0, false);
+
+    assert_eq!(fill(16, &path, FillRule::NonZero), fill(16, &path, FillRule::EvenOdd));
+    assert!(!fill(16, &path, FillRule::NonZero).contains(&(8, 8)));
+}
+
+#[test]
+fn overlapping_sub_paths_are_filled_once() {
+    let mut path = Path::new();
+    square(&mut path, 1.0, 1.0, 9.0, 9.0, true);
+    square(&mut path, 5.0, 5.0, 13.0, 13.0, true);
+
+    assert_eq!(fill(16, &path, FillRule::NonZero).len(), 64 + 64 - 16);
+    assert_eq!(fill(16, &path, FillRule::EvenOdd).len(), 64 + 64 - 32);
+    assert_eq!(FillRule::default(), FillRule::NonZero);
+}
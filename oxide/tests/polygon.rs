@@ -0,0 +1,79 @@
+mod common;
+
+use common::{lit_pixels, pixel_camera};
+use oxide::target::RenderTarget;
+use oxide::{draw_polygon, Color, Vector2};
+
+// Pixels lit by filling the polygon white, the camera maps world units to pixels
+fn polygon_pixels(size: u32, points: &[(f32, f32)]) -> Vec<(u32, u32)> {
+    let mut target = RenderTarget::new(size, size);
+    let camera = pixel_camera(size, size);
+    let points: Vec<Vector2> = points.iter().map(|(x, y)| Vector2::new(*x, *y)).collect();
+    unsafe { draw_polygon(target.buffer(), camera, &points, Color::WHITE); }
+    lit_pixels(&target)
+}
+
+#[test]
+fn square_fills_the_pixels_whose_centers_are_inside() {
+    let pixels = polygon_pixels(8, &[(1.0, 1.0), (5.0, 1.0), (5.0, 4.0), (1.0, 4.0)]);
+
+    let mut expected = Vec::new();
+    for y in 1..4 {
+        for x in 1..5 {
+            expected.push((x, y));
+        }
+    }
+    assert_eq!(pixels, expected);
+}
+
+#[test]
+fn winding_direction_does_not_matter() {
+    let clockwise = [(1.0, 1.0), (6.0, 2.0), (3.0, 7.0)];
+    let counter_clockwise = [(3.0, 7.0), (6.0, 2.0), (1.0, 1.0)];
+    assert_eq!(polygon_pixels(8, &clockwise), polygon_pixels(8, &counter_clockwise));
+}
+
+#[test]
+fn concave_polygon_leaves_its_notch_empty() {
+    // A U with the gap between x 3 and 5 open at the top
+    let pixels = polygon_pixels(8, &[(1.0, 1.0), (3.0, 1.0), (3.0, 5.0), (5.0, 5.0), (5.0, 1.0), (7.0, 1.0), (7.0, 7.0), (1.0, 7.0)]);
+
+    assert!(pixels.contains(&(1, 1)) && pixels.contains(&(6, 1)));
+    assert!(!pixels.contains(&(3, 1)) && !pixels.contains(&(4, 4)));
+    assert!(pixels.contains(&(4, 5)) && pixels.contains(&(4, 6)));
+}
+
+#[test]
+fn self_intersecting_star_has_an_empty_center() {
+    // Five pointed star drawn in one stroke, the middle is crossed twice
+    let mut points = Vec::new();
+    let mut i = 0;
+    while i < 5 {
+        let angle = (-90.0 + 144.0 * i as f32).to_radians();
+        points.push((16.0 + 14.0 * angle.cos(), 16.0 + 14.0 * angle.sin()));
+        i += 1;
+    }
+    let pixels = polygon_pixels(32, &points);
+
+    assert!(!pixels.contains(&(16, 16)));
+    // In the top point and the lower left one
+    assert!(pixels.contains(&(15, 6)));
+    assert!(pixels.contains(&(8, 25)));
+}
+
+#[test]
+fn too_few_points_draw_nothing() {
+    assert!(polygon_pixels(8, &[]).is_empty());
+    assert!(polygon_pixels(8, &[(1.0, 1.0), (6.0, 6.0)]).is_empty());
+    // Every point on one line
+    assert!(polygon_pixels(8, &[(1.0, 1.0), (3.0, 3.0), (6.0, 6.0)]).is_empty());
+}
+
+#[test]
+fn polygon_is_clipped_to_the_buffer() {
+    assert!(polygon_pixels(8, &[(10.0, 10.0), (20.0, 10.0), (20.0, 20.0)]).is_empty());
+
+    // Covers the whole buffer and more on every side
+    let pixels = polygon_pixels(8, &[(-5.0, -5.0), (13.0, -5.0), (13.0, 13.0), (-5.0, 13.0)]);
+    assert_eq!(pixels.len(), 64);
+}
@@ -0,0 +1,79 @@
+use oxide::quadratic::cubic_to_quadratics;
+use oxide::{BezierCurve, QuadraticBezier, Vector2};
+
+fn curve() -> BezierCurve {
+    BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 4.0), Vector2::new(5.0, -2.0), Vector2::new(6.0, 2.0))
+}
+
+fn distance(a: Vector2, b: Vector2) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+// How far the cubic gets from the nearest point on the quadratics, both sampled finely
+fn furthest_from(curve: &BezierCurve, quadratics: &[QuadraticBezier]) -> f32 {
+    let mut samples = Vec::new();
+    for quadratic in quadratics {
+        for i in 0..=400 {
+            samples.push(quadratic.evaluate(i as f32 / 400.0));
+        }
+    }
+
+    let mut furthest: f32 = 0.0;
+    for i in 0..=200 {
+        let point = curve.evaluate(i as f32 / 200.0);
+        let nearest = samples.iter().map(|sample| distance(point, *sample)).fold(f32::MAX, f32::min);
+        furthest = furthest.max(nearest);
+    }
+    furthest
+}
+
+#[test]
+fn to_cubic_traces_the_same_curve() {
+    let quadratic = QuadraticBezier::new(Vector2::new(0.0, 0.0), Vector2::new(2.0, 4.0), Vector2::new(4.0, 0.0));
+    let cubic = quadratic.to_cubic();
+    for i in 0..=10 {
+        let t = i as f32 / 10.0;
+        assert!(distance(cubic.evaluate(t), quadratic.evaluate(t)) < 1e-5);
+    }
+}
+
+#[test]
+fn cubic_made_from_a_quadratic_comes_back_as_one() {
+    let quadratic = QuadraticBezier::new(Vector2::new(0.0, 0.0), Vector2::new(2.0, 4.0), Vector2::new(4.0, 0.0));
+    let quadratics = cubic_to_quadratics(&quadratic.to_cubic(), 0.001);
+
+    assert_eq!(quadratics.len(), 1);
+    assert!(distance(quadratics[0].p1, quadratic.p1) < 1e-5);
+    assert_eq!((quadratics[0].p0, quadratics[0].p2), (quadratic.p0, quadratic.p2));
+}
+
+#[test]
+fn quadratics_stay_within_the_tolerance() {
+    let curve = curve();
+    for tolerance in [0.2, 0.05, 0.01] {
+        let quadratics = curve.to_quadratics(tolerance);
+        assert!(furthest_from(&curve, &quadratics) <= tolerance, "{}", tolerance);
+    }
+}
+
+#[test]
+fn quadratics_join_up_from_end_to_end() {
+    let curve = curve();
+    let quadratics = curve.to_quadratics(0.01);
+
+    assert!(quadratics.len() > 1);
+    assert_eq!(quadratics[0].p0, curve.p0);
+    assert!(distance(quadratics.last().unwrap().p2, curve.p3) < 1e-5);
+    for pair in quadratics.windows(2) {
+        assert_eq!(pair[0].p2, pair[1].p0);
+    }
+}
+
+#[test]
+fn tighter_tolerance_needs_more_pieces() {
+    let curve = curve();
+    assert!(curve.to_quadratics(0.001).len() > curve.to_quadratics(0.1).len());
+    // No tolerance cuts it into as many pieces as allowed
+    assert_eq!(curve.to_quadratics(0.0).len(), 64);
+    assert_eq!(curve.to_quadratics(1e-12).len(), 64);
+}
@@ -0,0 +1,60 @@
+use oxide::{Rectangle, Vector2};
+
+#[test]
+fn intersects_needs_overlap_on_both_axes() {
+    let a = Rectangle::new(0.0, 0.0, 2.0, 2.0);
+
+    assert!(a.intersects(Rectangle::new(1.0, 1.0, 2.0, 2.0)));
+    assert!(a.intersects(Rectangle::new(0.5, 0.5, 1.0, 1.0)));
+    assert!(Rectangle::new(0.5, 0.5, 1.0, 1.0).intersects(a));
+    // Right of it, and below it
+    assert!(!a.intersects(Rectangle::new(3.0, 0.0, 1.0, 1.0)));
+    assert!(!a.intersects(Rectangle::new(0.0, 3.0, 1.0, 1.0)));
+    // Overlaps on x only, and left of and above it
+    assert!(!a.intersects(Rectangle::new(1.0, 5.0, 1.0, 1.0)));
+    assert!(!a.intersects(Rectangle::new(-3.0, -3.0, 1.0, 1.0)));
+}
+
+#[test]
+fn touching_edges_intersect() {
+    let a = Rectangle::new(0.0, 0.0, 2.0, 2.0);
+    assert!(a.intersects(Rectangle::new(2.0, 0.0, 1.0, 1.0)));
+    assert!(a.intersects(Rectangle::new(-1.0, -1.0, 1.0, 1.0)));
+}
+
+#[test]
+fn contains_point_includes_edges() {
+    let a = Rectangle::new(-1.0, -1.0, 2.0, 2.0);
+    assert!(a.contains_point(Vector2::new(0.0, 0.0)));
+    assert!(a.contains_point(Vector2::new(1.0, -1.0)));
+    assert!(!a.contains_point(Vector2::new(1.5, 0.0)));
+    assert!(!a.contains_point(Vector2::new(0.0, -1.5)));
+}
+
+#[test]
+fn union_covers_both() {
+    let union = Rectangle::new(0.0, 0.0, 1.0, 1.0).union(Rectangle::new(2.0, -1.0, 1.0, 3.0));
+    assert_eq!(union, Rectangle::new(0.0, -1.0, 3.0, 3.0));
+
+    let inner = Rectangle::new(0.25, 0.25, 0.5, 0.5);
+    assert_eq!(Rectangle::new(0.0, 0.0, 1.0, 1.0).union(inner), Rectangle::new(0.0, 0.0, 1.0, 1.0));
+}
+
+#[test]
+fn expand_grows_every_side() {
+    assert_eq!(Rectangle::new(0.0, 0.0, 2.0, 1.0).expand(0.5), Rectangle::new(-0.5, -0.5, 3.0, 2.0));
+    assert_eq!(Rectangle::new(0.0, 0.0, 2.0, 1.0).expand(-0.25), Rectangle::new(0.25, 0.25, 1.5, 0.5));
+}
+
+#[test]
+fn expand_shrinks_to_the_center() {
+    assert_eq!(Rectangle::new(0.0, 0.0, 2.0, 1.0).expand(-1.0), Rectangle::new(1.0, 0.5, 0.0, 0.0));
+}
+
+#[test]
+fn from_points_bounds_every_point() {
+    let points = [Vector2::new(1.0, 2.0), Vector2::new(-1.0, 0.5), Vector2::new(3.0, -2.0)];
+    assert_eq!(Rectangle::from_points(&points), Rectangle::new(-1.0, -2.0, 4.0, 4.0));
+    assert_eq!(Rectangle::from_points(&[Vector2::new(1.0, 1.0)]), Rectangle::new(1.0, 1.0, 0.0, 0.0));
+    assert_eq!(Rectangle::from_points(&[]), Rectangle::default());
+}
@@ -0,0 +1,79 @@
+use oxide::simplify::simplify_polyline;
+use oxide::Vector2;
+
+fn points(values: &[(f32, f32)]) -> Vec<Vector2> {
+    values.iter().map(|(x, y)| Vector2::new(*x, *y)).collect()
+}
+
+fn distance_to_polyline(point: Vector2, points: &[Vector2]) -> f32 {
+    let mut best = f32::MAX;
+    for pair in points.windows(2) {
+        let edge = pair[1] - pair[0];
+        let length_squared = edge.x * edge.x + edge.y * edge.y;
+        let t = if length_squared > 0.0 {
+            (((point.x - pair[0].x) * edge.x + (point.y - pair[0].y) * edge.y) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let closest = pair[0] + edge * t;
+        best = best.min(((point.x - closest.x).powi(2) + (point.y - closest.y).powi(2)).sqrt());
+    }
+    best
+}
+
+#[test]
+fn short_polylines_are_unchanged() {
+    assert!(simplify_polyline(&[], 1.0).is_empty());
+    let two = points(&[(0.0, 0.0), (5.0, 5.0)]);
+    assert_eq!(simplify_polyline(&two, 1.0), two);
+}
+
+#[test]
+fn straight_line_keeps_only_its_ends() {
+    let line = points(&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0), (7.0, 7.0)]);
+    assert_eq!(simplify_polyline(&line, 0.01), points(&[(0.0, 0.0), (7.0, 7.0)]));
+}
+
+#[test]
+fn bumps_under_the_tolerance_are_removed() {
+    let wobbly = points(&[(0.0, 0.0), (1.0, 0.05), (2.0, -0.05), (3.0, 0.05), (4.0, 0.0)]);
+    assert_eq!(simplify_polyline(&wobbly, 0.1), points(&[(0.0, 0.0), (4.0, 0.0)]));
+    assert_eq!(simplify_polyline(&wobbly, 0.01), wobbly);
+}
+
+#[test]
+fn corners_are_kept() {
+    let corner = points(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (2.0, 2.0)]);
+    assert_eq!(simplify_polyline(&corner, 0.1), points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0)]));
+}
+
+#[test]
+fn closed_loop_keeps_its_shape() {
+    // First and last points are the same, so the first range has no direction
+    let square = points(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0), (0.0, 1.0), (0.0, 0.0)]);
+    assert_eq!(simplify_polyline(&square, 0.1), points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0), (0.0, 0.0)]));
+}
+
+#[test]
+fn every_point_stays_within_the_tolerance() {
+    let mut samples = Vec::new();
+    let mut i = 0;
+    while i <= 200 {
+        let x = i as f32 / 20.0;
+        samples.push(Vector2::new(x, x.sin() * 2.0 + (x * 7.0).sin() * 0.2));
+        i += 1;
+    }
+
+    for tolerance in [0.5, 0.1, 0.02] {
+        let simplified = simplify_polyline(&samples, tolerance);
+        assert!(simplified.len() < samples.len());
+        // Only original points, in their original order
+        let mut next = 0;
+        for point in &simplified {
+            next += samples[next..].iter().position(|sample| sample == point).unwrap() + 1;
+        }
+        for sample in &samples {
+            assert!(distance_to_polyline(*sample, &simplified) <= tolerance + 1e-5);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::drawn_pixels;
+use oxide::stroke::{fill_convex_union, stroke_polyline};
+use oxide::target::RenderTarget;
+use oxide::{Color, LineCap, LineJoin, Vector2};
+
+fn points(values: &[(f32, f32)]) -> Vec<Vector2> {
+    values.iter().map(|(x, y)| Vector2::new(*x, *y)).collect()
+}
+
+// (min x, min y, max x, max y) over every piece
+fn bounds(pieces: &[Vec<Vector2>]) -> (f32, f32, f32, f32) {
+    let mut bounds = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for point in pieces.iter().flatten() {
+        bounds = (bounds.0.min(point.x), bounds.1.min(point.y), bounds.2.max(point.x), bounds.3.max(point.y));
+    }
+    bounds
+}
+
+fn has_point(pieces: &[Vec<Vector2>], x: f32, y: f32) -> bool {
+    pieces.iter().flatten().any(|point| (point.x - x).abs() < 1e-4 && (point.y - y).abs() < 1e-4)
+}
+
+#[test]
+fn straight_segment_is_one_quad() {
+    let pieces = stroke_polyline(&points(&[(0.0, 0.0), (10.0, 0.0)]), 2.0, LineJoin::Miter, LineCap::Butt);
+    assert_eq!(pieces, vec![points(&[(0.0, 1.0), (10.0, 1.0), (10.0, -1.0), (0.0, -1.0)])]);
+}
+
+#[test]
+fn caps_extend_past_the_end_points() {
+    let line = points(&[(0.0, 0.0), (10.0, 0.0)]);
+    assert_eq!(bounds(&stroke_polyline(&line, 4.0, LineJoin::Miter, LineCap::Butt)), (0.0, -2.0, 10.0, 2.0));
+    assert_eq!(bounds(&stroke_polyline(&line, 4.0, LineJoin::Miter, LineCap::Square)), (-2.0, -2.0, 12.0, 2.0));
+
+    let (x0, _, x1, _) = bounds(&stroke_polyline(&line, 4.0, LineJoin::Miter, LineCap::Round));
+    assert!((x0 + 2.0).abs() < 1e-4 && (x1 - 12.0).abs() < 1e-4);
+}
+
+#[test]
+fn miter_join_meets_at_the_outer_corner() {
+    let corner = points(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+    let pieces = stroke_polyline(&corner, 2.0, LineJoin::Miter, LineCap::Butt);
+    assert_eq!(pieces.len(), 3);
+    assert!(has_point(&pieces, 11.0, -1.0));
+}
+
+#[test]
+fn bevel_join_cuts_the_corner() {
+    let corner = points(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+    let pieces = stroke_polyline(&corner, 2.0, LineJoin::Bevel, LineCap::Butt);
+    assert_eq!(pieces.len(), 3);
+    assert_eq!(pieces[2], points(&[(10.0, 0.0), (10.0, -1.0), (11.0, 0.0)]));
+    assert!(!has_point(&pieces, 11.0, -1.0));
+}
+
+#[test]
+fn round_join_reaches_half_the_width_around_the_corner() {
+    let corner = points(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+    let pieces = stroke_polyline(&corner, 2.0, LineJoin::Round, LineCap::Butt);
+    assert_eq!(pieces.len(), 3);
+    assert!(pieces[2].iter().all(|point| (point.distance(Vector2::new(10.0, 0.0)) - 1.0).abs() < 1e-4));
+}
+
+#[test]
+fn sharp_miter_falls_back_to_bevel() {
+    // Turns almost all the way back, the miter tip would be far past the limit
+    let spike = points(&[(0.0, 0.0), (10.0, 0.0), (0.0, 1.0)]);
+    let pieces = stroke_polyline(&spike, 2.0, LineJoin::Miter, LineCap::Butt);
+    assert_eq!(pieces.len(), 3);
+    assert_eq!(pieces[2].len(), 3);
+    // A bevel stays within half the width of the corner, the miter would have been past the limit
+    let corner = Vector2::new(10.0, 0.0);
+    assert!(pieces[2].iter().all(|point| point.distance(corner) <= 1.0 + 1e-4));
+}
+
+#[test]
+fn straight_continuation_has_no_join() {
+    let line = points(&[(0.0, 0.0), (5.0, 0.0), (10.0, 0.0)]);
+    assert_eq!(stroke_polyline(&line, 2.0, LineJoin::Round, LineCap::Butt).len(), 2);
+}
+
+#[test]
+fn repeated_points_are_skipped() {
+    let repeated = points(&[(0.0, 0.0), (0.0, 0.0), (10.0, 0.0), (10.0, 0.0)]);
+    let line = points(&[(0.0, 0.0), (10.0, 0.0)]);
+    assert_eq!(
+        stroke_polyline(&repeated, 2.0, LineJoin::Miter, LineCap::Butt),
+        stroke_polyline(&line, 2.0, LineJoin::Miter, LineCap::Butt));
+}
+
+#[test]
+fn nothing_to_stroke() {
+    let line = points(&[(0.0, 0.0), (10.0, 0.0)]);
+    assert!(stroke_polyline(&line, 0.0, LineJoin::Miter, LineCap::Round).is_empty());
+    assert!(stroke_polyline(&[], 2.0, LineJoin::Miter, LineCap::Round).is_empty());
+
+    // A lone point is only drawn with a round cap
+    let point = points(&[(3.0, 3.0)]);
+    assert!(stroke_polyline(&point, 2.0, LineJoin::Miter, LineCap::Butt).is_empty());
+    assert_eq!(stroke_polyline(&point, 2.0, LineJoin::Miter, LineCap::Round).len(), 1);
+}
+
+#[test]
+fn overlapping_pieces_are_filled_once() {
+    let mut target = RenderTarget::new(8, 8);
+    let pieces = vec![
+        points(&[(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)]),
+        points(&[(3.0, 3.0), (8.0, 3.0), (8.0, 8.0), (3.0, 8.0)])
+    ];
+    unsafe { fill_convex_union(target.buffer(), &pieces, Color::rgba(0xFF, 0x00, 0x00, 0x80).to_pixel()); }
+
+    let covered: Vec<(u32, u32)> = (0..64).map(|index| (index % 8, index / 8))
+        .filter(|(x, y)| (*x < 5 && *y < 5) || (*x >= 3 && *y >= 3))
+        .collect();
+    assert_eq!(drawn_pixels(&target), covered);
+    // Blending the overlap twice would make it brighter than the rest
+    let inside = target.pixels()[0];
+    assert!(target.pixels().iter().all(|pixel| *pixel == inside || *pixel == 0));
+}
@@ -0,0 +1,120 @@
+use oxide::render::{Layer, RenderCommand, RenderCommands};
+use oxide::{
+    export_frame_svg, world_space_to_screen_space_f32, BezierCurve, Camera, ClipRect, Color, DashPattern, GameState,
+    Rectangle, StrokeStyle, StrokeWidth, Vector2
+};
+
+// 8 world units tall in an 800 by 600 frame, 75 pixels to the unit
+fn camera() -> Camera {
+    let y_scale = 600.0 / 8.0;
+    Camera { x: 0.0, y: 0.0, width: 800.0 / y_scale, height: 8.0, y_scale, rotation: 0.0, screen_offset: Vector2::new(0.0, 0.0), y_up: false }
+}
+
+fn svg_lines(commands: &RenderCommands) -> Vec<String> {
+    commands.to_svg(800, 600).to_svg_string().lines().map(|line| line.trim().to_string()).collect()
+}
+
+fn attribute<'a>(element: &'a str, name: &str) -> &'a str {
+    let start = element.find(&format!(" {}=\"", name)).unwrap_or_else(|| panic!("no {} in {}", name, element)) + name.len() + 3;
+    let end = start + element[start..].find('"').unwrap();
+    &element[start..end]
+}
+
+fn numbers(text: &str) -> Vec<f32> {
+    text.split(|c: char| c == ' ' || c == ',').filter(|part| !part.is_empty() && *part != "C" && *part != "M").map(|part| part.parse().unwrap()).collect()
+}
+
+fn assert_points(actual: &[f32], expected: &[Vector2]) {
+    assert_eq!(actual.len(), expected.len() * 2, "{:?} != {:?}", actual, expected);
+    for (pair, point) in actual.chunks(2).zip(expected) {
+        assert!((pair[0] - point.x).abs() < 1e-3 && (pair[1] - point.y).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+    }
+}
+
+#[test]
+fn rectangles_become_rects_until_the_camera_turns() {
+    let rectangle = Rectangle { x: 1.0, y: -1.0, width: 2.0, height: 1.0 };
+    let mut commands = RenderCommands::new();
+    commands.push(Layer::World, RenderCommand::DrawRect { camera: camera(), rectangle, color: Color::WHITE });
+    let lines = svg_lines(&commands);
+    let rect = lines.iter().find(|line| line.starts_with("<rect")).unwrap();
+    assert_eq!((attribute(rect, "x"), attribute(rect, "y"), attribute(rect, "width"), attribute(rect, "height")), ("475", "225", "150", "75"));
+
+    let turned = Camera { rotation: 0.5, ..camera() };
+    let mut commands = RenderCommands::new();
+    commands.push(Layer::World, RenderCommand::DrawRect { camera: turned, rectangle, color: Color::WHITE });
+    let lines = svg_lines(&commands);
+    assert!(!lines.iter().any(|line| line.starts_with("<rect")));
+    let polygon = lines.iter().find(|line| line.starts_with("<polygon")).unwrap();
+    let corners = rectangle.corners().map(|corner| world_space_to_screen_space_f32(turned, corner));
+    assert_points(&numbers(attribute(polygon, "points")), &corners);
+}
+
+#[test]
+fn curves_stay_curves_with_their_dashes_in_pixels() {
+    let camera = Camera { y_up: true, rotation: 0.3, ..camera() };
+    let curve = BezierCurve::new(Vector2::new(-1.0, 0.0), Vector2::new(0.0, 2.0), Vector2::new(1.0, -2.0), Vector2::new(2.0, 0.0));
+    let style = StrokeStyle::new(Color::WHITE, StrokeWidth::World(0.1)).with_dash(DashPattern::new(0.2, 0.1));
+    let mut commands = RenderCommands::new();
+    commands.push(Layer::World, RenderCommand::DrawStrokeCurve { camera, curve, style });
+    let lines = svg_lines(&commands);
+    let path = lines.iter().find(|line| line.starts_with("<path")).unwrap();
+
+    let expected = [curve.p0, curve.p1, curve.p2, curve.p3].map(|point| world_space_to_screen_space_f32(camera, point));
+    assert!(attribute(path, "d").contains(" C "));
+    assert_points(&numbers(attribute(path, "d")), &expected);
+    assert_points(&numbers(attribute(path, "stroke-dasharray")), &[Vector2::new(15.0, 7.5)]);
+    assert_eq!(attribute(path, "stroke-width"), "7.5");
+}
+
+#[test]
+fn clip_rects_become_balanced_clip_groups() {
+    let rectangle = Rectangle { x: 0.0, y: 0.0, width: 1.0, height: 1.0 };
+    let mut commands = RenderCommands::new();
+    commands.push(Layer::World, RenderCommand::DrawRect { camera: camera(), rectangle, color: Color::WHITE });
+    commands.push_clip_rect(ClipRect::new(10, 20, 110, 70));
+    commands.push(Layer::World, RenderCommand::DrawRect { camera: camera(), rectangle, color: Color::WHITE });
+    commands.push(Layer::World, RenderCommand::DrawRect { camera: camera(), rectangle, color: Color::WHITE });
+    commands.pop_clip_rect();
+    commands.push(Layer::World, RenderCommand::DrawRect { camera: camera(), rectangle, color: Color::WHITE });
+
+    let lines = svg_lines(&commands);
+    let tags: Vec<&str> = lines.iter().map(|line| line.split([' ', '>']).next().unwrap()).collect();
+    assert_eq!(tags, ["<svg", "<rect", "<clipPath", "<g", "<rect", "<rect", "</g", "<rect", "</svg"]);
+    let clip = &lines[2];
+    assert_eq!((attribute(clip, "x"), attribute(clip, "y"), attribute(clip, "width"), attribute(clip, "height")), ("10", "20", "100", "50"));
+}
+
+#[test]
+fn text_is_escaped_and_sits_below_its_position() {
+    let mut commands = RenderCommands::new();
+    commands.push(Layer::Ui, RenderCommand::DrawText {
+        camera: camera(),
+        position: Vector2::new(0.0, 0.0),
+        text: "A<&>".to_string(),
+        scale: 2,
+        color: Color::WHITE,
+        background: Color::TRANSPARENT
+    });
+    let lines = svg_lines(&commands);
+    let text = lines.iter().find(|line| line.starts_with("<text")).unwrap();
+    assert!(text.ends_with(">A&lt;&amp;&gt;</text>"));
+    // The baseline is under the glyphs, 7 rows at scale 2
+    assert_eq!((attribute(text, "x"), attribute(text, "y")), ("400", "314"));
+    assert!(!lines.iter().any(|line| line.starts_with("<rect")));
+}
+
+#[test]
+fn exported_frame_has_the_grid_and_its_labels() {
+    let mut game_state = GameState::default();
+    game_state.camera = Camera { y_up: true, ..camera() };
+    let path = std::env::temp_dir().join("oxide_test_frame.svg");
+    let path = path.to_str().unwrap();
+    export_frame_svg(&game_state, 800, 600, path).unwrap();
+    let svg = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("<line"));
+    assert!(svg.contains(">0</text>"));
+}
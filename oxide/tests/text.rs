@@ -0,0 +1,43 @@
+use oxide::render::{Layer, RenderCommand, RenderCommands};
+use oxide::target::RenderTarget;
+use oxide::text::{glyph, text_size};
+use oxide::{profiler, Camera, Color, Vector2};
+
+#[test]
+fn text_size_leaves_no_gap_after_the_last_glyph() {
+    assert_eq!(text_size("", 1), (0, 0));
+    assert_eq!(text_size("A", 1), (5, 7));
+    assert_eq!(text_size("AB", 1), (11, 7));
+    assert_eq!(text_size("AB", 2), (22, 14));
+}
+
+#[test]
+fn lowercase_and_unknown_characters() {
+    assert_eq!(glyph('a'), glyph('A'));
+    assert_eq!(glyph('~'), glyph('?'));
+    assert_eq!(glyph(' '), [0; 7]);
+}
+
+#[test]
+fn draws_glyph_pixels_at_the_position() {
+    let mut target = RenderTarget::new(12, 12);
+    let mut commands = RenderCommands::new();
+    let camera = Camera::new(6.0, 6.0, 12.0, 12.0);
+    commands.push(Layer::Ui, RenderCommand::DrawText {
+        camera,
+        position: Vector2::new(3.0, 2.0),
+        text: "T".to_string(),
+        scale: 1,
+        color: Color::WHITE,
+        background: Color::TRANSPARENT
+    });
+    unsafe { commands.execute(target.buffer(), &mut profiler::FrameProfiler::default()); }
+
+    let pixels = target.pixels();
+    let lit = |x: usize, y: usize| pixels[y * 12 + x] == 0xFFFFFFFF;
+    // The bar across the top, then the stem down the middle
+    assert!((3..8).all(|x| lit(x, 2)));
+    assert!(!lit(2, 2) && !lit(8, 2));
+    assert!((3..9).all(|y| lit(5, y)));
+    assert!(!lit(4, 3) && !lit(5, 9));
+}
@@ -0,0 +1,132 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+use oxide::render::{Layer, RenderCommand, RenderCommands};
+use oxide::transform::Transform2D;
+use oxide::{BezierCurve, Camera, Color, Rectangle, Vector2};
+
+fn assert_close(a: Vector2, b: Vector2) {
+    assert!((a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4, "{:?} != {:?}", a, b);
+}
+
+fn camera() -> Camera {
+    Camera::new(0.0, 0.0, 16.0, 9.0)
+}
+
+#[test]
+fn basic_transforms_move_points() {
+    let point = Vector2::new(2.0, 1.0);
+    assert_eq!(Transform2D::identity().apply(point), point);
+    assert_eq!(Transform2D::translation(Vector2::new(1.0, -3.0)).apply(point), Vector2::new(3.0, -2.0));
+    assert_eq!(Transform2D::scale(2.0, 3.0).apply(point), Vector2::new(4.0, 3.0));
+    // Positive angles turn x towards y
+    assert_close(Transform2D::rotation(FRAC_PI_2).apply(point), Vector2::new(-1.0, 2.0));
+}
+
+#[test]
+fn vectors_ignore_the_translation() {
+    let transform = Transform2D::translation(Vector2::new(5.0, 5.0)).then(Transform2D::scale(2.0, 2.0));
+    assert_eq!(transform.apply_vector(Vector2::new(1.0, 0.0)), Vector2::new(2.0, 0.0));
+}
+
+#[test]
+fn then_applies_self_first() {
+    let move_right = Transform2D::translation(Vector2::new(1.0, 0.0));
+    let turn = Transform2D::rotation(FRAC_PI_2);
+    let point = Vector2::new(1.0, 0.0);
+
+    assert_close(move_right.then(turn).apply(point), turn.apply(move_right.apply(point)));
+    assert_close(move_right.then(turn).apply(point), Vector2::new(0.0, 2.0));
+    assert_close(turn.then(move_right).apply(point), Vector2::new(1.0, 1.0));
+}
+
+#[test]
+fn rotation_around_keeps_the_center_still() {
+    let center = Vector2::new(3.0, 2.0);
+    let transform = Transform2D::rotation_around(FRAC_PI_2, center);
+    assert_close(transform.apply(center), center);
+    assert_close(transform.apply(Vector2::new(4.0, 2.0)), Vector2::new(3.0, 3.0));
+}
+
+#[test]
+fn invert_undoes_the_transform() {
+    let transform = Transform2D::rotation(0.7).then(Transform2D::scale(2.0, 0.5)).then(Transform2D::translation(Vector2::new(-3.0, 4.0)));
+    let inverse = transform.invert().unwrap();
+    for point in [Vector2::new(0.0, 0.0), Vector2::new(1.5, -2.0), Vector2::new(-7.0, 3.0)] {
+        assert_close(inverse.apply(transform.apply(point)), point);
+        assert_close(transform.apply(inverse.apply(point)), point);
+    }
+
+    // Flattened onto a line, there's no way back
+    assert!(Transform2D::scale(1.0, 0.0).invert().is_none());
+}
+
+#[test]
+fn rotation_angle_only_for_turns_and_even_scales() {
+    let turned = Transform2D::rotation(0.5).then(Transform2D::scale(3.0, 3.0)).then(Transform2D::translation(Vector2::new(1.0, 1.0)));
+    assert!((turned.rotation_angle().unwrap() - 0.5).abs() < 1e-5);
+    assert!((turned.scale_factor() - 3.0).abs() < 1e-5);
+
+    assert!(Transform2D::scale(2.0, 1.0).rotation_angle().is_none());
+    assert!(Transform2D::scale(-1.0, -1.0).then(Transform2D::scale(1.0, -1.0)).rotation_angle().is_none());
+    let skew = Transform2D { c: 0.5, ..Transform2D::identity() };
+    assert!(skew.rotation_angle().is_none());
+}
+
+#[test]
+fn axis_aligned_transforms() {
+    assert!(Transform2D::scale(2.0, -1.0).is_axis_aligned());
+    assert!(Transform2D::translation(Vector2::new(3.0, 1.0)).is_axis_aligned());
+    let quarter = Transform2D { a: 0.0, b: 1.0, c: -1.0, d: 0.0, tx: 0.0, ty: 0.0 };
+    assert!(quarter.is_axis_aligned());
+    assert!(!Transform2D::rotation(FRAC_PI_4).is_axis_aligned());
+}
+
+#[test]
+fn curves_transform_exactly() {
+    let curve = BezierCurve::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 2.0), Vector2::new(3.0, 2.0), Vector2::new(4.0, 0.0));
+    let transform = Transform2D::rotation(0.3).then(Transform2D::scale(2.0, 0.5)).then(Transform2D::translation(Vector2::new(1.0, 1.0)));
+    let moved = transform.apply_curve(curve);
+    for i in 0..=10 {
+        let t = i as f32 / 10.0;
+        assert_close(moved.evaluate(t), transform.apply(curve.evaluate(t)));
+    }
+}
+
+#[test]
+fn nested_transforms_apply_the_child_first() {
+    let mut commands = RenderCommands::new();
+    commands.push_transform(Transform2D::translation(Vector2::new(10.0, 0.0)));
+    commands.push_transform(Transform2D::scale(2.0, 2.0));
+    commands.push(Layer::World, RenderCommand::DrawCircle { camera: camera(), center: Vector2::new(1.0, 1.0), radius: 1.0, color: Color::WHITE });
+    commands.pop_transform();
+    commands.push(Layer::World, RenderCommand::DrawCircle { camera: camera(), center: Vector2::new(1.0, 1.0), radius: 1.0, color: Color::WHITE });
+    commands.pop_transform();
+    commands.push(Layer::World, RenderCommand::DrawCircle { camera: camera(), center: Vector2::new(1.0, 1.0), radius: 1.0, color: Color::WHITE });
+
+    let circles: Vec<(Vector2, f32)> = commands.sorted().iter().map(|command| match command {
+        RenderCommand::DrawCircle { center, radius, .. } => (*center, *radius),
+        _ => panic!("expected a circle")
+    }).collect();
+    assert_eq!(circles, vec![(Vector2::new(12.0, 2.0), 2.0), (Vector2::new(11.0, 1.0), 1.0), (Vector2::new(1.0, 1.0), 1.0)]);
+}
+
+#[test]
+fn rectangles_become_what_the_transform_turns_them_into() {
+    let rectangle = Rectangle::new(0.0, 0.0, 2.0, 1.0);
+    let command = RenderCommand::DrawRect { camera: camera(), rectangle, color: Color::WHITE };
+
+    match command.transformed(Transform2D::scale(2.0, 2.0)) {
+        RenderCommand::DrawRect { rectangle, .. } => assert_eq!(rectangle, Rectangle::new(0.0, 0.0, 4.0, 2.0)),
+        _ => panic!("scaled rectangle should stay a rectangle")
+    }
+    match command.transformed(Transform2D::rotation(FRAC_PI_4)) {
+        RenderCommand::DrawRectRotated { rotation, .. } => assert!((rotation - FRAC_PI_4).abs() < 1e-5),
+        _ => panic!("turned rectangle should be a rotated rectangle")
+    }
+    match command.transformed(Transform2D { c: 0.5, ..Transform2D::identity() }) {
+        RenderCommand::DrawPolygon { points, .. } => {
+            assert_eq!(points.len(), 4);
+            assert_close(points[2], Vector2::new(2.5, 1.0));
+        },
+        _ => panic!("skewed rectangle should be a polygon")
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{drawn_pixels, pixel_camera};
+use oxide::target::RenderTarget;
+use oxide::{draw_triangle, draw_triangle_mesh, Color, Vector2};
+
+static SIZE: u32 = 8;
+
+fn points(values: [(f32, f32); 3]) -> [Vector2; 3] {
+    values.map(|(x, y)| Vector2::new(x, y))
+}
+
+#[test]
+fn diagonal_edge_belongs_to_one_triangle() {
+    // The diagonal of the square goes through pixel centers
+    let mut target = RenderTarget::new(SIZE, SIZE);
+    unsafe { draw_triangle(target.buffer(), pixel_camera(SIZE, SIZE), points([(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)]), Color::WHITE); }
+    assert_eq!(drawn_pixels(&target).len(), 6);
+
+    let mut target = RenderTarget::new(SIZE, SIZE);
+    unsafe { draw_triangle(target.buffer(), pixel_camera(SIZE, SIZE), points([(4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]), Color::WHITE); }
+    assert_eq!(drawn_pixels(&target).len(), 10);
+}
+
+#[test]
+fn mesh_draws_shared_edges_once() {
+    let mut target = RenderTarget::new(SIZE, SIZE);
+    let vertices = [Vector2::new(0.0, 0.0), Vector2::new(6.0, 0.0), Vector2::new(6.0, 6.0), Vector2::new(0.0, 6.0)];
+    let colors = [Color::rgba(0xFF, 0x00, 0x00, 0x80); 4];
+    unsafe { draw_triangle_mesh(target.buffer(), pixel_camera(SIZE, SIZE), &vertices, &colors, &[0, 1, 2, 0, 2, 3]); }
+
+    // Blending a pixel twice would make it brighter than the rest
+    let pixels = target.pixels();
+    let first = pixels[0];
+    assert!(first & 0xFFFFFF != 0);
+    let mut count = 0;
+    for (index, pixel) in pixels.iter().enumerate() {
+        let (x, y) = (index as u32 % SIZE, index as u32 / SIZE);
+        if x < 6 && y < 6 {
+            assert_eq!(*pixel, first, "{} {}", x, y);
+            count += 1;
+        } else {
+            assert_eq!(*pixel & 0xFFFFFF, 0, "{} {}", x, y);
+        }
+    }
+    assert_eq!(count, 36);
+}
+
+#[test]
+fn winding_direction_does_not_matter() {
+    let mut clockwise = RenderTarget::new(SIZE, SIZE);
+    let mut counter_clockwise = RenderTarget::new(SIZE, SIZE);
+    unsafe {
+        draw_triangle(clockwise.buffer(), pixel_camera(SIZE, SIZE), points([(1.0, 1.0), (7.0, 2.5), (2.0, 6.0)]), Color::WHITE);
+        draw_triangle(counter_clockwise.buffer(), pixel_camera(SIZE, SIZE), points([(2.0, 6.0), (7.0, 2.5), (1.0, 1.0)]), Color::WHITE);
+    }
+    assert_eq!(clockwise.pixels(), counter_clockwise.pixels());
+}
+
+#[test]
+fn degenerate_triangle_draws_nothing() {
+    let mut target = RenderTarget::new(SIZE, SIZE);
+    unsafe {
+        draw_triangle(target.buffer(), pixel_camera(SIZE, SIZE), points([(1.0, 1.0), (4.0, 4.0), (7.0, 7.0)]), Color::WHITE);
+        draw_triangle(target.buffer(), pixel_camera(SIZE, SIZE), points([(3.0, 3.0), (3.0, 3.0), (3.0, 3.0)]), Color::WHITE);
+    }
+    assert!(drawn_pixels(&target).is_empty());
+}
+
+#[test]
+fn mesh_skips_triangles_with_missing_vertices() {
+    let mut target = RenderTarget::new(SIZE, SIZE);
+    let vertices = [Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0), Vector2::new(0.0, 4.0)];
+    let colors = [Color::WHITE; 3];
+    // The second triangle points past the vertices and the trailing index is left over
+    unsafe { draw_triangle_mesh(target.buffer(), pixel_camera(SIZE, SIZE), &vertices, &colors, &[0, 1, 2, 0, 1, 9, 2]); }
+    assert_eq!(drawn_pixels(&target).len(), 6);
+
+    let mut target = RenderTarget::new(SIZE, SIZE);
+    unsafe { draw_triangle_mesh(target.buffer(), pixel_camera(SIZE, SIZE), &vertices, &colors[..2], &[0, 1, 2]); }
+    assert!(drawn_pixels(&target).is_empty());
+}
@@ -0,0 +1,62 @@
+use oxide::{trim_polyline, Vector2};
+
+fn points(values: &[(f32, f32)]) -> Vec<Vector2> {
+    values.iter().map(|(x, y)| Vector2::new(*x, *y)).collect()
+}
+
+fn assert_points(actual: &[Vector2], expected: &[(f32, f32)]) {
+    assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+    for (point, (x, y)) in actual.iter().zip(expected) {
+        assert!((point.x - x).abs() < 1e-5 && (point.y - y).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+    }
+}
+
+// Three units along x, then four down
+fn corner() -> Vec<Vector2> {
+    points(&[(0.0, 0.0), (3.0, 0.0), (3.0, 4.0)])
+}
+
+#[test]
+fn no_trim_keeps_every_point() {
+    assert_eq!(trim_polyline(&corner(), 0.0, 0.0), corner());
+}
+
+#[test]
+fn trims_within_the_first_and_last_segments() {
+    assert_points(&trim_polyline(&corner(), 1.0, 0.0), &[(1.0, 0.0), (3.0, 0.0), (3.0, 4.0)]);
+    assert_points(&trim_polyline(&corner(), 0.0, 1.0), &[(0.0, 0.0), (3.0, 0.0), (3.0, 3.0)]);
+    assert_points(&trim_polyline(&corner(), 1.0, 1.0), &[(1.0, 0.0), (3.0, 0.0), (3.0, 3.0)]);
+}
+
+#[test]
+fn trims_past_a_corner() {
+    // Past the corner the first segment is gone entirely
+    assert_points(&trim_polyline(&corner(), 4.0, 0.0), &[(3.0, 1.0), (3.0, 4.0)]);
+    assert_points(&trim_polyline(&corner(), 0.0, 5.0), &[(0.0, 0.0), (2.0, 0.0)]);
+    assert_points(&trim_polyline(&corner(), 2.0, 4.5), &[(2.0, 0.0), (2.5, 0.0)]);
+}
+
+#[test]
+fn trimming_the_whole_line_leaves_nothing() {
+    assert!(trim_polyline(&corner(), 7.0, 0.0).is_empty());
+    assert!(trim_polyline(&corner(), 0.0, 10.0).is_empty());
+    // Each end is shorter than the line, together they're longer
+    assert!(trim_polyline(&corner(), 4.0, 4.0).is_empty());
+    assert!(trim_polyline(&corner(), 100.0, 100.0).is_empty());
+}
+
+#[test]
+fn negative_lengths_do_not_extend_the_line() {
+    assert_eq!(trim_polyline(&corner(), -1.0, -1.0), corner());
+    assert_points(&trim_polyline(&corner(), -2.0, 1.0), &[(0.0, 0.0), (3.0, 0.0), (3.0, 3.0)]);
+    assert_points(&trim_polyline(&corner(), 1.0, -2.0), &[(1.0, 0.0), (3.0, 0.0), (3.0, 4.0)]);
+}
+
+#[test]
+fn repeated_points_and_short_lines() {
+    let repeated = points(&[(0.0, 0.0), (0.0, 0.0), (2.0, 0.0), (2.0, 0.0)]);
+    assert_points(&trim_polyline(&repeated, 0.5, 0.5), &[(0.5, 0.0), (1.5, 0.0)]);
+
+    assert!(trim_polyline(&[], 1.0, 1.0).is_empty());
+    assert!(trim_polyline(&points(&[(1.0, 1.0)]), 1.0, 0.0).is_empty());
+}
@@ -0,0 +1,35 @@
+use oxide::Vector2;
+
+#[test]
+fn subtraction_is_per_component() {
+    // x used to be taken from self.y
+    let a = Vector2::new(5.0, 2.0);
+    let b = Vector2::new(1.0, 3.0);
+    assert_eq!(a - b, Vector2::new(4.0, -1.0));
+    assert_eq!(b - a, Vector2::new(-4.0, 1.0));
+}
+
+#[test]
+fn subtraction_undoes_addition() {
+    let a = Vector2::new(-2.5, 7.0);
+    let b = Vector2::new(3.0, 0.5);
+    assert_eq!((a + b) - b, a);
+    assert_eq!(a - a, Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn length_and_distance() {
+    let a = Vector2::new(3.0, 4.0);
+    assert_eq!(a.length(), 5.0);
+    assert_eq!(a.dot(Vector2::new(2.0, -1.0)), 2.0);
+    assert_eq!(Vector2::new(1.0, 1.0).distance(Vector2::new(4.0, 5.0)), 5.0);
+}
+
+#[test]
+fn normalized_keeps_the_direction() {
+    let unit = Vector2::new(0.0, -3.0).normalized();
+    assert_eq!(unit, Vector2::new(0.0, -1.0));
+    assert!((Vector2::new(2.0, 7.0).normalized().length() - 1.0).abs() < 1e-6);
+    // Too short to have a direction
+    assert_eq!(Vector2::zero().normalized(), Vector2::zero());
+}